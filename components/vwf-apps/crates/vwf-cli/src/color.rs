@@ -0,0 +1,45 @@
+//! ANSI color handling for terminal output.
+
+use std::io::IsTerminal;
+
+pub const GREEN: &str = "32";
+pub const RED: &str = "31";
+
+/// Whether to emit color: stdout is a TTY, `--no-color` wasn't given and
+/// `NO_COLOR` is unset or empty.
+pub fn enabled(no_color_flag: bool) -> bool {
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    resolve(no_color_flag, no_color_env, std::io::stdout().is_terminal())
+}
+
+fn resolve(no_color_flag: bool, no_color_env: bool, is_tty: bool) -> bool {
+    is_tty && !no_color_flag && !no_color_env
+}
+
+/// Wrap `text` in the given SGR color code when `color` is on.
+pub fn paint(text: &str, code: &str, color: bool) -> String {
+    if color {
+        format!("\x1b[{code}m{text}\x1b[0m")
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_requires_tty_and_no_opt_out() {
+        assert!(resolve(false, false, true));
+        assert!(!resolve(false, false, false));
+        assert!(!resolve(true, false, true));
+        assert!(!resolve(false, true, true));
+    }
+
+    #[test]
+    fn paint_is_plain_when_disabled() {
+        assert_eq!(paint("[OK]", GREEN, false), "[OK]");
+        assert_eq!(paint("[OK]", GREEN, true), "\x1b[32m[OK]\x1b[0m");
+    }
+}
@@ -0,0 +1,62 @@
+//! Confirmation prompts before `confirm: true` steps.
+
+use std::io::{BufRead, IsTerminal, Write};
+
+use vwf_core::ConfirmCallback;
+
+/// Callback that asks on the terminal, or `None` to run such steps unasked
+/// (`--assume-yes`, or stdin isn't a TTY and `--interactive` wasn't given).
+pub fn callback(assume_yes: bool, interactive: bool) -> Option<ConfirmCallback> {
+    if !should_prompt(assume_yes, interactive, std::io::stdin().is_terminal()) {
+        return None;
+    }
+    Some(Box::new(|_, summary| {
+        ask(
+            &mut std::io::stdin().lock(),
+            &mut std::io::stderr(),
+            summary,
+        )
+    }))
+}
+
+fn should_prompt(assume_yes: bool, interactive: bool, is_tty: bool) -> bool {
+    !assume_yes && (interactive || is_tty)
+}
+
+/// Show `summary` and read an answer; only `y` or `yes` approves.
+fn ask(input: &mut dyn BufRead, out: &mut dyn Write, summary: &str) -> bool {
+    let _ = write!(out, "\n{summary}\nRun this step? [y/N] ");
+    let _ = out.flush();
+    let mut answer = String::new();
+    if input.read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assume_yes_never_prompts() {
+        assert!(!should_prompt(true, false, true));
+        assert!(!should_prompt(true, true, false));
+        assert!(callback(true, false).is_none());
+        assert!(should_prompt(false, false, true));
+        assert!(!should_prompt(false, false, false));
+        assert!(should_prompt(false, true, false));
+    }
+
+    #[test]
+    fn only_yes_approves() {
+        let mut out = vec![];
+        assert!(ask(&mut "y\n".as_bytes(), &mut out, "render (RunCommand)"));
+        assert!(ask(&mut "YES\n".as_bytes(), &mut vec![], ""));
+        assert!(!ask(&mut "n\n".as_bytes(), &mut vec![], ""));
+        assert!(!ask(&mut "\n".as_bytes(), &mut vec![], ""));
+        assert!(!ask(&mut "".as_bytes(), &mut vec![], ""));
+        let shown = String::from_utf8(out).unwrap();
+        assert_eq!(shown, "\nrender (RunCommand)\nRun this step? [y/N] ");
+    }
+}
@@ -0,0 +1,271 @@
+//! Environment diagnostics command.
+//!
+//! Checks that the external tools the step library shells out to are
+//! installed, and that the default services answer.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+
+use vwf_core::{CommandRunner, SystemRunner, is_not_found};
+
+use crate::color::{self, GREEN, RED};
+use crate::services::{self, ServiceStatus};
+
+/// An external program some steps need.
+struct Tool {
+    name: &'static str,
+    /// Overrides the first candidate, as the steps themselves do
+    env_var: Option<&'static str>,
+    /// Binaries tried in order; the first that runs counts
+    candidates: &'static [&'static str],
+    /// Arguments that print a version; `None` if the tool has no such flag
+    version_args: Option<&'static [&'static str]>,
+    needed_by: &'static str,
+}
+
+const TOOLS: &[Tool] = &[
+    Tool {
+        name: "ffmpeg",
+        env_var: Some("VWF_FFMPEG"),
+        candidates: &["ffmpeg"],
+        version_args: Some(&["-version"]),
+        needed_by: "audio_mix, normalize_volume, video_concat, llm_audit",
+    },
+    Tool {
+        name: "ffprobe",
+        env_var: Some("VWF_FFPROBE"),
+        candidates: &["ffprobe"],
+        version_args: Some(&["-version"]),
+        needed_by: "probe_media, video_concat, output validation",
+    },
+    Tool {
+        name: "ImageMagick",
+        env_var: None,
+        candidates: &["convert", "magick"],
+        version_args: Some(&["-version"]),
+        needed_by: "create_slide, text_to_image (non-PNG formats)",
+    },
+    Tool {
+        name: "python3",
+        env_var: None,
+        candidates: &["python3"],
+        version_args: Some(&["--version"]),
+        needed_by: "tts_generate, text_to_image, image_to_video, text_to_video",
+    },
+    Tool {
+        name: "whisper-cli",
+        env_var: None,
+        candidates: &["whisper-cli", "/opt/homebrew/bin/whisper-cli"],
+        version_args: None,
+        needed_by: "whisper_transcribe",
+    },
+];
+
+/// What was found for one tool.
+#[derive(Debug, PartialEq)]
+pub struct ToolStatus {
+    pub name: &'static str,
+    pub needed_by: &'static str,
+    /// Program that ran and its location on disk, if it could be found
+    pub found: Option<(String, Option<PathBuf>)>,
+    /// First line of the version output
+    pub version: Option<String>,
+}
+
+/// Check every tool with `runner`; `locate` maps a program to its path.
+pub fn check_tools(
+    runner: &mut dyn CommandRunner,
+    locate: &dyn Fn(&str) -> Option<PathBuf>,
+) -> Vec<ToolStatus> {
+    TOOLS
+        .iter()
+        .map(|tool| check_tool(tool, runner, locate))
+        .collect()
+}
+
+fn check_tool(
+    tool: &Tool,
+    runner: &mut dyn CommandRunner,
+    locate: &dyn Fn(&str) -> Option<PathBuf>,
+) -> ToolStatus {
+    let configured = tool.env_var.and_then(|v| std::env::var(v).ok());
+    let candidates: Vec<&str> = match &configured {
+        Some(program) => vec![program.as_str()],
+        None => tool.candidates.to_vec(),
+    };
+    let args: Vec<String> = tool
+        .version_args
+        .unwrap_or(&["--help"])
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+    let mut status = ToolStatus {
+        name: tool.name,
+        needed_by: tool.needed_by,
+        found: None,
+        version: None,
+    };
+    for program in candidates {
+        let out = match runner.run(program, &args) {
+            Err(e) if is_not_found(&e) => continue,
+            Err(_) => None,
+            Ok(out) => Some(out),
+        };
+        status.found = Some((program.to_string(), locate(program)));
+        if tool.version_args.is_some() {
+            status.version = out.and_then(|out| {
+                let text = if out.stdout.trim().is_empty() {
+                    out.stderr
+                } else {
+                    out.stdout
+                };
+                text.lines().next().map(|l| l.trim().to_string())
+            });
+        }
+        break;
+    }
+    status
+}
+
+/// Where `program` would be run from: itself if it is a path, else the
+/// first match on `PATH`.
+pub fn locate_on_path(program: &str) -> Option<PathBuf> {
+    if program.contains('/') {
+        return Path::new(program).is_file().then(|| PathBuf::from(program));
+    }
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Run all checks and print the results, failing if anything is missing.
+pub fn doctor(color: bool) -> Result<()> {
+    let tools = check_tools(&mut SystemRunner, &locate_on_path);
+    let mut probe = services::http_probe()?;
+    let services = services::check_all(&mut probe);
+    print!("{}", render(&tools, &services, color));
+    let problems = tools.iter().filter(|t| t.found.is_none()).count()
+        + services.iter().filter(|s| !s.running).count();
+    if problems > 0 {
+        bail!("{problems} problem(s) found");
+    }
+    println!("\nEverything looks good.");
+    Ok(())
+}
+
+/// The report printed by `vwf doctor`.
+fn render(tools: &[ToolStatus], services: &[ServiceStatus], color: bool) -> String {
+    let mut out = String::from("--- tools ---\n");
+    for tool in tools {
+        let line = match &tool.found {
+            Some((program, path)) => {
+                let location = path
+                    .as_deref()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_else(|| program.clone());
+                let version = tool.version.as_deref().unwrap_or("version unknown");
+                format!(
+                    "{} {}: {location} ({version})",
+                    color::paint("[OK]", GREEN, color),
+                    tool.name
+                )
+            }
+            None => format!(
+                "{} {}: missing (needed by {})",
+                color::paint("[MISSING]", RED, color),
+                tool.name,
+                tool.needed_by
+            ),
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out.push_str("\n--- services ---\n");
+    for s in services {
+        out.push_str(&format!(
+            "{} {} {}\n",
+            services::status_label(s.running, color),
+            s.name,
+            s.url
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vwf_core::CmdOut;
+
+    /// Answers version queries, failing to spawn the `missing` programs.
+    struct FakeRunner {
+        missing: Vec<&'static str>,
+        calls: Vec<String>,
+    }
+
+    impl CommandRunner for FakeRunner {
+        fn run(&mut self, program: &str, _args: &[String]) -> Result<CmdOut> {
+            self.calls.push(program.to_string());
+            if self.missing.contains(&program) {
+                return Err(
+                    anyhow::Error::new(std::io::Error::from(std::io::ErrorKind::NotFound))
+                        .context(format!("Failed to run {program}")),
+                );
+            }
+            Ok(CmdOut {
+                status: 0,
+                stdout: format!("{program} version 1.0\nbuilt with gcc\n"),
+                stderr: String::new(),
+                duration_ms: 0,
+            })
+        }
+    }
+
+    #[test]
+    fn missing_tool_is_reported_as_missing() {
+        let mut runner = FakeRunner {
+            missing: vec!["convert", "whisper-cli", "/opt/homebrew/bin/whisper-cli"],
+            calls: vec![],
+        };
+        let locate = |program: &str| Some(PathBuf::from("/usr/bin").join(program));
+        let tools = check_tools(&mut runner, &locate);
+
+        let by_name = |name: &str| tools.iter().find(|t| t.name == name).unwrap();
+        let python = by_name("python3");
+        assert_eq!(
+            python.found,
+            Some(("python3".into(), Some("/usr/bin/python3".into())))
+        );
+        assert_eq!(python.version.as_deref(), Some("python3 version 1.0"));
+        // ImageMagick 7 without the convert shim still counts
+        assert_eq!(by_name("ImageMagick").found.as_ref().unwrap().0, "magick");
+        let whisper = by_name("whisper-cli");
+        assert_eq!(whisper.found, None);
+        assert!(
+            runner
+                .calls
+                .contains(&"/opt/homebrew/bin/whisper-cli".into())
+        );
+
+        let services = [ServiceStatus {
+            name: "Ollama",
+            url: "http://localhost:11434",
+            running: false,
+        }];
+        let report = render(&tools, &services, false);
+        assert!(
+            report.contains("[MISSING] whisper-cli: missing (needed by whisper_transcribe)"),
+            "{report}"
+        );
+        assert!(
+            report.contains("[OK] python3: /usr/bin/python3 (python3 version 1.0)"),
+            "{report}"
+        );
+        assert!(
+            report.contains("[NOT RUNNING] Ollama http://localhost:11434"),
+            "{report}"
+        );
+    }
+}
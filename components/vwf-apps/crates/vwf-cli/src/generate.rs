@@ -4,11 +4,12 @@
 //! then constructs valid YAML programmatically. The LLM provides
 //! creative content; the code ensures valid structure.
 
+use std::collections::BTreeSet;
 use std::path::Path;
 
 use anyhow::{bail, Context, Result};
 
-use vwf_core::{LlmClient, LlmReq, OllamaClient};
+use vwf_core::{DEFAULT_ACRONYMS, LlmClient, LlmReq, OllamaClient};
 
 /// A segment identified from the brief.
 #[derive(Debug, Clone)]
@@ -21,8 +22,122 @@ struct SegmentPlan {
     duration_hint: String,
 }
 
+/// Default layout for generated files, e.g. `work/audio/01-intro.wav`.
+pub const DEFAULT_NAMING: &str = "{kind}/{index}-{segment}.{ext}";
+
+/// What `setup_dirs` creates with the default layout.
+const DEFAULT_DIRS: [&str; 7] = [
+    "work/scripts",
+    "work/audio",
+    "work/images",
+    "work/videos",
+    "work/clips",
+    "work/reports",
+    "output",
+];
+
+/// A kind of file the generated workflow produces for each segment.
+#[derive(Debug, Clone, Copy)]
+enum Output {
+    Script,
+    Audio,
+    Image,
+    Clip,
+}
+
+impl Output {
+    /// `{kind}` in a naming template; also the default directory.
+    fn kind(self) -> &'static str {
+        match self {
+            Output::Script => "scripts",
+            Output::Audio => "audio",
+            Output::Image => "images",
+            Output::Clip => "clips",
+        }
+    }
+
+    fn ext(self) -> &'static str {
+        match self {
+            Output::Script => "txt",
+            Output::Audio => "wav",
+            Output::Image => "png",
+            Output::Clip => "mp4",
+        }
+    }
+}
+
+/// Paths of generated files under `work/`, from a template such as
+/// `{segment}/{kind}.{ext}`.
+///
+/// Placeholders: `{segment}` (segment id), `{index}` (two-digit position),
+/// `{kind}` (scripts, audio, images or clips) and `{ext}`.
+#[derive(Debug, Clone)]
+pub struct Naming {
+    template: String,
+}
+
+impl Naming {
+    /// Check that `template` only uses known placeholders and gives every
+    /// file of every segment its own path.
+    pub fn new(template: &str) -> Result<Self> {
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let Some(len) = rest[start..].find('}') else {
+                bail!("naming template `{template}` has an unclosed `{{`");
+            };
+            let name = &rest[start + 1..start + len];
+            if !matches!(name, "segment" | "index" | "kind" | "ext") {
+                bail!(
+                    "naming template `{template}` uses unknown placeholder `{{{name}}}`; \
+                     use {{segment}}, {{index}}, {{kind}} or {{ext}}"
+                );
+            }
+            rest = &rest[start + len + 1..];
+        }
+        let has = |name: &str| template.contains(&format!("{{{name}}}"));
+        if !has("segment") && !has("index") {
+            bail!(
+                "naming template `{template}` needs {{segment}} or {{index}} to tell segments apart"
+            );
+        }
+        if !has("kind") && !has("ext") {
+            bail!("naming template `{template}` needs {{kind}} or {{ext}} to tell files apart");
+        }
+        if template.contains(['"', '\\']) {
+            bail!("naming template `{template}` cannot contain `\"` or `\\`");
+        }
+        if template.starts_with('/') || template.split('/').any(|part| part == "..") {
+            bail!("naming template `{template}` must stay inside work/");
+        }
+        Ok(Self {
+            template: template.to_string(),
+        })
+    }
+
+    fn path(&self, output: Output, index: usize, segment: &str) -> String {
+        let name = self
+            .template
+            .replace("{segment}", segment)
+            .replace("{index}", &format!("{index:02}"))
+            .replace("{kind}", output.kind())
+            .replace("{ext}", output.ext());
+        format!("work/{name}")
+    }
+}
+
+impl Default for Naming {
+    fn default() -> Self {
+        Self::new(DEFAULT_NAMING).expect("default naming template is valid")
+    }
+}
+
 /// Generate a workflow from a project brief using structured LLM queries.
-pub fn generate(project_dir: &Path, model: &str, _context_dir: Option<&Path>) -> Result<()> {
+pub fn generate(
+    project_dir: &Path,
+    model: &str,
+    _context_dir: Option<&Path>,
+    naming: &Naming,
+) -> Result<()> {
     // Read the project brief
     let brief_path = project_dir.join("brief.txt");
     if !brief_path.exists() {
@@ -60,7 +175,7 @@ pub fn generate(project_dir: &Path, model: &str, _context_dir: Option<&Path>) ->
 
     // Step 4: Build the workflow YAML programmatically
     println!("\n[4/4] Building workflow YAML...");
-    let yaml = build_workflow_yaml(&project_name, &segments);
+    let yaml = build_workflow_yaml(&project_name, &segments, naming);
 
     // Write the workflow
     let output_path = project_dir.join("workflow.yaml");
@@ -180,13 +295,7 @@ DURATION HINT: {duration}
 RULES:
 1. Write natural, conversational speech
 2. Spell out acronyms phonetically:
-   - VWF → "Vee Double-You Eff"
-   - API → "A-P-I"
-   - CLI → "command line"
-   - TTS → "text-to-speech"
-   - LLM → "large language model"
-   - YAML → "yaml"
-   - ffmpeg → "eff eff em peg"
+{acronyms}
 3. Keep sentences short and punchy
 4. Match the duration hint (~150 words per minute)
 5. Do NOT include stage directions or [brackets]
@@ -195,6 +304,7 @@ Output ONLY the narration text, nothing else."#,
             segment.id,
             title = segment.title,
             duration = segment.duration_hint,
+            acronyms = acronym_rules(),
         );
 
         let response = llm.generate(LlmReq {
@@ -209,6 +319,16 @@ Output ONLY the narration text, nothing else."#,
     Ok(segments)
 }
 
+/// The `expand_acronyms` defaults as prompt rules, so narration the LLM
+/// writes and narration rewritten later agree on pronunciation.
+fn acronym_rules() -> String {
+    DEFAULT_ACRONYMS
+        .iter()
+        .map(|(acronym, spoken)| format!("   - {acronym} → \"{spoken}\""))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 /// Determine visual style for each segment.
 fn determine_visuals(
     llm: &mut OllamaClient,
@@ -278,8 +398,9 @@ fn normalize_visual_style(s: &str) -> String {
 }
 
 /// Build the workflow YAML programmatically from segment plans.
-fn build_workflow_yaml(project_name: &str, segments: &[SegmentPlan]) -> String {
+fn build_workflow_yaml(project_name: &str, segments: &[SegmentPlan], naming: &Naming) -> String {
     let mut yaml = String::new();
+    let path = |output, i, seg: &SegmentPlan| naming.path(output, i, &seg.id);
 
     // Header
     yaml.push_str(&format!(
@@ -324,31 +445,39 @@ segments:
 
     yaml.push_str("\nsteps:\n");
 
-    // Directory setup
-    yaml.push_str(
-        r#"  # ========== Directory Setup ==========
-  - id: setup_dirs
-    kind: ensure_dirs
-    dirs:
-      - "work/scripts"
-      - "work/audio"
-      - "work/images"
-      - "work/videos"
-      - "work/clips"
-      - "work/reports"
-      - "output"
-
-"#,
-    );
+    // Directory setup: the default layout's directories, or every
+    // directory a generated file lands in
+    yaml.push_str("  # ========== Directory Setup ==========\n");
+    yaml.push_str("  - id: setup_dirs\n    kind: ensure_dirs\n    dirs:\n");
+    let mut dirs: Vec<String> = vec![];
+    if naming.template == DEFAULT_NAMING {
+        dirs.extend(DEFAULT_DIRS.map(String::from));
+    }
+    for output in [Output::Script, Output::Audio, Output::Image, Output::Clip] {
+        for (i, seg) in segments.iter().enumerate() {
+            let narrated = seg.narration.is_some();
+            if (narrated || matches!(output, Output::Image | Output::Clip))
+                && let Some((dir, _)) = path(output, i, seg).rsplit_once('/')
+            {
+                dirs.push(dir.to_string());
+            }
+        }
+    }
+    dirs.extend(["work/reports".to_string(), "output".to_string()]);
+    let mut seen = BTreeSet::new();
+    for dir in dirs.into_iter().filter(|d| seen.insert(d.clone())) {
+        yaml.push_str(&format!("      - \"{dir}\"\n"));
+    }
+    yaml.push('\n');
 
     // Script writing steps
     yaml.push_str("  # ========== Scripts ==========\n");
     for (i, seg) in segments.iter().enumerate() {
         if let Some(ref narration) = seg.narration {
             yaml.push_str(&format!(
-                "  - id: script_{id}\n    kind: write_file\n    path: \"work/scripts/{i:02}-{id}.txt\"\n    content: |\n{content}\n\n",
+                "  - id: script_{id}\n    kind: write_file\n    path: \"{script}\"\n    content: |\n{content}\n\n",
                 id = seg.id,
-                i = i,
+                script = path(Output::Script, i, seg),
                 content = indent_content(narration, 6)
             ));
         }
@@ -361,16 +490,17 @@ segments:
             yaml.push_str(&format!(
                 r#"  - id: tts_{id}
     kind: tts_generate
-    resume_output: "work/audio/{i:02}-{id}.wav"
-    script_path: "work/scripts/{i:02}-{id}.txt"
-    output_path: "work/audio/{i:02}-{id}.wav"
+    resume_output: "{audio}"
+    script_path: "{script}"
+    output_path: "{audio}"
     server: "{{{{tts_server}}}}"
     reference_audio: "{{{{voice_ref}}}}"
     reference_text: "{{{{voice_ref_text}}}}"
 
 "#,
                 id = seg.id,
-                i = i
+                script = path(Output::Script, i, seg),
+                audio = path(Output::Audio, i, seg)
             ));
         }
     }
@@ -378,20 +508,20 @@ segments:
     // Visual generation based on style
     yaml.push_str("  # ========== Visual Generation ==========\n");
     for (i, seg) in segments.iter().enumerate() {
+        let image = path(Output::Image, i, seg);
         match seg.visual_style.as_str() {
             "title_card" => {
                 yaml.push_str(&format!(
                     r#"  - id: img_{id}
     kind: create_slide
-    resume_output: "work/images/{i:02}-{id}.png"
+    resume_output: "{image}"
     template: title
     text: "{title}"
-    output_path: "work/images/{i:02}-{id}.png"
+    output_path: "{image}"
     orientation: landscape
 
 "#,
                     id = seg.id,
-                    i = i,
                     title = seg.title
                 ));
             }
@@ -399,16 +529,15 @@ segments:
                 yaml.push_str(&format!(
                     r#"  - id: img_{id}
     kind: text_to_image
-    resume_output: "work/images/{i:02}-{id}.png"
+    resume_output: "{image}"
     prompt: "Professional diagram showing {title_lower}, dark theme, clean modern design, high contrast"
-    output_path: "work/images/{i:02}-{id}.png"
+    output_path: "{image}"
     orientation: landscape
     seed: {seed}
     server: "{{{{tti_server}}}}"
 
 "#,
                     id = seg.id,
-                    i = i,
                     title_lower = seg.title.to_lowercase(),
                     seed = 2026 + i
                 ));
@@ -418,16 +547,15 @@ segments:
                 yaml.push_str(&format!(
                     r##"  - id: img_{id}
     kind: create_slide
-    resume_output: "work/images/{i:02}-{id}.png"
+    resume_output: "{image}"
     template: title
     text: "{title}"
     background_color: "#1a1a2e"
-    output_path: "work/images/{i:02}-{id}.png"
+    output_path: "{image}"
     orientation: landscape
 
 "##,
                     id = seg.id,
-                    i = i,
                     title = seg.title
                 ));
             }
@@ -437,6 +565,8 @@ segments:
     // Video clip creation
     yaml.push_str("  # ========== Clip Assembly ==========\n");
     for (i, seg) in segments.iter().enumerate() {
+        let image = path(Output::Image, i, seg);
+        let clip = path(Output::Clip, i, seg);
         if seg.segment_type == "music_only" {
             // Music-only clip
             yaml.push_str(&format!(
@@ -448,7 +578,7 @@ segments:
       - "-loop"
       - "1"
       - "-i"
-      - "work/images/{i:02}-{id}.png"
+      - "{image}"
       - "-i"
       - "{{{{music_file}}}}"
       - "-filter_complex"
@@ -467,11 +597,10 @@ segments:
       - "yuv420p"
       - "-t"
       - "5"
-      - "work/clips/{i:02}-{id}.mp4"
+      - "{clip}"
 
 "#,
-                id = seg.id,
-                i = i
+                id = seg.id
             ));
         } else {
             // Narration clip
@@ -484,9 +613,9 @@ segments:
       - "-loop"
       - "1"
       - "-i"
-      - "work/images/{i:02}-{id}.png"
+      - "{image}"
       - "-i"
-      - "work/audio/{i:02}-{id}.wav"
+      - "{audio}"
       - "-c:v"
       - "libx264"
       - "-tune"
@@ -498,11 +627,11 @@ segments:
       - "-pix_fmt"
       - "yuv420p"
       - "-shortest"
-      - "work/clips/{i:02}-{id}.mp4"
+      - "{clip}"
 
 "#,
                 id = seg.id,
-                i = i
+                audio = path(Output::Audio, i, seg)
             ));
         }
     }
@@ -512,9 +641,8 @@ segments:
     yaml.push_str("  - id: final_concat\n    kind: video_concat\n    clips:\n");
     for (i, seg) in segments.iter().enumerate() {
         yaml.push_str(&format!(
-            "      - \"work/clips/{i:02}-{id}.mp4\"\n",
-            i = i,
-            id = seg.id
+            "      - \"{clip}\"\n",
+            clip = path(Output::Clip, i, seg)
         ));
     }
     yaml.push_str("    output_path: \"output/final.mp4\"\n    reencode: true\n\n");
@@ -563,6 +691,13 @@ mod tests {
         assert_eq!(normalize_segment_type("unknown"), "narration_only");
     }
 
+    #[test]
+    fn acronym_rules_list_the_step_defaults() {
+        let rules = acronym_rules();
+        assert_eq!(rules.lines().count(), DEFAULT_ACRONYMS.len());
+        assert!(rules.contains("   - VWF → \"Vee Double-You Eff\""));
+    }
+
     #[test]
     fn test_normalize_visual_style() {
         assert_eq!(normalize_visual_style("title_card"), "title_card");
@@ -571,6 +706,220 @@ mod tests {
         assert_eq!(normalize_visual_style("unknown"), "static");
     }
 
+    fn plans() -> Vec<SegmentPlan> {
+        let plan = |id: &str, segment_type: &str, narration: Option<&str>| SegmentPlan {
+            id: id.to_string(),
+            title: id.to_uppercase(),
+            segment_type: segment_type.to_string(),
+            narration: narration.map(str::to_string),
+            visual_style: "static".to_string(),
+            duration_hint: "10s".to_string(),
+        };
+        vec![
+            plan("title", "music_only", None),
+            plan("hook", "narration_only", Some("Hello there.")),
+        ]
+    }
+
+    /// Every quoted `work/...` path in the YAML, in order.
+    fn work_paths(yaml: &str) -> Vec<&str> {
+        yaml.split('"').filter(|s| s.starts_with("work/")).collect()
+    }
+
+    #[test]
+    fn default_naming_keeps_numbered_paths() {
+        let yaml = build_workflow_yaml("demo", &plans(), &Naming::default());
+        let paths = work_paths(&yaml);
+        for expected in [
+            "work/scripts/01-hook.txt",
+            "work/audio/01-hook.wav",
+            "work/images/00-title.png",
+            "work/clips/00-title.mp4",
+            "work/clips/01-hook.mp4",
+        ] {
+            assert!(
+                paths.contains(&expected),
+                "{expected} missing from {paths:?}"
+            );
+        }
+        let dirs: String = DEFAULT_DIRS
+            .iter()
+            .map(|dir| format!("      - \"{dir}\"\n"))
+            .collect();
+        assert!(yaml.contains(&format!("    dirs:\n{dirs}\n")), "{yaml}");
+        let workflow: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(workflow["steps"][0]["id"], "setup_dirs");
+    }
+
+    #[test]
+    fn custom_naming_changes_every_output_path() {
+        let naming = Naming::new("{segment}/{kind}.{ext}").unwrap();
+        let yaml = build_workflow_yaml("demo", &plans(), &naming);
+        let paths: BTreeSet<&str> = work_paths(&yaml).into_iter().collect();
+        assert_eq!(
+            paths,
+            BTreeSet::from([
+                "work/title",
+                "work/hook",
+                "work/reports",
+                "work/reports/audit.json",
+                "work/title/images.png",
+                "work/title/clips.mp4",
+                "work/hook/scripts.txt",
+                "work/hook/audio.wav",
+                "work/hook/images.png",
+                "work/hook/clips.mp4",
+            ])
+        );
+        // The TTS step reads the script the write step wrote and the clip
+        // step reads the audio the TTS step wrote
+        assert_eq!(yaml.matches("\"work/hook/scripts.txt\"").count(), 2);
+        assert_eq!(yaml.matches("\"work/hook/audio.wav\"").count(), 3);
+        assert_eq!(yaml.matches("\"work/hook/clips.mp4\"").count(), 2);
+    }
+
+    #[test]
+    fn naming_templates_are_validated() {
+        assert!(Naming::new("{index}_{segment}_{kind}.{ext}").is_ok());
+        for (template, problem) in [
+            ("{kind}/{name}.{ext}", "unknown placeholder `{name}`"),
+            ("{kind}/{segment.{ext}", "unknown placeholder"),
+            ("{kind}/clip.{ext}", "needs {segment} or {index}"),
+            ("{segment}/file", "needs {kind} or {ext}"),
+            ("../{segment}/{kind}.{ext}", "must stay inside work/"),
+            ("{segment}/{kind}.{ext", "unclosed"),
+            ("{segment}\"/{kind}.{ext}", "cannot contain"),
+            ("{segment}\\{kind}.{ext}", "cannot contain"),
+        ] {
+            let err = Naming::new(template).unwrap_err().to_string();
+            assert!(err.contains(problem), "{template}: {err}");
+        }
+    }
+
+    /// A title card over music, a narrated diagram and a mixed segment.
+    fn three_segment_plan() -> Vec<SegmentPlan> {
+        let plan =
+            |id: &str, segment_type: &str, narration: Option<&str>, style: &str| SegmentPlan {
+                id: id.to_string(),
+                title: format!("The {id}"),
+                segment_type: segment_type.to_string(),
+                narration: narration.map(str::to_string),
+                visual_style: style.to_string(),
+                duration_hint: "10s".to_string(),
+            };
+        vec![
+            plan("title", "music_only", None, "title_card"),
+            plan(
+                "problem",
+                "narration_only",
+                Some("Renders break.\nOften."),
+                "diagram",
+            ),
+            plan("demo", "mixed", Some("Watch this."), "static"),
+        ]
+    }
+
+    /// One line per segment and step: the parts of the generated workflow
+    /// other code relies on, without the prompt and ffmpeg details.
+    fn outline(cfg: &vwf_core::WorkflowConfig) -> String {
+        let mut lines = vec![];
+        for seg in &cfg.segments {
+            lines.push(format!("segment {} -> {}", seg.id, seg.steps.join(", ")));
+        }
+        for step in &cfg.steps {
+            let kind = serde_json::to_value(&step.kind).unwrap();
+            let mut line = format!("{} {}", step.id, kind.as_str().unwrap());
+            for path in step.output_paths() {
+                line.push_str(&format!(" > {path}"));
+            }
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn generated_workflow_matches_its_outline() {
+        let yaml = build_workflow_yaml("demo", &three_segment_plan(), &Naming::default());
+        let cfg = vwf_core::WorkflowConfig::from_yaml(&yaml).unwrap();
+        vwf_core::Runner::validate(&cfg).unwrap();
+        assert_eq!(
+            outline(&cfg),
+            "\
+segment title -> img_title
+segment problem -> script_problem
+segment demo -> script_demo
+setup_dirs ensure_dirs
+script_problem write_file > work/scripts/01-problem.txt
+script_demo write_file > work/scripts/02-demo.txt
+tts_problem tts_generate > work/audio/01-problem.wav
+tts_demo tts_generate > work/audio/02-demo.wav
+img_title create_slide > work/images/00-title.png
+img_problem text_to_image > work/images/01-problem.png
+img_demo create_slide > work/images/02-demo.png
+clip_title run_command
+clip_problem run_command
+clip_demo run_command
+final_concat video_concat > output/final.mp4
+audit_output llm_audit > work/reports/audit.json"
+        );
+    }
+
+    #[test]
+    fn generated_steps_only_read_files_made_earlier() {
+        let yaml = build_workflow_yaml("demo", &three_segment_plan(), &Naming::default());
+        let cfg = vwf_core::WorkflowConfig::from_yaml(&yaml).unwrap();
+        let dirs: Vec<&str> = cfg.steps[0].payload["dirs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|d| d.as_str())
+            .collect();
+        let strings = |step: &vwf_core::StepConfig, field: &str| -> Vec<String> {
+            step.payload[field]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        };
+        let mut written: BTreeSet<String> = BTreeSet::new();
+        for step in &cfg.steps {
+            // A generated ffmpeg command writes its last argument
+            let mut args = strings(step, "args");
+            let command_output = args.pop();
+            let reads = step
+                .input_paths()
+                .into_iter()
+                .map(String::from)
+                .chain(args)
+                .chain(strings(step, "clips"))
+                .chain(strings(step, "assets"));
+            for path in reads.filter(|p| p.starts_with("work/") || p.starts_with("output/")) {
+                assert!(
+                    written.contains(&path),
+                    "{} reads {path} before it is written",
+                    step.id
+                );
+            }
+            let writes = step
+                .output_paths()
+                .into_iter()
+                .map(String::from)
+                .chain(command_output);
+            for path in writes {
+                let dir = path.rsplit_once('/').unwrap().0;
+                assert!(
+                    dirs.contains(&dir),
+                    "{} writes {path} outside setup_dirs",
+                    step.id
+                );
+                written.insert(path);
+            }
+        }
+        // The narration keeps its line breaks in the script step
+        assert_eq!(cfg.steps[1].payload["content"], "Renders break.\nOften.\n");
+    }
+
     #[test]
     fn test_indent_content() {
         let content = "line 1\nline 2";
@@ -0,0 +1,169 @@
+//! Project scaffolding command.
+
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+const WORKFLOW_TEMPLATE: &str = r#"version: 1
+name: {{name}}
+
+vars:
+  topic: "what this video is about"
+
+steps:
+  - id: setup
+    kind: ensure_dirs
+    dirs: ["work", "work/prompts", "work/scripts"]
+
+  - id: write_prompt
+    kind: write_file
+    depends_on: [setup]
+    path: "work/prompts/narration.txt"
+    content: |
+      Write a 30 second narration script about {{topic}}.
+      Use short, spoken sentences.
+
+  - id: narration
+    kind: llm_generate
+    depends_on: [write_prompt]
+    resume_output: "work/scripts/narration.txt"
+    system: "You write concise, friendly video narration."
+    user_prompt_path: "work/prompts/narration.txt"
+    output_path: "work/scripts/narration.txt"
+    provider: ollama
+"#;
+
+const BRIEF_TEMPLATE: &str = "\
+# Video Brief
+
+## Overview
+One or two sentences on what the video covers and how long it should be.
+
+## Target Audience
+- Who is watching
+
+## Key Messages
+1. The main point
+
+## Video Structure
+
+#### Segment 1: Title (5 seconds)
+- Type: music_only
+- Visual: title card
+
+#### Segment 2: Main content (60 seconds)
+- Type: narration_only
+- Narration: what to say
+";
+
+const GITIGNORE: &str = "work/\nrun*.json\n";
+
+/// Write a starter `workflow.yaml`, `brief.txt` and `.gitignore` into `dir`.
+///
+/// Existing files are never overwritten unless `force` is set.
+pub fn init(dir: &Path, force: bool) -> Result<()> {
+    let name = project_name(dir);
+    let files = [
+        ("workflow.yaml", workflow_yaml(&name)),
+        ("brief.txt", BRIEF_TEMPLATE.to_string()),
+        (".gitignore", GITIGNORE.to_string()),
+    ];
+    if !force {
+        let existing: Vec<&str> = files
+            .iter()
+            .filter(|(f, _)| dir.join(f).exists())
+            .map(|(f, _)| *f)
+            .collect();
+        if !existing.is_empty() {
+            bail!(
+                "{} already contains {} (use --force to overwrite)",
+                dir.display(),
+                existing.join(", ")
+            );
+        }
+    }
+    std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    for (file, content) in &files {
+        let path = dir.join(file);
+        std::fs::write(&path, content).with_context(|| format!("write {}", path.display()))?;
+        println!("  Created: {}", path.display());
+    }
+    println!("\nNext steps:");
+    println!("  1. Edit vars in workflow.yaml, or describe your video in brief.txt");
+    println!("     and run: vwf generate {}", dir.display());
+    println!(
+        "  2. Check it: vwf validate {}",
+        dir.join("workflow.yaml").display()
+    );
+    println!(
+        "  3. Preview: vwf run {} --workdir {} --dry-run",
+        dir.join("workflow.yaml").display(),
+        dir.display()
+    );
+    Ok(())
+}
+
+fn workflow_yaml(name: &str) -> String {
+    WORKFLOW_TEMPLATE.replacen("{{name}}", name, 1)
+}
+
+/// Workflow name from the directory, reduced to `[a-z0-9_]`.
+fn project_name(dir: &Path) -> String {
+    let raw = dir
+        .canonicalize()
+        .ok()
+        .as_deref()
+        .unwrap_or(dir)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let name: String = raw
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let name = name.trim_matches('_');
+    if name.is_empty() {
+        "my_video".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use vwf_core::{DryRunRuntime, MockLlmClient, RunOptions, Runner, WorkflowConfig};
+
+    #[test]
+    fn scaffold_parses_validates_and_dry_runs() {
+        let dir = std::env::temp_dir().join(format!("vwf_init_{}", std::process::id()));
+        init(&dir, false).unwrap();
+        let yaml = std::fs::read_to_string(dir.join("workflow.yaml")).unwrap();
+        let cfg = WorkflowConfig::from_yaml(&yaml).unwrap();
+        crate::validate::validate(&dir.join("workflow.yaml"), None, true).unwrap();
+        assert_eq!(cfg.name, format!("vwf_init_{}", std::process::id()));
+        assert!(dir.join("brief.txt").exists());
+        assert!(
+            std::fs::read_to_string(dir.join(".gitignore"))
+                .unwrap()
+                .contains("work/")
+        );
+
+        let mut rt = DryRunRuntime::new(&dir, Box::new(MockLlmClient::echo()));
+        let report =
+            Runner::run_with_options(&mut rt, &cfg, BTreeMap::new(), RunOptions::default())
+                .unwrap();
+        assert_eq!(report.steps.len(), 3);
+
+        assert!(init(&dir, false).is_err());
+        init(&dir, true).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn project_name_is_sanitized() {
+        assert_eq!(project_name(Path::new("/nope/My Video-2")), "my_video_2");
+        assert_eq!(project_name(Path::new("/")), "my_video");
+    }
+}
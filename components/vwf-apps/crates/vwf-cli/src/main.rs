@@ -1,13 +1,25 @@
+mod color;
+mod confirm;
+mod doctor;
 mod generate;
+mod init;
+mod matrix;
+mod progress;
 mod run;
 mod services;
+mod status;
+mod steps;
+mod validate;
 
 use std::path::PathBuf;
+use std::process::ExitCode;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use tracing_subscriber::EnvFilter;
 
+use vwf_core::StepKind;
+
 const LONG_ABOUT: &str = "\
 Video Workflow Framework CLI - Repeatable video production workflows.
 
@@ -16,6 +28,7 @@ AI CODING AGENT INSTRUCTIONS:
 This CLI runs YAML-defined video production workflows.
 
 USAGE FOR AI AGENTS:
+  0. Use `vwf init <project-dir>` to scaffold a starter workflow and brief
   1. Use `vwf generate <project-dir>` to create a workflow from a brief
   2. Use `vwf show <workflow.yaml>` to inspect workflow structure
   3. Use `vwf run <workflow.yaml> --workdir <dir> --dry-run` to preview
@@ -23,11 +36,19 @@ USAGE FOR AI AGENTS:
 
 WORKFLOW STEPS: ensure_dirs, write_file, split_sections, run_command, llm_generate, tts_generate,
                 text_to_image, image_to_video, text_to_video, normalize_volume, audio_mix,
-                video_concat, create_slide, whisper_transcribe
+                video_concat, create_slide, whisper_transcribe, llm_audit, probe_media,
+                fetch_url, expand_acronyms
+  Use `vwf steps --describe <kind>` to list a step's fields, types and defaults.
+  Use `vwf doctor` to check that ffmpeg, ImageMagick, python3 and whisper-cli are installed.
 
 RESUME MODE:
   Use `--resume` to skip steps whose output files already exist and are valid.
   Useful after power outage or interrupted workflow.
+  Use `--continue` to re-run only the steps that failed or were blocked in the
+  last run.json, e.g. after fixing a service.
+
+EXIT CODES (vwf run):
+  0 success, 1 a step failed or was blocked, 2 only `continue_on_error` steps failed.
 ";
 
 #[derive(Parser, Debug)]
@@ -35,18 +56,44 @@ RESUME MODE:
 struct Cli {
     #[command(subcommand)]
     cmd: Cmd,
+    /// Disable colored output (also honors NO_COLOR)
+    #[arg(long, global = true)]
+    no_color: bool,
 }
 
 #[derive(Subcommand, Debug)]
 enum Cmd {
     /// Run a workflow YAML file.
-    Run(RunArgs),
+    Run(Box<RunArgs>),
     /// Print a workflow after parsing (debug).
     Show { workflow: PathBuf },
+    /// Check a workflow YAML file for structural errors without running it.
+    Validate(ValidateArgs),
+    /// Scaffold a starter workflow.yaml, brief.txt and .gitignore in a directory.
+    Init {
+        dir: PathBuf,
+        /// Overwrite files that already exist
+        #[arg(long)]
+        force: bool,
+    },
     /// Generate a workflow.yaml from a project brief using LLM.
     Generate(GenerateArgs),
     /// Check availability of services required by a workflow.
     Services { workflow: PathBuf },
+    /// Check that the external tools and default services steps rely on are available.
+    Doctor,
+    /// Summarize the persisted workflow state (state.json) in a workdir.
+    Status { workdir: PathBuf },
+    /// Reset failed tasks in the persisted workflow state so the next run retries them.
+    Reset { workdir: PathBuf },
+    /// List step kinds, or describe one kind's payload fields.
+    Steps {
+        /// Step kind to describe (e.g. text_to_image)
+        #[arg(long, value_name = "KIND")]
+        describe: Option<String>,
+    },
+    /// Print a JSON Schema for workflow files (point your YAML language server at it).
+    Schema,
 }
 
 #[derive(Parser, Debug)]
@@ -59,6 +106,26 @@ struct GenerateArgs {
     /// Path to LLM context documents (default: auto-detect)
     #[arg(long)]
     context_dir: Option<PathBuf>,
+    /// Where generated files go under work/, from {segment}, {index}, {kind} and {ext}
+    #[arg(long, default_value = generate::DEFAULT_NAMING)]
+    naming: String,
+}
+
+#[derive(Parser, Debug)]
+struct ValidateArgs {
+    workflow: PathBuf,
+    /// On errors, ask the LLM for a corrected workflow and print it as a diff
+    #[arg(long)]
+    fix: bool,
+    /// With --fix, overwrite the workflow if the suggestion validates
+    #[arg(long, requires = "fix")]
+    write: bool,
+    /// Ollama model used by --fix
+    #[arg(long, default_value = "qwen2.5-coder:14b")]
+    model: String,
+    /// Also report unused vars and references to undefined vars
+    #[arg(long)]
+    strict_vars: bool,
 }
 
 #[derive(Parser, Debug)]
@@ -68,17 +135,128 @@ struct RunArgs {
     workdir: PathBuf,
     #[arg(long = "var", value_parser = parse_kv, num_args = 0..)]
     vars: Vec<(String, String)>,
+    /// JSON or YAML mapping of vars; nested keys are flattened with dots, and `--var` wins
+    #[arg(long, value_name = "PATH")]
+    var_file: Option<PathBuf>,
+    /// Run once per value (key=a,b,c); repeat for every combination of several vars
+    #[arg(
+        long,
+        value_name = "KEY=A,B,...",
+        value_parser = matrix::parse_axis,
+        conflicts_with_all = [
+            "dry_run",
+            "resume",
+            "continue_run",
+            "output",
+            "timestamped",
+            "run_id",
+            "profile"
+        ]
+    )]
+    matrix: Vec<(String, Vec<String>)>,
+    /// Partial workflow layered on top (vars and step fields by id); repeatable, applied in order
+    #[arg(long, value_name = "PATH")]
+    overlay: Vec<PathBuf>,
     #[arg(long)]
     dry_run: bool,
+    /// Write the workflow with vars applied and templates rendered to PATH
+    /// instead of running it
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["dry_run", "matrix", "continue_run", "only_services"]
+    )]
+    render_only: Option<PathBuf>,
+    /// Let at most N steps of a kind into each batch (kind=N); repeatable
+    #[arg(long, value_name = "KIND=N", value_parser = run::parse_kind_limit)]
+    limit: Vec<(StepKind, usize)>,
+    /// With --dry-run, also health-check the services the workflow needs
+    #[arg(long, requires = "dry_run")]
+    check_services: bool,
+    /// Before running, create every output's parent dir and fail if any isn't writable
+    #[arg(long)]
+    check_outputs: bool,
+    /// Health-check the services first and block only the steps whose service is down
+    #[arg(long, conflicts_with = "dry_run")]
+    only_services: bool,
+    /// With --dry-run, print each LLM request's provider and rendered prompts
+    /// (no model is called)
+    #[arg(long, requires = "dry_run")]
+    explain_llm: bool,
     #[arg(long)]
     resume: bool,
+    /// Re-run only the steps that failed or were blocked in the last run.json
+    #[arg(long = "continue")]
+    continue_run: bool,
+    /// Keep task state in state.json: a failed step stalls only its dependents
+    /// and the next run picks up where this one stopped (see `vwf status`)
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "dry_run",
+            "matrix",
+            "resume",
+            "continue_run",
+            "render_only",
+            "only_services",
+            "limit",
+            "check_outputs",
+            "output",
+            "profile",
+            "timestamped",
+            "max_runtime",
+            "run_id",
+            "deterministic"
+        ]
+    )]
+    incremental: bool,
+    /// Refuse to run if a var is unused or a step references an undefined var
+    #[arg(long)]
+    strict_vars: bool,
     #[arg(long = "allow", num_args = 0..)]
     allow: Vec<String>,
+    /// Without --allow, only permit the bundled media tools (ffmpeg, ffprobe,
+    /// convert, magick, python3, whisper-cli) instead of any program
+    #[arg(long)]
+    safe: bool,
+    /// Show run_command output live instead of only capturing it
+    #[arg(long)]
+    stream_output: bool,
     #[arg(long)]
     mock_llm_canned: Option<String>,
     /// Ollama model for LLM generation (e.g., "qwen2.5-coder:14b", "gemma2:9b")
     #[arg(long)]
     llm_model: Option<String>,
+    /// Retry failed LLM requests this many times, doubling the delay from 1s
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    llm_retries: u32,
+    /// Manifest path, absolute or relative to workdir (default: run.json)
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// Write step timings as Chrome trace JSON (absolute or relative to workdir)
+    #[arg(long, value_name = "PATH")]
+    profile: Option<PathBuf>,
+    /// Name the default manifest `run-<run_id>.json` so runs don't clobber each other
+    #[arg(long)]
+    timestamped: bool,
+    /// Stop starting new steps after this many seconds (remaining steps are blocked)
+    #[arg(long, visible_alias = "timeout", value_name = "SECS")]
+    max_runtime: Option<u64>,
+    /// Use this run id instead of a random one
+    #[arg(long, value_name = "UUID", conflicts_with = "deterministic")]
+    run_id: Option<vwf_core::Uuid>,
+    /// Derive the run id from the workflow and vars so identical inputs give identical reports
+    #[arg(long)]
+    deterministic: bool,
+    /// Step progress display
+    #[arg(long, value_enum, default_value_t = progress::ProgressMode::Plain)]
+    progress: progress::ProgressMode,
+    /// Run `confirm: true` steps without asking
+    #[arg(long, short = 'y', conflicts_with = "interactive")]
+    assume_yes: bool,
+    /// Ask before `confirm: true` steps even when stdin isn't a terminal
+    #[arg(long)]
+    interactive: bool,
 }
 
 fn version_string() -> &'static str {
@@ -102,26 +280,52 @@ fn parse_kv(s: &str) -> Result<(String, String), String> {
         .ok_or_else(|| "expected key=value".into())
 }
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
     tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::from_default_env().add_directive("info".parse().unwrap()))
+        .with_ansi(color::enabled(cli.no_color))
         .init();
-    let cli = Cli::parse();
-    match cli.cmd {
+    let result = match cli.cmd {
         Cmd::Show { workflow } => run::show(&workflow),
-        Cmd::Run(args) => run::execute(
-            &args.workflow,
-            &args.workdir,
-            args.vars,
-            args.dry_run,
-            args.resume,
-            args.allow,
-            args.mock_llm_canned,
-            args.llm_model,
-        ),
+        Cmd::Validate(args) => {
+            let fix = args.fix.then_some(validate::FixOptions {
+                model: args.model,
+                write: args.write,
+            });
+            validate::validate(&args.workflow, fix.as_ref(), args.strict_vars)
+        }
+        Cmd::Init { dir, force } => init::init(&dir, force),
+        Cmd::Run(args) => return run::execute(*args, color::enabled(cli.no_color)),
         Cmd::Generate(args) => {
-            generate::generate(&args.project_dir, &args.model, args.context_dir.as_deref())
+            let naming = generate::Naming::new(&args.naming)?;
+            generate::generate(
+                &args.project_dir,
+                &args.model,
+                args.context_dir.as_deref(),
+                &naming,
+            )
+        }
+        Cmd::Services { workflow } => {
+            services::check_services(&workflow, color::enabled(cli.no_color))
+        }
+        Cmd::Doctor => doctor::doctor(color::enabled(cli.no_color)),
+        Cmd::Status { workdir } => status::show(&workdir),
+        Cmd::Reset { workdir } => status::reset(&workdir),
+        Cmd::Steps { describe } => match describe {
+            Some(kind) => steps::describe(&kind).map(|text| print!("{text}")),
+            None => {
+                print!("{}", steps::list());
+                Ok(())
+            }
+        },
+        Cmd::Schema => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&vwf_core::workflow_schema())?
+            );
+            Ok(())
         }
-        Cmd::Services { workflow } => services::check_services(&workflow),
-    }
+    };
+    result.map(|()| ExitCode::SUCCESS)
 }
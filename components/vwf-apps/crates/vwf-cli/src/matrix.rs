@@ -0,0 +1,200 @@
+//! `vwf run --matrix`: one run per combination of var values.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use serde_json::json;
+
+use vwf_core::{FailedRun, RunReport, WorkflowConfig};
+
+use crate::run::{with_workflow_suffix, write_manifest};
+
+/// `output_dir` used when neither the vars nor the workflow set one.
+const DEFAULT_OUTPUT_DIR: &str = "output";
+
+/// Parse `key=a,b,c` into a var name and its values.
+pub fn parse_axis(s: &str) -> Result<(String, Vec<String>), String> {
+    let (key, values) = s
+        .split_once('=')
+        .ok_or_else(|| "expected key=value1,value2,...".to_string())?;
+    let values: Vec<String> = values.split(',').map(String::from).collect();
+    if key.is_empty() || values.iter().any(String::is_empty) {
+        return Err("expected key=value1,value2,... with no empty values".into());
+    }
+    Ok((key.to_string(), values))
+}
+
+/// Every combination of the axes' values, the first axis varying slowest.
+fn expand(axes: &[(String, Vec<String>)]) -> Vec<BTreeMap<String, String>> {
+    let mut variants = vec![BTreeMap::new()];
+    for (key, values) in axes {
+        variants = variants
+            .into_iter()
+            .flat_map(|vars| {
+                values.iter().map(move |v| {
+                    let mut vars = vars.clone();
+                    vars.insert(key.clone(), v.clone());
+                    vars
+                })
+            })
+            .collect();
+    }
+    variants
+}
+
+/// Directory-safe name for a variant, e.g. `prompt-sunset_seed-7`.
+fn variant_name(vars: &BTreeMap<String, String>) -> String {
+    vars.iter()
+        .map(|(k, v)| format!("{k}-{v}"))
+        .collect::<Vec<_>>()
+        .join("_")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Run the pipeline once per variant with `run_one`, one after another.
+///
+/// Each variant's `output_dir` is `<output_dir>/<variant>`, which also
+/// receives its manifest; `matrix.json` in the workdir summarizes them all.
+/// A failed variant doesn't stop the others. Returns the reports of every
+/// variant if all of them completed.
+pub fn run(
+    workdir: &Path,
+    pipeline: &[WorkflowConfig],
+    base_vars: BTreeMap<String, String>,
+    axes: &[(String, Vec<String>)],
+    mut run_one: impl FnMut(BTreeMap<String, String>) -> Result<Vec<RunReport>>,
+) -> Result<Vec<RunReport>> {
+    let output_root = base_vars
+        .get("output_dir")
+        .or_else(|| pipeline.first()?.vars.get("output_dir"))
+        .map_or(DEFAULT_OUTPUT_DIR, String::as_str)
+        .to_string();
+    let variants = expand(axes);
+    let mut summary = vec![];
+    let mut all_reports = vec![];
+    let mut failed = 0;
+    for (i, variant) in variants.iter().enumerate() {
+        let name = variant_name(variant);
+        let output_dir = format!("{output_root}/{name}");
+        eprintln!("=== variant {}/{}: {name} ===", i + 1, variants.len());
+        let mut vars = base_vars.clone();
+        vars.extend(variant.clone());
+        vars.insert("output_dir".into(), output_dir.clone());
+
+        let (reports, error) = match run_one(vars) {
+            Ok(reps) => (reps, None),
+            Err(e) => match e.downcast_ref::<FailedRun>() {
+                Some(FailedRun(rep)) => (vec![rep.clone()], Some("run failed".to_string())),
+                None => (vec![], Some(format!("{e:#}"))),
+            },
+        };
+        let mut manifests = vec![];
+        for rep in &reports {
+            let mut path = workdir.join(&output_dir).join("run.json");
+            if pipeline.len() > 1 {
+                path = with_workflow_suffix(&path, &rep.workflow_name);
+            }
+            write_manifest(&path, rep)?;
+            manifests.push(path.strip_prefix(workdir).unwrap_or(&path).to_path_buf());
+        }
+        if error.is_some() {
+            failed += 1;
+        }
+        summary.push(json!({
+            "name": name,
+            "vars": variant,
+            "output_dir": output_dir,
+            "status": if error.is_some() { "failed" } else { "ok" },
+            "error": error,
+            "manifests": manifests,
+        }));
+        all_reports.extend(reports);
+    }
+    let path = workdir.join("matrix.json");
+    std::fs::write(
+        &path,
+        serde_json::to_vec_pretty(&json!({ "variants": summary }))?,
+    )?;
+    println!("Wrote {}", path.display());
+    if failed > 0 {
+        bail!(
+            "{failed} of {} matrix variant(s) failed; see {}",
+            variants.len(),
+            path.display()
+        );
+    }
+    Ok(all_reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vwf_core::{FsRuntime, MockLlmClient, RunOptions, Runner};
+
+    #[test]
+    fn axes_expand_to_every_combination() {
+        let axes = [
+            parse_axis("seed=1,2").unwrap(),
+            parse_axis("prompt=sunset").unwrap(),
+        ];
+        let names: Vec<String> = expand(&axes).iter().map(variant_name).collect();
+        assert_eq!(names, ["prompt-sunset_seed-1", "prompt-sunset_seed-2"]);
+        assert!(parse_axis("seed").is_err());
+        assert!(parse_axis("seed=1,,2").is_err());
+        assert_eq!(
+            variant_name(&BTreeMap::from([("p".into(), "a b/c".into())])),
+            "p-a_b_c"
+        );
+    }
+
+    #[test]
+    fn each_variant_gets_its_vars_and_output_dir() {
+        let workdir = std::env::temp_dir().join(format!("vwf_matrix_{}", std::process::id()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        let pipeline = WorkflowConfig::pipeline_from_yaml(
+            r#"
+version: 1
+name: thumbs
+steps:
+  - id: thumb
+    kind: write_file
+    deliverable: true
+    path: "work/thumb.txt"
+    content: "seed {{seed}}"
+"#,
+        )
+        .unwrap();
+        let axes = [parse_axis("seed=1,2").unwrap()];
+        run(&workdir, &pipeline, BTreeMap::new(), &axes, |vars| {
+            let mut rt = FsRuntime::new(&workdir, Box::new(MockLlmClient::echo()));
+            Runner::run_pipeline(&mut rt, &pipeline, vars, RunOptions::default())
+        })
+        .unwrap();
+
+        for seed in ["1", "2"] {
+            let dir = workdir.join(format!("output/seed-{seed}"));
+            let thumb = std::fs::read_to_string(dir.join("thumb.txt")).unwrap();
+            assert_eq!(thumb, format!("seed {seed}"));
+            let manifest = std::fs::read_to_string(dir.join("run.json")).unwrap();
+            let rep: RunReport = serde_json::from_str(&manifest).unwrap();
+            assert_eq!(rep.vars["seed"], seed);
+        }
+        let summary: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(workdir.join("matrix.json")).unwrap()).unwrap();
+        let variants = summary["variants"].as_array().unwrap();
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[1]["vars"], json!({ "seed": "2" }));
+        assert_eq!(variants[1]["output_dir"], "output/seed-2");
+        assert_eq!(variants[1]["status"], "ok");
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+}
@@ -0,0 +1,74 @@
+//! Single-line progress display for `vwf run --progress bar`.
+
+use std::io::{IsTerminal, Write};
+
+use clap::ValueEnum;
+use vwf_core::{EventCallback, Progress};
+
+const BAR_WIDTH: usize = 24;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ProgressMode {
+    /// One log line per step event.
+    #[default]
+    Plain,
+    /// Redrawn progress bar (falls back to plain when stdout isn't a TTY).
+    Bar,
+}
+
+/// Callback that redraws the bar on stderr, or `None` for plain logs.
+pub fn callback(mode: ProgressMode) -> Option<EventCallback> {
+    if mode == ProgressMode::Plain || !std::io::stdout().is_terminal() {
+        return None;
+    }
+    Some(Box::new(|_, progress| {
+        let mut err = std::io::stderr();
+        let _ = write!(err, "\r\x1b[2K{}", render(progress));
+        if progress.completed == progress.total {
+            let _ = writeln!(err);
+        }
+        let _ = err.flush();
+    }))
+}
+
+fn render(p: &Progress) -> String {
+    let filled = (p.completed * BAR_WIDTH)
+        .checked_div(p.total)
+        .unwrap_or(BAR_WIDTH);
+    let mut line = format!(
+        "[{}{}] {}/{} steps",
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        p.completed,
+        p.total
+    );
+    if p.failed > 0 {
+        line.push_str(&format!(", {} failed", p.failed));
+    }
+    if !p.running.is_empty() {
+        line.push_str(&format!(" | running: {}", p.running.join(", ")));
+    }
+    line.push_str(&format!(" | {}s", p.elapsed.as_secs()));
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn render_shows_counts_running_and_elapsed() {
+        let p = Progress {
+            total: 4,
+            completed: 2,
+            failed: 1,
+            running: vec!["tts".into()],
+            elapsed: Duration::from_secs(12),
+        };
+        assert_eq!(
+            render(&p),
+            "[############------------] 2/4 steps, 1 failed | running: tts | 12s"
+        );
+    }
+}
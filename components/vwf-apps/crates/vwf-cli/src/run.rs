@@ -1,15 +1,20 @@
 //! Workflow execution commands.
 
 use std::collections::{BTreeMap, BTreeSet};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 
 use vwf_core::{
-    DryRunRuntime, FsRuntime, LlmClient, MockLlmClient, OllamaClient, RunOptions, RunReport,
-    Runner, WorkflowConfig,
+    CompletedRuns, DryRunRuntime, ExplainingLlmClient, FailedRun, FsRuntime, LlmClient,
+    MockLlmClient, OllamaClient, RetryingLlmClient, RunOptions, RunReport, Runner, SAFE_COMMANDS,
+    SchedulerEvent, StepKind, StepStatus, WorkflowConfig, check_vars, chrome_trace,
 };
 
+use crate::{RunArgs, confirm, matrix, progress, services};
+
 pub fn show(workflow: &Path) -> Result<()> {
     let text = std::fs::read_to_string(workflow)
         .with_context(|| format!("read {}", workflow.display()))?;
@@ -18,73 +23,791 @@ pub fn show(workflow: &Path) -> Result<()> {
     Ok(())
 }
 
-#[allow(clippy::too_many_arguments)]
-pub fn execute(
-    workflow: &Path,
-    workdir: &Path,
-    vars: Vec<(String, String)>,
-    dry_run: bool,
-    resume: bool,
-    allow: Vec<String>,
-    mock_llm: Option<String>,
-    llm_model: Option<String>,
+/// Read a JSON/YAML mapping of vars, flattening nested keys with dots.
+fn load_var_file(path: &Path) -> Result<BTreeMap<String, String>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+    let value: serde_yaml::Value = serde_yaml::from_str(&text)
+        .with_context(|| format!("parse var file {}", path.display()))?;
+    let serde_yaml::Value::Mapping(map) = value else {
+        anyhow::bail!("var file {} must be a mapping of vars", path.display());
+    };
+    let mut vars = BTreeMap::new();
+    flatten_vars("", &map, &mut vars)?;
+    Ok(vars)
+}
+
+fn flatten_vars(
+    prefix: &str,
+    map: &serde_yaml::Mapping,
+    out: &mut BTreeMap<String, String>,
 ) -> Result<()> {
-    let text = std::fs::read_to_string(workflow)
-        .with_context(|| format!("read {}", workflow.display()))?;
-    let cfg = WorkflowConfig::from_yaml(&text)?;
-    let extra_vars: BTreeMap<_, _> = vars.into_iter().collect();
-    let llm: Box<dyn LlmClient> = match (mock_llm, llm_model) {
-        (Some(s), _) => Box::new(MockLlmClient::canned(s)),
-        (None, Some(model)) => Box::new(OllamaClient::new(model)),
-        (None, None) => Box::new(MockLlmClient::echo()),
+    for (k, v) in map {
+        let key = match k {
+            serde_yaml::Value::String(s) => s.clone(),
+            other => serde_yaml::to_string(other)?.trim().to_string(),
+        };
+        let key = if prefix.is_empty() {
+            key
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match v {
+            serde_yaml::Value::Mapping(inner) => flatten_vars(&key, inner, out)?,
+            serde_yaml::Value::String(s) => {
+                out.insert(key, s.clone());
+            }
+            serde_yaml::Value::Null => {
+                out.insert(key, String::new());
+            }
+            other => {
+                out.insert(key, serde_yaml::to_string(other)?.trim().to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Var-file values overlaid by explicit `--var` pairs.
+fn merge_vars(
+    file_vars: BTreeMap<String, String>,
+    cli_vars: Vec<(String, String)>,
+) -> BTreeMap<String, String> {
+    let mut vars = file_vars;
+    vars.extend(cli_vars);
+    vars
+}
+
+/// The workflow with each `--overlay` file layered on, in order.
+fn apply_overlays(
+    mut pipeline: Vec<WorkflowConfig>,
+    overlays: &[PathBuf],
+) -> Result<Vec<WorkflowConfig>> {
+    if overlays.is_empty() {
+        return Ok(pipeline);
+    }
+    if pipeline.len() > 1 {
+        anyhow::bail!("--overlay only works with single-workflow files");
+    }
+    for path in overlays {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        let overlay: serde_json::Value = serde_yaml::from_str(&text)
+            .with_context(|| format!("parse overlay {}", path.display()))?;
+        pipeline[0] = WorkflowConfig::merge(&pipeline[0], &overlay)
+            .with_context(|| format!("apply overlay {}", path.display()))?;
+    }
+    Ok(pipeline)
+}
+
+/// Parse `kind=N` into a step kind and how many of its steps may share a batch.
+pub fn parse_kind_limit(s: &str) -> Result<(StepKind, usize), String> {
+    let (kind, limit) = s
+        .split_once('=')
+        .ok_or_else(|| "expected kind=N, e.g. text_to_image=1".to_string())?;
+    let kind: StepKind = serde_json::from_value(serde_json::json!(kind))
+        .map_err(|_| format!("unknown step kind `{kind}`"))?;
+    match limit.parse() {
+        Ok(n) if n > 0 => Ok((kind, n)),
+        _ => Err(format!("limit must be a positive integer, got `{limit}`")),
+    }
+}
+
+/// Exit status of a run that completed but had `continue_on_error` steps fail.
+pub const EXIT_TOLERATED: u8 = 2;
+
+/// Exit status for runs that completed: 0, or [`EXIT_TOLERATED`] if any
+/// step failed (which the engine only lets through for `continue_on_error`).
+fn exit_code(reports: &[RunReport]) -> ExitCode {
+    let tolerated = reports
+        .iter()
+        .flat_map(|rep| &rep.steps)
+        .filter(|step| step.status == StepStatus::Failed)
+        .count();
+    if tolerated == 0 {
+        return ExitCode::SUCCESS;
+    }
+    eprintln!("Completed with {tolerated} tolerated failure(s)");
+    ExitCode::from(EXIT_TOLERATED)
+}
+
+/// Run a workflow; hard failures are errors (exit 1).
+pub fn execute(args: RunArgs, color: bool) -> Result<ExitCode> {
+    let text = std::fs::read_to_string(&args.workflow)
+        .with_context(|| format!("read {}", args.workflow.display()))?;
+    let pipeline = apply_overlays(WorkflowConfig::pipeline_from_yaml(&text)?, &args.overlay)?;
+    let file_vars = match &args.var_file {
+        Some(path) => load_var_file(path)?,
+        None => BTreeMap::new(),
     };
-    let opts = RunOptions { resume };
-    if dry_run {
-        run_dry(workdir, llm, &cfg, extra_vars)
+    let extra_vars = merge_vars(file_vars, args.vars);
+    if args.strict_vars {
+        let problems = check_vars(&pipeline, &extra_vars).problems();
+        for p in &problems {
+            eprintln!("ERROR: {p}");
+        }
+        if !problems.is_empty() {
+            anyhow::bail!("{} var problem(s) with --strict-vars", problems.len());
+        }
+    }
+    if let Some(path) = &args.render_only {
+        render_only(&pipeline, extra_vars, path)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    let llm_client = || -> Box<dyn LlmClient> {
+        let llm: Box<dyn LlmClient> = match (&args.mock_llm_canned, &args.llm_model) {
+            (Some(s), _) => Box::new(MockLlmClient::canned(s.clone())),
+            (None, Some(model)) => Box::new(OllamaClient::new(model.clone())),
+            (None, None) => Box::new(MockLlmClient::echo()),
+        };
+        match args.llm_retries {
+            0 => llm,
+            n => Box::new(RetryingLlmClient::new(llm, n)),
+        }
+    };
+    if args.incremental {
+        run_incremental(
+            &args.workdir,
+            llm_client(),
+            &pipeline,
+            extra_vars,
+            command_allowlist(&args.allow, args.safe),
+            args.stream_output,
+        )?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    let unavailable_kinds = if args.only_services {
+        let mut probe = services::http_probe()?;
+        let statuses = services::preflight(&pipeline, &mut probe);
+        services::print_preflight(&statuses, color);
+        services::down_kinds(&statuses)
     } else {
-        run_real(workdir, llm, &cfg, extra_vars, allow, opts)
+        BTreeMap::new()
+    };
+    let kind_limits: BTreeMap<StepKind, usize> = args.limit.iter().cloned().collect();
+    if !args.matrix.is_empty() {
+        return matrix::run(&args.workdir, &pipeline, extra_vars, &args.matrix, |vars| {
+            let on_event = progress::callback(args.progress);
+            let opts = RunOptions {
+                resume: args.resume,
+                deadline: args.max_runtime.map(Duration::from_secs),
+                quiet: on_event.is_some(),
+                on_event,
+                deterministic: args.deterministic,
+                confirm: confirm::callback(args.assume_yes, args.interactive),
+                unavailable_kinds: unavailable_kinds.clone(),
+                kind_limits: kind_limits.clone(),
+                check_outputs: args.check_outputs,
+                ..Default::default()
+            };
+            run_real(
+                &args.workdir,
+                llm_client(),
+                &pipeline,
+                vars,
+                command_allowlist(&args.allow, args.safe),
+                args.stream_output,
+                opts,
+            )
+        })
+        .map(|reps| exit_code(&reps));
     }
+    let llm = llm_client();
+    let on_event = progress::callback(args.progress);
+    let opts = RunOptions {
+        resume: args.resume,
+        deadline: args.max_runtime.map(Duration::from_secs),
+        quiet: on_event.is_some(),
+        on_event,
+        run_id: args.run_id,
+        deterministic: args.deterministic,
+        previous: if args.continue_run {
+            previous_reports(
+                &args.workdir,
+                args.output.as_deref(),
+                args.timestamped,
+                &pipeline,
+            )?
+        } else {
+            vec![]
+        },
+        confirm: confirm::callback(args.assume_yes, args.interactive),
+        unavailable_kinds,
+        kind_limits: kind_limits.clone(),
+        check_outputs: args.check_outputs,
+    };
+    if args.dry_run {
+        let dry_opts = RunOptions {
+            run_id: args.run_id,
+            deterministic: args.deterministic,
+            kind_limits,
+            check_outputs: args.check_outputs,
+            ..Default::default()
+        };
+        let llm: Box<dyn LlmClient> = if args.explain_llm {
+            // Only show the requests; --llm-model would otherwise really call Ollama
+            let mock = match &args.mock_llm_canned {
+                Some(s) => MockLlmClient::canned(s.clone()),
+                None => MockLlmClient::echo(),
+            };
+            Box::new(ExplainingLlmClient::new(Box::new(mock)))
+        } else {
+            llm
+        };
+        let reps = run_dry(&args.workdir, llm, &pipeline, extra_vars, dry_opts)?;
+        if args.check_services {
+            let mut probe = services::http_probe()?;
+            let statuses = services::preflight(&pipeline, &mut probe);
+            services::report_preflight(&statuses, color)?;
+        }
+        return Ok(exit_code(&reps));
+    }
+    let result = run_real(
+        &args.workdir,
+        llm,
+        &pipeline,
+        extra_vars,
+        command_allowlist(&args.allow, args.safe),
+        args.stream_output,
+        opts,
+    );
+    let save = |rep: &RunReport| {
+        let mut path = manifest_path(&args.workdir, args.output.as_deref(), args.timestamped, rep);
+        if pipeline.len() > 1 && !args.timestamped {
+            path = with_workflow_suffix(&path, &rep.workflow_name);
+        }
+        write_manifest(&path, rep)
+    };
+    let profile = |reps: &[RunReport]| match &args.profile {
+        Some(path) => write_profile(&args.workdir.join(path), reps),
+        None => Ok(()),
+    };
+    match result {
+        Ok(reps) => {
+            reps.iter().try_for_each(save)?;
+            profile(&reps)?;
+            Ok(exit_code(&reps))
+        }
+        Err(e) => {
+            // Keep every manifest so `--continue` skips the workflows that
+            // completed and resumes the failed one.
+            let mut reps = match e.downcast_ref::<CompletedRuns>() {
+                Some(CompletedRuns(done)) => done.clone(),
+                None => vec![],
+            };
+            if let Some(FailedRun(rep)) = e.downcast_ref::<FailedRun>() {
+                reps.push(rep.clone());
+            }
+            reps.iter().try_for_each(save)?;
+            if !reps.is_empty() {
+                profile(&reps)?;
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Write the resolved pipeline (see [`Runner::resolve`]) as YAML, one
+/// document per workflow, without running anything.
+///
+/// As in a run, vars resolved by earlier workflows carry into later ones.
+fn render_only(
+    pipeline: &[WorkflowConfig],
+    extra: BTreeMap<String, String>,
+    path: &Path,
+) -> Result<()> {
+    let mut carried = extra;
+    let mut docs = vec![];
+    for cfg in pipeline {
+        let resolved = Runner::resolve(cfg, carried)?;
+        docs.push(serde_yaml::to_string(&resolved)?);
+        carried = resolved.vars;
+    }
+    std::fs::write(path, docs.join("---\n"))
+        .with_context(|| format!("write {}", path.display()))?;
+    println!("Resolved workflow written to {}", path.display());
+    Ok(())
+}
+
+/// The manifests `--continue` resumes from, one per workflow that has one.
+///
+/// With `--timestamped` (and no `--output`) that is the newest
+/// `run-<run_id>.json` for each workflow.
+fn previous_reports(
+    workdir: &Path,
+    output: Option<&Path>,
+    timestamped: bool,
+    pipeline: &[WorkflowConfig],
+) -> Result<Vec<RunReport>> {
+    if timestamped && output.is_none() {
+        return latest_timestamped_reports(workdir, pipeline);
+    }
+    let base = workdir.join(output.unwrap_or(Path::new("run.json")));
+    let mut reports = vec![];
+    for cfg in pipeline {
+        let path = if pipeline.len() > 1 {
+            with_workflow_suffix(&base, &cfg.name)
+        } else {
+            base.clone()
+        };
+        if !path.exists() {
+            eprintln!(
+                "No {} to continue; running `{}` in full",
+                path.display(),
+                cfg.name
+            );
+            continue;
+        }
+        let text =
+            std::fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+        let report = serde_json::from_str(&text)
+            .with_context(|| format!("parse {} for --continue", path.display()))?;
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+/// The newest `run-*.json` in `workdir` for each workflow in `pipeline`.
+fn latest_timestamped_reports(
+    workdir: &Path,
+    pipeline: &[WorkflowConfig],
+) -> Result<Vec<RunReport>> {
+    let mut latest: BTreeMap<String, RunReport> = BTreeMap::new();
+    let entries = std::fs::read_dir(workdir).into_iter().flatten().flatten();
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !(name.starts_with("run-") && name.ends_with(".json")) {
+            continue;
+        }
+        let path = entry.path();
+        let text =
+            std::fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+        // Other files may share the prefix; only run reports count.
+        let Ok(report) = serde_json::from_str::<RunReport>(&text) else {
+            continue;
+        };
+        let newer = latest
+            .get(&report.workflow_name)
+            .is_none_or(|r| r.started_at < report.started_at);
+        if newer {
+            latest.insert(report.workflow_name.clone(), report);
+        }
+    }
+    let mut reports = vec![];
+    for cfg in pipeline {
+        match latest.remove(&cfg.name) {
+            Some(report) => reports.push(report),
+            None => eprintln!(
+                "No run-*.json for `{}` to continue; running it in full",
+                cfg.name
+            ),
+        }
+    }
+    Ok(reports)
 }
 
 fn run_dry(
     workdir: &Path,
     llm: Box<dyn LlmClient>,
-    cfg: &WorkflowConfig,
+    pipeline: &[WorkflowConfig],
     vars: BTreeMap<String, String>,
-) -> Result<()> {
+    opts: RunOptions,
+) -> Result<Vec<RunReport>> {
     let mut rt = DryRunRuntime::new(workdir, llm);
-    let rep = Runner::run(&mut rt, cfg, vars)?;
-    println!("{}", serde_json::to_string_pretty(&rep)?);
-    println!("--- planned dirs ---\n{}", rt.planned_dirs.join("\n"));
+    let reps = Runner::run_pipeline(&mut rt, pipeline, vars, opts)?;
+    for rep in &reps {
+        println!("{}", serde_json::to_string_pretty(rep)?);
+    }
     println!(
-        "--- planned writes ---\n{}",
-        rt.planned_writes
-            .iter()
-            .map(|(p, _)| p.as_str())
-            .collect::<Vec<_>>()
-            .join("\n")
+        "--- plan ---\n{}",
+        serde_json::to_string_pretty(&rt.plan())?
     );
-    Ok(())
+    Ok(reps)
 }
 
 fn run_real(
     workdir: &Path,
     llm: Box<dyn LlmClient>,
-    cfg: &WorkflowConfig,
+    pipeline: &[WorkflowConfig],
     vars: BTreeMap<String, String>,
-    allow: Vec<String>,
+    allow: BTreeSet<String>,
+    stream_output: bool,
     opts: RunOptions,
+) -> Result<Vec<RunReport>> {
+    std::fs::create_dir_all(workdir).with_context(|| format!("create {}", workdir.display()))?;
+    let mut rt = FsRuntime::new(workdir, llm);
+    rt.command_allowlist = allow;
+    rt.stream_output = stream_output;
+    Runner::run_pipeline(&mut rt, pipeline, vars, opts)
+}
+
+/// Run a single workflow with `--incremental`, logging each task, and fail
+/// if it stopped short of completing.
+fn run_incremental(
+    workdir: &Path,
+    llm: Box<dyn LlmClient>,
+    pipeline: &[WorkflowConfig],
+    vars: BTreeMap<String, String>,
+    allow: BTreeSet<String>,
+    stream_output: bool,
 ) -> Result<()> {
+    let [cfg] = pipeline else {
+        anyhow::bail!("--incremental only works with single-workflow files");
+    };
     std::fs::create_dir_all(workdir).with_context(|| format!("create {}", workdir.display()))?;
     let mut rt = FsRuntime::new(workdir, llm);
-    rt.command_allowlist = allow.into_iter().collect::<BTreeSet<_>>();
-    let rep = Runner::run_with_options(&mut rt, cfg, vars, opts)?;
-    write_manifest(workdir, &rep)
+    rt.command_allowlist = allow;
+    rt.stream_output = stream_output;
+    let mut blocked = None;
+    vwf_core::run_incremental(&mut rt, cfg, vars, workdir, |event| match event {
+        SchedulerEvent::TaskStarted { task_id } => eprintln!("[{task_id}] started"),
+        SchedulerEvent::TaskComplete { task_id } => eprintln!("[{task_id}] done"),
+        SchedulerEvent::TaskFailed { task_id, error } => eprintln!("[{task_id}] FAILED: {error}"),
+        SchedulerEvent::WorkflowBlocked { reason } => blocked = Some(reason),
+        _ => {}
+    })?;
+    if let Some(reason) = blocked {
+        anyhow::bail!(
+            "workflow did not complete ({reason}); see `vwf status {}`",
+            workdir.display()
+        );
+    }
+    Ok(())
+}
+
+/// Programs run_command may start; empty means any.
+///
+/// `--safe` swaps an empty `--allow` list for [`SAFE_COMMANDS`] rather than
+/// allowing everything.
+fn command_allowlist(allow: &[String], safe: bool) -> BTreeSet<String> {
+    if safe && allow.is_empty() {
+        SAFE_COMMANDS.iter().map(|c| c.to_string()).collect()
+    } else {
+        allow.iter().cloned().collect()
+    }
+}
+
+/// Resolve where the run manifest goes (absolute or workdir-relative).
+fn manifest_path(
+    workdir: &Path,
+    output: Option<&Path>,
+    timestamped: bool,
+    rep: &RunReport,
+) -> PathBuf {
+    match output {
+        Some(p) => workdir.join(p),
+        None if timestamped => workdir.join(format!("run-{}.json", rep.run_id)),
+        None => workdir.join("run.json"),
+    }
+}
+
+/// `run.json` -> `run-<workflow>.json`, so pipeline manifests don't collide.
+pub(crate) fn with_workflow_suffix(path: &Path, workflow: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}-{workflow}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{workflow}"),
+    };
+    path.with_file_name(name)
 }
 
-fn write_manifest(workdir: &Path, rep: &RunReport) -> Result<()> {
-    let path = workdir.join("run.json");
-    std::fs::write(&path, serde_json::to_vec_pretty(rep)?)?;
+pub(crate) fn write_manifest(path: &Path, rep: &RunReport) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_vec_pretty(rep)?)
+        .with_context(|| format!("write {}", path.display()))?;
     println!("Wrote {}", path.display());
     Ok(())
 }
+
+/// Write step spans in Chrome's trace format for chrome://tracing or Perfetto.
+fn write_profile(path: &Path, reps: &[RunReport]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_vec(&chrome_trace(reps))?)
+        .with_context(|| format!("write {}", path.display()))?;
+    println!("Wrote profile {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vwf_core::MockLlmClient;
+
+    #[test]
+    fn kind_limit_parses_kind_and_count() {
+        assert_eq!(
+            parse_kind_limit("text_to_image=1"),
+            Ok((StepKind::TextToImage, 1))
+        );
+        assert!(parse_kind_limit("text_to_image").is_err());
+        assert!(
+            parse_kind_limit("teleport=1")
+                .unwrap_err()
+                .contains("unknown")
+        );
+        assert!(parse_kind_limit("tts_generate=0").is_err());
+    }
+
+    fn report(workdir: &Path) -> RunReport {
+        let cfg = WorkflowConfig::from_yaml(
+            "version: 1\nname: t\nsteps:\n  - id: d\n    kind: ensure_dirs\n    dirs: [\"a\"]\n",
+        )
+        .unwrap();
+        let mut rt = FsRuntime::new(workdir, Box::new(MockLlmClient::echo()));
+        Runner::run(&mut rt, &cfg, BTreeMap::new()).unwrap()
+    }
+
+    #[test]
+    fn safe_mode_only_allows_the_media_tools() {
+        use vwf_core::Runtime;
+        let not_allowed = |rt: &mut FsRuntime, prog: &str| match rt.run_command(prog, &[], None) {
+            Ok(_) => false,
+            Err(e) => e.to_string().contains("Command not allowed"),
+        };
+        let mut rt = FsRuntime::new(std::env::temp_dir(), Box::new(MockLlmClient::echo()));
+        rt.command_allowlist = command_allowlist(&[], true);
+        assert!(not_allowed(&mut rt, "curl"));
+        // ffmpeg may not be installed here; it just mustn't be refused.
+        assert!(!not_allowed(&mut rt, "ffmpeg"));
+
+        rt.command_allowlist = command_allowlist(&["curl".into()], true);
+        assert!(!not_allowed(&mut rt, "curl"));
+        assert!(not_allowed(&mut rt, "ffmpeg"));
+        assert!(command_allowlist(&[], false).is_empty());
+    }
+
+    #[test]
+    fn var_file_is_flattened_and_overridden_by_cli() {
+        let path = std::env::temp_dir().join(format!("vwf_vars_{}.yaml", std::process::id()));
+        std::fs::write(
+            &path,
+            "project: demo\nepisode: 7\nserver:\n  tts: http://a:7860\n  llm: http://b\n",
+        )
+        .unwrap();
+        let file_vars = load_var_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(file_vars["server.tts"], "http://a:7860");
+        assert_eq!(file_vars["episode"], "7");
+
+        let vars = merge_vars(file_vars, vec![("server.tts".into(), "http://c".into())]);
+        assert_eq!(vars["server.tts"], "http://c");
+        assert_eq!(vars["server.llm"], "http://b");
+        assert_eq!(vars["project"], "demo");
+    }
+
+    #[test]
+    fn tolerated_failure_exits_with_its_own_code() {
+        use clap::Parser;
+
+        let dir = std::env::temp_dir().join(format!("vwf_exit_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let workflow = |optional: bool| {
+            let path = dir.join(format!("optional_{optional}.yaml"));
+            std::fs::write(
+                &path,
+                format!(
+                    "version: 1\nname: t\nsteps:\n  - id: note\n    kind: write_file\n    \
+                     path: note.txt\n    content: hi\n  - id: split\n    kind: split_sections\n    \
+                     continue_on_error: {optional}\n    input_path: missing.txt\n    outputs: []\n"
+                ),
+            )
+            .unwrap();
+            let args = [
+                "run",
+                path.to_str().unwrap(),
+                "--workdir",
+                dir.to_str().unwrap(),
+            ];
+            RunArgs::try_parse_from(args).unwrap()
+        };
+
+        let code = execute(workflow(true), false).unwrap();
+        assert_eq!(code, ExitCode::from(EXIT_TOLERATED));
+        assert!(execute(workflow(false), false).is_err());
+
+        let mut matrix = workflow(true);
+        matrix.matrix = vec![("seed".into(), vec!["1".into(), "2".into()])];
+        let code = execute(matrix, false).unwrap();
+        assert_eq!(code, ExitCode::from(EXIT_TOLERATED));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn incremental_run_keeps_state_for_the_next_run() {
+        use clap::Parser;
+
+        let dir = std::env::temp_dir().join(format!("vwf_incremental_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let workflow = dir.join("workflow.yaml");
+        std::fs::write(
+            &workflow,
+            "version: 1\nname: t\nsteps:\n  - id: note\n    kind: write_file\n    \
+             path: note.txt\n    content: hi\n  - id: split\n    kind: split_sections\n    \
+             input_path: missing.txt\n    outputs: []\n",
+        )
+        .unwrap();
+        let args = [
+            "run",
+            workflow.to_str().unwrap(),
+            "--workdir",
+            dir.to_str().unwrap(),
+            "--incremental",
+        ];
+        let err = execute(RunArgs::try_parse_from(args).unwrap(), false).unwrap_err();
+        assert!(err.to_string().contains("cannot run: split"), "{err}");
+
+        let state = vwf_core::StateStore::new(&dir).load().unwrap().unwrap();
+        assert!(state.tasks["note"].is_complete());
+        assert!(
+            RunArgs::try_parse_from([
+                "run",
+                "w.yaml",
+                "--workdir",
+                ".",
+                "--incremental",
+                "--dry-run"
+            ])
+            .is_err()
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn render_only_writes_the_resolved_workflow_without_running() {
+        use clap::Parser;
+
+        let dir = std::env::temp_dir().join(format!("vwf_render_only_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let workflow = dir.join("workflow.yaml");
+        std::fs::write(
+            &workflow,
+            "version: 1\nname: t\nvars:\n  who: world\nsteps:\n  - id: greet\n    \
+             kind: write_file\n    path: \"{{out}}/hello.txt\"\n    content: \"Hello {{who}}\"\n",
+        )
+        .unwrap();
+        let resolved = dir.join("resolved.yaml");
+        let args = [
+            "run",
+            workflow.to_str().unwrap(),
+            "--workdir",
+            dir.to_str().unwrap(),
+            "--var",
+            "out=site",
+            "--render-only",
+            resolved.to_str().unwrap(),
+        ];
+        let code = execute(RunArgs::try_parse_from(args).unwrap(), false).unwrap();
+        assert_eq!(code, ExitCode::SUCCESS);
+
+        let text = std::fs::read_to_string(&resolved).unwrap();
+        assert!(!text.contains("{{"), "{text}");
+        let cfg = WorkflowConfig::from_yaml(&text).unwrap();
+        assert_eq!(cfg.steps[0].payload["path"], "site/hello.txt");
+        assert_eq!(cfg.steps[0].payload["content"], "Hello world");
+        assert!(!dir.join("site").exists(), "nothing should run");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn var_file_accepts_json() {
+        let path = std::env::temp_dir().join(format!("vwf_vars_{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"a": {"b": "c"}, "flag": true}"#).unwrap();
+        let vars = load_var_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(vars["a.b"], "c");
+        assert_eq!(vars["flag"], "true");
+    }
+
+    #[test]
+    fn overlays_apply_in_order() {
+        let base = WorkflowConfig::pipeline_from_yaml(
+            "version: 1\nname: t\nvars:\n  server: dev\n  voice: a\nsteps: []\n",
+        )
+        .unwrap();
+        let dir = std::env::temp_dir().join(format!("vwf_overlay_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("prod.yaml"), "vars:\n  server: prod\n  voice: b\n").unwrap();
+        std::fs::write(dir.join("local.yaml"), "vars:\n  voice: c\n").unwrap();
+        let overlays = [dir.join("prod.yaml"), dir.join("local.yaml")];
+        let merged = apply_overlays(base.clone(), &overlays).unwrap();
+        assert_eq!(merged[0].vars["server"], "prod");
+        assert_eq!(merged[0].vars["voice"], "c");
+
+        let pipeline = [base.clone(), base].concat();
+        let err = apply_overlays(pipeline, &overlays).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "--overlay only works with single-workflow files"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn output_flag_overrides_manifest_path() {
+        let workdir = std::env::temp_dir().join(format!("vwf_manifest_{}", std::process::id()));
+        let rep = report(&workdir);
+        let path = manifest_path(&workdir, Some(Path::new("custom.json")), false, &rep);
+        write_manifest(&path, &rep).unwrap();
+        assert!(workdir.join("custom.json").exists());
+        assert!(!workdir.join("run.json").exists());
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+
+    #[test]
+    fn timestamped_manifest_includes_run_id() {
+        let workdir = std::env::temp_dir().join(format!("vwf_stamped_{}", std::process::id()));
+        let rep = report(&workdir);
+        let path = manifest_path(&workdir, None, true, &rep);
+        assert_eq!(path, workdir.join(format!("run-{}.json", rep.run_id)));
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+
+    #[test]
+    fn pipeline_manifests_are_suffixed_by_workflow() {
+        let path = with_workflow_suffix(Path::new("/w/run.json"), "assemble");
+        assert_eq!(path, PathBuf::from("/w/run-assemble.json"));
+    }
+
+    #[test]
+    fn continue_reads_the_last_manifest() {
+        let dir = std::env::temp_dir().join(format!("vwf_continue_{}", std::process::id()));
+        let rep = report(&dir);
+        write_manifest(&dir.join("run.json"), &rep).unwrap();
+        let cfg = WorkflowConfig::from_yaml(&format!(
+            "version: 1\nname: {}\nsteps: []\n",
+            rep.workflow_name
+        ))
+        .unwrap();
+
+        let previous = previous_reports(&dir, None, false, std::slice::from_ref(&cfg)).unwrap();
+        assert_eq!(previous, std::slice::from_ref(&rep));
+        assert!(
+            previous_reports(
+                &dir,
+                Some(Path::new("none.json")),
+                false,
+                std::slice::from_ref(&cfg)
+            )
+            .unwrap()
+            .is_empty()
+        );
+
+        // --timestamped picks the newest run-<run_id>.json.
+        assert!(
+            previous_reports(&dir, None, true, std::slice::from_ref(&cfg))
+                .unwrap()
+                .is_empty()
+        );
+        let mut newer = report(&dir);
+        newer.started_at = rep.started_at + Duration::from_secs(1);
+        for r in [&rep, &newer] {
+            write_manifest(&manifest_path(&dir, None, true, r), r).unwrap();
+        }
+        let previous = previous_reports(&dir, None, true, &[cfg]).unwrap();
+        assert_eq!(previous, [newer]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,13 +2,15 @@
 //!
 //! Parses a workflow to detect required services and checks their availability.
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::path::Path;
 use std::time::Duration;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 
-use vwf_core::WorkflowConfig;
+use vwf_core::{StepKind, WorkflowConfig};
+
+use crate::color::{self, GREEN, RED};
 
 /// Known service endpoints and their health check URLs.
 #[derive(Debug, Clone)]
@@ -17,7 +19,7 @@ struct ServiceInfo {
     description: &'static str,
     default_url: &'static str,
     health_path: &'static str,
-    step_kinds: &'static [&'static str],
+    step_kinds: &'static [StepKind],
 }
 
 const SERVICES: &[ServiceInfo] = &[
@@ -26,40 +28,140 @@ const SERVICES: &[ServiceInfo] = &[
         description: "Local LLM (text generation & vision audit)",
         default_url: "http://localhost:11434",
         health_path: "/api/tags",
-        step_kinds: &["llm_generate", "llm_audit"],
+        step_kinds: &[StepKind::LlmGenerate, StepKind::LlmAudit],
     },
     ServiceInfo {
         name: "VoxCPM",
         description: "Voice cloning TTS",
         default_url: "http://curiosity:7860",
         health_path: "/api/predict",
-        step_kinds: &["tts_generate"],
+        step_kinds: &[StepKind::TtsGenerate],
     },
     ServiceInfo {
         name: "FLUX.1",
         description: "Text-to-image generation",
         default_url: "http://192.168.1.64:8570",
         health_path: "/system_stats",
-        step_kinds: &["text_to_image"],
+        step_kinds: &[StepKind::TextToImage],
     },
     ServiceInfo {
         name: "SVD-XT",
         description: "Image-to-video animation",
         default_url: "http://192.168.1.64:8100",
         health_path: "/system_stats",
-        step_kinds: &["image_to_video"],
+        step_kinds: &[StepKind::ImageToVideo],
     },
     ServiceInfo {
         name: "Wan 2.2",
         description: "Text-to-video generation",
         default_url: "http://192.168.1.64:6000",
         health_path: "/system_stats",
-        step_kinds: &["text_to_video"],
+        step_kinds: &[StepKind::TextToVideo],
     },
 ];
 
+/// A required service and whether its health check passed.
+#[derive(Debug)]
+pub struct ServiceStatus {
+    pub name: &'static str,
+    pub url: &'static str,
+    pub running: bool,
+}
+
+/// Services needed by any step in the given workflows.
+fn required_services(pipeline: &[WorkflowConfig]) -> Vec<&'static ServiceInfo> {
+    let step_kinds: HashSet<&StepKind> = pipeline
+        .iter()
+        .flat_map(|cfg| &cfg.steps)
+        .map(|s| &s.kind)
+        .collect();
+    SERVICES
+        .iter()
+        .filter(|service| service.step_kinds.iter().any(|k| step_kinds.contains(k)))
+        .collect()
+}
+
+/// Health-check every service the workflows need; `is_up` gets the health URL.
+pub fn preflight(
+    pipeline: &[WorkflowConfig],
+    is_up: &mut dyn FnMut(&str) -> bool,
+) -> Vec<ServiceStatus> {
+    required_services(pipeline)
+        .into_iter()
+        .map(|service| ServiceStatus {
+            name: service.name,
+            url: service.default_url,
+            running: is_up(&format!("{}{}", service.default_url, service.health_path)),
+        })
+        .collect()
+}
+
+/// Health-check every known service at its default URL.
+pub fn check_all(is_up: &mut dyn FnMut(&str) -> bool) -> Vec<ServiceStatus> {
+    SERVICES
+        .iter()
+        .map(|service| ServiceStatus {
+            name: service.name,
+            url: service.default_url,
+            running: is_up(&format!("{}{}", service.default_url, service.health_path)),
+        })
+        .collect()
+}
+
+/// Print a preflight result, failing if any required service is down.
+pub fn report_preflight(statuses: &[ServiceStatus], color: bool) -> Result<()> {
+    print_preflight(statuses, color);
+    let down: Vec<&str> = statuses
+        .iter()
+        .filter(|s| !s.running)
+        .map(|s| s.name)
+        .collect();
+    if !down.is_empty() {
+        bail!(
+            "{} required service(s) unreachable: {}",
+            down.len(),
+            down.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Print a preflight result.
+pub fn print_preflight(statuses: &[ServiceStatus], color: bool) {
+    println!("--- service preflight ---");
+    if statuses.is_empty() {
+        println!("No remote services required.");
+    }
+    for s in statuses {
+        println!("{} {} {}", status_label(s.running, color), s.name, s.url);
+    }
+}
+
+/// Step kinds served by a service that is down, with the reason to report.
+pub fn down_kinds(statuses: &[ServiceStatus]) -> BTreeMap<StepKind, String> {
+    statuses
+        .iter()
+        .filter(|s| !s.running)
+        .filter_map(|s| SERVICES.iter().find(|info| info.name == s.name))
+        .flat_map(|info| {
+            let reason = format!("service down: {} ({})", info.name, info.default_url);
+            info.step_kinds
+                .iter()
+                .map(move |kind| (kind.clone(), reason.clone()))
+        })
+        .collect()
+}
+
+/// Probe that GETs health URLs over HTTP with a short timeout.
+pub fn http_probe() -> Result<impl FnMut(&str) -> bool> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()?;
+    Ok(move |url: &str| check_service_health(&client, url))
+}
+
 /// Check service availability for a workflow.
-pub fn check_services(workflow_path: &Path) -> Result<()> {
+pub fn check_services(workflow_path: &Path, color: bool) -> Result<()> {
     let text = std::fs::read_to_string(workflow_path)
         .with_context(|| format!("read {}", workflow_path.display()))?;
     let cfg = WorkflowConfig::from_yaml(&text)?;
@@ -67,21 +169,7 @@ pub fn check_services(workflow_path: &Path) -> Result<()> {
     println!("Checking services for: {}", cfg.name);
     println!();
 
-    // Collect required step kinds
-    let step_kinds: HashSet<String> = cfg
-        .steps
-        .iter()
-        .map(|s| format!("{:?}", s.kind).to_lowercase())
-        .collect();
-
-    // Determine which services are required
-    let mut required_services = Vec::new();
-    for service in SERVICES {
-        let is_required = service.step_kinds.iter().any(|k| step_kinds.contains(*k));
-        if is_required {
-            required_services.push(service);
-        }
-    }
+    let required_services = required_services(std::slice::from_ref(&cfg));
 
     if required_services.is_empty() {
         println!("No remote services required for this workflow.");
@@ -93,20 +181,14 @@ pub fn check_services(workflow_path: &Path) -> Result<()> {
     println!();
 
     let mut all_ok = true;
-    let client = reqwest::blocking::Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build()?;
+    let mut probe = http_probe()?;
 
     for service in &required_services {
         let url = format!("{}{}", service.default_url, service.health_path);
-        let status = check_service_health(&client, &url);
+        let status = probe(&url);
 
-        let status_str = if status {
-            "\x1b[32m[RUNNING]\x1b[0m"
-        } else {
-            all_ok = false;
-            "\x1b[31m[NOT RUNNING]\x1b[0m"
-        };
+        all_ok &= status;
+        let status_str = status_label(status, color);
 
         println!(
             "  {} {} - {} {}",
@@ -127,6 +209,14 @@ pub fn check_services(workflow_path: &Path) -> Result<()> {
     Ok(())
 }
 
+pub fn status_label(running: bool, color: bool) -> String {
+    if running {
+        color::paint("[RUNNING]", GREEN, color)
+    } else {
+        color::paint("[NOT RUNNING]", RED, color)
+    }
+}
+
 fn check_service_health(client: &reqwest::blocking::Client, url: &str) -> bool {
     match client.get(url).send() {
         Ok(response) => response.status().is_success() || response.status().as_u16() == 422,
@@ -198,4 +288,77 @@ mod tests {
             assert!(!service.step_kinds.is_empty());
         }
     }
+
+    #[test]
+    fn preflight_reports_unreachable_required_service() {
+        let cfg = WorkflowConfig::from_yaml(
+            "version: 1\nname: t\nsteps:\n  - id: img\n    kind: text_to_image\n    prompt: p\n    output_path: o.png\n",
+        )
+        .unwrap();
+        let mut probed = vec![];
+        let statuses = preflight(&[cfg], &mut |url| {
+            probed.push(url.to_string());
+            false
+        });
+        assert_eq!(probed, vec!["http://192.168.1.64:8570/system_stats"]);
+        assert_eq!(statuses.len(), 1);
+        assert!(!statuses[0].running);
+        let err = report_preflight(&statuses, false).unwrap_err().to_string();
+        assert!(err.contains("unreachable: FLUX.1"), "{err}");
+    }
+
+    #[test]
+    fn down_services_map_to_their_step_kinds() {
+        let yaml = r#"
+version: 1
+name: partial
+steps:
+  - id: script
+    kind: write_file
+    path: "work/script.txt"
+    content: "Hello"
+  - id: narrate
+    kind: tts_generate
+    depends_on: [script]
+    script_path: "work/script.txt"
+    output_path: "work/narration.wav"
+    reference_audio: "voice.wav"
+    reference_text: "sample"
+  - id: thumbnail
+    kind: text_to_image
+    prompt: "a lighthouse"
+    output_path: "work/thumb.png"
+  - id: notes
+    kind: write_file
+    depends_on: [script]
+    path: "work/notes.txt"
+    content: "notes"
+"#;
+        let cfg = WorkflowConfig::from_yaml(yaml).unwrap();
+        // VoxCPM and FLUX.1 are down
+        let statuses = preflight(&[cfg], &mut |url| {
+            !url.contains("curiosity") && !url.contains(":8570")
+        });
+        let down = down_kinds(&statuses);
+        assert_eq!(
+            down,
+            BTreeMap::from([
+                (
+                    StepKind::TextToImage,
+                    "service down: FLUX.1 (http://192.168.1.64:8570)".to_string()
+                ),
+                (
+                    StepKind::TtsGenerate,
+                    "service down: VoxCPM (http://curiosity:7860)".to_string()
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn status_label_has_no_ansi_without_color() {
+        assert_eq!(status_label(true, false), "[RUNNING]");
+        assert_eq!(status_label(false, false), "[NOT RUNNING]");
+        assert!(status_label(true, true).contains("\x1b[32m"));
+    }
 }
@@ -0,0 +1,60 @@
+//! Workflow state inspection command.
+
+use std::path::Path;
+
+use anyhow::{Result, bail};
+
+use vwf_core::StateStore;
+
+/// Print a summary of the persisted workflow state in `workdir`.
+pub fn show(workdir: &Path) -> Result<()> {
+    let Some(state) = StateStore::new(workdir).load()? else {
+        bail!("No workflow state found in {}", workdir.display());
+    };
+    print!("{}", state.summary());
+    Ok(())
+}
+
+/// Reset the failed tasks in `workdir`'s persisted state and save it.
+pub fn reset(workdir: &Path) -> Result<()> {
+    let store = StateStore::new(workdir);
+    let Some(mut state) = store.load()? else {
+        bail!("No workflow state found in {}", workdir.display());
+    };
+    let reset = state.reset_failed();
+    store.save(&state)?;
+    if reset.is_empty() {
+        println!("No failed tasks to reset.");
+    }
+    for id in &reset {
+        println!("Reset {id}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vwf_core::{Task, TaskStatus, WorkflowState};
+
+    #[test]
+    fn reset_saves_failed_tasks_as_runnable() {
+        let dir = std::env::temp_dir().join(format!("vwf_reset_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(reset(&dir).is_err(), "no state yet");
+
+        let mut state = WorkflowState::new("t", 1);
+        let mut narrate = Task::new("narrate", "tts_generate");
+        narrate.status = TaskStatus::Failed {
+            error: "service down".to_string(),
+        };
+        state.add_task(narrate);
+        let store = StateStore::new(&dir);
+        store.save(&state).unwrap();
+
+        reset(&dir).unwrap();
+        let saved = store.load().unwrap().unwrap();
+        assert_eq!(saved.get_task("narrate").unwrap().status, TaskStatus::Ready);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,82 @@
+//! Step catalog command: list step kinds and describe their payloads.
+
+use anyhow::{Result, anyhow};
+
+use vwf_core::{StepKind, step_doc, step_docs};
+
+/// One line per step kind with its summary.
+pub fn list() -> String {
+    let docs = step_docs();
+    let width = docs.iter().map(|d| d.kind.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for doc in docs {
+        out.push_str(&format!("{:width$}  {}\n", doc.kind, doc.summary));
+    }
+    out
+}
+
+/// Table of payload fields for one step kind.
+pub fn describe(kind: &str) -> Result<String> {
+    let parsed: StepKind = serde_json::from_value(serde_json::Value::String(kind.to_string()))
+        .map_err(|_| anyhow!("unknown step kind '{kind}' (run `vwf steps` to list them)"))?;
+    let doc = step_doc(&parsed);
+    let rows: Vec<[String; 5]> = doc
+        .fields
+        .iter()
+        .map(|f| {
+            [
+                f.name.to_string(),
+                f.ty.to_string(),
+                if f.is_required() { "yes" } else { "no" }.to_string(),
+                f.default_value()
+                    .map_or_else(|| "-".to_string(), |v| v.to_string()),
+                f.doc.to_string(),
+            ]
+        })
+        .collect();
+    let header = ["FIELD", "TYPE", "REQUIRED", "DEFAULT", "DESCRIPTION"].map(String::from);
+    let mut widths = header.clone().map(|h| h.len());
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.len());
+        }
+    }
+    let mut out = format!("{} - {}\n\n", doc.kind, doc.summary);
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, w)| format!("{cell:w$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_shows_orientation_default() {
+        let text = describe("text_to_image").unwrap();
+        let line = text
+            .lines()
+            .find(|l| l.starts_with("orientation "))
+            .unwrap();
+        assert!(line.contains("\"portrait\""), "{line}");
+        assert!(line.contains(" no "), "{line}");
+        let prompt = text.lines().find(|l| l.starts_with("prompt ")).unwrap();
+        assert!(prompt.contains(" yes "), "{prompt}");
+    }
+
+    #[test]
+    fn list_covers_every_kind_and_unknown_kind_fails() {
+        let text = list();
+        assert!(text.lines().any(|l| l.starts_with("probe_media ")));
+        assert_eq!(text.lines().count(), step_docs().len());
+        assert!(describe("text_to_imag").is_err());
+    }
+}
@@ -0,0 +1,211 @@
+//! Workflow validation command, with an optional LLM-suggested fix.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+use vwf_core::{LlmClient, LlmReq, OllamaClient, Runner, WorkflowConfig, check_vars};
+
+use crate::services;
+
+const FIX_SYSTEM_PROMPT: &str = "You repair YAML workflow files for the vwf video workflow tool. \
+Reply with the complete corrected workflow in a single ```yaml code block and nothing else. \
+Change only what is needed to fix the listed errors.";
+
+/// How `--fix` should reach the LLM.
+pub struct FixOptions {
+    pub model: String,
+    /// Overwrite the workflow file with a suggestion that validates.
+    pub write: bool,
+}
+
+/// Validate a workflow file; with `fix`, ask the LLM for a corrected version.
+pub fn validate(workflow: &Path, fix: Option<&FixOptions>, strict_vars: bool) -> Result<()> {
+    let text = std::fs::read_to_string(workflow)
+        .with_context(|| format!("read {}", workflow.display()))?;
+    let mut errors = collect_errors(&text);
+    if strict_vars && let Ok(pipeline) = WorkflowConfig::pipeline_from_yaml(&text) {
+        errors.extend(check_vars(&pipeline, &BTreeMap::new()).problems());
+    }
+    if errors.is_empty() {
+        return Ok(());
+    }
+    for e in &errors {
+        eprintln!("ERROR: {e}");
+    }
+    if let Some(fix) = fix {
+        let server = OllamaClient::new(&fix.model).server;
+        let mut probe = services::http_probe()?;
+        if probe(&server) {
+            let mut llm = OllamaClient::new(&fix.model);
+            let provider = format!("ollama:{}", fix.model);
+            apply_fix(workflow, &text, &errors, &mut llm, &provider, fix.write)?;
+        } else {
+            eprintln!("LLM server {server} not reachable; skipping --fix");
+        }
+    }
+    bail!(
+        "{} validation error(s) in {}",
+        errors.len(),
+        workflow.display()
+    )
+}
+
+/// Every problem found, or empty if the file is valid. Prints each
+/// workflow that passes.
+fn collect_errors(text: &str) -> Vec<String> {
+    let pipeline = match WorkflowConfig::pipeline_from_yaml(text) {
+        Ok(p) => p,
+        Err(e) => return vec![format!("{e:#}")],
+    };
+    let mut errors = vec![];
+    for cfg in &pipeline {
+        for warning in cfg.resume_guard_warnings() {
+            eprintln!("WARNING: workflow `{}`: {warning}", cfg.name);
+        }
+        match Runner::validate(cfg) {
+            Ok(()) => println!("OK: {} ({} steps)", cfg.name, cfg.steps.len()),
+            Err(e) => errors.push(format!("workflow `{}`: {e:#}", cfg.name)),
+        }
+    }
+    errors
+}
+
+fn apply_fix(
+    workflow: &Path,
+    text: &str,
+    errors: &[String],
+    llm: &mut dyn LlmClient,
+    provider: &str,
+    write: bool,
+) -> Result<()> {
+    let suggestion = suggest_fix(llm, provider, text, errors)?;
+    let remaining = collect_errors(&suggestion);
+    println!("--- suggested fix for {} ---", workflow.display());
+    print!("{}", line_diff(text, &suggestion));
+    if !remaining.is_empty() {
+        println!("(suggestion still has errors: {})", remaining.join("; "));
+    } else if write {
+        std::fs::write(workflow, &suggestion)
+            .with_context(|| format!("write {}", workflow.display()))?;
+        println!("Wrote fixed workflow to {}", workflow.display());
+    }
+    Ok(())
+}
+
+/// Ask the LLM for a corrected workflow and return its YAML.
+fn suggest_fix(
+    llm: &mut dyn LlmClient,
+    provider: &str,
+    text: &str,
+    errors: &[String],
+) -> Result<String> {
+    let user = format!(
+        "WORKFLOW:\n```yaml\n{text}```\n\nERRORS:\n{}\n\nReturn the corrected workflow.",
+        errors
+            .iter()
+            .map(|e| format!("- {e}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+    let response = llm.generate(LlmReq {
+        system: FIX_SYSTEM_PROMPT.to_string(),
+        user,
+        provider: provider.to_string(),
+    })?;
+    Ok(extract_yaml(&response))
+}
+
+/// The body of the first ``` fenced block, or the whole response.
+fn extract_yaml(response: &str) -> String {
+    let body = response
+        .split_once("```")
+        .and_then(|(_, rest)| {
+            let rest = rest.split_once('\n')?.1;
+            rest.split_once("```").map(|(block, _)| block)
+        })
+        .unwrap_or(response);
+    let body = body.trim_matches('\n');
+    format!("{body}\n")
+}
+
+/// Minimal line diff (`-`/`+`/` ` prefixes) via longest common subsequence.
+fn line_diff(old: &str, new: &str) -> String {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j, mut out) = (0, 0, String::new());
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            out.push_str(&format!("  {}\n", a[i]));
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            out.push_str(&format!("+ {}\n", b[j]));
+            j += 1;
+        } else {
+            out.push_str(&format!("- {}\n", a[i]));
+            i += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vwf_core::MockLlmClient;
+
+    const BROKEN: &str = "\
+version: 1
+name: demo
+steps:
+  - id: setup
+    kind: ensure_dir
+    dirs: [\"work\"]
+";
+
+    #[test]
+    fn mock_llm_fix_is_extracted_and_diffed() {
+        let errors = collect_errors(BROKEN);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("ensure_dir"));
+
+        let mut llm = MockLlmClient::canned(
+            "Here you go:\n```yaml\nversion: 1\nname: demo\nsteps:\n  - id: setup\n    kind: ensure_dirs\n    dirs: [\"work\"]\n```\n",
+        );
+        let fixed = suggest_fix(&mut llm, "mock", BROKEN, &errors).unwrap();
+        assert!(collect_errors(&fixed).is_empty());
+
+        let diff = line_diff(BROKEN, &fixed);
+        assert!(diff.contains("-     kind: ensure_dir\n"));
+        assert!(diff.contains("+     kind: ensure_dirs\n"));
+        assert!(diff.contains("  name: demo\n"));
+    }
+
+    #[test]
+    fn write_only_replaces_file_when_suggestion_validates() {
+        let path = std::env::temp_dir().join(format!("vwf_fix_{}.yaml", std::process::id()));
+        std::fs::write(&path, BROKEN).unwrap();
+        let errors = collect_errors(BROKEN);
+
+        let mut bad = MockLlmClient::canned("```yaml\nnot: [valid\n```");
+        apply_fix(&path, BROKEN, &errors, &mut bad, "mock", true).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), BROKEN);
+
+        let mut good = MockLlmClient::canned(BROKEN.replace("ensure_dir\n", "ensure_dirs\n"));
+        apply_fix(&path, BROKEN, &errors, &mut good, "mock", true).unwrap();
+        assert!(validate(&path, None, false).is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -44,7 +44,7 @@ pub fn run_status_viewer(props: &Props) -> Html {
                             if blocked > 0 {
                                 {format!("{} step(s) blocked by failed dependencies. ", blocked)}
                             }
-                            {"Check service status and re-run with --resume."}
+                            {"Check service status and re-run with --continue."}
                         </div>
                     }
 
@@ -52,10 +52,10 @@ pub fn run_status_viewer(props: &Props) -> Html {
                         <thead>
                             <tr>
                                 <th>{"Status"}</th>
-                                <th>{"Step ID"}</th>
+                                <th>{"Step"}</th>
                                 <th>{"Kind"}</th>
                                 <th>{"Duration"}</th>
-                                <th>{"Error"}</th>
+                                <th>{"Details"}</th>
                             </tr>
                         </thead>
                         <tbody>
@@ -75,10 +75,10 @@ pub fn run_status_viewer(props: &Props) -> Html {
                                 html! {
                                     <tr class={format!("step-row {}", status_class)}>
                                         <td><span class={format!("status-indicator {}", status_class)}>{status_icon}</span></td>
-                                        <td class="step-id">{&step.id}</td>
+                                        <td class="step-id" title={step.id.clone()}>{step.label.as_deref().unwrap_or(&step.id)}</td>
                                         <td class="step-kind">{&step.kind}</td>
                                         <td class="step-duration">{format!("{}ms", step.duration_ms)}</td>
-                                        <td class="step-error">{step.error.as_deref().unwrap_or("-")}</td>
+                                        <td class="step-error">{step.error.as_deref().or(step.skip_reason.as_deref()).unwrap_or("-")}</td>
                                     </tr>
                                 }
                             })}
@@ -7,7 +7,7 @@ mod report;
 use components::{RunStatusViewer, ServicePanel, VarEditor, WorkdirInput, WorkflowEditor};
 use gloo::file::callbacks::FileReader;
 use gloo::file::File;
-use report::RunReport;
+use report::{RunReport, parse_report};
 use serde::{Deserialize, Serialize};
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
@@ -89,10 +89,9 @@ fn app() -> Html {
                 let report = report.clone();
                 let reader = gloo::file::callbacks::read_as_text(&file, move |res| {
                     if let Ok(text) = res {
-                        if let Ok(parsed) = serde_json::from_str::<RunReport>(&text) {
-                            report.set(Some(parsed));
-                        } else {
-                            gloo::dialogs::alert("Failed to parse run.json");
+                        match parse_report(&text) {
+                            Ok(parsed) => report.set(Some(parsed)),
+                            Err(e) => gloo::dialogs::alert(&e),
                         }
                     }
                 });
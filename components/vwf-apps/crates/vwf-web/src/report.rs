@@ -6,8 +6,13 @@
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
+/// Newest `run.json` format this UI understands.
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RunReport {
+    #[serde(default = "first_schema_version")]
+    pub schema_version: u32,
     pub run_id: String,
     pub workflow_name: String,
     pub started_at: String,
@@ -16,15 +21,43 @@ pub struct RunReport {
     pub vars: BTreeMap<String, String>,
 }
 
+fn first_schema_version() -> u32 {
+    1
+}
+
+/// Parse a `run.json`, naming the format version when it's too new.
+pub fn parse_report(text: &str) -> Result<RunReport, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| format!("run.json is not valid JSON: {e}"))?;
+    let version = value
+        .get("schema_version")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(1);
+    if version > u64::from(REPORT_SCHEMA_VERSION) {
+        return Err(format!(
+            "report format v{version}, expected v{REPORT_SCHEMA_VERSION}; update vwf-web"
+        ));
+    }
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse run.json: {e}"))
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StepReport {
     pub id: String,
     pub kind: String,
+    #[serde(default)]
+    pub label: Option<String>,
     pub status: StepStatus,
     pub started_at: String,
     pub finished_at: String,
     pub error: Option<String>,
+    #[serde(default)]
+    pub skip_reason: Option<String>,
     pub duration_ms: u128,
+    #[serde(default)]
+    pub segment: Option<String>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -35,3 +68,34 @@ pub enum StepStatus {
     Failed,
     Blocked,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"{
+        "run_id": "00000000-0000-0000-0000-000000000000",
+        "workflow_name": "demo",
+        "started_at": "2026-01-01T00:00:00Z",
+        "finished_at": "2026-01-01T00:00:01Z",
+        "steps": [],
+        "vars": {}
+    }"#;
+
+    #[test]
+    fn unversioned_report_loads_as_v1() {
+        let report = parse_report(REPORT).unwrap();
+        assert_eq!(report.schema_version, 1);
+        let text = serde_json::to_string(&report).unwrap();
+        assert_eq!(parse_report(&text).unwrap(), report);
+    }
+
+    #[test]
+    fn newer_report_version_is_named() {
+        let newer = REPORT.replacen('{', r#"{"schema_version": 2,"#, 1);
+        assert_eq!(
+            parse_report(&newer).unwrap_err(),
+            "report format v2, expected v1; update vwf-web"
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! `depends_on` inference from the files steps read and write.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+use serde_json::Value;
+
+use super::{StepConfig, StepKind, WorkflowConfig};
+
+/// Payload fields naming a single file a step reads.
+const INPUT_FIELDS: &[&str] = &[
+    "input_path",
+    "user_prompt_path",
+    "script_path",
+    "context_path",
+    "clip_path",
+    "base_clip",
+    "overlay_audio",
+    "background",
+    "reference_audio",
+    "init_image",
+];
+
+/// Payload fields naming a list of files a step reads.
+const INPUT_LIST_FIELDS: &[&str] = &["clips", "assets"];
+
+/// Payload fields naming a file a step writes.
+const OUTPUT_FIELDS: &[&str] = &["output_path", "capture_path"];
+
+impl StepConfig {
+    /// Files this step reads, as written in the workflow (unrendered).
+    pub fn input_paths(&self) -> Vec<&str> {
+        let mut paths: Vec<&str> = INPUT_FIELDS
+            .iter()
+            .filter_map(|f| self.payload.get(*f)?.as_str())
+            .chain(self.needs.iter().map(String::as_str))
+            .collect();
+        for field in INPUT_LIST_FIELDS {
+            if let Some(items) = self.payload.get(*field).and_then(Value::as_array) {
+                paths.extend(items.iter().filter_map(Value::as_str));
+            }
+        }
+        paths
+    }
+
+    /// Files this step writes, as written in the workflow (unrendered).
+    pub fn output_paths(&self) -> Vec<&str> {
+        let mut paths: Vec<&str> = OUTPUT_FIELDS
+            .iter()
+            .filter_map(|f| self.payload.get(*f)?.as_str())
+            .collect();
+        let nested = |field: &str| -> Vec<&Value> {
+            match self.payload.get(field) {
+                Some(Value::Array(items)) => items.iter().collect(),
+                Some(Value::Object(map)) => map.values().collect(),
+                _ => vec![],
+            }
+        };
+        match self.kind {
+            StepKind::WriteFile => paths.extend(self.payload.get("path").and_then(Value::as_str)),
+            StepKind::SplitSections => paths.extend(
+                nested("outputs")
+                    .into_iter()
+                    .filter_map(|o| o.get("path")?.as_str()),
+            ),
+            StepKind::LlmGenerate => paths.extend(
+                nested("extract_sections")
+                    .into_iter()
+                    .filter_map(|o| o.get("path")?.as_str()),
+            ),
+            _ => {}
+        }
+        if let Some(resume) = &self.resume_output {
+            paths.push(resume);
+        }
+        paths.sort_unstable();
+        paths.dedup();
+        paths
+    }
+}
+
+impl WorkflowConfig {
+    /// Add a `depends_on` edge from each step to the step that writes each
+    /// file in its `needs`.
+    ///
+    /// A needed file no step writes is expected to exist already and adds
+    /// no edge. A file written by more than one step is an error.
+    pub fn resolve_needs(&mut self) -> Result<()> {
+        let producers = self.producers();
+        for step in &mut self.steps {
+            for path in step.needs.iter().map(|p| normalize(p)) {
+                let Some(ids) = producers.get(&path) else {
+                    continue;
+                };
+                let ids: Vec<&String> = ids.iter().filter(|id| **id != step.id).collect();
+                match ids.as_slice() {
+                    [] => {}
+                    [producer] => {
+                        if !step.depends_on.contains(producer) {
+                            step.depends_on.push((*producer).clone());
+                        }
+                    }
+                    many => bail!(
+                        "step `{}` needs `{path}`, which is written by {}; use depends_on instead",
+                        step.id,
+                        quoted(many)
+                    ),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Give each `video_concat` step with `chapters: true` a chapter title
+    /// per clip: the title (or id) of the segment holding the step that
+    /// writes the clip. The titles go in the step's `chapter_titles`.
+    ///
+    /// A clip no step writes, or whose writer is in no segment, is an error.
+    pub fn resolve_chapters(&mut self) -> Result<()> {
+        let producers = self.producers();
+        let titles: BTreeMap<String, String> = self
+            .segments
+            .iter()
+            .flat_map(|seg| {
+                let title = seg.title.as_ref().unwrap_or(&seg.id);
+                seg.steps.iter().map(|id| (id.clone(), title.clone()))
+            })
+            .collect();
+        for step in &mut self.steps {
+            if step.kind != StepKind::VideoConcat || step.payload["chapters"] != true {
+                continue;
+            }
+            let clips: Vec<&str> = step.payload["clips"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            let mut chapter_titles = vec![];
+            for clip in clips {
+                let path = normalize(clip);
+                let writers: Vec<&String> = producers
+                    .get(&path)
+                    .into_iter()
+                    .flatten()
+                    .filter(|id| **id != step.id)
+                    .collect();
+                let writer = match writers.as_slice() {
+                    [writer] => *writer,
+                    [] => bail!(
+                        "step `{}` wants chapters, but no step writes clip `{path}`",
+                        step.id
+                    ),
+                    many => bail!(
+                        "step `{}` wants chapters, but clip `{path}` is written by {}",
+                        step.id,
+                        quoted(many)
+                    ),
+                };
+                let Some(title) = titles.get(writer) else {
+                    bail!(
+                        "step `{}` wants chapters, but `{writer}` (which writes `{path}`) is in no segment",
+                        step.id
+                    );
+                };
+                chapter_titles.push(Value::from(title.as_str()));
+            }
+            step.payload["chapter_titles"] = Value::Array(chapter_titles);
+        }
+        Ok(())
+    }
+
+    /// Add `depends_on` edges from each step to the step that writes a file
+    /// it reads.
+    ///
+    /// Paths are compared as written, so `{{var}}` templates must match
+    /// textually. A file written by more than one step gets no edge; each
+    /// such ambiguity is returned as a message.
+    pub fn infer_dependencies(&mut self) -> Vec<String> {
+        let producers = self.producers();
+        let mut problems = vec![];
+        for step in &mut self.steps {
+            let inputs: Vec<String> = step.input_paths().into_iter().map(normalize).collect();
+            for path in inputs {
+                let Some(ids) = producers.get(&path) else {
+                    continue;
+                };
+                let ids: Vec<&String> = ids.iter().filter(|id| **id != step.id).collect();
+                match ids.as_slice() {
+                    [] => {}
+                    [producer] => {
+                        if !step.depends_on.contains(producer) {
+                            step.depends_on.push((*producer).clone());
+                        }
+                    }
+                    many => problems.push(format!(
+                        "step `{}` reads `{path}`, which is written by {}; add depends_on by hand",
+                        step.id,
+                        quoted(many)
+                    )),
+                }
+            }
+        }
+        problems
+    }
+
+    /// Normalized output path -> ids of the steps that write it.
+    fn producers(&self) -> BTreeMap<String, Vec<String>> {
+        let mut producers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for step in &self.steps {
+            for path in step.output_paths() {
+                producers
+                    .entry(normalize(path))
+                    .or_default()
+                    .push(step.id.clone());
+            }
+        }
+        producers
+    }
+}
+
+fn quoted(ids: &[&String]) -> String {
+    ids.iter()
+        .map(|id| format!("`{id}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn normalize(path: &str) -> String {
+    path.trim().trim_start_matches("./").to_string()
+}
@@ -1,7 +1,11 @@
 //! Workflow configuration parsing for VWF.
 
+mod infer;
+mod merge;
+mod migrate;
 mod step;
 mod workflow;
 
+pub use migrate::SUPPORTED_VERSIONS;
 pub use step::{StepConfig, StepKind};
-pub use workflow::{Segment, SegmentType, WorkflowConfig};
+pub use workflow::{Segment, SegmentType, WorkflowConfig, WorkflowInput};
@@ -0,0 +1,73 @@
+//! Layering an overlay (e.g. per-environment servers) onto a base workflow.
+
+use anyhow::{Context, Result, bail};
+use serde_json::{Map, Value};
+
+use super::WorkflowConfig;
+
+impl WorkflowConfig {
+    /// `base` with `overlay` applied on top.
+    ///
+    /// The overlay is a partial workflow document:
+    /// - `vars` are merged key by key, the overlay winning
+    /// - each of its `steps` replaces the listed fields of the base step with
+    ///   the same id; steps with a new id are appended and must be complete
+    /// - any other top-level field replaces the base's
+    pub fn merge(base: &Self, overlay: &Value) -> Result<Self> {
+        let Value::Object(overlay) = overlay else {
+            bail!("workflow overlay must be a mapping");
+        };
+        let Value::Object(mut merged) = serde_json::to_value(base)? else {
+            unreachable!("a workflow serializes to a mapping");
+        };
+        for (key, value) in overlay {
+            match key.as_str() {
+                "vars" => merge_vars(&mut merged, value)?,
+                "steps" => merge_steps(&mut merged, value)?,
+                _ => {
+                    merged.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        let cfg = serde_json::from_value::<Self>(Value::Object(merged))
+            .context("Failed to apply workflow overlay")?
+            .migrate()?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+}
+
+fn merge_vars(merged: &mut Map<String, Value>, vars: &Value) -> Result<()> {
+    let Value::Object(vars) = vars else {
+        bail!("overlay `vars` must be a mapping");
+    };
+    let base = merged
+        .entry("vars")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if let Value::Object(base) = base {
+        base.extend(vars.clone());
+    }
+    Ok(())
+}
+
+fn merge_steps(merged: &mut Map<String, Value>, steps: &Value) -> Result<()> {
+    let Value::Array(steps) = steps else {
+        bail!("overlay `steps` must be a list");
+    };
+    let Some(Value::Array(base)) = merged.get_mut("steps") else {
+        unreachable!("a workflow always has steps");
+    };
+    for step in steps {
+        let Some(id) = step.get("id").and_then(Value::as_str) else {
+            bail!("overlay step without an `id`");
+        };
+        let Value::Object(fields) = step else {
+            unreachable!("a step with an id is a mapping");
+        };
+        match base.iter_mut().find(|s| s["id"] == id) {
+            Some(Value::Object(existing)) => existing.extend(fields.clone()),
+            _ => base.push(step.clone()),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,79 @@
+//! Workflow schema versions and upgrades of older spellings.
+
+use std::ops::RangeInclusive;
+
+use anyhow::{Result, bail};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::WorkflowConfig;
+
+/// `version:` values this build can read.
+pub const SUPPORTED_VERSIONS: RangeInclusive<u32> = 1..=1;
+
+/// Just enough of a workflow document to check its version.
+#[derive(Deserialize)]
+pub(crate) struct Header {
+    #[serde(default)]
+    version: Option<u32>,
+}
+
+impl Header {
+    /// Fail clearly on a version this build doesn't know.
+    ///
+    /// A missing or malformed version is left for the full parse to report.
+    pub(crate) fn check(&self) -> Result<()> {
+        match self.version {
+            Some(v) if !SUPPORTED_VERSIONS.contains(&v) => {
+                let (lo, hi) = (SUPPORTED_VERSIONS.start(), SUPPORTED_VERSIONS.end());
+                let supported = if lo == hi {
+                    format!("version {lo}")
+                } else {
+                    format!("versions {lo}-{hi}")
+                };
+                bail!(
+                    "Unsupported workflow version {v}: this vwf reads {supported}; \
+                     upgrade vwf or rewrite the file for a supported version"
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl WorkflowConfig {
+    /// Rewrite deprecated spellings into the current schema.
+    ///
+    /// Version 1:
+    /// - step `after:` (a step id or list of step ids) is read as `depends_on`
+    pub fn migrate(mut self) -> Result<Self> {
+        for step in &mut self.steps {
+            let Some(after) = step.payload.as_object_mut().and_then(|p| p.remove("after")) else {
+                continue;
+            };
+            let ids = match after {
+                Value::String(id) => vec![id],
+                Value::Array(items) => items
+                    .into_iter()
+                    .map(|v| match v {
+                        Value::String(id) => Ok(id),
+                        other => bail!(
+                            "Step `{}`: after entries must be step ids, got `{other}`",
+                            step.id
+                        ),
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+                other => bail!(
+                    "Step `{}`: after must be a step id or a list of step ids, got `{other}`",
+                    step.id
+                ),
+            };
+            for id in ids {
+                if !step.depends_on.contains(&id) {
+                    step.depends_on.push(id);
+                }
+            }
+        }
+        Ok(self)
+    }
+}
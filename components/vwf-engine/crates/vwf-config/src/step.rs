@@ -1,11 +1,16 @@
 //! Step configuration types.
 
-use serde::{Deserialize, Serialize};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StepConfig {
     pub id: String,
     pub kind: StepKind,
+    /// Human-readable name for logs and reports (defaults to `id`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
     /// Output path for resume checking (optional).
     /// Use a separate field name to avoid conflicts with step payload fields.
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -14,11 +19,81 @@ pub struct StepConfig {
     /// If empty, step can run immediately (no dependencies).
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub depends_on: Vec<String>,
+    /// Files this step reads; each becomes a dependency on the step that
+    /// writes it.
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub needs: Vec<String>,
+    /// Ask before running this step (destructive or expensive work).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub confirm: bool,
+    /// A failure of this step doesn't fail the run (its dependents are
+    /// still blocked).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub continue_on_error: bool,
+    /// Copy this step's outputs into the run's `output_dir` when it finishes.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deliverable: bool,
+    /// Outputs this step writes only sometimes (e.g. a report written only
+    /// when there are issues); a missing one is not a failure.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub optional_outputs: Vec<String>,
+    /// Resource held while running (e.g. `gpu`); overrides the kind's default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+    /// Steps in the same group never run at the same time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequential_group: Option<String>,
     #[serde(flatten)]
     pub payload: serde_json::Value,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl StepConfig {
+    /// The step's label, or its id when no label is set.
+    pub fn display_name(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.id)
+    }
+
+    /// The file this step rewrites in place (unrendered): a
+    /// `normalize_volume` clip, or an `audio_mix` whose output is its
+    /// base clip. Running such a step twice applies it twice.
+    pub fn in_place_path(&self) -> Option<&str> {
+        let field = |name: &str| self.payload.get(name).and_then(Value::as_str);
+        match self.kind {
+            StepKind::NormalizeVolume => field("clip_path"),
+            StepKind::AudioMix => {
+                field("output_path").filter(|out| field("base_clip") == Some(out))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A string or a list of strings; anything else is an error naming the
+/// offending value.
+fn one_or_many<'de, D: Deserializer<'de>>(de: D) -> Result<Vec<String>, D::Error> {
+    match Value::deserialize(de)? {
+        Value::Null => Ok(vec![]),
+        Value::String(s) => Ok(vec![s]),
+        Value::Array(items) => items
+            .into_iter()
+            .map(|v| match v {
+                Value::String(s) => Ok(s),
+                other => Err(D::Error::custom(format!(
+                    "needs entries must be strings, got `{other}`"
+                ))),
+            })
+            .collect(),
+        other => Err(D::Error::custom(format!(
+            "needs must be a string or a list of strings, got `{other}`"
+        ))),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum StepKind {
     EnsureDirs,
@@ -36,4 +111,7 @@ pub enum StepKind {
     AudioMix,
     CreateSlide,
     LlmAudit,
+    ProbeMedia,
+    FetchUrl,
+    ExpandAcronyms,
 }
@@ -6,9 +6,10 @@ use anyhow::bail;
 use serde::{Deserialize, Serialize};
 
 use super::StepConfig;
+use super::migrate::Header;
 
 /// Segment type determines what audio content is allowed.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum SegmentType {
     /// Music only - no narration allowed (e.g., intro/outro)
@@ -16,15 +17,10 @@ pub enum SegmentType {
     /// Narration only - no music allowed (main content)
     NarrationOnly,
     /// Both music and narration allowed
+    #[default]
     Mixed,
 }
 
-impl Default for SegmentType {
-    fn default() -> Self {
-        Self::Mixed
-    }
-}
-
 /// A semantic grouping of workflow steps.
 ///
 /// Segments help organize video production by preventing invalid
@@ -40,10 +36,26 @@ pub struct Segment {
     /// Description of this segment's purpose
     #[serde(default)]
     pub description: Option<String>,
+    /// Chapter title in the final video (defaults to the id)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
     /// Step IDs that belong to this segment
     pub steps: Vec<String>,
 }
 
+/// A var the caller supplies, e.g. with `--var`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WorkflowInput {
+    pub name: String,
+    /// What the var is for, shown when it's missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Value used when the caller doesn't supply one; without a default
+    /// the input is required
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkflowConfig {
     pub version: u32,
@@ -52,20 +64,65 @@ pub struct WorkflowConfig {
     pub description: Option<String>,
     #[serde(default)]
     pub vars: BTreeMap<String, String>,
+    /// Vars the caller must supply (unless they have a default)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<WorkflowInput>,
     pub steps: Vec<StepConfig>,
     /// Optional semantic segments for organizing steps
     #[serde(default)]
     pub segments: Vec<Segment>,
+    /// Derive missing `depends_on` edges from the files steps read and write
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub infer_depends_on: bool,
+}
+
+/// Characters allowed in step ids, which end up in file names, DOT node
+/// names and log lines.
+fn is_id_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '/')
 }
 
 impl WorkflowConfig {
     pub fn from_yaml(yaml: &str) -> anyhow::Result<Self> {
-        let cfg: Self = serde_yaml::from_str(yaml)
-            .map_err(|e| anyhow::anyhow!("Failed to parse workflow YAML: {e}"))?;
+        if let Ok(header) = serde_yaml::from_str::<Header>(yaml) {
+            header.check()?;
+        }
+        let cfg = serde_yaml::from_str::<Self>(yaml)
+            .map_err(|e| anyhow::anyhow!("Failed to parse workflow YAML: {e}"))?
+            .migrate()?;
         cfg.validate()?;
         Ok(cfg)
     }
 
+    /// Parse a `---`-separated multi-document file into a pipeline of
+    /// workflows, in document order.
+    pub fn pipeline_from_yaml(yaml: &str) -> anyhow::Result<Vec<Self>> {
+        for (i, doc) in serde_yaml::Deserializer::from_str(yaml).enumerate() {
+            // A document that doesn't parse ends the iteration (it would
+            // repeat its error); the full parse below reports it.
+            let Ok(header) = Header::deserialize(doc) else {
+                break;
+            };
+            header
+                .check()
+                .map_err(|e| e.context(format!("document {}", i + 1)))?;
+        }
+        let mut pipeline = vec![];
+        for (i, doc) in serde_yaml::Deserializer::from_str(yaml).enumerate() {
+            let cfg = Self::deserialize(doc)
+                .map_err(|e| {
+                    anyhow::anyhow!("Failed to parse workflow YAML (document {}): {e}", i + 1)
+                })?
+                .migrate()?;
+            cfg.validate()?;
+            pipeline.push(cfg);
+        }
+        if pipeline.is_empty() {
+            bail!("Workflow file contains no documents");
+        }
+        Ok(pipeline)
+    }
+
     pub fn validate(&self) -> anyhow::Result<()> {
         // Validate step IDs
         let mut seen_ids = HashSet::new();
@@ -73,14 +130,54 @@ impl WorkflowConfig {
             if step.id.is_empty() {
                 bail!("Step id cannot be empty");
             }
+            if let Some(c) = step.id.chars().find(|c| !is_id_char(*c)) {
+                bail!(
+                    "Step id `{}` contains {c:?}; ids may only use ASCII letters, digits, `_`, `-` and `/`",
+                    step.id
+                );
+            }
+            if step.id.split('/').any(str::is_empty) {
+                bail!(
+                    "Step id `{}` has an empty part; `/` only separates namespaces, as in `intro/tts`",
+                    step.id
+                );
+            }
             if !seen_ids.insert(&step.id) {
                 bail!("Duplicate step id: `{}`", step.id);
             }
+            let outputs = step.output_paths();
+            if let Some(path) = step
+                .optional_outputs
+                .iter()
+                .find(|p| !outputs.contains(&p.as_str()))
+            {
+                bail!(
+                    "Step `{}` lists optional output `{path}`, which it doesn't write",
+                    step.id
+                );
+            }
+        }
+
+        let mut seen_inputs = HashSet::new();
+        for input in &self.inputs {
+            if input.name.is_empty() {
+                bail!("Input name cannot be empty");
+            }
+            if !seen_inputs.insert(&input.name) {
+                bail!("Duplicate input: `{}`", input.name);
+            }
+            if self.vars.contains_key(&input.name) {
+                bail!(
+                    "`{}` is declared in both inputs and vars; give inputs a `default` instead",
+                    input.name
+                );
+            }
         }
 
         // Validate segments if present
         let step_ids: HashSet<_> = self.steps.iter().map(|s| s.id.as_str()).collect();
         let mut seen_segment_ids = HashSet::new();
+        let mut assigned: BTreeMap<&str, &str> = BTreeMap::new();
 
         for segment in &self.segments {
             if segment.id.is_empty() {
@@ -98,16 +195,106 @@ impl WorkflowConfig {
                         step_ref
                     );
                 }
+                if let Some(other) = assigned.insert(step_ref, &segment.id) {
+                    bail!(
+                        "Step `{}` is listed in both segment `{}` and `{}`",
+                        step_ref,
+                        other,
+                        segment.id
+                    );
+                }
             }
         }
 
         Ok(())
     }
 
+    /// Defaults of the inputs that have one.
+    pub fn input_defaults(&self) -> BTreeMap<String, String> {
+        self.inputs
+            .iter()
+            .filter_map(|i| Some((i.name.clone(), i.default.clone()?)))
+            .collect()
+    }
+
+    /// Fail, listing each with its description, if a required input is not
+    /// among the `supplied` vars.
+    pub fn check_inputs(&self, supplied: &BTreeMap<String, String>) -> anyhow::Result<()> {
+        let missing: Vec<String> = self
+            .inputs
+            .iter()
+            .filter(|i| i.default.is_none() && !supplied.contains_key(&i.name))
+            .map(|i| match &i.description {
+                Some(description) => format!("  {}: {description}", i.name),
+                None => format!("  {}", i.name),
+            })
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        bail!(
+            "Workflow `{}` is missing {} required input(s); pass them with --var NAME=VALUE:\n{}",
+            self.name,
+            missing.len(),
+            missing.join("\n")
+        )
+    }
+
+    /// Steps that modify a file in place without a `resume_output` marker
+    /// that `--resume` could skip them by, one message each.
+    pub fn resume_guard_warnings(&self) -> Vec<String> {
+        self.steps
+            .iter()
+            .filter_map(|step| {
+                let path = step.in_place_path()?;
+                match step.resume_output.as_deref() {
+                    None => Some(format!(
+                        "step `{}` modifies `{path}` in place and has no resume_output, \
+                         so --resume would apply it again; add e.g. resume_output: \"{path}.done\"",
+                        step.id
+                    )),
+                    Some(marker) if marker == path => Some(format!(
+                        "step `{}` uses the file it modifies in place as resume_output, \
+                         so --resume can't tell whether it ran; use a marker such as \"{path}.done\"",
+                        step.id
+                    )),
+                    Some(_) => None,
+                }
+            })
+            .collect()
+    }
+
     /// Get the segment containing a given step ID, if any.
     pub fn segment_for_step(&self, step_id: &str) -> Option<&Segment> {
         self.segments
             .iter()
             .find(|s| s.steps.iter().any(|id| id == step_id))
     }
+
+    /// Look up a segment by id.
+    pub fn segment(&self, segment_id: &str) -> Option<&Segment> {
+        self.segments.iter().find(|s| s.id == segment_id)
+    }
+
+    /// Steps belonging to a segment, in the segment's listed order.
+    pub fn steps_in_segment(&self, segment_id: &str) -> Vec<&StepConfig> {
+        let Some(segment) = self.segment(segment_id) else {
+            return vec![];
+        };
+        segment
+            .steps
+            .iter()
+            .filter_map(|id| self.steps.iter().find(|s| &s.id == id))
+            .collect()
+    }
+
+    /// Map of step id to the id of the segment it belongs to.
+    ///
+    /// Steps outside any segment are absent.
+    pub fn step_segments(&self) -> BTreeMap<&str, &str> {
+        self.segments
+            .iter()
+            .flat_map(|seg| seg.steps.iter().map(|id| (id.as_str(), seg.id.as_str())))
+            .collect()
+    }
 }
@@ -0,0 +1,74 @@
+//! Layering an overlay onto a base workflow.
+
+use vwf_config::WorkflowConfig;
+
+const BASE: &str = r#"
+version: 1
+name: episode
+vars:
+  tts_server: "http://localhost:7860"
+  voice: "narrator"
+steps:
+  - id: script
+    kind: write_file
+    path: "work/script.txt"
+    content: "Hello"
+  - id: narrate
+    kind: tts_generate
+    depends_on: [script]
+    server: "{{tts_server}}"
+    script_path: "work/script.txt"
+    output_path: "work/narration.wav"
+"#;
+
+fn overlay(yaml: &str) -> serde_json::Value {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+#[test]
+fn overlay_changes_vars_and_step_fields_by_id() {
+    let base = WorkflowConfig::from_yaml(BASE).unwrap();
+    let prod = overlay(
+        r#"
+vars:
+  tts_server: "http://curiosity:7860"
+steps:
+  - id: narrate
+    server: "http://curiosity:7861"
+  - id: publish
+    kind: write_file
+    depends_on: [narrate]
+    path: "work/published.txt"
+    content: "done"
+"#,
+    );
+    let merged = WorkflowConfig::merge(&base, &prod).unwrap();
+
+    assert_eq!(merged.name, "episode");
+    assert_eq!(merged.vars["tts_server"], "http://curiosity:7860");
+    assert_eq!(merged.vars["voice"], "narrator");
+    let ids: Vec<&str> = merged.steps.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, ["script", "narrate", "publish"]);
+    assert_eq!(
+        merged.steps[0].payload, base.steps[0].payload,
+        "untouched step changed"
+    );
+    let narrate = &merged.steps[1];
+    assert_eq!(narrate.payload["server"], "http://curiosity:7861");
+    assert_eq!(narrate.payload["output_path"], "work/narration.wav");
+    assert_eq!(narrate.depends_on, ["script"]);
+}
+
+#[test]
+fn new_overlay_step_must_be_complete() {
+    let base = WorkflowConfig::from_yaml(BASE).unwrap();
+    let err =
+        WorkflowConfig::merge(&base, &overlay("steps:\n  - id: extra\n    path: x\n")).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("missing field `kind`"),
+        "{err:#}"
+    );
+
+    let err = WorkflowConfig::merge(&base, &overlay("steps:\n  - path: x\n")).unwrap_err();
+    assert_eq!(err.to_string(), "overlay step without an `id`");
+}
@@ -1,5 +1,7 @@
 //! Workflow configuration tests.
 
+use std::collections::BTreeMap;
+
 use vwf_config::WorkflowConfig;
 
 #[test]
@@ -63,6 +65,27 @@ steps:
     assert!(err.contains("empty"));
 }
 
+#[test]
+fn step_ids_with_reserved_characters_error() {
+    let with_id = |id: &str| {
+        format!(
+            "version: 1\nname: test\nsteps:\n  - id: '{id}'\n    kind: ensure_dirs\n    dirs: [a]\n"
+        )
+    };
+    let err = WorkflowConfig::from_yaml(&with_id("tts hook")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Step id `tts hook` contains ' '; ids may only use ASCII letters, digits, `_`, `-` and `/`"
+    );
+    let err = WorkflowConfig::from_yaml(&with_id("say\"hi\"")).unwrap_err();
+    assert!(err.to_string().contains("contains '\"'"), "{err}");
+    let err = WorkflowConfig::from_yaml(&with_id("intro//tts")).unwrap_err();
+    assert!(err.to_string().contains("has an empty part"), "{err}");
+
+    let cfg = WorkflowConfig::from_yaml(&with_id("intro/tts_hook-2")).unwrap();
+    assert_eq!(cfg.steps[0].id, "intro/tts_hook-2");
+}
+
 #[test]
 fn vars_substitution_in_workflow() {
     let yaml = r#"
@@ -80,3 +103,385 @@ steps:
     assert_eq!(cfg.vars.get("project"), Some(&"demo".to_string()));
     assert_eq!(cfg.vars.get("output_dir"), Some(&"work".to_string()));
 }
+
+const SEGMENTED: &str = r#"
+version: 1
+name: test
+steps:
+  - id: a
+    kind: ensure_dirs
+    dirs: ["work"]
+  - id: b
+    kind: ensure_dirs
+    dirs: ["out"]
+  - id: c
+    kind: ensure_dirs
+    dirs: ["tmp"]
+segments:
+  - id: intro
+    steps: [b, a]
+"#;
+
+#[test]
+fn segment_accessors_group_steps() {
+    let cfg = WorkflowConfig::from_yaml(SEGMENTED).unwrap();
+    let ids: Vec<&str> = cfg
+        .steps_in_segment("intro")
+        .iter()
+        .map(|s| s.id.as_str())
+        .collect();
+    assert_eq!(ids, vec!["b", "a"]);
+    assert!(cfg.steps_in_segment("missing").is_empty());
+    assert!(cfg.segment("intro").is_some());
+
+    let map = cfg.step_segments();
+    assert_eq!(map.get("a"), Some(&"intro"));
+    assert_eq!(map.get("c"), None);
+}
+
+#[test]
+fn step_in_two_segments_errors() {
+    let yaml = format!("{SEGMENTED}  - id: outro\n    steps: [a]\n");
+    let err = WorkflowConfig::from_yaml(&yaml).unwrap_err();
+    assert!(err.to_string().contains("both segment `intro` and `outro`"));
+}
+
+#[test]
+fn infers_depends_on_from_file_paths() {
+    let yaml = r#"
+version: 1
+name: inferred
+infer_depends_on: true
+steps:
+  - id: split
+    kind: split_sections
+    input_path: "input.txt"
+    outputs:
+      - heading: "INTRO:"
+        path: "work/intro.txt"
+  - id: write_input
+    kind: write_file
+    path: "./input.txt"
+    content: "INTRO:\nhello"
+  - id: speak
+    kind: tts_generate
+    script_path: "work/intro.txt"
+    output_path: "work/intro.wav"
+"#;
+    let mut cfg = WorkflowConfig::from_yaml(yaml).unwrap();
+    assert!(cfg.infer_depends_on);
+    assert!(cfg.infer_dependencies().is_empty());
+    assert_eq!(cfg.steps[0].depends_on, ["write_input"]);
+    assert!(cfg.steps[1].depends_on.is_empty());
+    assert_eq!(cfg.steps[2].depends_on, ["split"]);
+}
+
+#[test]
+fn ambiguous_producers_are_reported() {
+    let yaml = r#"
+version: 1
+name: ambiguous
+steps:
+  - id: a
+    kind: write_file
+    path: "input.txt"
+    content: "one"
+  - id: b
+    kind: write_file
+    path: "input.txt"
+    content: "two"
+  - id: split
+    kind: split_sections
+    input_path: "input.txt"
+    outputs: []
+"#;
+    let mut cfg = WorkflowConfig::from_yaml(yaml).unwrap();
+    let problems = cfg.infer_dependencies();
+    assert_eq!(
+        problems,
+        ["step `split` reads `input.txt`, which is written by `a`, `b`; add depends_on by hand"]
+    );
+    assert!(cfg.steps[2].depends_on.is_empty());
+}
+
+#[test]
+fn unsupported_version_errors_clearly() {
+    let yaml = "version: 2\nname: future\nsteps: []\n";
+    let err = WorkflowConfig::from_yaml(yaml).unwrap_err().to_string();
+    assert!(
+        err.starts_with("Unsupported workflow version 2: this vwf reads version 1"),
+        "{err}"
+    );
+
+    let pipeline = "version: 1\nname: a\nsteps: []\n---\nversion: 3\nname: b\nsteps: []\n";
+    let err = WorkflowConfig::pipeline_from_yaml(pipeline).unwrap_err();
+    assert_eq!(err.to_string(), "document 2");
+    assert!(format!("{err:#}").contains("Unsupported workflow version 3"));
+}
+
+#[test]
+fn deprecated_after_is_read_as_depends_on() {
+    let yaml = r#"
+version: 1
+name: test
+steps:
+  - id: a
+    kind: ensure_dirs
+    dirs: ["work"]
+  - id: b
+    kind: ensure_dirs
+    dirs: ["out"]
+  - id: c
+    kind: write_file
+    after: [a, b]
+    depends_on: [a]
+    path: "out/c.txt"
+    content: "c"
+  - id: d
+    kind: write_file
+    after: c
+    path: "out/d.txt"
+    content: "d"
+"#;
+    let cfg = WorkflowConfig::from_yaml(yaml).unwrap();
+    assert_eq!(cfg.steps[2].depends_on, vec!["a", "b"]);
+    assert_eq!(cfg.steps[3].depends_on, vec!["c"]);
+    assert!(cfg.steps[2].payload.get("after").is_none());
+
+    let malformed = yaml.replace("after: [a, b]", "after: [a, 2]");
+    let err = WorkflowConfig::from_yaml(&malformed)
+        .unwrap_err()
+        .to_string();
+    assert_eq!(err, "Step `c`: after entries must be step ids, got `2`");
+}
+
+#[test]
+fn needs_resolves_files_to_their_producers() {
+    let yaml = r#"
+version: 1
+name: needs
+steps:
+  - id: setup
+    kind: ensure_dirs
+    dirs: ["work"]
+  - id: write_a
+    kind: write_file
+    path: "work/a.txt"
+    content: "a"
+  - id: use_a
+    kind: run_command
+    program: "cat"
+    depends_on: [setup]
+    needs: [./work/a.txt, input/brief.txt]
+"#;
+    let mut cfg = WorkflowConfig::from_yaml(yaml).unwrap();
+    cfg.resolve_needs().unwrap();
+    assert_eq!(cfg.steps[2].depends_on, ["setup", "write_a"]);
+}
+
+#[test]
+fn needs_entry_named_like_a_step_is_still_a_file() {
+    let yaml = r#"
+version: 1
+name: needs
+steps:
+  - id: render
+    kind: write_file
+    path: "out.txt"
+    content: "out"
+  - id: write_render
+    kind: write_file
+    path: "render"
+    content: "file named render"
+  - id: use_render
+    kind: run_command
+    program: "cat"
+    needs: [render]
+"#;
+    let mut cfg = WorkflowConfig::from_yaml(yaml).unwrap();
+    assert_eq!(cfg.steps[2].needs, ["render"]);
+    assert!(cfg.steps[2].depends_on.is_empty());
+    cfg.resolve_needs().unwrap();
+    assert_eq!(cfg.steps[2].depends_on, ["write_render"]);
+}
+
+#[test]
+fn non_string_needs_entries_error() {
+    let yaml = r#"
+version: 1
+name: needs
+steps:
+  - id: use_a
+    kind: run_command
+    program: "cat"
+    needs: [work/a.txt, {path: work/b.txt}]
+"#;
+    let err = WorkflowConfig::from_yaml(yaml).unwrap_err().to_string();
+    assert!(
+        err.contains(r#"needs entries must be strings, got `{"path":"work/b.txt"}`"#),
+        "{err}"
+    );
+}
+
+#[test]
+fn needs_with_two_producers_errors() {
+    let yaml = r#"
+version: 1
+name: needs
+steps:
+  - id: a
+    kind: write_file
+    path: "work/a.txt"
+    content: "one"
+  - id: b
+    kind: write_file
+    path: "work/a.txt"
+    content: "two"
+  - id: use_a
+    kind: run_command
+    program: "cat"
+    needs: work/a.txt
+"#;
+    let mut cfg = WorkflowConfig::from_yaml(yaml).unwrap();
+    let err = cfg.resolve_needs().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "step `use_a` needs `work/a.txt`, which is written by `a`, `b`; use depends_on instead"
+    );
+}
+
+#[test]
+fn chapters_are_titled_by_the_segment_that_writes_each_clip() {
+    let yaml = r#"
+version: 1
+name: chapters
+segments:
+  - id: intro
+    title: "Welcome"
+    steps: [title_clip, hook_clip]
+  - id: body
+    steps: [body_clip]
+steps:
+  - id: title_clip
+    kind: write_file
+    path: "clips/title.mp4"
+    content: "t"
+  - id: hook_clip
+    kind: write_file
+    path: "clips/hook.mp4"
+    content: "h"
+  - id: body_clip
+    kind: write_file
+    path: "clips/body.mp4"
+    content: "b"
+  - id: final
+    kind: video_concat
+    clips: ["clips/title.mp4", "./clips/hook.mp4", "clips/body.mp4"]
+    output_path: "final.mp4"
+    chapters: true
+"#;
+    let mut cfg = WorkflowConfig::from_yaml(yaml).unwrap();
+    cfg.resolve_chapters().unwrap();
+    assert_eq!(
+        cfg.steps[3].payload["chapter_titles"],
+        serde_json::json!(["Welcome", "Welcome", "body"])
+    );
+
+    cfg.segments.pop();
+    let err = cfg.resolve_chapters().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "step `final` wants chapters, but `body_clip` (which writes `clips/body.mp4`) is in no segment"
+    );
+}
+
+#[test]
+fn malformed_pipeline_document_errors() {
+    let err = WorkflowConfig::pipeline_from_yaml("version: 1\nname: [unclosed\n").unwrap_err();
+    assert!(err.to_string().contains("document 1"), "{err}");
+}
+
+#[test]
+fn in_place_steps_without_a_resume_marker_are_warned_about() {
+    let yaml = r#"
+version: 1
+name: guards
+steps:
+  - id: loud
+    kind: normalize_volume
+    clip_path: "work/a.mp4"
+  - id: self_guarded
+    kind: normalize_volume
+    clip_path: "work/b.mp4"
+    resume_output: "work/b.mp4"
+  - id: marked
+    kind: normalize_volume
+    clip_path: "work/c.mp4"
+    resume_output: "work/c.mp4.done"
+  - id: remix
+    kind: audio_mix
+    base_clip: "work/d.mp4"
+    overlay_audio: "music.wav"
+    output_path: "work/d.mp4"
+  - id: mix
+    kind: audio_mix
+    base_clip: "work/d.mp4"
+    overlay_audio: "music.wav"
+    output_path: "work/e.mp4"
+"#;
+    let cfg = WorkflowConfig::from_yaml(yaml).unwrap();
+    let warnings = cfg.resume_guard_warnings();
+    assert_eq!(warnings.len(), 3, "{warnings:?}");
+    assert!(warnings[0].starts_with("step `loud` modifies `work/a.mp4` in place"));
+    assert!(warnings[0].contains("resume_output: \"work/a.mp4.done\""));
+    assert!(warnings[1].starts_with("step `self_guarded` uses the file"));
+    assert!(warnings[2].starts_with("step `remix` modifies `work/d.mp4`"));
+}
+
+const INPUTS_YAML: &str = r#"
+version: 1
+name: explainer
+inputs:
+  - name: topic
+    description: "What the video explains"
+  - name: audience
+  - name: voice
+    default: "assets/voice.wav"
+steps:
+  - id: script
+    kind: write_file
+    path: "work/script.txt"
+    content: "{{topic}} for {{audience}}"
+"#;
+
+#[test]
+fn missing_required_inputs_are_listed_with_descriptions() {
+    let cfg = WorkflowConfig::from_yaml(INPUTS_YAML).unwrap();
+    let err = cfg.check_inputs(&BTreeMap::new()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Workflow `explainer` is missing 2 required input(s); pass them with --var NAME=VALUE:\n  \
+         topic: What the video explains\n  audience"
+    );
+
+    let supplied = BTreeMap::from([
+        ("topic".to_string(), "DAGs".to_string()),
+        ("audience".to_string(), "beginners".to_string()),
+    ]);
+    cfg.check_inputs(&supplied).unwrap();
+    assert_eq!(
+        cfg.input_defaults(),
+        BTreeMap::from([("voice".to_string(), "assets/voice.wav".to_string())])
+    );
+}
+
+#[test]
+fn input_also_in_vars_errors() {
+    let yaml = INPUTS_YAML.replace("inputs:", "vars:\n  audience: \"everyone\"\ninputs:");
+    let err = WorkflowConfig::from_yaml(&yaml).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("`audience` is declared in both inputs and vars"),
+        "{err}"
+    );
+}
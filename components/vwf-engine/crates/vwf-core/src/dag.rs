@@ -0,0 +1,183 @@
+//! DAG tasks built from workflow steps.
+
+use anyhow::{Result, bail};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use vwf_config::{StepConfig, StepKind, WorkflowConfig};
+use vwf_dag::{
+    Constraint, DagRunner, InputSpec, OutputSpec, Scheduler, SchedulerEvent, StateStore, Task,
+    WorkflowState,
+};
+use vwf_render::render_template;
+use vwf_runtime::Runtime;
+use vwf_steps::execute_step;
+
+use super::engine::Runner;
+
+/// The constraint a step kind gets unless the step sets its own.
+///
+/// The GPU services share one GPU, so image and video generation hold the
+/// `gpu` resource and never run at the same time; TTS has its own server.
+pub fn default_constraint(kind: &StepKind) -> Constraint {
+    let resource = match kind {
+        StepKind::TextToImage | StepKind::ImageToVideo | StepKind::TextToVideo => Some("gpu"),
+        StepKind::TtsGenerate => Some("tts"),
+        _ => None,
+    };
+    Constraint {
+        resource: resource.map(String::from),
+        ..Default::default()
+    }
+}
+
+/// The DAG task for a step, with its kind's default constraint overridden
+/// by the step's own `resource` and `sequential_group`.
+pub fn step_task(step: &StepConfig) -> Task {
+    let kind = serde_json::to_value(&step.kind)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default();
+    let mut task = Task::new(&step.id, kind);
+    task.name = step.display_name().to_string();
+    task.constraints = default_constraint(&step.kind);
+    if let Some(resource) = &step.resource {
+        task.constraints.resource = Some(resource.clone());
+    }
+    if let Some(group) = &step.sequential_group {
+        task.constraints.sequential_group = Some(group.clone());
+    }
+    task.config = step.payload.clone();
+    task
+}
+
+/// Build reactive-engine state from a linear workflow.
+///
+/// Each step becomes a task (see [`step_task`]). The files a step writes
+/// become its outputs, optional ones as listed in `optional_outputs`. The
+/// files it reads become inputs: required when another step writes them,
+/// optional when they come from outside the workflow. A `depends_on` edge
+/// with no file in common becomes a marker artifact `step:<id>`, available
+/// once that step completes. Paths are rendered with the workflow's `vars`
+/// where possible.
+pub fn workflow_state(cfg: &WorkflowConfig) -> WorkflowState {
+    let path = |raw: &str| {
+        let rendered = render_template(raw, &cfg.vars).unwrap_or_else(|_| raw.to_string());
+        rendered.trim().trim_start_matches("./").to_string()
+    };
+    let outputs: BTreeMap<&str, Vec<String>> = cfg
+        .steps
+        .iter()
+        .map(|s| {
+            (
+                s.id.as_str(),
+                s.output_paths().into_iter().map(path).collect(),
+            )
+        })
+        .collect();
+    let produced: BTreeSet<&String> = outputs.values().flatten().collect();
+    let depended_on: BTreeSet<&str> = cfg
+        .steps
+        .iter()
+        .flat_map(|s| s.depends_on.iter().map(String::as_str))
+        .collect();
+
+    let mut state = WorkflowState::new(&cfg.name, cfg.version);
+    for step in &cfg.steps {
+        let mut task = step_task(step);
+        let optional: BTreeSet<String> = step.optional_outputs.iter().map(|p| path(p)).collect();
+        for artifact in &outputs[step.id.as_str()] {
+            task = if optional.contains(artifact) {
+                task.with_optional_output(artifact)
+            } else {
+                task.with_output(artifact)
+            };
+        }
+        if depended_on.contains(step.id.as_str()) {
+            task.outputs.push(OutputSpec {
+                artifact: step_marker(&step.id),
+                primary: false,
+                optional: true,
+            });
+        }
+        let inputs: BTreeSet<String> = step.input_paths().into_iter().map(path).collect();
+        for artifact in inputs {
+            task.inputs.push(if produced.contains(&artifact) {
+                InputSpec::Required { artifact }
+            } else {
+                InputSpec::Optional {
+                    artifact,
+                    default: None,
+                }
+            });
+        }
+        for dep in &step.depends_on {
+            let shares_file = outputs.get(dep.as_str()).is_some_and(|files| {
+                task.inputs.iter().any(
+                    |i| matches!(i, InputSpec::Required { artifact } if files.contains(artifact)),
+                )
+            });
+            if !shares_file {
+                task.inputs.push(InputSpec::Required {
+                    artifact: step_marker(dep),
+                });
+            }
+        }
+        state.add_task(task);
+    }
+    Scheduler::default().update_task_statuses(&mut state);
+    state
+}
+
+/// Run a workflow on the reactive engine ([`DagRunner`]), keeping task
+/// state in `workdir/state.json` between runs.
+///
+/// The workflow is [resolved](Runner::resolve) first, so templates can't
+/// use vars that steps export. A failed step only stalls the steps that
+/// need its outputs. The next call picks up from the saved state: finished
+/// steps don't run again and failed ones stay failed until reset (see
+/// [`WorkflowState::reset_failed`]). Saved state for a different workflow
+/// or a different set of steps is replaced. Returns the saved state.
+///
+/// There is nobody to ask, so `confirm: true` steps are refused.
+pub fn run_incremental(
+    rt: &mut dyn Runtime,
+    cfg: &WorkflowConfig,
+    extra: BTreeMap<String, String>,
+    workdir: &Path,
+    on_event: impl FnMut(SchedulerEvent),
+) -> Result<WorkflowState> {
+    let resolved = Runner::resolve(cfg, extra)?;
+    if let Some(step) = resolved.steps.iter().find(|s| s.confirm) {
+        bail!(
+            "step `{}` needs confirmation, which incremental runs can't ask for",
+            step.id
+        );
+    }
+    let store = StateStore::new(workdir);
+    let fresh = workflow_state(&resolved);
+    let mut state = match store.load_reconciled()? {
+        Some(saved)
+            if saved.workflow_name == fresh.workflow_name
+                && saved.tasks.keys().eq(fresh.tasks.keys()) =>
+        {
+            saved
+        }
+        _ => fresh,
+    };
+    let steps: BTreeMap<&str, &StepConfig> =
+        resolved.steps.iter().map(|s| (s.id.as_str(), s)).collect();
+    let result = DagRunner::new(workdir).run(
+        &mut state,
+        |task| execute_step(rt, &resolved.vars, steps[task.id.as_str()]).map(|_| ()),
+        on_event,
+    );
+    store.save(&state)?;
+    result?;
+    Ok(state)
+}
+
+/// Artifact standing for "step `id` has completed".
+fn step_marker(id: &str) -> String {
+    format!("step:{id}")
+}
@@ -9,22 +9,95 @@
 
 use anyhow::{Context, Result, bail};
 use chrono::{DateTime, Utc};
-use std::collections::{BTreeMap, HashMap, HashSet};
-use std::time::Instant;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
-use vwf_config::{StepConfig, WorkflowConfig};
+use vwf_config::{StepConfig, StepKind, WorkflowConfig};
 use vwf_render::render_template;
-use vwf_runtime::{output_is_valid, Runtime};
+use vwf_runtime::{DryRunRuntime, FsRuntime, MockLlmClient, Runtime, output_is_valid};
 use vwf_steps::execute_step;
 
-use super::report::{RunReport, StepReport, StepStatus};
+use super::dag::workflow_state;
+use super::progress::{EventCallback, Progress, RunEvent};
+use super::report::{REPORT_SCHEMA_VERSION, RunReport, StepReport, StepStatus};
+
+/// `output_dir` used when the workflow doesn't set one.
+const DEFAULT_OUTPUT_DIR: &str = "output";
 
 /// Options for workflow execution.
 #[derive(Default)]
 pub struct RunOptions {
     /// Skip steps whose output_path already exists and is valid.
     pub resume: bool,
+    /// Wall-clock cap on the whole run. Once exceeded, no new steps start
+    /// and the remaining ones are reported as blocked.
+    pub deadline: Option<Duration>,
+    /// Called as steps start and finish, e.g. to drive a progress bar.
+    pub on_event: Option<EventCallback>,
+    /// Suppress the per-step `[RUNNING]`/`[OK]` log lines.
+    pub quiet: bool,
+    /// Report this run id instead of a random one.
+    pub run_id: Option<Uuid>,
+    /// Derive the run id from the workflow and resolved vars, so identical
+    /// inputs produce identical reports. Ignored when `run_id` is set.
+    pub deterministic: bool,
+    /// Reports of an earlier run to continue, matched to workflows by name.
+    /// Steps that were ok or skipped there are skipped without checking
+    /// their outputs, and the vars they exported are restored.
+    pub previous: Vec<RunReport>,
+    /// Asked before each `confirm: true` step, with a summary of what it
+    /// will do; a step it declines is skipped and its dependents blocked.
+    /// Without one, such steps run unasked.
+    pub confirm: Option<ConfirmCallback>,
+    /// Step kinds that can't run right now, with the reason. Their steps
+    /// are reported as blocked, as are their dependents, and everything
+    /// else runs.
+    pub unavailable_kinds: BTreeMap<StepKind, String>,
+    /// Most steps of a kind let into one batch of runnable steps, e.g. one
+    /// `text_to_image` at a time for a single GPU. The rest wait for a later
+    /// batch; kinds without a limit are unrestricted. A limit of 0 counts as 1.
+    pub kind_limits: BTreeMap<StepKind, usize>,
+    /// Before running anything, create the parent directory of every file a
+    /// step declares it writes and check it accepts new files. Problems are
+    /// reported together, by step id, and nothing runs.
+    pub check_outputs: bool,
+}
+
+/// Callback deciding whether a `confirm: true` step may run.
+pub type ConfirmCallback = Box<dyn FnMut(&StepConfig, &str) -> bool>;
+
+impl RunOptions {
+    fn emit(&mut self, event: RunEvent, progress: Progress) {
+        if let Some(cb) = self.on_event.as_mut() {
+            cb(&event, &progress);
+        }
+    }
+}
+
+/// Error context holding the report of a run that finished with failed or
+/// blocked steps; displays as the report's JSON.
+#[derive(Debug)]
+pub struct FailedRun(pub RunReport);
+
+impl std::fmt::Display for FailedRun {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&serde_json::to_string_pretty(&self.0).unwrap_or_default())
+    }
+}
+
+/// Error context on a pipeline that stopped at a failed workflow, holding
+/// the reports of the workflows that completed before it.
+#[derive(Debug)]
+pub struct CompletedRuns(pub Vec<RunReport>);
+
+impl std::fmt::Display for CompletedRuns {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<&str> = self.0.iter().map(|r| r.workflow_name.as_str()).collect();
+        write!(f, "pipeline stopped after completing {}", names.join(", "))
+    }
 }
 
 pub struct Runner;
@@ -42,17 +115,282 @@ impl Runner {
         rt: &mut dyn Runtime,
         cfg: &WorkflowConfig,
         extra: BTreeMap<String, String>,
-        opts: RunOptions,
+        mut opts: RunOptions,
     ) -> Result<RunReport> {
-        let run_id = Uuid::new_v4();
-        let started_at = Utc::now();
-        let mut vars = cfg.vars.clone();
+        run_workflow(rt, cfg, extra, &mut opts)
+    }
+
+    /// Check a workflow's structure and step dependency graph without running it.
+    pub fn validate(cfg: &WorkflowConfig) -> Result<()> {
+        cfg.validate()?;
+        validate_dag(&with_inferred_deps(cfg)?.steps)
+    }
+
+    /// The workflow as another executor would need it: vars resolved
+    /// (config and input defaults, then `extra`), every `{{var}}` in the
+    /// description and steps rendered, and `needs` / `infer_depends_on`
+    /// turned into explicit `depends_on` edges.
+    ///
+    /// Fails on a missing required input or a template whose var isn't
+    /// known before the run, such as one a step exports.
+    pub fn resolve(
+        cfg: &WorkflowConfig,
+        extra: BTreeMap<String, String>,
+    ) -> Result<WorkflowConfig> {
+        let mut resolved = with_inferred_deps(cfg)?.into_owned();
+        let mut vars = resolved.vars.clone();
+        vars.extend(resolved.input_defaults());
         vars.extend(extra);
+        resolved.check_inputs(&vars)?;
+        if let Some(description) = &resolved.description {
+            resolved.description = Some(render_template(description, &vars)?);
+        }
+        for step in &mut resolved.steps {
+            let mut value = serde_json::to_value(&*step)?;
+            render_strings(&mut value, &vars)
+                .with_context(|| format!("render step `{}`", step.id))?;
+            *step = serde_json::from_value(value)?;
+        }
+        resolved.vars = vars;
+        resolved.inputs.clear();
+        resolved.infer_depends_on = false;
+        Self::validate(&resolved)?;
+        Ok(resolved)
+    }
+
+    /// Run workflows back to back against the same runtime.
+    ///
+    /// Stops at the first workflow that fails; the reports of the workflows
+    /// before it are attached to the error as [`CompletedRuns`]. Vars
+    /// resolved by earlier workflows are passed to later ones as overrides,
+    /// so `extra` and upstream values win over a later workflow's own
+    /// defaults.
+    pub fn run_pipeline(
+        rt: &mut dyn Runtime,
+        pipeline: &[WorkflowConfig],
+        extra: BTreeMap<String, String>,
+        mut opts: RunOptions,
+    ) -> Result<Vec<RunReport>> {
+        let mut carried = extra;
+        let mut reports = vec![];
+        for cfg in pipeline {
+            let report = match run_workflow(rt, cfg, carried, &mut opts) {
+                Ok(report) => report,
+                Err(e) if reports.is_empty() => return Err(e),
+                Err(e) => return Err(e.context(CompletedRuns(reports))),
+            };
+            carried = report.vars.clone();
+            reports.push(report);
+        }
+        Ok(reports)
+    }
+
+    /// Parse `yaml` and run it against the real filesystem in `workdir`.
+    ///
+    /// LLM steps use an echoing mock client; build an `FsRuntime` yourself
+    /// to plug in a real provider.
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use vwf_core::{RunOptions, Runner};
+    ///
+    /// let yaml = r#"
+    /// version: 1
+    /// name: hello
+    /// steps:
+    ///   - id: greet
+    ///     kind: write_file
+    ///     path: "hello.txt"
+    ///     content: "Hello {{who}}"
+    /// "#;
+    /// let workdir = std::env::temp_dir().join("vwf_run_yaml_doctest");
+    /// let vars = BTreeMap::from([("who".to_string(), "world".to_string())]);
+    /// let report = Runner::run_yaml(yaml, &workdir, vars, RunOptions::default()).unwrap();
+    /// assert_eq!(report.steps.len(), 1);
+    /// let text = std::fs::read_to_string(workdir.join("hello.txt")).unwrap();
+    /// assert_eq!(text, "Hello world");
+    /// # std::fs::remove_dir_all(&workdir).unwrap();
+    /// ```
+    pub fn run_yaml(
+        yaml: &str,
+        workdir: impl AsRef<Path>,
+        extra: BTreeMap<String, String>,
+        opts: RunOptions,
+    ) -> Result<RunReport> {
+        let cfg = WorkflowConfig::from_yaml(yaml)?;
+        let workdir = workdir.as_ref();
+        std::fs::create_dir_all(workdir)
+            .with_context(|| format!("create {}", workdir.display()))?;
+        let mut rt = FsRuntime::new(workdir, Box::new(MockLlmClient::echo()));
+        Self::run_with_options(&mut rt, &cfg, extra, opts)
+    }
+
+    /// Parse `yaml` and run it without touching disk.
+    ///
+    /// Returns the report plus the runtime so callers can inspect
+    /// `planned_dirs` and `planned_writes`.
+    pub fn run_yaml_dry(
+        yaml: &str,
+        workdir: impl AsRef<Path>,
+        extra: BTreeMap<String, String>,
+        opts: RunOptions,
+    ) -> Result<(RunReport, DryRunRuntime)> {
+        let cfg = WorkflowConfig::from_yaml(yaml)?;
+        let mut rt = DryRunRuntime::new(workdir.as_ref(), Box::new(MockLlmClient::echo()));
+        let report = Self::run_with_options(&mut rt, &cfg, extra, opts)?;
+        Ok((report, rt))
+    }
+}
+
+fn run_workflow(
+    rt: &mut dyn Runtime,
+    cfg: &WorkflowConfig,
+    extra: BTreeMap<String, String>,
+    opts: &mut RunOptions,
+) -> Result<RunReport> {
+    let cfg = &*with_inferred_deps(cfg)?;
+    let started_at = Utc::now();
+    let mut vars = cfg.vars.clone();
+    vars.extend(cfg.input_defaults());
+    log_var_sources(&vars, &extra);
+    vars.extend(extra);
+    let run_id = match opts.run_id {
+        Some(id) => id,
+        None if opts.deterministic => deterministic_run_id(cfg, &vars),
+        None => Uuid::new_v4(),
+    };
+
+    // Validate the workflow DAG before execution
+    validate_dag(&cfg.steps)?;
+    if opts.resume {
+        for warning in cfg.resume_guard_warnings() {
+            eprintln!("WARNING: {warning}");
+        }
+    }
+
+    if let Some(prev) = previous_report(opts, &cfg.name) {
+        for (k, v) in &prev.vars {
+            vars.entry(k.clone()).or_insert_with(|| v.clone());
+        }
+    }
+    cfg.check_inputs(&vars)?;
+
+    if opts.check_outputs {
+        check_outputs(rt, cfg, &vars)?;
+    }
+
+    execute_dag(rt, &mut vars, cfg, run_id, started_at, opts)
+}
+
+/// Fail if any declared output (see [`workflow_state`]) can't be written.
+///
+/// Paths that still hold a template after rendering with `vars` (e.g. a var
+/// exported by an earlier step) and glob patterns are not checked.
+fn check_outputs(
+    rt: &mut dyn Runtime,
+    cfg: &WorkflowConfig,
+    vars: &BTreeMap<String, String>,
+) -> Result<()> {
+    let resolved = WorkflowConfig {
+        vars: vars.clone(),
+        ..cfg.clone()
+    };
+    let state = workflow_state(&resolved);
+    let mut problems = vec![];
+    for step in &cfg.steps {
+        // Optional outputs are `depends_on` markers, not files
+        let outputs = state.tasks[&step.id]
+            .outputs
+            .iter()
+            .filter(|o| !o.optional && !o.is_pattern() && !o.artifact.contains("{{"));
+        for output in outputs {
+            if let Err(e) = rt.check_writable(&output.artifact) {
+                problems.push(format!("step `{}`: {e:#}", step.id));
+            }
+        }
+    }
+    if problems.is_empty() {
+        return Ok(());
+    }
+    bail!(
+        "{} output path(s) not writable:\n  {}",
+        problems.len(),
+        problems.join("\n  ")
+    )
+}
 
-        // Validate the workflow DAG before execution
-        validate_dag(&cfg.steps)?;
+/// Render every string in `value` in place.
+fn render_strings(value: &mut serde_json::Value, vars: &BTreeMap<String, String>) -> Result<()> {
+    match value {
+        serde_json::Value::String(s) => *s = render_template(s, vars)?,
+        serde_json::Value::Array(items) => {
+            for item in items {
+                render_strings(item, vars)?;
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for item in map.values_mut() {
+                render_strings(item, vars)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
 
-        execute_dag(rt, &vars, &cfg.steps, run_id, &cfg.name, started_at, &opts)
+/// The earlier run of workflow `name` being continued, if any.
+fn previous_report<'a>(opts: &'a RunOptions, name: &str) -> Option<&'a RunReport> {
+    opts.previous.iter().find(|r| r.workflow_name == name)
+}
+
+/// The workflow with `depends_on` edges added for each step's `needs`, and
+/// inferred from file paths if it opted in. `video_concat` steps asking
+/// for chapters get their titles from the segments.
+fn with_inferred_deps(cfg: &WorkflowConfig) -> Result<Cow<'_, WorkflowConfig>> {
+    let has_needs = cfg.steps.iter().any(|s| !s.needs.is_empty());
+    let has_chapters = cfg.steps.iter().any(|s| s.payload["chapters"] == true);
+    if !cfg.infer_depends_on && !has_needs && !has_chapters {
+        return Ok(Cow::Borrowed(cfg));
+    }
+    let mut inferred = cfg.clone();
+    inferred.resolve_needs()?;
+    inferred.resolve_chapters()?;
+    if inferred.infer_depends_on {
+        for problem in inferred.infer_dependencies() {
+            eprintln!("WARNING: {problem}");
+        }
+    }
+    Ok(Cow::Owned(inferred))
+}
+
+/// Stable run id for a workflow and its resolved vars.
+///
+/// FNV-1a (128-bit) over the serialized config and vars, stamped as a
+/// version 8 UUID.
+pub fn deterministic_run_id(cfg: &WorkflowConfig, vars: &BTreeMap<String, String>) -> Uuid {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    let input = serde_json::to_vec(&(cfg, vars)).unwrap_or_default();
+    let hash = input
+        .iter()
+        .fold(OFFSET, |h, b| (h ^ u128::from(*b)).wrapping_mul(PRIME));
+    uuid::Builder::from_custom_bytes(hash.to_be_bytes()).into_uuid()
+}
+
+/// Log each resolved var with the source that won (config default or override).
+fn log_var_sources(defaults: &BTreeMap<String, String>, extra: &BTreeMap<String, String>) {
+    let keys: BTreeSet<&String> = defaults.keys().chain(extra.keys()).collect();
+    for key in keys {
+        match (extra.get(key), defaults.get(key)) {
+            (Some(value), Some(default)) => tracing::debug!(
+                "var {key} = {value:?} (source: override, replaces config default {default:?})"
+            ),
+            (Some(value), None) => tracing::debug!("var {key} = {value:?} (source: override)"),
+            (None, Some(value)) => {
+                tracing::debug!("var {key} = {value:?} (source: config default)")
+            }
+            (None, None) => {}
+        }
     }
 }
 
@@ -133,13 +471,13 @@ fn detect_cycle<'a>(
 /// failed step are marked as blocked.
 fn execute_dag(
     rt: &mut dyn Runtime,
-    vars: &BTreeMap<String, String>,
-    steps: &[StepConfig],
+    vars: &mut BTreeMap<String, String>,
+    cfg: &WorkflowConfig,
     run_id: Uuid,
-    name: &str,
     started: DateTime<Utc>,
-    opts: &RunOptions,
+    opts: &mut RunOptions,
 ) -> Result<RunReport> {
+    let steps = &cfg.steps;
     // Build step lookup and dependency info
     let step_map: HashMap<&str, &StepConfig> = steps.iter().map(|s| (s.id.as_str(), s)).collect();
 
@@ -148,11 +486,36 @@ fn execute_dag(
     let mut failed: HashSet<String> = HashSet::new();
     let mut blocked: HashSet<String> = HashSet::new();
     let mut reports: HashMap<String, StepReport> = HashMap::new();
+    let done_before: HashSet<String> = previous_report(opts, &cfg.name)
+        .map(|prev| {
+            prev.steps
+                .iter()
+                .filter(|s| matches!(s.status, StepStatus::Ok | StepStatus::Skipped))
+                .map(|s| s.id.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    let clock = Instant::now();
+    let deadline = opts.deadline.map(|d| clock + d);
+    let progress = |reports: &HashMap<String, StepReport>, running: Option<&str>| Progress {
+        total: steps.len(),
+        completed: reports.len(),
+        failed: reports
+            .values()
+            .filter(|r| r.status == StepStatus::Failed)
+            .count(),
+        running: running.into_iter().map(String::from).collect(),
+        elapsed: clock.elapsed(),
+    };
+    let mut deadline_hit = false;
 
     // Keep running while we can make progress
-    let mut last_runnable_count = usize::MAX;
-    loop {
-        let runnable = find_runnable(steps, &completed, &failed, &blocked);
+    while !deadline_hit {
+        let runnable = limit_kinds(
+            find_runnable(steps, &completed, &failed, &blocked),
+            &step_map,
+            &opts.kind_limits,
+        );
 
         if runnable.is_empty() {
             // No more runnable steps - check for infinite postponement
@@ -175,37 +538,105 @@ fn execute_dag(
         }
 
         // Safety check: ensure we're making progress
-        if runnable.len() == last_runnable_count {
-            eprintln!("WARNING: No progress made in DAG execution loop");
-            break;
-        }
-        last_runnable_count = runnable.len();
+        let processed_before = reports.len();
 
         for step_id in runnable {
             let step = step_map[step_id.as_str()];
 
-            // Check resume skip
-            if opts.resume && should_skip(rt, vars, step) {
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                eprintln!("  [DEADLINE] run exceeded its deadline, not starting new steps");
+                deadline_hit = true;
+                break;
+            }
+
+            // Check continue/resume skip
+            let skip_reason = if done_before.contains(&step_id) {
+                Some("done in previous run".to_string())
+            } else if opts.resume {
+                resume_skip_reason(rt, vars, step)
+            } else {
+                None
+            };
+            if let Some(reason) = skip_reason {
+                if !opts.quiet {
+                    eprintln!("  [SKIPPED] {} ({reason})", step.display_name());
+                }
                 completed.insert(step_id.clone());
-                reports.insert(step_id.clone(), skipped_report(step));
-                eprintln!("  [SKIPPED] {}", step_id);
+                reports.insert(step_id.clone(), skipped_report(step, reason));
+                let event = RunEvent::StepFinished {
+                    id: step_id.clone(),
+                    status: StepStatus::Skipped,
+                };
+                opts.emit(event, progress(&reports, None));
+                continue;
+            }
+
+            if let Some(reason) = opts.unavailable_kinds.get(&step.kind) {
+                if !opts.quiet {
+                    eprintln!("  [BLOCKED] {} ({reason})", step.display_name());
+                }
+                blocked.insert(step_id.clone());
+                reports.insert(step_id.clone(), blocked_report(step, reason.clone()));
+                let event = RunEvent::StepFinished {
+                    id: step_id.clone(),
+                    status: StepStatus::Blocked,
+                };
+                opts.emit(event, progress(&reports, None));
+                continue;
+            }
+
+            if step.confirm
+                && let Some(confirm) = opts.confirm.as_mut()
+                && !confirm(step, &confirm_summary(vars, step))
+            {
+                let reason = "declined at confirmation prompt".to_string();
+                if !opts.quiet {
+                    eprintln!("  [SKIPPED] {} ({reason})", step.display_name());
+                }
+                // Blocked, so its dependents are reported as blocked by it
+                blocked.insert(step_id.clone());
+                reports.insert(step_id.clone(), skipped_report(step, reason));
+                let event = RunEvent::StepFinished {
+                    id: step_id.clone(),
+                    status: StepStatus::Skipped,
+                };
+                opts.emit(event, progress(&reports, None));
                 continue;
             }
 
             // Run the step
-            eprintln!("  [RUNNING] {} ({:?})", step_id, step.kind);
+            if !opts.quiet {
+                eprintln!("  [RUNNING] {} ({:?})", step.display_name(), step.kind);
+            }
+            opts.emit(
+                RunEvent::StepStarted {
+                    id: step_id.clone(),
+                },
+                progress(&reports, Some(&step_id)),
+            );
             let report = run_step(rt, vars, step);
             let status = report.status.clone();
 
             match &status {
-                StepStatus::Ok => eprintln!("  [OK] {} ({}ms)", step_id, report.duration_ms),
-                StepStatus::Failed => {
-                    eprintln!("  [FAILED] {}: {}", step_id, report.error.as_deref().unwrap_or("unknown"));
+                StepStatus::Ok if !opts.quiet => {
+                    eprintln!("  [OK] {} ({}ms)", step.display_name(), report.duration_ms)
+                }
+                StepStatus::Failed if !opts.quiet => {
+                    eprintln!(
+                        "  [FAILED] {}: {}",
+                        step.display_name(),
+                        report.error.as_deref().unwrap_or("unknown")
+                    );
                 }
                 _ => {}
             }
 
             reports.insert(step_id.clone(), report);
+            let event = RunEvent::StepFinished {
+                id: step_id.clone(),
+                status: status.clone(),
+            };
+            opts.emit(event, progress(&reports, None));
 
             match status {
                 StepStatus::Ok | StepStatus::Skipped => {
@@ -227,6 +658,11 @@ fn execute_dag(
                 }
             }
         }
+
+        if !deadline_hit && reports.len() == processed_before {
+            eprintln!("WARNING: No progress made in DAG execution loop");
+            break;
+        }
     }
 
     // Generate blocked reports for any steps we never ran
@@ -239,26 +675,41 @@ fn execute_dag(
                 .filter(|d| failed.contains(*d) || blocked.contains(*d))
                 .map(|s| s.as_str())
                 .collect();
+            let error = if blocking_deps.is_empty() && deadline_hit {
+                "deadline exceeded".to_string()
+            } else {
+                format!("Blocked by: {}", blocking_deps.join(", "))
+            };
 
             reports.insert(
                 step.id.clone(),
                 StepReport {
                     id: step.id.clone(),
                     kind: format!("{:?}", step.kind),
+                    label: step.label.clone(),
                     status: StepStatus::Blocked,
                     started_at: now,
                     finished_at: now,
-                    error: Some(format!("Blocked by: {}", blocking_deps.join(", "))),
+                    error: Some(error),
+                    skip_reason: None,
                     duration_ms: 0,
+                    segment: None,
+                    warnings: vec![],
+                    retries: 0,
                 },
             );
         }
     }
 
     // Build ordered report list (preserve original step order)
+    let segments = cfg.step_segments();
     let step_reports: Vec<StepReport> = steps
         .iter()
         .filter_map(|s| reports.remove(&s.id))
+        .map(|mut r| {
+            r.segment = segments.get(r.id.as_str()).map(|seg| seg.to_string());
+            r
+        })
         .collect();
 
     // Print summary
@@ -271,6 +722,16 @@ fn execute_dag(
     eprintln!("Summary: {} ok, {} skipped, {} failed, {} blocked",
               ok_count, skipped_count, failed_count, blocked_count);
 
+    if step_reports.iter().any(|r| !r.warnings.is_empty()) {
+        eprintln!();
+        eprintln!("Warnings:");
+        for report in &step_reports {
+            for warning in &report.warnings {
+                eprintln!("  - {}: {warning}", report.id);
+            }
+        }
+    }
+
     if blocked_count > 0 {
         eprintln!();
         eprintln!("Blocked steps (waiting on failed dependencies):");
@@ -280,29 +741,94 @@ fn execute_dag(
             }
         }
         eprintln!();
-        eprintln!("To unblock: fix the failed step(s), then re-run with --resume");
+        eprintln!("To unblock: fix the failed step(s), then re-run with --continue");
     }
 
-    let has_failures = failed_count > 0 || blocked_count > 0;
+    // Failures of continue_on_error steps alone leave the run successful
+    let tolerated = step_reports
+        .iter()
+        .filter(|r| r.status == StepStatus::Failed && step_map[r.id.as_str()].continue_on_error)
+        .count();
+    if tolerated > 0 {
+        eprintln!();
+        eprintln!("{tolerated} failure(s) tolerated by continue_on_error");
+    }
+    let has_failures = failed_count > tolerated || blocked_count > 0;
+    let deliverables = collect_deliverables(rt, vars, steps, &step_reports);
 
     let report = RunReport {
+        schema_version: REPORT_SCHEMA_VERSION,
         run_id,
-        workflow_name: name.into(),
+        workflow_name: cfg.name.clone(),
         started_at: started,
         finished_at: Utc::now(),
         steps: step_reports,
         vars: vars.clone(),
+        deliverables,
     };
 
     if has_failures {
         // Return error but include full report in context
-        Err(anyhow::anyhow!("Workflow completed with failures"))
-            .context(serde_json::to_string_pretty(&report).unwrap_or_default())
+        Err(anyhow::anyhow!("Workflow completed with failures")).context(FailedRun(report))
     } else {
         Ok(report)
     }
 }
 
+/// Copy the outputs of finished `deliverable` steps into `output_dir`.
+///
+/// Returns the copies, relative to the workdir. A step's `resume_output`
+/// marker is not a deliverable. Outputs that can't be copied are reported
+/// and left out.
+fn collect_deliverables(
+    rt: &mut dyn Runtime,
+    vars: &BTreeMap<String, String>,
+    steps: &[StepConfig],
+    reports: &[StepReport],
+) -> Vec<String> {
+    let output_dir = vars
+        .get("output_dir")
+        .map_or(DEFAULT_OUTPUT_DIR, String::as_str)
+        .trim_end_matches('/');
+    let finished: HashSet<&str> = reports
+        .iter()
+        .filter(|r| matches!(r.status, StepStatus::Ok | StepStatus::Skipped))
+        .map(|r| r.id.as_str())
+        .collect();
+    let mut collected = vec![];
+    for step in steps
+        .iter()
+        .filter(|s| s.deliverable && finished.contains(s.id.as_str()))
+    {
+        let marker = step.resume_output.as_deref();
+        for path in step
+            .output_paths()
+            .into_iter()
+            .filter(|p| Some(*p) != marker)
+        {
+            let copied = render_template(path, vars).and_then(|from| {
+                let name = Path::new(&from)
+                    .file_name()
+                    .with_context(|| format!("`{from}` has no file name"))?;
+                let to = format!("{output_dir}/{}", name.to_string_lossy());
+                rt.copy_file(&from, &to)?;
+                Ok(to)
+            });
+            match copied {
+                Ok(to) => collected.push(to),
+                Err(e) => eprintln!(
+                    "WARNING: deliverable `{path}` of step `{}` not collected: {e:#}",
+                    step.id
+                ),
+            }
+        }
+    }
+    if !collected.is_empty() {
+        eprintln!("Deliverables: {} file(s) in {output_dir}/", collected.len());
+    }
+    collected
+}
+
 /// Find steps that can be run right now.
 /// A step is runnable if:
 /// - It hasn't been completed, failed, or blocked
@@ -327,6 +853,28 @@ fn find_runnable(
         .collect()
 }
 
+/// Drop runnable steps beyond their kind's limit; they stay runnable for
+/// the next batch.
+fn limit_kinds(
+    runnable: Vec<String>,
+    step_map: &HashMap<&str, &StepConfig>,
+    limits: &BTreeMap<StepKind, usize>,
+) -> Vec<String> {
+    let mut taken: HashMap<&StepKind, usize> = HashMap::new();
+    runnable
+        .into_iter()
+        .filter(|id| {
+            let kind = &step_map[id.as_str()].kind;
+            let Some(limit) = limits.get(kind) else {
+                return true;
+            };
+            let count = taken.entry(kind).or_default();
+            *count += 1;
+            *count <= (*limit).max(1)
+        })
+        .collect()
+}
+
 /// Find all steps that directly or transitively depend on the given step.
 fn find_all_dependents(steps: &[StepConfig], step_id: &str) -> HashSet<String> {
     let mut dependents = HashSet::new();
@@ -344,37 +892,108 @@ fn find_all_dependents(steps: &[StepConfig], step_id: &str) -> HashSet<String> {
     dependents
 }
 
-fn should_skip(rt: &dyn Runtime, vars: &BTreeMap<String, String>, step: &StepConfig) -> bool {
-    let Some(ref output) = step.resume_output else {
-        return false;
-    };
-    let Ok(path) = render_template(output, vars) else {
-        return false;
-    };
-    let full_path = rt.workdir().join(&path);
-    output_is_valid(&full_path)
+/// Why `--resume` can skip the step, or `None` if it has to run.
+fn resume_skip_reason(
+    rt: &dyn Runtime,
+    vars: &BTreeMap<String, String>,
+    step: &StepConfig,
+) -> Option<String> {
+    let output = step.resume_output.as_ref()?;
+    let path = render_template(output, vars).ok()?;
+    output_is_valid(&rt.workdir().join(&path)).then(|| format!("output exists: {path}"))
+}
+
+/// What a step is about to do, for a confirmation prompt.
+fn confirm_summary(vars: &BTreeMap<String, String>, step: &StepConfig) -> String {
+    let render = |s: &str| render_template(s, vars).unwrap_or_else(|_| s.to_string());
+    let mut lines = vec![format!("{} ({:?})", step.display_name(), step.kind)];
+    if let Some(program) = step.payload.get("program").and_then(|p| p.as_str()) {
+        let args = step.payload.get("args").and_then(|a| a.as_array());
+        let words = args.into_iter().flatten().filter_map(|a| a.as_str());
+        let command: Vec<String> = std::iter::once(program).chain(words).map(render).collect();
+        lines.push(format!("runs: {}", command.join(" ")));
+    }
+    for path in step.output_paths() {
+        lines.push(format!("writes: {}", render(path)));
+    }
+    lines.join("\n")
 }
 
-fn skipped_report(step: &StepConfig) -> StepReport {
+fn skipped_report(step: &StepConfig, reason: String) -> StepReport {
     let now = Utc::now();
     StepReport {
         id: step.id.clone(),
         kind: format!("{:?}", step.kind),
+        label: step.label.clone(),
         status: StepStatus::Skipped,
         started_at: now,
         finished_at: now,
         error: None,
+        skip_reason: Some(reason),
         duration_ms: 0,
+        segment: None,
+        warnings: vec![],
+        retries: 0,
     }
 }
 
-fn run_step(rt: &mut dyn Runtime, vars: &BTreeMap<String, String>, step: &StepConfig) -> StepReport {
+fn blocked_report(step: &StepConfig, reason: String) -> StepReport {
+    let now = Utc::now();
+    StepReport {
+        id: step.id.clone(),
+        kind: format!("{:?}", step.kind),
+        label: step.label.clone(),
+        status: StepStatus::Blocked,
+        started_at: now,
+        finished_at: now,
+        error: Some(reason),
+        skip_reason: None,
+        duration_ms: 0,
+        segment: None,
+        warnings: vec![],
+        retries: 0,
+    }
+}
+
+/// Write the `resume_output` marker of a step that modifies a file in
+/// place, since the step itself produces no new file to resume by.
+fn mark_done(
+    rt: &mut dyn Runtime,
+    vars: &BTreeMap<String, String>,
+    step: &StepConfig,
+) -> Result<()> {
+    let (Some(path), Some(marker)) = (step.in_place_path(), &step.resume_output) else {
+        return Ok(());
+    };
+    if marker == path {
+        // Never overwrite the modified file itself
+        return Ok(());
+    }
+    let marker = render_template(marker, vars)?;
+    rt.write_text(&marker, &format!("{}\n", step.id))
+}
+
+/// Run one step, merging any vars it exports into `vars` for later steps.
+fn run_step(
+    rt: &mut dyn Runtime,
+    vars: &mut BTreeMap<String, String>,
+    step: &StepConfig,
+) -> StepReport {
     let started = Utc::now();
     let t0 = Instant::now();
-    let result = execute_step(rt, vars, step);
+    let mut warnings = vec![];
+    let mut retries = 0;
+    let result = execute_step(rt, vars, step)
+        .map(|outcome| {
+            vars.extend(outcome.exports);
+            warnings = outcome.warnings;
+            retries = outcome.retries;
+        })
+        .and_then(|()| mark_done(rt, vars, step));
     StepReport {
         id: step.id.clone(),
         kind: format!("{:?}", step.kind),
+        label: step.label.clone(),
         status: if result.is_ok() {
             StepStatus::Ok
         } else {
@@ -383,6 +1002,10 @@ fn run_step(rt: &mut dyn Runtime, vars: &BTreeMap<String, String>, step: &StepCo
         started_at: started,
         finished_at: Utc::now(),
         error: result.err().map(|e| e.to_string()),
+        skip_reason: None,
         duration_ms: t0.elapsed().as_millis(),
+        segment: None,
+        warnings,
+        retries,
     }
 }
@@ -1,14 +1,35 @@
 //! Workflow engine orchestration for VWF.
 
+mod dag;
 mod engine;
+mod progress;
 mod report;
+mod schema;
+mod vars;
 
-pub use engine::{RunOptions, Runner};
-pub use report::{RunReport, StepReport, StepStatus};
+pub use dag::{default_constraint, run_incremental, step_task, workflow_state};
+pub use engine::{
+    CompletedRuns, ConfirmCallback, FailedRun, RunOptions, Runner, deterministic_run_id,
+};
+pub use progress::{EventCallback, Progress, RunEvent};
+pub use report::{REPORT_SCHEMA_VERSION, RunReport, StepReport, StepStatus, chrome_trace};
+pub use schema::workflow_schema;
+pub use vars::{VarCheck, check_vars};
 
 // Re-export dependencies for convenience
+pub use uuid::Uuid;
 pub use vwf_config::{StepConfig, StepKind, WorkflowConfig};
+pub use vwf_dag::{
+    Constraint, InputSpec, SchedulerEvent, StateStore, StateSummary, Task, TaskStatus,
+    WorkflowState,
+};
 pub use vwf_render::render_template;
 pub use vwf_runtime::{
-    CmdOut, DryRunRuntime, FsRuntime, LlmClient, LlmReq, MockLlmClient, OllamaClient, Runtime,
+    CmdOut, DryRunRuntime, ExplainingLlmClient, FsRuntime, LlmClient, LlmProvider, LlmReq,
+    MockLlmClient, OllamaClient, OpenAiClient, RetryingLlmClient, Runtime, SAFE_COMMANDS,
+    make_llm_client,
+};
+pub use vwf_steps::{
+    CACHE_VAR, CommandRunner, DEFAULT_ACRONYMS, FieldDoc, Presence, StepDoc, SystemRunner,
+    exported_vars, is_not_found, step_doc, step_docs,
 };
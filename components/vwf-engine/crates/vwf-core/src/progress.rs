@@ -0,0 +1,27 @@
+//! Progress events emitted while a workflow runs.
+
+use std::time::Duration;
+
+use super::report::StepStatus;
+
+/// A step-level transition reported to the `on_event` callback.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunEvent {
+    StepStarted { id: String },
+    StepFinished { id: String, status: StepStatus },
+}
+
+/// Run-wide counters at the time of an event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub total: usize,
+    /// Steps that finished in any status (ok, skipped, failed).
+    pub completed: usize,
+    pub failed: usize,
+    /// Ids of steps currently executing.
+    pub running: Vec<String>,
+    pub elapsed: Duration,
+}
+
+/// Callback invoked on every [`RunEvent`].
+pub type EventCallback = Box<dyn FnMut(&RunEvent, &Progress)>;
@@ -5,25 +5,107 @@ use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use uuid::Uuid;
 
+/// Format version of `run.json`, bumped when fields change incompatibly.
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RunReport {
+    /// Reports written before versioning have the version 1 layout.
+    #[serde(default = "first_schema_version")]
+    pub schema_version: u32,
     pub run_id: Uuid,
     pub workflow_name: String,
     pub started_at: DateTime<Utc>,
     pub finished_at: DateTime<Utc>,
     pub steps: Vec<StepReport>,
     pub vars: BTreeMap<String, String>,
+    /// Deliverables collected into `output_dir`, relative to the workdir.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deliverables: Vec<String>,
+}
+
+fn first_schema_version() -> u32 {
+    1
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StepReport {
     pub id: String,
     pub kind: String,
+    /// Human-readable step label from the workflow, if one was given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
     pub status: StepStatus,
     pub started_at: DateTime<Utc>,
     pub finished_at: DateTime<Utc>,
     pub error: Option<String>,
+    /// Why a skipped step didn't run, e.g. its resume output already exists.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_reason: Option<String>,
     pub duration_ms: u128,
+    /// Id of the workflow segment this step belongs to, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment: Option<String>,
+    /// Caveats of a step that still finished, e.g. outputs regenerated
+    /// after failing validation.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Times the step regenerated an output that failed validation.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub retries: u32,
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+impl StepReport {
+    /// The step's label, or its id when no label is set.
+    pub fn display_name(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.id)
+    }
+}
+
+/// Step spans of one or more runs in Chrome's trace event format.
+///
+/// Each step becomes a `B`/`E` event pair. Timestamps are microseconds from
+/// the earliest run start, and steps that overlap are put on separate
+/// threads so the viewer can nest the pairs.
+pub fn chrome_trace(reports: &[RunReport]) -> serde_json::Value {
+    let origin = reports.iter().map(|r| r.started_at).min();
+    let micros =
+        |t: DateTime<Utc>| origin.map_or(0, |o| (t - o).num_microseconds().unwrap_or(i64::MAX));
+    let mut events = vec![];
+    for report in reports {
+        let mut steps: Vec<&StepReport> = report.steps.iter().collect();
+        steps.sort_by_key(|s| s.started_at);
+        // Finish time of the last step on each thread.
+        let mut lanes: Vec<DateTime<Utc>> = vec![];
+        for step in steps {
+            let tid = match lanes.iter().position(|end| *end <= step.started_at) {
+                Some(lane) => lane,
+                None => {
+                    lanes.push(step.started_at);
+                    lanes.len() - 1
+                }
+            };
+            lanes[tid] = step.finished_at;
+            let event = |ph: &str, at: DateTime<Utc>| {
+                serde_json::json!({
+                    "name": step.display_name(),
+                    "cat": report.workflow_name,
+                    "ph": ph,
+                    "ts": micros(at),
+                    "pid": 1,
+                    "tid": tid + 1,
+                    "args": {"id": step.id, "kind": step.kind, "status": step.status},
+                })
+            };
+            events.push(event("B", step.started_at));
+            events.push(event("E", step.finished_at));
+        }
+    }
+    serde_json::json!({ "traceEvents": events, "displayTimeUnit": "ms" })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -0,0 +1,156 @@
+//! JSON Schema for workflow files, for editor completion and validation.
+
+use serde_json::{Map, Value, json};
+use vwf_config::SUPPORTED_VERSIONS;
+use vwf_steps::{FieldDoc, step_docs};
+
+/// Draft-07 JSON Schema for a workflow document.
+///
+/// Step payloads come from the step catalog, so the schema lists exactly
+/// the fields `vwf steps --describe` does. A step's `kind` selects its
+/// payload schema through `if`/`then`.
+pub fn workflow_schema() -> Value {
+    let docs = step_docs();
+    let kinds: Vec<&str> = docs.iter().map(|d| d.kind).collect();
+    let mut definitions = Map::new();
+    let mut by_kind = vec![];
+    for doc in &docs {
+        let required: Vec<&str> = doc
+            .fields
+            .iter()
+            .filter(|f| f.is_required())
+            .map(|f| f.name)
+            .collect();
+        let properties: Map<String, Value> = doc
+            .fields
+            .iter()
+            .map(|f| (f.name.to_string(), field_schema(f)))
+            .collect();
+        definitions.insert(
+            doc.kind.to_string(),
+            json!({
+                "description": doc.summary,
+                "properties": properties,
+                "required": required,
+            }),
+        );
+        by_kind.push(json!({
+            "if": { "properties": { "kind": { "const": doc.kind } } },
+            "then": { "$ref": format!("#/definitions/{}", doc.kind) },
+        }));
+    }
+    let strings = json!({ "type": "array", "items": { "type": "string" } });
+    definitions.insert(
+        "step".into(),
+        json!({
+            "type": "object",
+            "required": ["id", "kind"],
+            "properties": {
+                "id": { "type": "string", "description": "Unique step id" },
+                "kind": { "enum": kinds, "description": "Step type" },
+                "label": { "type": "string", "description": "Name shown in logs and reports" },
+                "resume_output": {
+                    "type": "string",
+                    "description": "Skip with --resume when this file exists"
+                },
+                "depends_on": strings.clone(),
+                "needs": {
+                    "anyOf": [{ "type": "string" }, strings.clone()],
+                    "description": "Files this step reads; each adds a dependency on its producer"
+                },
+                "confirm": { "type": "boolean", "description": "Ask before running" },
+                "continue_on_error": {
+                    "type": "boolean",
+                    "description": "A failure of this step doesn't fail the run"
+                },
+                "deliverable": {
+                    "type": "boolean",
+                    "description": "Copy outputs into output_dir when the run ends"
+                },
+                "optional_outputs": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Outputs written only sometimes; a missing one is not a failure"
+                },
+                "resource": { "type": "string", "description": "Resource held while running" },
+                "sequential_group": {
+                    "type": "string",
+                    "description": "Steps in the same group never run at once"
+                },
+            },
+            "allOf": by_kind,
+        }),
+    );
+    definitions.insert(
+        "segment".into(),
+        json!({
+            "type": "object",
+            "required": ["id", "steps"],
+            "properties": {
+                "id": { "type": "string" },
+                "segment_type": { "enum": ["music_only", "narration_only", "mixed"] },
+                "description": { "type": "string" },
+                "title": { "type": "string" },
+                "steps": strings,
+            },
+        }),
+    );
+    definitions.insert(
+        "input".into(),
+        json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "default": { "type": "string" },
+            },
+        }),
+    );
+    let versions: Vec<u32> = SUPPORTED_VERSIONS.collect();
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "vwf workflow",
+        "type": "object",
+        "required": ["version", "name", "steps"],
+        "properties": {
+            "version": { "enum": versions },
+            "name": { "type": "string" },
+            "description": { "type": "string" },
+            "vars": {
+                "type": "object",
+                "additionalProperties": { "type": "string" },
+                "description": "Template variables"
+            },
+            "inputs": {
+                "type": "array",
+                "items": { "$ref": "#/definitions/input" },
+                "description": "Vars the caller must supply, unless they have a default"
+            },
+            "steps": { "type": "array", "items": { "$ref": "#/definitions/step" } },
+            "segments": { "type": "array", "items": { "$ref": "#/definitions/segment" } },
+            "infer_depends_on": {
+                "type": "boolean",
+                "description": "Derive depends_on from the files steps read and write"
+            },
+        },
+        "definitions": definitions,
+    })
+}
+
+/// Schema for one payload field from its catalog type.
+fn field_schema(field: &FieldDoc) -> Value {
+    let mut schema = match field.ty {
+        "string" | "integer" | "number" | "boolean" | "array" => json!({ "type": field.ty }),
+        "map" => json!({ "type": "object" }),
+        ty => match ty.strip_prefix("array[").and_then(|t| t.strip_suffix(']')) {
+            Some(item) => json!({ "type": "array", "items": { "type": item } }),
+            None => json!({}),
+        },
+    };
+    schema["description"] = field.doc.into();
+    if let Some(default) = field.default_value() {
+        schema["default"] = default;
+    }
+    schema
+}
@@ -0,0 +1,81 @@
+//! Static checks of template var usage across a workflow.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::Value;
+use vwf_config::WorkflowConfig;
+use vwf_render::template_vars;
+use vwf_steps::{CACHE_VAR, SEED_VAR, exported_vars};
+
+/// Vars the engine and steps read directly rather than through a `{{var}}`.
+const ENGINE_VARS: &[&str] = &["output_dir", SEED_VAR, CACHE_VAR];
+
+/// Var problems found without running anything.
+#[derive(Debug, Default, PartialEq)]
+pub struct VarCheck {
+    /// Referenced but never defined, with the ids of the steps using each.
+    pub undefined: BTreeMap<String, Vec<String>>,
+    /// Defined in `vars:` or passed in, but never referenced.
+    pub unused: BTreeSet<String>,
+}
+
+impl VarCheck {
+    pub fn is_clean(&self) -> bool {
+        self.undefined.is_empty() && self.unused.is_empty()
+    }
+
+    /// One line per problem.
+    pub fn problems(&self) -> Vec<String> {
+        let undefined = self.undefined.iter().map(|(var, steps)| {
+            let steps: Vec<String> = steps.iter().map(|s| format!("`{s}`")).collect();
+            format!("undefined var `{var}` (used by {})", steps.join(", "))
+        });
+        let unused = self.unused.iter().map(|var| format!("unused var `{var}`"));
+        undefined.chain(unused).collect()
+    }
+}
+
+/// Compare the vars a pipeline defines with the ones its steps reference.
+///
+/// `extra` holds vars supplied from outside (`--var`, var files). Vars
+/// exported by steps such as `probe_media` count as defined.
+pub fn check_vars(pipeline: &[WorkflowConfig], extra: &BTreeMap<String, String>) -> VarCheck {
+    let mut declared: BTreeSet<String> = extra.keys().cloned().collect();
+    let mut defined: BTreeSet<String> = ENGINE_VARS.iter().map(|v| v.to_string()).collect();
+    let mut used: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for cfg in pipeline {
+        declared.extend(cfg.vars.keys().cloned());
+        declared.extend(cfg.inputs.iter().map(|i| i.name.clone()));
+        for step in &cfg.steps {
+            defined.extend(exported_vars(&step.kind, &step.payload));
+            let value = serde_json::to_value(step).unwrap_or_default();
+            let mut names = BTreeSet::new();
+            collect_refs(&value, &mut names);
+            for name in names {
+                used.entry(name).or_default().push(step.id.clone());
+            }
+        }
+    }
+    defined.extend(declared.iter().cloned());
+    VarCheck {
+        undefined: used
+            .iter()
+            .filter(|(var, _)| !defined.contains(*var))
+            .map(|(var, steps)| (var.clone(), steps.clone()))
+            .collect(),
+        unused: declared
+            .into_iter()
+            .filter(|var| !used.contains_key(var) && !ENGINE_VARS.contains(&var.as_str()))
+            .collect(),
+    }
+}
+
+/// Every var referenced by a string anywhere in `value`.
+fn collect_refs(value: &Value, out: &mut BTreeSet<String>) {
+    match value {
+        Value::String(s) => out.extend(template_vars(s)),
+        Value::Array(items) => items.iter().for_each(|v| collect_refs(v, out)),
+        Value::Object(map) => map.values().for_each(|v| collect_refs(v, out)),
+        _ => {}
+    }
+}
@@ -0,0 +1,76 @@
+//! `check_outputs` finds unwritable output paths before any step runs.
+
+use std::collections::BTreeMap;
+
+use vwf_core::{FailedRun, RunOptions, Runner};
+
+const YAML: &str = r#"
+version: 1
+name: outputs
+vars:
+  out_dir: "output"
+steps:
+  - id: script
+    kind: write_file
+    path: "work/script.txt"
+    content: "Hello"
+  - id: publish
+    kind: write_file
+    depends_on: [script]
+    path: "{{out_dir}}/final.txt"
+    content: "done"
+"#;
+
+fn opts() -> RunOptions {
+    RunOptions {
+        check_outputs: true,
+        quiet: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn unwritable_output_dir_fails_before_any_step_runs() {
+    let tmp = tempfile::tempdir().unwrap();
+    // A file where the output directory should be, like a broken mount
+    std::fs::write(tmp.path().join("output"), "").unwrap();
+
+    let err = Runner::run_yaml(YAML, tmp.path(), BTreeMap::new(), opts()).unwrap_err();
+    let msg = err.to_string();
+    assert!(msg.starts_with("1 output path(s) not writable:"), "{msg}");
+    assert!(msg.contains("step `publish`: create_dir_all"), "{msg}");
+    assert!(msg.contains("/output"), "{msg}");
+    assert!(
+        !tmp.path().join("work/script.txt").exists(),
+        "no step should have run"
+    );
+
+    // Without the check, the first step runs and the second fails
+    let err = Runner::run_yaml(
+        YAML,
+        tmp.path(),
+        BTreeMap::new(),
+        RunOptions {
+            quiet: true,
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert!(err.downcast_ref::<FailedRun>().is_some());
+    assert!(tmp.path().join("work/script.txt").exists());
+}
+
+#[test]
+fn writable_outputs_get_their_parent_dirs() {
+    let tmp = tempfile::tempdir().unwrap();
+    let vars = BTreeMap::from([("out_dir".to_string(), "dist".to_string())]);
+    Runner::run_yaml(YAML, tmp.path(), vars, opts()).unwrap();
+    assert!(tmp.path().join("dist/final.txt").exists());
+    assert!(!tmp.path().join("output").exists());
+}
+
+#[test]
+fn dry_run_records_the_parent_dirs() {
+    let (_, rt) = Runner::run_yaml_dry(YAML, "/tmp", BTreeMap::new(), opts()).unwrap();
+    assert_eq!(rt.planned_dirs, ["work", "output"]);
+}
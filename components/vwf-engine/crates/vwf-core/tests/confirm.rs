@@ -0,0 +1,77 @@
+//! `confirm: true` steps and the confirmation callback.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use vwf_core::{FailedRun, RunOptions, Runner, StepStatus};
+
+const YAML: &str = r#"
+version: 1
+name: confirmed
+steps:
+  - id: render
+    kind: run_command
+    confirm: true
+    program: "render-batch"
+    args: ["--frames", "{{frames}}"]
+    capture_path: "work/render.log"
+  - id: publish
+    kind: write_file
+    depends_on: [render]
+    path: "work/done.txt"
+    content: "done"
+"#;
+
+fn vars() -> BTreeMap<String, String> {
+    BTreeMap::from([("frames".to_string(), "900".to_string())])
+}
+
+#[test]
+fn steps_run_unasked_without_a_callback() {
+    let (report, _) = Runner::run_yaml_dry(YAML, "/tmp", vars(), RunOptions::default()).unwrap();
+    assert!(report.steps.iter().all(|s| s.status == StepStatus::Ok));
+}
+
+#[test]
+fn approved_step_runs_and_sees_a_summary() {
+    let asked = Rc::new(RefCell::new(vec![]));
+    let seen = Rc::clone(&asked);
+    let opts = RunOptions {
+        confirm: Some(Box::new(move |step, summary| {
+            seen.borrow_mut()
+                .push((step.id.clone(), summary.to_string()));
+            true
+        })),
+        ..Default::default()
+    };
+    let (report, _) = Runner::run_yaml_dry(YAML, "/tmp", vars(), opts).unwrap();
+    assert!(report.steps.iter().all(|s| s.status == StepStatus::Ok));
+    assert_eq!(
+        *asked.borrow(),
+        [(
+            "render".to_string(),
+            "render (RunCommand)\nruns: render-batch --frames 900\nwrites: work/render.log"
+                .to_string()
+        )]
+    );
+}
+
+#[test]
+fn declined_step_is_skipped_and_blocks_dependents() {
+    let opts = RunOptions {
+        confirm: Some(Box::new(|_, _| false)),
+        ..Default::default()
+    };
+    let Err(err) = Runner::run_yaml_dry(YAML, "/tmp", vars(), opts) else {
+        panic!("a blocked step should fail the run");
+    };
+    let FailedRun(report) = err.downcast_ref::<FailedRun>().unwrap();
+    assert_eq!(report.steps[0].status, StepStatus::Skipped);
+    assert_eq!(
+        report.steps[0].skip_reason.as_deref(),
+        Some("declined at confirmation prompt")
+    );
+    assert_eq!(report.steps[1].status, StepStatus::Blocked);
+    assert_eq!(report.steps[1].error.as_deref(), Some("Blocked by: render"));
+}
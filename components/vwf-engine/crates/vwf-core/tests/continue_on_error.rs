@@ -0,0 +1,41 @@
+//! Steps whose failure doesn't fail the run.
+
+use std::collections::BTreeMap;
+
+use vwf_core::{FailedRun, RunOptions, Runner, StepStatus};
+
+const YAML: &str = r#"
+version: 1
+name: optional
+steps:
+  - id: note
+    kind: write_file
+    path: "note.txt"
+    content: "hi"
+  - id: split
+    kind: split_sections
+    continue_on_error: true
+    input_path: "missing.txt"
+    outputs: []
+"#;
+
+#[test]
+fn tolerated_failure_leaves_the_run_successful() {
+    let (report, _) =
+        Runner::run_yaml_dry(YAML, "/tmp", BTreeMap::new(), RunOptions::default()).unwrap();
+    assert_eq!(report.steps[0].status, StepStatus::Ok);
+    assert_eq!(report.steps[1].status, StepStatus::Failed);
+}
+
+#[test]
+fn blocked_dependent_of_a_tolerated_failure_fails_the_run() {
+    let yaml = format!(
+        "{YAML}  - id: after\n    kind: write_file\n    depends_on: [split]\n    path: \"after.txt\"\n    content: \"x\"\n"
+    );
+    let Err(err) = Runner::run_yaml_dry(&yaml, "/tmp", BTreeMap::new(), RunOptions::default())
+    else {
+        panic!("a blocked step should fail the run");
+    };
+    let FailedRun(report) = err.downcast_ref().unwrap();
+    assert_eq!(report.steps[2].status, StepStatus::Blocked);
+}
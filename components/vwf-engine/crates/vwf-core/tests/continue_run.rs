@@ -0,0 +1,80 @@
+//! Continuing a previous run (`--continue`).
+
+use std::collections::BTreeMap;
+
+use vwf_core::{RunOptions, Runner, StepStatus};
+
+const YAML: &str = r#"
+version: 1
+name: cont
+steps:
+  - id: script
+    kind: write_file
+    path: "script.txt"
+    content: "Hello"
+  - id: voice
+    kind: write_file
+    path: "voice.txt"
+    content: "voice"
+    depends_on: [script]
+  - id: mix
+    kind: write_file
+    path: "mix.txt"
+    content: "mix"
+    depends_on: [voice]
+  - id: thumbnail
+    kind: write_file
+    path: "thumb.txt"
+    content: "thumb"
+"#;
+
+#[test]
+fn only_failed_and_blocked_steps_run_again() {
+    let run = |opts| Runner::run_yaml_dry(YAML, "/tmp", BTreeMap::new(), opts).unwrap();
+    let (mut previous, _) = run(RunOptions::default());
+    for step in &mut previous.steps {
+        step.status = match step.id.as_str() {
+            "voice" => StepStatus::Failed,
+            "mix" => StepStatus::Blocked,
+            _ => StepStatus::Ok,
+        };
+    }
+
+    let (report, rt) = run(RunOptions {
+        previous: vec![previous],
+        ..Default::default()
+    });
+    let statuses: Vec<(&str, &StepStatus)> = report
+        .steps
+        .iter()
+        .map(|s| (s.id.as_str(), &s.status))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            ("script", &StepStatus::Skipped),
+            ("voice", &StepStatus::Ok),
+            ("mix", &StepStatus::Ok),
+            ("thumbnail", &StepStatus::Skipped),
+        ]
+    );
+    assert_eq!(
+        report.steps[0].skip_reason.as_deref(),
+        Some("done in previous run")
+    );
+    let written: Vec<&str> = rt.planned_writes.iter().map(|(p, _)| p.as_str()).collect();
+    assert_eq!(written, ["voice.txt", "mix.txt"]);
+}
+
+#[test]
+fn previous_run_of_another_workflow_is_ignored() {
+    let (mut previous, _) =
+        Runner::run_yaml_dry(YAML, "/tmp", BTreeMap::new(), RunOptions::default()).unwrap();
+    previous.workflow_name = "other".into();
+    let opts = RunOptions {
+        previous: vec![previous],
+        ..Default::default()
+    };
+    let (report, _) = Runner::run_yaml_dry(YAML, "/tmp", BTreeMap::new(), opts).unwrap();
+    assert!(report.steps.iter().all(|s| s.status == StepStatus::Ok));
+}
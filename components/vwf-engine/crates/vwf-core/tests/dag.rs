@@ -0,0 +1,197 @@
+//! Step to DAG task conversion tests.
+
+use std::collections::BTreeMap;
+
+use vwf_core::{
+    FsRuntime, InputSpec, MockLlmClient, StateStore, TaskStatus, WorkflowConfig, run_incremental,
+    step_task, workflow_state,
+};
+
+const YAML: &str = r#"
+version: 1
+name: gpu
+steps:
+  - id: title_image
+    kind: text_to_image
+    prompt: "a title card"
+    output_path: work/title.png
+  - id: upscale
+    kind: run_command
+    program: realesrgan
+    resource: gpu
+  - id: voice
+    kind: tts_generate
+    script_path: script.txt
+    output_path: work/voice.wav
+    resource: tts-remote
+    sequential_group: audio
+"#;
+
+#[test]
+fn gpu_kinds_get_the_gpu_resource() {
+    let cfg = WorkflowConfig::from_yaml(YAML).unwrap();
+    let task = step_task(&cfg.steps[0]);
+    assert_eq!(task.kind, "text_to_image");
+    assert_eq!(task.constraints.resource.as_deref(), Some("gpu"));
+    assert_eq!(task.constraints.sequential_group, None);
+}
+
+#[test]
+fn step_fields_override_kind_defaults() {
+    let cfg = WorkflowConfig::from_yaml(YAML).unwrap();
+    let upscale = step_task(&cfg.steps[1]);
+    assert_eq!(upscale.constraints.resource.as_deref(), Some("gpu"));
+    assert!(upscale.config.get("resource").is_none());
+
+    let voice = step_task(&cfg.steps[2]);
+    assert_eq!(voice.constraints.resource.as_deref(), Some("tts-remote"));
+    assert_eq!(voice.constraints.sequential_group.as_deref(), Some("audio"));
+}
+
+#[test]
+fn linear_workflow_becomes_task_graph() {
+    let yaml = r#"
+version: 1
+name: narrate
+vars:
+  work: work
+steps:
+  - id: setup
+    kind: ensure_dirs
+    dirs: ["{{work}}"]
+  - id: script
+    kind: write_file
+    path: "{{work}}/script.txt"
+    content: "Hello"
+    depends_on: [setup]
+  - id: voice
+    kind: tts_generate
+    script_path: "./{{work}}/script.txt"
+    output_path: "{{work}}/voice.wav"
+    reference_audio: refs/me.wav
+    reference_text: "me"
+    depends_on: [script]
+"#;
+    let cfg = WorkflowConfig::from_yaml(yaml).unwrap();
+    let state = workflow_state(&cfg);
+    assert_eq!(state.tasks.len(), 3);
+
+    let required = |id: &str| -> Vec<String> {
+        state.tasks[id]
+            .inputs
+            .iter()
+            .filter_map(|i| match i {
+                InputSpec::Required { artifact } => Some(artifact.clone()),
+                _ => None,
+            })
+            .collect()
+    };
+    assert!(required("setup").is_empty());
+    assert_eq!(required("script"), ["step:setup"]);
+    assert_eq!(required("voice"), ["work/script.txt"]);
+    assert!(matches!(
+        &state.tasks["voice"].inputs[..],
+        [InputSpec::Optional { artifact, .. }, _] if artifact == "refs/me.wav"
+    ));
+
+    assert_eq!(state.tasks["setup"].status, TaskStatus::Ready);
+    assert_eq!(
+        state.tasks["script"].status,
+        TaskStatus::Blocked {
+            waiting_on: vec!["setup".to_string()]
+        }
+    );
+    assert_eq!(
+        state.tasks["voice"].status,
+        TaskStatus::Blocked {
+            waiting_on: vec!["script".to_string()]
+        }
+    );
+    assert_eq!(
+        state.tasks["voice"].constraints.resource.as_deref(),
+        Some("tts")
+    );
+}
+
+#[test]
+fn optional_outputs_are_optional_task_outputs() {
+    let yaml = r#"
+version: 1
+name: lint
+vars:
+  name: ep1
+steps:
+  - id: lint
+    kind: run_command
+    program: lint
+    capture_path: "work/lint.log"
+    output_path: "work/{{name}}_issues.txt"
+    optional_outputs: ["work/{{name}}_issues.txt"]
+"#;
+    let cfg = WorkflowConfig::from_yaml(yaml).unwrap();
+    let state = workflow_state(&cfg);
+    let outputs: Vec<(&str, bool)> = state.tasks["lint"]
+        .outputs
+        .iter()
+        .map(|o| (o.artifact.as_str(), o.optional))
+        .collect();
+    assert_eq!(
+        outputs,
+        [("work/lint.log", false), ("work/ep1_issues.txt", true)]
+    );
+
+    let typo = yaml.replace("optional_outputs: [\"work/", "optional_outputs: [\"out/");
+    let err = WorkflowConfig::from_yaml(&typo).unwrap_err().to_string();
+    assert_eq!(
+        err,
+        "Step `lint` lists optional output `out/{{name}}_issues.txt`, which it doesn't write"
+    );
+}
+
+#[test]
+fn incremental_run_resumes_from_saved_state() {
+    let yaml = r#"
+version: 1
+name: incremental
+steps:
+  - id: note
+    kind: write_file
+    path: "note.txt"
+    content: "hi"
+  - id: split
+    kind: split_sections
+    input_path: "in.txt"
+    outputs: []
+  - id: after
+    kind: write_file
+    depends_on: [split]
+    path: "after.txt"
+    content: "x"
+"#;
+    let cfg = WorkflowConfig::from_yaml(yaml).unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let mut rt = FsRuntime::new(tmp.path(), Box::new(MockLlmClient::echo()));
+
+    let state = run_incremental(&mut rt, &cfg, BTreeMap::new(), tmp.path(), |_| {}).unwrap();
+    assert!(state.tasks["note"].is_complete());
+    assert!(matches!(
+        state.tasks["split"].status,
+        TaskStatus::Failed { .. }
+    ));
+    assert!(!state.tasks["after"].is_complete());
+    assert!(!tmp.path().join("after.txt").exists());
+
+    // Fix the input and reset the failure; finished steps don't run again
+    std::fs::write(tmp.path().join("in.txt"), "text").unwrap();
+    std::fs::write(tmp.path().join("note.txt"), "edited").unwrap();
+    let store = StateStore::new(tmp.path());
+    let mut saved = store.load().unwrap().unwrap();
+    assert_eq!(saved.reset_failed(), ["split"]);
+    store.save(&saved).unwrap();
+
+    let state = run_incremental(&mut rt, &cfg, BTreeMap::new(), tmp.path(), |_| {}).unwrap();
+    assert!(state.complete);
+    assert!(tmp.path().join("after.txt").exists());
+    let note = std::fs::read_to_string(tmp.path().join("note.txt")).unwrap();
+    assert_eq!(note, "edited");
+}
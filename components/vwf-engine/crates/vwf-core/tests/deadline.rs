@@ -0,0 +1,67 @@
+//! Whole-run deadline tests.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Result;
+use vwf_core::{
+    CmdOut, DryRunRuntime, LlmClient, MockLlmClient, RunOptions, Runner, Runtime, WorkflowConfig,
+};
+
+/// Dry-run runtime whose commands take a while.
+struct SlowRuntime(DryRunRuntime);
+
+impl Runtime for SlowRuntime {
+    fn workdir(&self) -> &Path {
+        self.0.workdir()
+    }
+    fn ensure_dir(&mut self, rel: &str) -> Result<()> {
+        self.0.ensure_dir(rel)
+    }
+    fn write_text(&mut self, rel: &str, content: &str) -> Result<()> {
+        self.0.write_text(rel, content)
+    }
+    fn read_text(&self, rel: &str) -> Result<String> {
+        self.0.read_text(rel)
+    }
+    fn copy_file(&mut self, from: &str, to: &str) -> Result<()> {
+        self.0.copy_file(from, to)
+    }
+    fn run_command(&mut self, prog: &str, args: &[String], cwd: Option<&str>) -> Result<CmdOut> {
+        std::thread::sleep(Duration::from_millis(200));
+        self.0.run_command(prog, args, cwd)
+    }
+    fn llm(&mut self) -> &mut dyn LlmClient {
+        self.0.llm()
+    }
+}
+
+#[test]
+fn deadline_blocks_remaining_steps() {
+    let cfg = WorkflowConfig::from_yaml(
+        r#"
+version: 1
+name: slow
+steps:
+  - id: slow
+    kind: run_command
+    program: sleep
+  - id: after
+    kind: write_file
+    depends_on: [slow]
+    path: "out.txt"
+    content: "done"
+"#,
+    )
+    .unwrap();
+    let mut rt = SlowRuntime(DryRunRuntime::new("/tmp", Box::new(MockLlmClient::echo())));
+    let opts = RunOptions {
+        deadline: Some(Duration::from_millis(50)),
+        ..Default::default()
+    };
+    let err = Runner::run_with_options(&mut rt, &cfg, BTreeMap::new(), opts).unwrap_err();
+    let report = err.to_string();
+    assert!(report.contains("deadline exceeded"));
+    assert!(rt.0.planned_writes.is_empty());
+}
@@ -0,0 +1,75 @@
+//! Deliverable step outputs are copied into `output_dir`.
+
+use std::collections::BTreeMap;
+use vwf_core::{FsRuntime, MockLlmClient, RunOptions, Runner, WorkflowConfig};
+
+const WORKFLOW: &str = r#"
+version: 1
+name: deliverables
+steps:
+  - id: notes
+    kind: write_file
+    path: "work/notes.txt"
+    content: "scratch"
+  - id: description
+    kind: write_file
+    deliverable: true
+    path: "work/{{slug}}.txt"
+    content: "Final description"
+"#;
+
+#[test]
+fn deliverable_write_file_is_copied_to_output_dir() {
+    let workdir = std::env::temp_dir().join(format!("vwf_deliverables_{}", std::process::id()));
+    let cfg = WorkflowConfig::from_yaml(WORKFLOW).unwrap();
+    let mut rt = FsRuntime::new(&workdir, Box::new(MockLlmClient::echo()));
+    let vars = BTreeMap::from([("slug".to_string(), "episode_1".to_string())]);
+    let report = Runner::run_with_options(&mut rt, &cfg, vars, RunOptions::default()).unwrap();
+
+    assert_eq!(report.deliverables, vec!["output/episode_1.txt"]);
+    assert_eq!(
+        std::fs::read_to_string(workdir.join("output/episode_1.txt")).unwrap(),
+        "Final description"
+    );
+    assert!(!workdir.join("output/notes.txt").exists());
+    std::fs::remove_dir_all(&workdir).unwrap();
+}
+
+#[test]
+fn output_dir_var_overrides_default() {
+    let vars = BTreeMap::from([
+        ("slug".to_string(), "ep".to_string()),
+        ("output_dir".to_string(), "dist/".to_string()),
+    ]);
+    let (report, rt) = Runner::run_yaml_dry(WORKFLOW, "/tmp", vars, RunOptions::default()).unwrap();
+    assert_eq!(report.deliverables, vec!["dist/ep.txt"]);
+    assert_eq!(
+        rt.planned_copies,
+        vec![("work/ep.txt".to_string(), "dist/ep.txt".to_string())]
+    );
+}
+
+#[test]
+fn resume_marker_is_not_a_deliverable() {
+    let yaml = r#"
+version: 1
+name: deliverables
+steps:
+  - id: polish
+    kind: write_file
+    deliverable: true
+    path: "work/polished.txt"
+    content: "Polished"
+    resume_output: "work/polished.done"
+"#;
+    let (report, rt) =
+        Runner::run_yaml_dry(yaml, "/tmp", BTreeMap::new(), RunOptions::default()).unwrap();
+    assert_eq!(report.deliverables, vec!["output/polished.txt"]);
+    assert_eq!(
+        rt.planned_copies,
+        vec![(
+            "work/polished.txt".to_string(),
+            "output/polished.txt".to_string()
+        )]
+    );
+}
@@ -0,0 +1,63 @@
+//! `--explain-llm`: rendered LLM requests are printed, not sent.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::rc::Rc;
+
+use vwf_core::{
+    DryRunRuntime, ExplainingLlmClient, MockLlmClient, RunOptions, Runner, WorkflowConfig,
+};
+
+/// Collects what the client prints.
+#[derive(Clone, Default)]
+struct Captured(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn rendered_prompts_are_printed() {
+    let yaml = r#"
+version: 1
+name: explain
+vars:
+  topic: "borrow checker"
+steps:
+  - id: prompt
+    kind: write_file
+    path: "work/prompt.txt"
+    content: "Write a 60 second script about the {{topic}}."
+  - id: script
+    kind: llm_generate
+    depends_on: [prompt]
+    system: "You explain {{topic}} to beginners."
+    user_prompt_path: "work/prompt.txt"
+    output_path: "work/script.txt"
+    provider: "ollama:qwen2.5-coder:14b"
+"#;
+    let cfg = WorkflowConfig::from_yaml(yaml).unwrap();
+    let out = Captured::default();
+    let llm = ExplainingLlmClient::new(Box::new(MockLlmClient::echo()))
+        .with_output(Box::new(out.clone()));
+    let mut rt = DryRunRuntime::new("/tmp", Box::new(llm));
+    let vars = BTreeMap::from([("topic".to_string(), "lifetimes".to_string())]);
+    Runner::run_with_options(&mut rt, &cfg, vars, RunOptions::default()).unwrap();
+
+    let printed = String::from_utf8(out.0.borrow().clone()).unwrap();
+    assert_eq!(
+        printed,
+        "=== LLM request 1 (provider: ollama:qwen2.5-coder:14b) ===\n\
+         --- system ---\n\
+         You explain lifetimes to beginners.\n\
+         --- user ---\n\
+         Write a 60 second script about the lifetimes.\n\n"
+    );
+}
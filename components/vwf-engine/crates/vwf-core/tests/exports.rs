@@ -0,0 +1,78 @@
+//! Vars exported by steps are visible to later steps.
+
+use std::collections::BTreeMap;
+use vwf_core::{FsRuntime, MockLlmClient, RunOptions, Runner, WorkflowConfig};
+
+#[test]
+fn extracted_section_feeds_later_step() {
+    let yaml = r#"
+version: 1
+name: exports
+steps:
+  - id: prompt
+    kind: write_file
+    path: "prompt.txt"
+    content: "ignored"
+  - id: script
+    kind: llm_generate
+    depends_on: [prompt]
+    system: "sys"
+    user_prompt_path: "prompt.txt"
+    output_path: "script.txt"
+    provider: "mock"
+    mock_response: "TITLE:\nRust in 60s\n"
+    extract_sections:
+      TITLE: title
+  - id: card
+    kind: write_file
+    depends_on: [script]
+    path: "card.txt"
+    content: "Now showing: {{title}}"
+"#;
+    let (report, rt) =
+        Runner::run_yaml_dry(yaml, "/tmp", BTreeMap::new(), RunOptions::default()).unwrap();
+    assert_eq!(report.vars["title"], "Rust in 60s");
+    assert!(
+        rt.planned_writes
+            .iter()
+            .any(|(p, c)| p == "card.txt" && c == "Now showing: Rust in 60s")
+    );
+}
+
+const EXTRACT_YAML: &str = r#"
+version: 1
+name: extract
+steps:
+  - id: render
+    kind: run_command
+    program: echo
+    args: ["rendered 1200 frames to out/take_07.mp4"]
+    extract:
+      regex: 'to (\S+\.mp4)'
+      into_var: take
+  - id: notes
+    kind: write_file
+    depends_on: [render]
+    path: "notes.txt"
+    content: "Latest take: {{take}}"
+"#;
+
+#[test]
+fn extracted_stdout_feeds_later_step() {
+    let workdir = tempfile::tempdir().unwrap();
+    let cfg = WorkflowConfig::from_yaml(EXTRACT_YAML).unwrap();
+    let mut rt = FsRuntime::new(workdir.path(), Box::new(MockLlmClient::echo()));
+    rt.command_allowlist.insert("echo".into());
+    let report =
+        Runner::run_with_options(&mut rt, &cfg, BTreeMap::new(), RunOptions::default()).unwrap();
+    assert_eq!(report.vars["take"], "out/take_07.mp4");
+    let notes = std::fs::read_to_string(workdir.path().join("notes.txt")).unwrap();
+    assert_eq!(notes, "Latest take: out/take_07.mp4");
+}
+
+#[test]
+fn dry_run_extract_uses_a_placeholder() {
+    let (report, _) =
+        Runner::run_yaml_dry(EXTRACT_YAML, "/tmp", BTreeMap::new(), RunOptions::default()).unwrap();
+    assert_eq!(report.vars["take"], "[dry-run] take");
+}
@@ -0,0 +1,61 @@
+//! Inferred `depends_on` edges order execution.
+
+use std::collections::BTreeMap;
+use vwf_core::{RunOptions, Runner};
+
+#[test]
+fn inferred_dependency_runs_producer_first() {
+    let yaml = r#"
+version: 1
+name: inferred
+infer_depends_on: true
+steps:
+  - id: split
+    kind: split_sections
+    input_path: "input.txt"
+    outputs:
+      - heading: "INTRO:"
+        path: "intro.txt"
+  - id: write_input
+    kind: write_file
+    path: "input.txt"
+    content: "INTRO:\nhello"
+"#;
+    let (report, rt) =
+        Runner::run_yaml_dry(yaml, "/nonexistent", BTreeMap::new(), RunOptions::default()).unwrap();
+    assert!(report.steps.iter().all(|s| s.error.is_none()));
+    assert!(
+        rt.planned_writes
+            .iter()
+            .any(|(p, c)| p == "intro.txt" && c == "hello")
+    );
+}
+
+#[test]
+fn needed_file_runs_its_producer_first() {
+    let yaml = r#"
+version: 1
+name: needs
+steps:
+  - id: split
+    kind: split_sections
+    input_path: "{{source}}"
+    needs: [work/a.txt]
+    outputs:
+      - heading: "INTRO:"
+        path: "work/intro.txt"
+  - id: write_a
+    kind: write_file
+    path: "work/a.txt"
+    content: "INTRO:\nhello"
+"#;
+    let vars = BTreeMap::from([("source".to_string(), "work/a.txt".to_string())]);
+    let (report, rt) =
+        Runner::run_yaml_dry(yaml, "/nonexistent", vars, RunOptions::default()).unwrap();
+    assert!(report.steps.iter().all(|s| s.error.is_none()));
+    assert!(
+        rt.planned_writes
+            .iter()
+            .any(|(p, c)| p == "work/intro.txt" && c == "hello")
+    );
+}
@@ -0,0 +1,61 @@
+//! Declared `inputs` are checked before any step runs.
+
+use std::collections::BTreeMap;
+
+use vwf_core::{DryRunRuntime, MockLlmClient, RunOptions, Runner, WorkflowConfig, check_vars};
+
+const YAML: &str = r#"
+version: 1
+name: explainer
+inputs:
+  - name: topic
+    description: "What the video explains"
+  - name: voice
+    default: "assets/voice.wav"
+steps:
+  - id: script
+    kind: write_file
+    path: "work/script.txt"
+    content: "{{topic}} read by {{voice}}"
+"#;
+
+fn quiet() -> RunOptions {
+    RunOptions {
+        quiet: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn missing_input_fails_before_any_step_runs() {
+    let cfg = WorkflowConfig::from_yaml(YAML).unwrap();
+    let mut rt = DryRunRuntime::new("/tmp", Box::new(MockLlmClient::echo()));
+    let err = Runner::run_with_options(&mut rt, &cfg, BTreeMap::new(), quiet()).unwrap_err();
+    let msg = err.to_string();
+    assert!(
+        msg.starts_with("Workflow `explainer` is missing 1 required input(s)"),
+        "{msg}"
+    );
+    assert!(msg.ends_with("\n  topic: What the video explains"), "{msg}");
+    assert!(rt.planned_writes.is_empty());
+}
+
+#[test]
+fn supplied_inputs_and_defaults_are_vars() {
+    let vars = BTreeMap::from([("topic".to_string(), "DAGs".to_string())]);
+    let (report, rt) = Runner::run_yaml_dry(YAML, "/tmp", vars, quiet()).unwrap();
+    assert_eq!(report.vars["voice"], "assets/voice.wav");
+    assert_eq!(
+        rt.planned_writes,
+        [(
+            "work/script.txt".to_string(),
+            "DAGs read by assets/voice.wav".to_string()
+        )]
+    );
+}
+
+#[test]
+fn inputs_count_as_defined_vars() {
+    let cfg = WorkflowConfig::from_yaml(YAML).unwrap();
+    assert!(check_vars(&[cfg], &BTreeMap::new()).is_clean());
+}
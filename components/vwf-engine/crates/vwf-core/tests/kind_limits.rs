@@ -0,0 +1,60 @@
+//! Per-kind batch limit tests.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use vwf_core::{RunEvent, RunOptions, Runner, StepKind};
+
+#[test]
+fn limited_kind_waits_for_the_next_batch() {
+    // A missing python makes the image steps fail at once without a server
+    let yaml = r#"
+version: 1
+name: limits
+steps:
+  - id: img_a
+    kind: text_to_image
+    prompt: "a"
+    output_path: "work/a.png"
+    python_path: "/nonexistent/python3"
+  - id: img_b
+    kind: text_to_image
+    prompt: "b"
+    output_path: "work/b.png"
+    python_path: "/nonexistent/python3"
+  - id: note_a
+    kind: write_file
+    path: "work/a.txt"
+    content: "a"
+  - id: note_b
+    kind: write_file
+    path: "work/b.txt"
+    content: "b"
+"#;
+    let started = |limits: BTreeMap<StepKind, usize>| {
+        let seen: Rc<RefCell<Vec<String>>> = Rc::default();
+        let sink = Rc::clone(&seen);
+        let opts = RunOptions {
+            on_event: Some(Box::new(move |e, _| {
+                if let RunEvent::StepStarted { id } = e {
+                    sink.borrow_mut().push(id.clone());
+                }
+            })),
+            quiet: true,
+            kind_limits: limits,
+            ..Default::default()
+        };
+        let _ = Runner::run_yaml_dry(yaml, "/tmp", BTreeMap::new(), opts);
+        seen.take()
+    };
+
+    assert_eq!(
+        started(BTreeMap::new()),
+        ["img_a", "img_b", "note_a", "note_b"]
+    );
+    assert_eq!(
+        started(BTreeMap::from([(StepKind::TextToImage, 1)])),
+        ["img_a", "note_a", "note_b", "img_b"]
+    );
+}
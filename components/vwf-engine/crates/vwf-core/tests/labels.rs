@@ -0,0 +1,34 @@
+//! Step labels in reports.
+
+use std::collections::BTreeMap;
+use vwf_core::{RunOptions, Runner};
+
+#[test]
+fn step_label_propagates_into_report() {
+    let yaml = r#"
+version: 1
+name: labelled
+steps:
+  - id: clip_intro
+    label: "Intro clip narration"
+    kind: write_file
+    path: "intro.txt"
+    content: "hello"
+  - id: dirs
+    kind: ensure_dirs
+    dirs: ["work"]
+"#;
+    let (report, _) =
+        Runner::run_yaml_dry(yaml, "/tmp", BTreeMap::new(), RunOptions::default()).unwrap();
+    assert_eq!(
+        report.steps[0].label.as_deref(),
+        Some("Intro clip narration")
+    );
+    assert_eq!(report.steps[0].display_name(), "Intro clip narration");
+    assert_eq!(report.steps[1].label, None);
+    assert_eq!(report.steps[1].display_name(), "dirs");
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["steps"][0]["label"], "Intro clip narration");
+    assert!(json["steps"][1].get("label").is_none());
+}
@@ -0,0 +1,110 @@
+//! Multi-document pipeline tests.
+
+use std::collections::BTreeMap;
+
+use vwf_core::{
+    CompletedRuns, FailedRun, FsRuntime, MockLlmClient, RunOptions, Runner, StepStatus,
+    WorkflowConfig,
+};
+
+const PIPELINE: &str = r#"
+version: 1
+name: generate_assets
+vars:
+  title: "Hello"
+steps:
+  - id: write_title
+    kind: write_file
+    path: "assets/title.txt"
+    content: "INTRO:\n{{title}}"
+---
+version: 1
+name: assemble
+steps:
+  - id: split
+    kind: split_sections
+    input_path: "assets/title.txt"
+    outputs:
+      - heading: "INTRO:"
+        path: "out/{{title}}.txt"
+"#;
+
+#[test]
+fn pipeline_parses_every_document() {
+    let pipeline = WorkflowConfig::pipeline_from_yaml(PIPELINE).unwrap();
+    let names: Vec<&str> = pipeline.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["generate_assets", "assemble"]);
+}
+
+#[test]
+fn second_workflow_reads_file_written_by_first() {
+    let workdir = tempfile::tempdir().unwrap();
+    let pipeline = WorkflowConfig::pipeline_from_yaml(PIPELINE).unwrap();
+    let mut rt = FsRuntime::new(workdir.path(), Box::new(MockLlmClient::echo()));
+    let reports =
+        Runner::run_pipeline(&mut rt, &pipeline, BTreeMap::new(), RunOptions::default()).unwrap();
+
+    assert_eq!(reports.len(), 2);
+    assert!(reports[1].steps.iter().all(|s| s.status == StepStatus::Ok));
+    // `title` was carried forward from the first workflow's vars.
+    assert_eq!(reports[1].vars["title"], "Hello");
+    let intro = std::fs::read_to_string(workdir.path().join("out/Hello.txt")).unwrap();
+    assert_eq!(intro, "Hello");
+}
+
+#[test]
+fn pipeline_stops_at_first_failure() {
+    let yaml = r#"
+version: 1
+name: broken
+steps:
+  - id: missing
+    kind: split_sections
+    input_path: "nope.txt"
+    outputs: []
+---
+version: 1
+name: never
+steps:
+  - id: d
+    kind: ensure_dirs
+    dirs: ["never"]
+"#;
+    let workdir = tempfile::tempdir().unwrap();
+    let pipeline = WorkflowConfig::pipeline_from_yaml(yaml).unwrap();
+    let mut rt = FsRuntime::new(workdir.path(), Box::new(MockLlmClient::echo()));
+    let result = Runner::run_pipeline(&mut rt, &pipeline, BTreeMap::new(), RunOptions::default());
+    assert!(result.is_err());
+    assert!(!workdir.path().join("never").exists());
+}
+
+#[test]
+fn failed_pipeline_keeps_reports_of_completed_workflows() {
+    let yaml = r#"
+version: 1
+name: first
+steps:
+  - id: w
+    kind: write_file
+    path: "a.txt"
+    content: "a"
+---
+version: 1
+name: second
+steps:
+  - id: missing
+    kind: split_sections
+    input_path: "nope.txt"
+    outputs: []
+"#;
+    let workdir = tempfile::tempdir().unwrap();
+    let pipeline = WorkflowConfig::pipeline_from_yaml(yaml).unwrap();
+    let mut rt = FsRuntime::new(workdir.path(), Box::new(MockLlmClient::echo()));
+    let err = Runner::run_pipeline(&mut rt, &pipeline, BTreeMap::new(), RunOptions::default())
+        .unwrap_err();
+    let CompletedRuns(done) = err.downcast_ref::<CompletedRuns>().unwrap();
+    assert_eq!(done.len(), 1);
+    assert_eq!(done[0].workflow_name, "first");
+    let FailedRun(failed) = err.downcast_ref::<FailedRun>().unwrap();
+    assert_eq!(failed.workflow_name, "second");
+}
@@ -0,0 +1,62 @@
+//! Engine event callback tests.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use vwf_core::{
+    DryRunRuntime, MockLlmClient, Progress, RunEvent, RunOptions, Runner, StepStatus,
+    WorkflowConfig,
+};
+
+#[test]
+fn callback_receives_progress_counts() {
+    let cfg = WorkflowConfig::from_yaml(
+        r#"
+version: 1
+name: progress
+steps:
+  - id: dirs
+    kind: ensure_dirs
+    dirs: ["out"]
+  - id: note
+    kind: write_file
+    depends_on: [dirs]
+    path: "out/note.txt"
+    content: "hi"
+"#,
+    )
+    .unwrap();
+    let seen: Rc<RefCell<Vec<(RunEvent, Progress)>>> = Rc::default();
+    let sink = Rc::clone(&seen);
+    let opts = RunOptions {
+        on_event: Some(Box::new(move |e, p| {
+            sink.borrow_mut().push((e.clone(), p.clone()))
+        })),
+        quiet: true,
+        ..Default::default()
+    };
+    let mut rt = DryRunRuntime::new("/tmp", Box::new(MockLlmClient::echo()));
+    Runner::run_with_options(&mut rt, &cfg, BTreeMap::new(), opts).unwrap();
+
+    let seen = seen.borrow();
+    let summary: Vec<_> = seen
+        .iter()
+        .map(|(e, p)| (e.clone(), p.total, p.completed, p.running.clone()))
+        .collect();
+    let started = |id: &str| RunEvent::StepStarted { id: id.into() };
+    let finished = |id: &str| RunEvent::StepFinished {
+        id: id.into(),
+        status: StepStatus::Ok,
+    };
+    assert_eq!(
+        summary,
+        vec![
+            (started("dirs"), 2, 0, vec!["dirs".to_string()]),
+            (finished("dirs"), 2, 1, vec![]),
+            (started("note"), 2, 1, vec!["note".to_string()]),
+            (finished("note"), 2, 2, vec![]),
+        ]
+    );
+    assert!(seen.iter().all(|(_, p)| p.failed == 0));
+}
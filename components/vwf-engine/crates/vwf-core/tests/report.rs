@@ -0,0 +1,65 @@
+//! run.json format tests.
+
+use std::collections::BTreeMap;
+use vwf_core::{REPORT_SCHEMA_VERSION, RunOptions, RunReport, Runner, chrome_trace};
+
+const YAML: &str = r#"
+version: 1
+name: versioned
+steps:
+  - id: greet
+    kind: write_file
+    path: "hello.txt"
+    content: "Hello"
+"#;
+
+#[test]
+fn report_carries_schema_version_and_round_trips() {
+    let (report, _) =
+        Runner::run_yaml_dry(YAML, "/tmp", BTreeMap::new(), RunOptions::default()).unwrap();
+    assert_eq!(report.schema_version, REPORT_SCHEMA_VERSION);
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["schema_version"], REPORT_SCHEMA_VERSION);
+    let back: RunReport = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(back, report);
+
+    let mut unversioned = json;
+    unversioned
+        .as_object_mut()
+        .unwrap()
+        .remove("schema_version");
+    let old: RunReport = serde_json::from_value(unversioned).unwrap();
+    assert_eq!(old.schema_version, 1);
+}
+
+#[test]
+fn chrome_trace_has_one_begin_end_pair_per_step() {
+    let yaml = r#"
+version: 1
+name: traced
+steps:
+  - id: dirs
+    kind: ensure_dirs
+    dirs: ["out"]
+  - id: greet
+    kind: write_file
+    path: "out/hello.txt"
+    content: "Hello"
+"#;
+    let (report, _) =
+        Runner::run_yaml_dry(yaml, "/tmp", BTreeMap::new(), RunOptions::default()).unwrap();
+    let trace = chrome_trace(std::slice::from_ref(&report));
+    let events = trace["traceEvents"].as_array().unwrap();
+    assert_eq!(events.len(), 2 * report.steps.len());
+    for step in &report.steps {
+        let phases: Vec<&str> = events
+            .iter()
+            .filter(|e| e["args"]["id"] == step.id.as_str())
+            .map(|e| e["ph"].as_str().unwrap())
+            .collect();
+        assert_eq!(phases, ["B", "E"], "step {}", step.id);
+    }
+    assert!(events.iter().all(|e| e["ts"].as_i64().unwrap() >= 0));
+    assert_eq!(events[0]["cat"], "traced");
+}
@@ -0,0 +1,71 @@
+//! Resolving a workflow for another executor, without running it.
+
+use std::collections::BTreeMap;
+
+use vwf_core::{Runner, WorkflowConfig};
+
+const YAML: &str = r#"
+version: 1
+name: explainer
+description: "An explainer about {{topic}}"
+infer_depends_on: true
+vars:
+  work: "work"
+inputs:
+  - name: topic
+  - name: voice
+    default: "assets/voice.wav"
+steps:
+  - id: script
+    kind: write_file
+    path: "{{work}}/script.txt"
+    content: "All about {{topic}}"
+  - id: narrate
+    kind: tts_generate
+    script_path: "{{work}}/script.txt"
+    output_path: "{{work}}/narration.wav"
+    reference_audio: "{{voice}}"
+    reference_text: "sample"
+    resume_output: "{{work}}/narration.wav"
+"#;
+
+#[test]
+fn resolved_workflow_has_no_placeholders_and_still_parses() {
+    let cfg = WorkflowConfig::from_yaml(YAML).unwrap();
+    let extra = BTreeMap::from([("topic".to_string(), "DAGs".to_string())]);
+    let resolved = Runner::resolve(&cfg, extra).unwrap();
+    let yaml = serde_yaml::to_string(&resolved).unwrap();
+    assert!(!yaml.contains("{{"), "{yaml}");
+
+    let reparsed = WorkflowConfig::from_yaml(&yaml).unwrap();
+    assert_eq!(
+        reparsed.description.as_deref(),
+        Some("An explainer about DAGs")
+    );
+    assert!(reparsed.inputs.is_empty() && !reparsed.infer_depends_on);
+    assert_eq!(reparsed.vars["voice"], "assets/voice.wav");
+    let narrate = &reparsed.steps[1];
+    assert_eq!(narrate.depends_on, ["script"]);
+    assert_eq!(narrate.resume_output.as_deref(), Some("work/narration.wav"));
+    assert_eq!(narrate.payload["reference_audio"], "assets/voice.wav");
+    assert_eq!(reparsed.steps[0].payload["content"], "All about DAGs");
+}
+
+#[test]
+fn unresolvable_templates_and_inputs_fail() {
+    let cfg = WorkflowConfig::from_yaml(YAML).unwrap();
+    let err = Runner::resolve(&cfg, BTreeMap::new()).unwrap_err();
+    assert!(
+        err.to_string().contains("missing 1 required input"),
+        "{err}"
+    );
+
+    let yaml = YAML.replace("All about {{topic}}", "{{summary}}");
+    let cfg = WorkflowConfig::from_yaml(&yaml).unwrap();
+    let extra = BTreeMap::from([("topic".to_string(), "DAGs".to_string())]);
+    let err = Runner::resolve(&cfg, extra).unwrap_err();
+    assert_eq!(
+        format!("{err:#}"),
+        "render step `script`: Missing template var: `summary`"
+    );
+}
@@ -0,0 +1,80 @@
+//! Resuming a run (`--resume`).
+
+use std::collections::BTreeMap;
+
+use vwf_core::{RunOptions, Runner, StepStatus};
+
+#[test]
+fn resume_skip_records_output_exists_reason() {
+    let workdir = tempfile::tempdir().unwrap();
+    std::fs::write(workdir.path().join("script.txt"), "Hello").unwrap();
+    let yaml = r#"
+version: 1
+name: resumed
+steps:
+  - id: script
+    kind: write_file
+    path: "script.txt"
+    content: "Hello"
+    resume_output: "script.txt"
+  - id: notes
+    kind: write_file
+    path: "notes.txt"
+    content: "notes"
+    resume_output: "notes.txt"
+"#;
+    let opts = RunOptions {
+        resume: true,
+        ..Default::default()
+    };
+    let (report, _) = Runner::run_yaml_dry(yaml, workdir.path(), BTreeMap::new(), opts).unwrap();
+    let script = &report.steps[0];
+    assert_eq!(script.status, StepStatus::Skipped);
+    assert_eq!(
+        script.skip_reason.as_deref(),
+        Some("output exists: script.txt")
+    );
+    let json = serde_json::to_value(script).unwrap();
+    assert_eq!(json["skip_reason"], "output exists: script.txt");
+
+    let notes = &report.steps[1];
+    assert_eq!(notes.status, StepStatus::Ok);
+    assert_eq!(notes.skip_reason, None);
+    assert!(
+        serde_json::to_value(notes)
+            .unwrap()
+            .get("skip_reason")
+            .is_none()
+    );
+}
+
+#[test]
+fn in_place_step_with_its_marker_is_skipped() {
+    let workdir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(workdir.path().join("work")).unwrap();
+    std::fs::write(workdir.path().join("work/clip.mp4"), "already normalized").unwrap();
+    std::fs::write(workdir.path().join("work/clip.mp4.done"), "normalize\n").unwrap();
+    let yaml = r#"
+version: 1
+name: renormalize
+steps:
+  - id: normalize
+    kind: normalize_volume
+    clip_path: "work/clip.mp4"
+    resume_output: "work/clip.mp4.done"
+"#;
+    let opts = RunOptions {
+        resume: true,
+        ..Default::default()
+    };
+    let (report, _) = Runner::run_yaml_dry(yaml, workdir.path(), BTreeMap::new(), opts).unwrap();
+    assert_eq!(report.steps[0].status, StepStatus::Skipped);
+    assert_eq!(
+        report.steps[0].skip_reason.as_deref(),
+        Some("output exists: work/clip.mp4.done")
+    );
+    assert_eq!(
+        std::fs::read_to_string(workdir.path().join("work/clip.mp4")).unwrap(),
+        "already normalized"
+    );
+}
@@ -0,0 +1,49 @@
+//! Pinned and deterministic run ids.
+
+use std::collections::BTreeMap;
+use vwf_core::{RunOptions, Runner, Uuid};
+
+const YAML: &str = r#"
+version: 1
+name: ids
+vars:
+  who: world
+steps:
+  - id: greet
+    kind: write_file
+    path: "hello.txt"
+    content: "Hello {{who}}"
+"#;
+
+fn run(opts: RunOptions, vars: &[(&str, &str)]) -> Uuid {
+    let vars = vars
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<BTreeMap<_, _>>();
+    let (report, _) = Runner::run_yaml_dry(YAML, "/tmp", vars, opts).unwrap();
+    report.run_id
+}
+
+#[test]
+fn supplied_run_id_is_reported_unchanged() {
+    let id = Uuid::parse_str("2f1c7d1e-8a4b-4c1d-9e3f-0123456789ab").unwrap();
+    let opts = RunOptions {
+        run_id: Some(id),
+        ..Default::default()
+    };
+    assert_eq!(run(opts, &[]), id);
+}
+
+#[test]
+fn deterministic_run_id_depends_only_on_inputs() {
+    let det = || RunOptions {
+        deterministic: true,
+        ..Default::default()
+    };
+    assert_eq!(run(det(), &[]), run(det(), &[]));
+    assert_ne!(run(det(), &[]), run(det(), &[("who", "there")]));
+    assert_ne!(
+        run(RunOptions::default(), &[]),
+        run(RunOptions::default(), &[])
+    );
+}
@@ -0,0 +1,43 @@
+//! One-call YAML runner tests.
+
+use std::collections::BTreeMap;
+use vwf_core::{RunOptions, Runner};
+
+const YAML: &str = r#"
+version: 1
+name: trivial
+steps:
+  - id: dirs
+    kind: ensure_dirs
+    dirs: ["work"]
+  - id: note
+    kind: write_file
+    depends_on: [dirs]
+    path: "work/note.txt"
+    content: "{{greeting}}"
+"#;
+
+#[test]
+fn run_yaml_dry_plans_without_disk_io() {
+    let vars = BTreeMap::from([("greeting".to_string(), "hi".to_string())]);
+    let (report, rt) =
+        Runner::run_yaml_dry(YAML, "/nonexistent", vars, RunOptions::default()).unwrap();
+    assert_eq!(report.workflow_name, "trivial");
+    assert_eq!(rt.planned_dirs, vec!["work"]);
+    assert_eq!(
+        rt.planned_writes,
+        vec![("work/note.txt".to_string(), "hi".to_string())]
+    );
+}
+
+#[test]
+fn run_yaml_rejects_invalid_yaml() {
+    let err = Runner::run_yaml(
+        "not: [valid",
+        "/tmp",
+        BTreeMap::new(),
+        RunOptions::default(),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Failed to parse workflow YAML"));
+}
@@ -0,0 +1,42 @@
+//! JSON Schema export of the workflow format.
+
+use vwf_core::workflow_schema;
+
+#[test]
+fn schema_is_json_and_describes_step_payloads() {
+    let schema = workflow_schema();
+    let text = serde_json::to_string_pretty(&schema).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(parsed, schema);
+
+    assert_eq!(
+        schema["required"],
+        serde_json::json!(["version", "name", "steps"])
+    );
+    let defs = &schema["definitions"];
+    let kinds = defs["step"]["properties"]["kind"]["enum"]
+        .as_array()
+        .unwrap();
+    assert!(kinds.contains(&"tts_generate".into()));
+
+    let tts = &defs["tts_generate"];
+    let required = tts["required"].as_array().unwrap();
+    assert!(required.contains(&"script_path".into()), "{tts}");
+    assert_eq!(tts["properties"]["script_path"]["type"], "string");
+
+    let run = &defs["run_command"]["properties"];
+    assert_eq!(run["args"]["items"]["type"], "string");
+    assert_eq!(run["inherit_stdio"]["default"], false);
+
+    let refs: Vec<&str> = defs["step"]["allOf"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|rule| rule["then"]["$ref"].as_str().unwrap())
+        .collect();
+    assert_eq!(refs.len(), kinds.len());
+    for r in refs {
+        let name = r.strip_prefix("#/definitions/").unwrap();
+        assert!(defs.get(name).is_some(), "dangling {r}");
+    }
+}
@@ -0,0 +1,32 @@
+//! Segment resolution in step reports.
+
+use std::collections::BTreeMap;
+use vwf_core::{RunOptions, Runner};
+
+#[test]
+fn step_report_carries_its_segment() {
+    let yaml = r#"
+version: 1
+name: segmented
+steps:
+  - id: intro_text
+    kind: write_file
+    path: "intro.txt"
+    content: "hello"
+  - id: loose
+    kind: ensure_dirs
+    dirs: ["work"]
+segments:
+  - id: intro
+    segment_type: music_only
+    steps: [intro_text]
+"#;
+    let (report, _) =
+        Runner::run_yaml_dry(yaml, "/tmp", BTreeMap::new(), RunOptions::default()).unwrap();
+    assert_eq!(report.steps[0].segment.as_deref(), Some("intro"));
+    assert_eq!(report.steps[1].segment, None);
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["steps"][0]["segment"], "intro");
+    assert!(json["steps"][1].get("segment").is_none());
+}
@@ -0,0 +1,55 @@
+//! Static var usage checks.
+
+use std::collections::BTreeMap;
+
+use vwf_core::{WorkflowConfig, check_vars};
+
+#[test]
+fn flags_unused_var_and_undefined_reference() {
+    let yaml = r#"
+version: 1
+name: typo
+vars:
+  voice_ref: narrator.wav
+  out: work
+steps:
+  - id: tts
+    kind: tts_generate
+    script_path: "{{out}}/script.txt"
+    output_path: "{{out}}/voice.wav"
+    voice_ref: "{{voice_reference}}"
+"#;
+    let cfg = WorkflowConfig::from_yaml(yaml).unwrap();
+    let check = check_vars(&[cfg], &BTreeMap::new());
+    assert_eq!(
+        check.problems(),
+        [
+            "undefined var `voice_reference` (used by `tts`)",
+            "unused var `voice_ref`",
+        ]
+    );
+}
+
+#[test]
+fn exported_and_passed_in_vars_count_as_defined() {
+    let yaml = r#"
+version: 1
+name: exports
+steps:
+  - id: probe
+    kind: probe_media
+    input_path: intro.mp4
+    into:
+      duration: intro_duration
+  - id: note
+    kind: write_file
+    path: "{{dir}}/note.txt"
+    content: "intro lasts {{intro_duration}}s"
+"#;
+    let cfg = WorkflowConfig::from_yaml(yaml).unwrap();
+    let extra = BTreeMap::from([("dir".to_string(), "work".to_string())]);
+    assert!(check_vars(std::slice::from_ref(&cfg), &extra).is_clean());
+
+    let check = check_vars(&[cfg], &BTreeMap::new());
+    assert_eq!(check.undefined.keys().collect::<Vec<_>>(), ["dir"]);
+}
@@ -0,0 +1,65 @@
+//! Steps whose kind is unavailable are blocked while the rest run.
+
+use std::collections::BTreeMap;
+
+use vwf_core::{FailedRun, RunOptions, Runner, StepKind, StepStatus};
+
+const YAML: &str = r#"
+version: 1
+name: partial
+steps:
+  - id: script
+    kind: write_file
+    path: "work/script.txt"
+    content: "Hello"
+  - id: narrate
+    kind: tts_generate
+    depends_on: [script]
+    script_path: "work/script.txt"
+    output_path: "work/narration.wav"
+    reference_audio: "voice.wav"
+    reference_text: "sample"
+  - id: mix
+    kind: write_file
+    depends_on: [narrate]
+    path: "work/mix.txt"
+    content: "mixed"
+  - id: notes
+    kind: write_file
+    depends_on: [script]
+    path: "work/notes.txt"
+    content: "notes"
+"#;
+
+#[test]
+fn unavailable_kind_blocks_its_steps_and_their_dependents() {
+    let opts = RunOptions {
+        unavailable_kinds: BTreeMap::from([(
+            StepKind::TtsGenerate,
+            "service down: VoxCPM".to_string(),
+        )]),
+        ..Default::default()
+    };
+    let Err(err) = Runner::run_yaml_dry(YAML, "/tmp", BTreeMap::new(), opts) else {
+        panic!("blocked steps should fail the run");
+    };
+    let FailedRun(report) = err.downcast_ref::<FailedRun>().unwrap();
+    let status: Vec<(&str, &StepStatus, Option<&str>)> = report
+        .steps
+        .iter()
+        .map(|s| (s.id.as_str(), &s.status, s.error.as_deref()))
+        .collect();
+    assert_eq!(
+        status,
+        [
+            ("script", &StepStatus::Ok, None),
+            (
+                "narrate",
+                &StepStatus::Blocked,
+                Some("service down: VoxCPM")
+            ),
+            ("mix", &StepStatus::Blocked, Some("Blocked by: narrate")),
+            ("notes", &StepStatus::Ok, None),
+        ]
+    );
+}
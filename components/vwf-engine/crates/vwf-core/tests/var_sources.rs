@@ -0,0 +1,57 @@
+//! Var precedence logging tests.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use vwf_core::{RunOptions, Runner};
+
+/// Log sink shared between the subscriber and the test.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn overridden_var_logs_override_source() {
+    let yaml = r#"
+version: 1
+name: vars
+vars:
+  voice: alice
+  fps: "30"
+steps:
+  - id: d
+    kind: ensure_dirs
+    dirs: ["work"]
+"#;
+    let sink = Captured::default();
+    let writer = sink.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    let extra = BTreeMap::from([("voice".to_string(), "bob".to_string())]);
+    tracing::subscriber::with_default(subscriber, || {
+        Runner::run_yaml_dry(yaml, "/tmp", extra, RunOptions::default()).unwrap();
+    });
+
+    let logs = String::from_utf8(sink.0.lock().unwrap().clone()).unwrap();
+    assert!(
+        logs.contains(r#"var voice = "bob" (source: override, replaces config default "alice")"#),
+        "{logs}"
+    );
+    assert!(
+        logs.contains(r#"var fps = "30" (source: config default)"#),
+        "{logs}"
+    );
+}
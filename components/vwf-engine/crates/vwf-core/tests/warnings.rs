@@ -0,0 +1,41 @@
+//! Step warnings in run reports.
+
+use std::collections::BTreeMap;
+
+use vwf_core::{RunOptions, Runner, StepStatus};
+
+#[test]
+fn handler_warning_appears_in_its_report() {
+    let yaml = r#"
+version: 1
+name: warned
+steps:
+  - id: piped
+    kind: run_command
+    program: "echo hi | tr a-z A-Z"
+    shell: true
+  - id: note
+    kind: write_file
+    path: "note.txt"
+    content: "hi"
+"#;
+    let opts = RunOptions {
+        quiet: true,
+        ..Default::default()
+    };
+    let (report, _) = Runner::run_yaml_dry(yaml, "/tmp", BTreeMap::new(), opts).unwrap();
+    let piped = &report.steps[0];
+    assert_eq!(piped.status, StepStatus::Ok);
+    assert_eq!(piped.warnings.len(), 1);
+    assert!(
+        piped.warnings[0].contains("the command allowlist only checks"),
+        "{:?}",
+        piped.warnings
+    );
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["steps"][0]["warnings"][0], piped.warnings[0].as_str());
+    assert!(json["steps"][1].get("warnings").is_none());
+    assert_eq!(piped.retries, 0);
+    assert!(json["steps"][0].get("retries").is_none());
+}
@@ -4,22 +4,54 @@ use anyhow::{Result, anyhow};
 use regex::Regex;
 use std::collections::BTreeMap;
 
+/// `{{var}}`, with an optional trim marker (`-` plus whitespace) on either
+/// side: `{{- var -}}`.
+fn var_pattern() -> Regex {
+    Regex::new(r#"\{\{(-\s+|\s*)([a-zA-Z0-9_\-\.]+)(\s+-|\s*)\}\}"#).unwrap()
+}
+
 /// Render a template by replacing `{{var}}` with values from vars.
+///
+/// `{{- var }}` strips all whitespace in the output before the value's
+/// first non-whitespace character, both the template's and the value's
+/// own; `{{ var -}}` does the same after its last one.
 pub fn render_template(input: &str, vars: &BTreeMap<String, String>) -> Result<String> {
-    let re = Regex::new(r#"\{\{\s*([a-zA-Z0-9_\-\.]+)\s*\}\}"#).unwrap();
+    let re = var_pattern();
     let mut out = String::with_capacity(input.len());
     let mut last = 0usize;
 
     for cap in re.captures_iter(input) {
         let m = cap.get(0).unwrap();
-        let key = cap.get(1).unwrap().as_str();
-        out.push_str(&input[last..m.start()]);
-        match vars.get(key) {
-            Some(v) => out.push_str(v),
-            None => return Err(anyhow!("Missing template var: `{key}`")),
+        let key = cap.get(2).unwrap().as_str();
+        let trim_left = cap[1].starts_with('-');
+        let trim_right = cap[3].ends_with('-');
+        // A trim marker on the previous placeholder may have eaten past this one
+        out.push_str(input.get(last..m.start()).unwrap_or_default());
+        let Some(mut value) = vars.get(key).map(String::as_str) else {
+            return Err(anyhow!("Missing template var: `{key}`"));
+        };
+        if trim_left {
+            out.truncate(out.trim_end().len());
+            value = value.trim_start();
+        }
+        if trim_right {
+            value = value.trim_end();
         }
+        out.push_str(value);
         last = m.end();
+        if trim_right {
+            let rest = &input[last..];
+            last += rest.len() - rest.trim_start().len();
+        }
     }
     out.push_str(&input[last..]);
     Ok(out)
 }
+
+/// Names of the vars `input` references, in order of appearance.
+pub fn template_vars(input: &str) -> Vec<String> {
+    var_pattern()
+        .captures_iter(input)
+        .map(|cap| cap[2].to_string())
+        .collect()
+}
@@ -1,7 +1,7 @@
 //! Template rendering tests.
 
 use std::collections::BTreeMap;
-use vwf_render::render_template;
+use vwf_render::{render_template, template_vars};
 
 #[test]
 fn replaces_vars() {
@@ -19,3 +19,45 @@ fn missing_var_errors() {
         .to_string();
     assert!(err.contains("Missing template var"));
 }
+
+#[test]
+fn lists_referenced_vars() {
+    let names = template_vars("{{ a }}/{{b.c}} and {{a}}, not {b}");
+    assert_eq!(names, ["a", "b.c", "a"]);
+}
+
+#[test]
+fn trim_markers_strip_whitespace_around_a_multi_line_value() {
+    let mut vars = BTreeMap::new();
+    vars.insert("narration".into(), "\n\nHello.\nBye.\n\n".into());
+    let render = |t: &str| render_template(t, &vars).unwrap();
+
+    assert_eq!(
+        render("Script:\n{{ narration }}\nEnd"),
+        "Script:\n\n\nHello.\nBye.\n\n\nEnd"
+    );
+    assert_eq!(
+        render("Script: {{- narration }}\nEnd"),
+        "Script:Hello.\nBye.\n\n\nEnd"
+    );
+    assert_eq!(
+        render("Script:\n{{ narration -}}\n\nEnd"),
+        "Script:\n\n\nHello.\nBye.End"
+    );
+    assert_eq!(
+        render("Script:\n  {{- narration -}}  \nEnd"),
+        "Script:Hello.\nBye.End"
+    );
+}
+
+#[test]
+fn trim_markers_need_whitespace_after_the_dash() {
+    let mut vars = BTreeMap::new();
+    vars.insert("-x".into(), "dash".into());
+    vars.insert("x".into(), "plain".into());
+    assert_eq!(
+        render_template("a {{-x}} {{- x -}} b", &vars).unwrap(),
+        "a dashplainb"
+    );
+    assert_eq!(template_vars("{{-x}} {{- x -}}"), ["-x", "x"]);
+}
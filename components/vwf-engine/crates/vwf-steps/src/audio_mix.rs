@@ -2,12 +2,16 @@
 //!
 //! This step takes a video with existing audio (e.g., narration) and mixes
 //! in an overlay audio track (e.g., background music) at a specified volume.
+//! With `duck: true` the music also dips whenever the narration is speaking.
 
 use anyhow::{Context, Result, bail};
 use serde::Deserialize;
-use std::process::Command;
+use std::path::Path;
 
+use crate::catalog::{FieldDoc, StepDoc};
 use crate::context::StepCtx;
+use crate::ffmpeg;
+use crate::temp::TempPath;
 
 #[derive(Deserialize)]
 struct Payload {
@@ -26,8 +30,89 @@ struct Payload {
     /// Fade out duration in seconds for overlay audio (default: 2.0)
     #[serde(default = "default_fade_out")]
     fade_out: f64,
+    /// Lower the overlay while the base audio is speaking (sidechain compression)
+    #[serde(default)]
+    duck: bool,
+    /// Base audio level (0-1) above which the overlay is ducked
+    #[serde(default = "default_duck_threshold")]
+    duck_threshold: f64,
+    /// How hard the overlay is compressed once ducking kicks in
+    #[serde(default = "default_duck_ratio")]
+    duck_ratio: f64,
+    /// Milliseconds for the overlay to dip once narration starts
+    #[serde(default = "default_duck_attack")]
+    duck_attack: f64,
+    /// Milliseconds for the overlay to come back once narration stops
+    #[serde(default = "default_duck_release")]
+    duck_release: f64,
 }
 
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "audio_mix",
+    summary: "Overlay an audio track (e.g. music) onto a video clip with ffmpeg",
+    fields: &[
+        FieldDoc::required(
+            "base_clip",
+            "string",
+            "Input video clip (with existing audio, e.g. narration)",
+        ),
+        FieldDoc::required(
+            "overlay_audio",
+            "string",
+            "Audio file to overlay (e.g. background music)",
+        ),
+        FieldDoc::defaulted(
+            "overlay_volume",
+            "integer",
+            || default_overlay_volume().into(),
+            "Overlay volume in dB",
+        ),
+        FieldDoc::required("output_path", "string", "Output path for the mixed video"),
+        FieldDoc::defaulted(
+            "loop_overlay",
+            "boolean",
+            || default_loop().into(),
+            "Loop the overlay to match video length",
+        ),
+        FieldDoc::defaulted(
+            "fade_out",
+            "number",
+            || default_fade_out().into(),
+            "Overlay fade-out duration in seconds",
+        ),
+        FieldDoc::defaulted(
+            "duck",
+            "boolean",
+            || false.into(),
+            "Lower the overlay while the base clip's audio is speaking",
+        ),
+        FieldDoc::defaulted(
+            "duck_threshold",
+            "number",
+            || default_duck_threshold().into(),
+            "Base audio level (0-1) that triggers ducking",
+        ),
+        FieldDoc::defaulted(
+            "duck_ratio",
+            "number",
+            || default_duck_ratio().into(),
+            "Compression ratio applied to the overlay while ducked",
+        ),
+        FieldDoc::defaulted(
+            "duck_attack",
+            "number",
+            || default_duck_attack().into(),
+            "Milliseconds for the overlay to dip",
+        ),
+        FieldDoc::defaulted(
+            "duck_release",
+            "number",
+            || default_duck_release().into(),
+            "Milliseconds for the overlay to recover",
+        ),
+    ],
+};
+
 fn default_overlay_volume() -> i32 {
     -32 // Background music should be quieter than narration
 }
@@ -40,6 +125,22 @@ fn default_fade_out() -> f64 {
     2.0
 }
 
+fn default_duck_threshold() -> f64 {
+    0.05 // About -26dB: quiet narration still ducks, room noise doesn't
+}
+
+fn default_duck_ratio() -> f64 {
+    8.0
+}
+
+fn default_duck_attack() -> f64 {
+    20.0
+}
+
+fn default_duck_release() -> f64 {
+    400.0 // Slow enough that music doesn't pump between words
+}
+
 pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()> {
     let p: Payload = serde_json::from_value(payload.clone())
         .with_context(|| ctx.error_context("payload decode audio_mix"))?;
@@ -50,24 +151,9 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
     let output_path = ctx.render(&p.output_path)?;
 
     // Resolve paths (relative to workdir)
-    let workdir = ctx.rt.workdir();
-    let resolved_base = if base_clip.starts_with('/') {
-        base_clip.clone()
-    } else {
-        workdir.join(&base_clip).to_string_lossy().to_string()
-    };
-
-    let resolved_overlay = if overlay_audio.starts_with('/') {
-        overlay_audio.clone()
-    } else {
-        workdir.join(&overlay_audio).to_string_lossy().to_string()
-    };
-
-    let resolved_output = if output_path.starts_with('/') {
-        output_path.clone()
-    } else {
-        workdir.join(&output_path).to_string_lossy().to_string()
-    };
+    let resolved_base = ctx.resolve(&base_clip).to_string_lossy().to_string();
+    let resolved_overlay = ctx.resolve(&overlay_audio).to_string_lossy().to_string();
+    let resolved_output = ctx.output_path(&output_path)?.to_string_lossy().to_string();
 
     // Verify inputs exist
     if !std::path::Path::new(&resolved_base).exists() {
@@ -89,12 +175,49 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
     }
 
     // Get video duration for fade-out calculation
-    let duration = get_video_duration(&resolved_base)?;
+    let duration = ffmpeg::duration_secs(&resolved_base)?;
     let fade_start = (duration - p.fade_out).max(0.0);
 
-    // Build complex audio filter
-    // [0:a] = base clip audio (narration)
-    // [1:a] = overlay audio (music)
+    let filter_complex = filter_graph(&p, fade_start);
+
+    // Write beside the output so a failed mix neither leaves a half-written
+    // file for --resume to trust nor destroys the last good one
+    let temp = TempPath::beside(Path::new(&resolved_output));
+    let temp_path = temp.path().to_string_lossy().to_string();
+    ffmpeg::run_ffmpeg(&[
+        "-y",
+        "-i",
+        &resolved_base,
+        "-stream_loop",
+        "-1", // Loop the overlay audio file
+        "-i",
+        &resolved_overlay,
+        "-filter_complex",
+        &filter_complex,
+        "-map",
+        "0:v", // Video from base clip
+        "-map",
+        "[aout]", // Mixed audio
+        "-c:v",
+        "copy", // Don't re-encode video
+        "-c:a",
+        "aac",
+        "-b:a",
+        "192k",
+        "-shortest", // Stop when shortest input ends
+        &temp_path,
+    ])
+    .with_context(|| ctx.error_context("ffmpeg audio_mix"))?;
+    temp.persist(Path::new(&resolved_output))
+        .with_context(|| format!("Failed to replace {resolved_output}"))?;
+
+    println!("  Created: {}", resolved_output);
+    Ok(())
+}
+
+/// The `-filter_complex` graph mixing `[0:a]` (base clip audio, e.g.
+/// narration) with `[1:a]` (overlay, e.g. music) into `[aout]`.
+fn filter_graph(p: &Payload, fade_start: f64) -> String {
     let mut filter_parts = Vec::new();
 
     // Loop overlay audio if needed
@@ -115,71 +238,26 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
         music_input, p.overlay_volume, fade_start, p.fade_out
     ));
 
-    // Mix base audio with adjusted overlay
-    filter_parts.push(
-        "[0:a][music_adj]amix=inputs=2:duration=first:dropout_transition=2[aout]".to_string(),
-    );
-
-    let filter_complex = filter_parts.join(";");
-
-    let status = Command::new("ffmpeg")
-        .args([
-            "-y",
-            "-i",
-            &resolved_base,
-            "-stream_loop",
-            "-1", // Loop the overlay audio file
-            "-i",
-            &resolved_overlay,
-            "-filter_complex",
-            &filter_complex,
-            "-map",
-            "0:v", // Video from base clip
-            "-map",
-            "[aout]", // Mixed audio
-            "-c:v",
-            "copy", // Don't re-encode video
-            "-c:a",
-            "aac",
-            "-b:a",
-            "192k",
-            "-shortest", // Stop when shortest input ends
-            &resolved_output,
-        ])
-        .status()
-        .with_context(|| ctx.error_context("spawn ffmpeg audio_mix"))?;
-
-    if !status.success() {
-        bail!(
-            "ffmpeg audio_mix failed with exit code: {:?}",
-            status.code()
+    if p.duck {
+        // The narration is both what gets mixed and the sidechain key
+        // that squeezes the music, so it has to be split in two.
+        filter_parts.push("[0:a]asplit=2[voice][voice_key]".to_string());
+        filter_parts.push(format!(
+            "[music_adj][voice_key]sidechaincompress=threshold={}:ratio={}:attack={}:release={}[music_ducked]",
+            p.duck_threshold, p.duck_ratio, p.duck_attack, p.duck_release
+        ));
+        filter_parts.push(
+            "[voice][music_ducked]amix=inputs=2:duration=first:dropout_transition=2[aout]"
+                .to_string(),
+        );
+    } else {
+        // Mix base audio with adjusted overlay
+        filter_parts.push(
+            "[0:a][music_adj]amix=inputs=2:duration=first:dropout_transition=2[aout]".to_string(),
         );
     }
 
-    println!("  Created: {}", resolved_output);
-    Ok(())
-}
-
-/// Get video duration in seconds using ffprobe
-fn get_video_duration(path: &str) -> Result<f64> {
-    let output = Command::new("ffprobe")
-        .args([
-            "-v",
-            "error",
-            "-show_entries",
-            "format=duration",
-            "-of",
-            "csv=p=0",
-            path,
-        ])
-        .output()
-        .context("Failed to run ffprobe for duration")?;
-
-    let duration_str = String::from_utf8_lossy(&output.stdout);
-    duration_str
-        .trim()
-        .parse::<f64>()
-        .context("Failed to parse video duration")
+    filter_parts.join(";")
 }
 
 #[cfg(test)]
@@ -192,4 +270,42 @@ mod tests {
         assert!(default_loop());
         assert_eq!(default_fade_out(), 2.0);
     }
+
+    fn payload(extra: serde_json::Value) -> Payload {
+        let mut json = serde_json::json!({
+            "base_clip": "clip.mp4",
+            "overlay_audio": "music.wav",
+            "output_path": "out.mp4",
+            "loop_overlay": false
+        });
+        json.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn fixed_volume_mix_is_the_default() {
+        let graph = filter_graph(&payload(serde_json::json!({})), 8.0);
+        assert_eq!(
+            graph,
+            "[1:a]volume=-32dB,afade=t=out:st=8:d=2[music_adj];\
+             [0:a][music_adj]amix=inputs=2:duration=first:dropout_transition=2[aout]"
+        );
+    }
+
+    #[test]
+    fn duck_routes_narration_into_a_sidechain() {
+        let p = payload(serde_json::json!({"duck": true, "duck_ratio": 4, "duck_release": 600}));
+        let graph = filter_graph(&p, 8.0);
+        assert_eq!(
+            graph.split(';').collect::<Vec<_>>(),
+            [
+                "[1:a]volume=-32dB,afade=t=out:st=8:d=2[music_adj]",
+                "[0:a]asplit=2[voice][voice_key]",
+                "[music_adj][voice_key]sidechaincompress=threshold=0.05:ratio=4:attack=20:release=600[music_ducked]",
+                "[voice][music_ducked]amix=inputs=2:duration=first:dropout_transition=2[aout]",
+            ]
+        );
+    }
 }
@@ -0,0 +1,124 @@
+//! Content-addressed cache for generated images and videos.
+//!
+//! With the `generation_cache` var set to a directory, a generative step
+//! hashes everything that shapes its output (kind, rendered prompt, seed,
+//! resolution, model and sampler settings) and looks for a file stored
+//! under that hash. On a hit the file is copied to `output_path` and no
+//! service is called; on a miss the step generates as usual and stores the
+//! result. The directory may sit outside the workdir, so projects share it.
+//! Without the var, or in a dry run, nothing is cached.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::context::StepCtx;
+use crate::hash::sha256_hex;
+use crate::temp::TempPath;
+
+/// Var naming the cache directory; a relative path is under the workdir.
+pub const CACHE_VAR: &str = "generation_cache";
+
+/// Produce `output` from the cache, or with `generate` and then store it.
+///
+/// `key` builds a value holding every input that changes the generated
+/// file; it is only called when caching is on. Output that needed a
+/// regeneration with a later seed is still stored under the original key,
+/// so the next lookup gets the valid file straight away.
+pub(crate) fn cached(
+    ctx: &mut StepCtx<'_>,
+    key: impl FnOnce() -> Result<Value>,
+    output: &Path,
+    generate: impl FnOnce(&mut StepCtx<'_>) -> Result<()>,
+) -> Result<()> {
+    let Some(dir) = cache_dir(ctx) else {
+        return generate(ctx);
+    };
+    let key = key().with_context(|| ctx.error_context("generation cache key"))?;
+    let entry = entry_path(&dir, &key, output);
+    if entry.is_file() {
+        copy_atomic(&entry, output)
+            .with_context(|| ctx.error_context("copy from generation cache"))?;
+        println!("  Cache hit: {}", entry.display());
+        return Ok(());
+    }
+    generate(ctx)?;
+    if let Err(e) = copy_atomic(output, &entry) {
+        ctx.warn(format!(
+            "could not store {} in the generation cache: {e:#}",
+            output.display()
+        ));
+    }
+    Ok(())
+}
+
+/// The cache directory, if caching is on.
+fn cache_dir(ctx: &StepCtx<'_>) -> Option<PathBuf> {
+    let dir = ctx.vars.get(CACHE_VAR).map(|d| d.trim())?;
+    if dir.is_empty() || ctx.rt.is_dry_run() {
+        return None;
+    }
+    Some(ctx.resolve(dir))
+}
+
+/// `<cache dir>/<sha256 of key>.<output's extension>`.
+fn entry_path(dir: &Path, key: &Value, output: &Path) -> PathBuf {
+    let mut name = sha256_hex(key.to_string().as_bytes());
+    if let Some(ext) = output.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
+    }
+    dir.join(name)
+}
+
+/// Copy `from` to `to` through a temp file beside it, so a reader never
+/// sees a partial file.
+fn copy_atomic(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("create_dir_all {}", parent.display()))?;
+    }
+    let tmp = TempPath::beside(to);
+    std::fs::copy(from, tmp.path())
+        .with_context(|| format!("copy {} to {}", from.display(), to.display()))?;
+    tmp.persist(to)
+        .with_context(|| format!("rename into {}", to.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use vwf_runtime::{DryRunRuntime, FsRuntime, MockLlmClient};
+
+    #[test]
+    fn different_keys_miss_and_dry_runs_skip_the_cache() {
+        let workdir = std::env::temp_dir().join(format!("vwf_cache_{}", std::process::id()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        let mut rt = FsRuntime::new(&workdir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::from([(CACHE_VAR.to_string(), "cache".to_string())]);
+        let mut ctx = StepCtx::new(&mut rt, &vars, "img");
+        let output = workdir.join("out.png");
+        let mut generated = 0;
+        for seed in [1, 2, 1] {
+            let key = || Ok(serde_json::json!({ "seed": seed }));
+            cached(&mut ctx, key, &output, |_| {
+                generated += 1;
+                std::fs::write(&output, format!("seed {seed}"))?;
+                Ok(())
+            })
+            .unwrap();
+            assert_eq!(
+                std::fs::read_to_string(&output).unwrap(),
+                format!("seed {seed}")
+            );
+        }
+        assert_eq!(generated, 2);
+        assert_eq!(std::fs::read_dir(workdir.join("cache")).unwrap().count(), 2);
+
+        let mut rt = DryRunRuntime::new(&workdir, Box::new(MockLlmClient::echo()));
+        let ctx = StepCtx::new(&mut rt, &vars, "img");
+        assert_eq!(cache_dir(&ctx), None);
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+}
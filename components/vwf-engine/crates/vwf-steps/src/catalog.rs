@@ -0,0 +1,84 @@
+//! Self-describing step catalog.
+//!
+//! Each handler module declares a `DOC` next to its `Payload` struct; the
+//! CLI prints these for `vwf steps`. The field list is written by hand and
+//! must be updated along with the struct. Only defaults are shared: they
+//! point at the same `default_*` functions serde uses.
+
+use serde_json::Value;
+
+/// Documentation for one step kind.
+#[derive(Debug)]
+pub struct StepDoc {
+    /// `kind:` value in workflow YAML.
+    pub kind: &'static str,
+    /// One-line description.
+    pub summary: &'static str,
+    pub fields: &'static [FieldDoc],
+}
+
+/// One payload field of a step.
+#[derive(Debug)]
+pub struct FieldDoc {
+    pub name: &'static str,
+    /// Type as written in YAML terms (`string`, `integer`, `array[string]`, ...).
+    pub ty: &'static str,
+    pub presence: Presence,
+    pub doc: &'static str,
+}
+
+/// Whether a field must be given, and what it is when omitted.
+#[derive(Debug)]
+pub enum Presence {
+    Required,
+    /// Omitted means unset/empty.
+    Optional,
+    /// Omitted means this value.
+    Default(fn() -> Value),
+}
+
+impl FieldDoc {
+    pub const fn required(name: &'static str, ty: &'static str, doc: &'static str) -> Self {
+        Self {
+            name,
+            ty,
+            presence: Presence::Required,
+            doc,
+        }
+    }
+
+    pub const fn optional(name: &'static str, ty: &'static str, doc: &'static str) -> Self {
+        Self {
+            name,
+            ty,
+            presence: Presence::Optional,
+            doc,
+        }
+    }
+
+    pub const fn defaulted(
+        name: &'static str,
+        ty: &'static str,
+        default: fn() -> Value,
+        doc: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            ty,
+            presence: Presence::Default(default),
+            doc,
+        }
+    }
+
+    pub fn is_required(&self) -> bool {
+        matches!(self.presence, Presence::Required)
+    }
+
+    /// The default rendered as JSON, if the field has one.
+    pub fn default_value(&self) -> Option<Value> {
+        match self.presence {
+            Presence::Default(f) => Some(f()),
+            _ => None,
+        }
+    }
+}
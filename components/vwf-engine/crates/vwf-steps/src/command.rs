@@ -0,0 +1,102 @@
+//! Subprocess seam so steps that shell out can be tested without the
+//! real binaries installed.
+
+use std::process::Command;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use vwf_runtime::CmdOut;
+
+/// Runs an external program and captures its output.
+pub trait CommandRunner {
+    fn run(&mut self, program: &str, args: &[String]) -> Result<CmdOut>;
+}
+
+/// Default runner backed by `std::process::Command`.
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&mut self, program: &str, args: &[String]) -> Result<CmdOut> {
+        let started = Instant::now();
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .with_context(|| format!("Failed to run {program}"))?;
+        Ok(CmdOut {
+            status: output.status.code().unwrap_or(-1),
+            stdout: String::from_utf8_lossy(&output.stdout).into(),
+            stderr: String::from_utf8_lossy(&output.stderr).into(),
+            duration_ms: started.elapsed().as_millis(),
+        })
+    }
+}
+
+/// Whether `err` came from a program that isn't installed.
+pub fn is_not_found(err: &anyhow::Error) -> bool {
+    err.root_cause()
+        .downcast_ref::<std::io::Error>()
+        .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+}
+
+#[cfg(test)]
+pub mod fake {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    /// Recorded `(program, args)` invocations.
+    pub type Calls = Rc<RefCell<Vec<(String, Vec<String>)>>>;
+
+    /// Records every invocation and answers with a canned result.
+    pub struct FakeRunner {
+        pub calls: Calls,
+        pub result: CmdOut,
+        /// Programs that fail to spawn as if not installed.
+        pub missing: Vec<String>,
+        /// Create the file named by the last argument, as ffmpeg and
+        /// ImageMagick do (even when they then fail half way). A `format:`
+        /// prefix is dropped as ImageMagick does.
+        pub writes_output: bool,
+    }
+
+    impl FakeRunner {
+        /// A runner whose commands all succeed with empty output.
+        pub fn succeeding() -> (Self, Calls) {
+            let calls = Calls::default();
+            let runner = Self {
+                calls: Rc::clone(&calls),
+                result: CmdOut {
+                    status: 0,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                    duration_ms: 0,
+                },
+                missing: vec![],
+                writes_output: false,
+            };
+            (runner, calls)
+        }
+    }
+
+    impl CommandRunner for FakeRunner {
+        fn run(&mut self, program: &str, args: &[String]) -> Result<CmdOut> {
+            if self.missing.iter().any(|m| m == program) {
+                return Err(std::io::Error::from(std::io::ErrorKind::NotFound))
+                    .with_context(|| format!("Failed to run {program}"));
+            }
+            self.calls
+                .borrow_mut()
+                .push((program.to_string(), args.to_vec()));
+            if self.writes_output
+                && let Some(output) = args.last()
+            {
+                let output = output
+                    .split_once(':')
+                    .map_or(output.as_str(), |(_, path)| path);
+                std::fs::write(output, program)?;
+            }
+            Ok(self.result.clone())
+        }
+    }
+}
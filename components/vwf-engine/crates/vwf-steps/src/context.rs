@@ -1,15 +1,28 @@
 //! Step execution context with dependency injection.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::collections::BTreeMap;
+use std::path::PathBuf;
 
 use vwf_runtime::Runtime;
 
+use crate::command::{CommandRunner, SystemRunner};
+
 /// Execution context passed to all step handlers.
 pub struct StepCtx<'a> {
     pub rt: &'a mut dyn Runtime,
     pub vars: &'a BTreeMap<String, String>,
     pub step_id: &'a str,
+    /// Subprocess runner for steps that shell out directly.
+    pub runner: Box<dyn CommandRunner>,
+    /// Vars set by the step, visible to later steps.
+    pub exports: BTreeMap<String, String>,
+    /// Workflow-wide seed that per-step seeds are derived from.
+    pub base_seed: Option<u64>,
+    /// Non-fatal problems, recorded in the step's report.
+    pub warnings: Vec<String>,
+    /// Outputs regenerated after failing validation.
+    pub retries: u32,
 }
 
 impl<'a> StepCtx<'a> {
@@ -18,14 +31,130 @@ impl<'a> StepCtx<'a> {
         vars: &'a BTreeMap<String, String>,
         step_id: &'a str,
     ) -> Self {
-        Self { rt, vars, step_id }
+        Self {
+            rt,
+            vars,
+            step_id,
+            runner: Box::new(SystemRunner),
+            exports: BTreeMap::new(),
+            base_seed: None,
+            warnings: vec![],
+            retries: 0,
+        }
+    }
+
+    /// Replace the subprocess runner (e.g. with a fake in tests).
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_runner(mut self, runner: Box<dyn CommandRunner>) -> Self {
+        self.runner = runner;
+        self
+    }
+
+    /// Use a workflow-wide seed instead of random seeds.
+    pub fn with_base_seed(mut self, base_seed: Option<u64>) -> Self {
+        self.base_seed = base_seed;
+        self
+    }
+
+    /// The seed for this step: `explicit` if the step set one, else derived
+    /// from the base seed and step id, else random.
+    pub fn seed(&self, explicit: Option<u64>) -> u64 {
+        match (explicit, self.base_seed) {
+            (Some(seed), _) => seed,
+            (None, Some(base)) => derive_seed(base, self.step_id),
+            (None, None) => rand::random(),
+        }
     }
 
     pub fn render(&self, template: &str) -> Result<String> {
         vwf_render::render_template(template, self.vars)
     }
 
+    /// Absolute path a step writes to: [`Self::resolve`], but it must also
+    /// stay inside the workdir.
+    pub fn output_path(&self, rel: &str) -> Result<PathBuf> {
+        self.rt
+            .resolve_safe(rel)
+            .with_context(|| self.error_context("output path"))
+    }
+
+    /// Where a path declared in a step points.
+    ///
+    /// Relative paths are always relative to the workdir, never to the
+    /// process's current directory or a `run_command` `cwd`. Absolute paths
+    /// are kept, since inputs may live outside the workdir (e.g. a shared
+    /// voice sample).
+    pub fn resolve(&self, path: &str) -> PathBuf {
+        self.rt.workdir().join(path)
+    }
+
+    /// Log a non-fatal problem and record it for the step's report.
+    pub fn warn(&mut self, msg: impl Into<String>) {
+        let msg = msg.into();
+        println!("  WARNING: {msg}");
+        self.warnings.push(msg);
+    }
+
     pub fn error_context(&self, msg: &str) -> String {
         format!("step `{}` {}", self.step_id, msg)
     }
 }
+
+/// FNV-1a over the base seed and step id, so each step gets its own
+/// stable seed.
+fn derive_seed(base: u64, step_id: &str) -> u64 {
+    base.to_le_bytes()
+        .iter()
+        .chain(step_id.as_bytes())
+        .fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+            (hash ^ u64::from(*b)).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vwf_runtime::{DryRunRuntime, MockLlmClient};
+
+    fn seed_for(step_id: &str, base: Option<u64>, explicit: Option<u64>) -> u64 {
+        let mut rt = DryRunRuntime::new("/tmp", Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        StepCtx::new(&mut rt, &vars, step_id)
+            .with_base_seed(base)
+            .seed(explicit)
+    }
+
+    #[test]
+    fn base_seed_gives_stable_per_step_seeds() {
+        let title = seed_for("title_image", Some(42), None);
+        assert_eq!(title, seed_for("title_image", Some(42), None));
+        assert_eq!(title, derive_seed(42, "title_image"));
+        assert_ne!(title, seed_for("outro_image", Some(42), None));
+        assert_ne!(title, seed_for("title_image", Some(43), None));
+        assert_eq!(seed_for("title_image", Some(42), Some(7)), 7);
+    }
+
+    #[test]
+    fn output_paths_must_stay_in_workdir() {
+        let mut rt = DryRunRuntime::new("/work", Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let ctx = StepCtx::new(&mut rt, &vars, "tts");
+        let err = ctx.output_path("../../etc/x").unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "step `tts` output path: path `../../etc/x` is outside the workdir"
+        );
+        assert_eq!(
+            ctx.output_path("audio/../audio/a.wav").unwrap(),
+            PathBuf::from("/work/audio/a.wav")
+        );
+        assert_eq!(
+            ctx.resolve("/voices/ref.wav"),
+            PathBuf::from("/voices/ref.wav")
+        );
+        assert_eq!(
+            ctx.resolve("voices/ref.wav"),
+            PathBuf::from("/work/voices/ref.wav")
+        );
+    }
+}
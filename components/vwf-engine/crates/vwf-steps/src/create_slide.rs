@@ -2,37 +2,57 @@
 //!
 //! Creates static image slides with text for title screens, bullet points,
 //! or quote cards. Uses ImageMagick for text rendering.
+//!
+//! With `markdown: true` the text is read as a small markdown subset
+//! (`#` headings, `-` bullets, `**bold**`) and each run is drawn as its own
+//! `-annotate` layer, ignoring `template`.
+//!
+//! `guides: true` draws the safe area on top of the slide, so text that
+//! would end up under a platform's buttons and captions is easy to spot.
 
 use anyhow::{Context, Result, bail};
 use serde::Deserialize;
-use std::process::Command;
 
+use crate::catalog::{FieldDoc, StepDoc};
 use crate::context::StepCtx;
+use crate::imagemagick;
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(rename_all = "snake_case")]
 enum SlideTemplate {
-    Title,  // Large centered text with optional subtitle
+    #[default]
+    Title, // Large centered text with optional subtitle
     Bullet, // List of bullet points
     Quote,  // Quoted text with attribution
 }
 
-impl Default for SlideTemplate {
-    fn default() -> Self {
-        Self::Title
-    }
-}
-
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(rename_all = "snake_case")]
 enum Orientation {
+    #[default]
     Landscape,
     Portrait,
 }
 
-impl Default for Orientation {
-    fn default() -> Self {
-        Self::Landscape
+/// Vertical-video platform whose UI overlays the slide.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Platform {
+    Shorts,
+    Tiktok,
+    Reels,
+}
+
+impl Platform {
+    /// Pixels covered by the platform's UI at 1080x1920, as
+    /// (top, right, bottom, left): the right edge holds the like/share
+    /// buttons and the bottom the caption and channel name.
+    fn insets(self) -> (u32, u32, u32, u32) {
+        match self {
+            Platform::Shorts => (200, 140, 400, 60),
+            Platform::Tiktok => (160, 140, 480, 60),
+            Platform::Reels => (220, 120, 420, 60),
+        }
     }
 }
 
@@ -63,8 +83,109 @@ struct Payload {
     /// Font name (default: DejaVu-Sans-Bold)
     #[serde(default = "default_font")]
     font: String,
+    /// Parse `text` as markdown (headings, bullets, bold) instead of using the template
+    #[serde(default)]
+    markdown: bool,
+    /// Font for non-bold markdown body text (default: DejaVu-Sans)
+    #[serde(default = "default_body_font")]
+    body_font: String,
+    /// ImageMagick binary; `convert` then `magick` are tried when unset.
+    #[serde(default)]
+    imagemagick_path: Option<String>,
+    /// Platform the slide is previewed for; implies a portrait canvas
+    #[serde(default)]
+    platform: Option<Platform>,
+    /// Draw the safe-area rectangle over the slide (for checking layout)
+    #[serde(default)]
+    guides: bool,
 }
 
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "create_slide",
+    summary: "Render a title, bullet or quote slide image with ImageMagick",
+    fields: &[
+        FieldDoc::defaulted(
+            "template",
+            "string",
+            || "title".into(),
+            "title, bullet or quote",
+        ),
+        FieldDoc::required("text", "string", "Main text content"),
+        FieldDoc::optional("subtext", "string", "Subtitle or attribution"),
+        FieldDoc::optional(
+            "background",
+            "string",
+            "Background image path (solid color if unset)",
+        ),
+        FieldDoc::defaulted(
+            "background_color",
+            "string",
+            || default_bg_color().into(),
+            "Background color when no image",
+        ),
+        FieldDoc::defaulted(
+            "text_color",
+            "string",
+            || default_text_color().into(),
+            "Text color",
+        ),
+        FieldDoc::required("output_path", "string", "Output image path"),
+        FieldDoc::defaulted(
+            "orientation",
+            "string",
+            || "landscape".into(),
+            "landscape (1920x1080) or portrait (1080x1920)",
+        ),
+        FieldDoc::defaulted("font", "string", || default_font().into(), "Font name"),
+        FieldDoc::defaulted(
+            "markdown",
+            "boolean",
+            || false.into(),
+            "Parse text as markdown (headings, bullets, bold)",
+        ),
+        FieldDoc::defaulted(
+            "body_font",
+            "string",
+            || default_body_font().into(),
+            "Font for non-bold markdown text",
+        ),
+        FieldDoc::optional(
+            "imagemagick_path",
+            "string",
+            "ImageMagick binary (convert, then magick, if unset)",
+        ),
+        FieldDoc::optional(
+            "platform",
+            "string",
+            "shorts, tiktok or reels: portrait canvas with that platform's safe area",
+        ),
+        FieldDoc::defaulted(
+            "guides",
+            "boolean",
+            || false.into(),
+            "Draw the safe area over the slide to check text placement",
+        ),
+    ],
+};
+
+/// A parsed line of slide markdown.
+#[derive(Debug, PartialEq)]
+enum MdLine {
+    Heading(usize, String),
+    Bullet(String),
+    Text(String),
+    Blank,
+}
+
+/// Fonts used for markdown rendering.
+struct MdFonts<'a> {
+    bold: &'a str,
+    body: &'a str,
+}
+
+/// Approximate advance width of one character, as a fraction of pointsize.
+const CHAR_WIDTH_RATIO: f64 = 0.6;
+
 fn default_bg_color() -> String {
     "#1a1a2e".to_string() // Dark blue-gray
 }
@@ -77,6 +198,10 @@ fn default_font() -> String {
     "DejaVu-Sans-Bold".to_string()
 }
 
+fn default_body_font() -> String {
+    "DejaVu-Sans".to_string()
+}
+
 pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()> {
     let p: Payload = serde_json::from_value(payload.clone())
         .with_context(|| ctx.error_context("payload decode create_slide"))?;
@@ -87,25 +212,13 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
     let output_path = ctx.render(&p.output_path)?;
     let background = p.background.as_ref().map(|b| ctx.render(b)).transpose()?;
 
-    let workdir = ctx.rt.workdir();
-    let resolved_output = if output_path.starts_with('/') {
-        output_path.clone()
-    } else {
-        workdir.join(&output_path).to_string_lossy().to_string()
-    };
-
-    let resolved_bg = background.map(|b| {
-        if b.starts_with('/') {
-            b
-        } else {
-            workdir.join(&b).to_string_lossy().to_string()
-        }
-    });
+    let resolved_output = ctx.output_path(&output_path)?.to_string_lossy().to_string();
+    let resolved_bg = background.map(|b| ctx.resolve(&b).to_string_lossy().to_string());
 
     // Get dimensions based on orientation
-    let (width, height) = match p.orientation {
-        Orientation::Landscape => (1920, 1080),
-        Orientation::Portrait => (1080, 1920),
+    let (width, height) = match (p.platform, &p.orientation) {
+        (Some(_), _) | (None, Orientation::Portrait) => (1080, 1920),
+        (None, Orientation::Landscape) => (1920, 1080),
     };
 
     println!(
@@ -128,86 +241,112 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
     let pointsize_quote = (width / 24).to_string();
     let pointsize_attr = (width / 40).to_string();
 
-    let mut cmd = Command::new("convert");
+    let mut args: Vec<String> = Vec::new();
 
     // Start with background
     if let Some(ref bg_path) = resolved_bg {
         if !std::path::Path::new(bg_path).exists() {
             bail!("Background image not found: {}", bg_path);
         }
-        cmd.args([
-            bg_path.as_str(),
-            "-resize",
-            &resize_str,
-            "-gravity",
-            "center",
-            "-extent",
-            &size_str,
-        ]);
+        args.extend(
+            [
+                bg_path.as_str(),
+                "-resize",
+                &resize_str,
+                "-gravity",
+                "center",
+                "-extent",
+                &size_str,
+            ]
+            .map(String::from),
+        );
     } else {
-        cmd.args(["-size", &size_str, &bg_canvas]);
+        args.extend(["-size", &size_str, &bg_canvas].map(String::from));
     }
 
     // Common text settings
-    cmd.args(["-fill", &p.text_color, "-font", &p.font]);
+    args.extend(["-fill", &p.text_color, "-font", &p.font].map(String::from));
 
     // Apply template-specific text rendering
-    match p.template {
-        SlideTemplate::Title => {
-            cmd.args(["-pointsize", &pointsize_main, "-gravity", "center"]);
-            if let Some(ref sub) = subtext {
-                cmd.args(["-annotate", "+0-50", &text]);
-                cmd.args(["-pointsize", &pointsize_sub, "-annotate", "+0+80", sub]);
-            } else {
-                cmd.args(["-annotate", "+0+0", &text]);
+    if p.markdown {
+        let fonts = MdFonts {
+            bold: &p.font,
+            body: &p.body_font,
+        };
+        args.extend(markdown_annotations(&text, width, height, &fonts));
+    } else {
+        match p.template {
+            SlideTemplate::Title => {
+                args.extend(
+                    ["-pointsize", &pointsize_main, "-gravity", "center"].map(String::from),
+                );
+                if let Some(ref sub) = subtext {
+                    args.extend(["-annotate", "+0-50", &text].map(String::from));
+                    args.extend(
+                        ["-pointsize", &pointsize_sub, "-annotate", "+0+80", sub].map(String::from),
+                    );
+                } else {
+                    args.extend(["-annotate", "+0+0", &text].map(String::from));
+                }
             }
-        }
-        SlideTemplate::Bullet => {
-            // Split text by newlines, each line becomes a bullet
-            let bullet_text: String = text
-                .lines()
-                .map(|line| format!("  \u{2022} {}", line.trim()))
-                .collect::<Vec<_>>()
-                .join("\n");
-            cmd.args([
-                "-pointsize",
-                &pointsize_sub,
-                "-gravity",
-                "west",
-                "-annotate",
-                "+100+0",
-                &bullet_text,
-            ]);
-        }
-        SlideTemplate::Quote => {
-            let quoted = format!("\u{201C}{}\u{201D}", text);
-            cmd.args(["-pointsize", &pointsize_quote, "-gravity", "center"]);
-            if let Some(ref attr) = subtext {
-                let attribution = format!("\u{2014} {}", attr);
-                cmd.args(["-annotate", "+0-30", &quoted]);
-                cmd.args([
-                    "-pointsize",
-                    &pointsize_attr,
-                    "-annotate",
-                    "+0+80",
-                    &attribution,
-                ]);
-            } else {
-                cmd.args(["-annotate", "+0+0", &quoted]);
+            SlideTemplate::Bullet => {
+                // Split text by newlines, each line becomes a bullet
+                let bullet_text: String = text
+                    .lines()
+                    .map(|line| format!("  \u{2022} {}", line.trim()))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                args.extend(
+                    [
+                        "-pointsize",
+                        &pointsize_sub,
+                        "-gravity",
+                        "west",
+                        "-annotate",
+                        "+100+0",
+                        &bullet_text,
+                    ]
+                    .map(String::from),
+                );
+            }
+            SlideTemplate::Quote => {
+                let quoted = format!("\u{201C}{}\u{201D}", text);
+                args.extend(
+                    ["-pointsize", &pointsize_quote, "-gravity", "center"].map(String::from),
+                );
+                if let Some(ref attr) = subtext {
+                    let attribution = format!("\u{2014} {}", attr);
+                    args.extend(["-annotate", "+0-30", &quoted].map(String::from));
+                    args.extend(
+                        [
+                            "-pointsize",
+                            &pointsize_attr,
+                            "-annotate",
+                            "+0+80",
+                            &attribution,
+                        ]
+                        .map(String::from),
+                    );
+                } else {
+                    args.extend(["-annotate", "+0+0", &quoted].map(String::from));
+                }
             }
         }
     }
 
-    cmd.arg(&resolved_output);
+    if p.guides {
+        args.extend(guide_args(width, height, p.platform));
+    }
 
-    let status = cmd
-        .status()
-        .with_context(|| ctx.error_context("spawn ImageMagick convert"))?;
+    args.push(resolved_output.clone());
 
-    if !status.success() {
+    let out = imagemagick::run(ctx.runner.as_mut(), p.imagemagick_path.as_deref(), &args)
+        .with_context(|| ctx.error_context("ImageMagick"))?;
+    if out.status != 0 {
         bail!(
-            "ImageMagick convert failed with exit code: {:?}",
-            status.code()
+            "ImageMagick failed with exit code {}: {}",
+            out.status,
+            out.stderr.trim()
         );
     }
 
@@ -215,9 +354,172 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
     Ok(())
 }
 
+/// The area text should stay inside, as (left, top, right, bottom).
+///
+/// Portrait slides without a platform get the strictest inset of every
+/// platform, so they're safe anywhere; landscape slides get the usual 5%
+/// action-safe margin.
+fn safe_area(width: u32, height: u32, platform: Option<Platform>) -> (u32, u32, u32, u32) {
+    let (top, right, bottom, left) = match platform {
+        Some(p) => p.insets(),
+        None if height > width => [Platform::Shorts, Platform::Tiktok, Platform::Reels]
+            .map(Platform::insets)
+            .into_iter()
+            .fold((0, 0, 0, 0), |a, b| {
+                (a.0.max(b.0), a.1.max(b.1), a.2.max(b.2), a.3.max(b.3))
+            }),
+        None => {
+            let (x, y) = (width / 20, height / 20);
+            return (x, y, width - x, height - y);
+        }
+    };
+    // Insets are for 1080x1920; scale them to the canvas.
+    let sx = |v: u32| v * width / 1080;
+    let sy = |v: u32| v * height / 1920;
+    (sx(left), sy(top), width - sx(right), height - sy(bottom))
+}
+
+/// ImageMagick args outlining the safe area, drawn over everything else.
+fn guide_args(width: u32, height: u32, platform: Option<Platform>) -> Vec<String> {
+    let (x0, y0, x1, y1) = safe_area(width, height, platform);
+    [
+        "-fill",
+        "none",
+        "-stroke",
+        "#ff00ff",
+        "-strokewidth",
+        "4",
+        "-draw",
+        &format!("rectangle {x0},{y0} {x1},{y1}"),
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+fn parse_markdown(text: &str) -> Vec<MdLine> {
+    text.lines()
+        .map(|line| {
+            let line = line.trim();
+            if line.is_empty() {
+                return MdLine::Blank;
+            }
+            let hashes = line.chars().take_while(|c| *c == '#').count();
+            if hashes > 0 && line[hashes..].starts_with(' ') {
+                return MdLine::Heading(hashes, line[hashes..].trim().to_string());
+            }
+            match line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+                Some(item) => MdLine::Bullet(item.trim().to_string()),
+                None => MdLine::Text(line.to_string()),
+            }
+        })
+        .collect()
+}
+
+/// Split `**bold**` spans into (text, is_bold) runs.
+///
+/// Unbalanced markers are left in place as literal text.
+fn inline_runs(text: &str) -> Vec<(String, bool)> {
+    let parts: Vec<&str> = text.split("**").collect();
+    if parts.len().is_multiple_of(2) {
+        return vec![(text.to_string(), false)];
+    }
+    parts
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| !s.is_empty())
+        .map(|(i, s)| (s.to_string(), i % 2 == 1))
+        .collect()
+}
+
+/// ImageMagick args drawing markdown as top-left anchored annotation layers.
+fn markdown_annotations(text: &str, width: u32, height: u32, fonts: &MdFonts) -> Vec<String> {
+    let margin = width / 16;
+    let body_pt = width / 32;
+    let mut y = height / 10;
+    let mut args = vec!["-gravity".to_string(), "northwest".to_string()];
+    let mut annotate = |font: &str, pt: u32, x: u32, y: u32, s: &str| {
+        args.extend([
+            "-font".to_string(),
+            font.to_string(),
+            "-pointsize".to_string(),
+            pt.to_string(),
+            "-annotate".to_string(),
+            format!("+{x}+{y}"),
+            s.to_string(),
+        ]);
+    };
+    for line in parse_markdown(text) {
+        let (pt, mut x, runs) = match line {
+            MdLine::Blank => {
+                y += body_pt / 2;
+                continue;
+            }
+            MdLine::Heading(level, s) => {
+                let pt = if level == 1 { width / 16 } else { width / 24 };
+                (pt, margin, vec![(s, true)])
+            }
+            MdLine::Bullet(s) => {
+                annotate(fonts.body, body_pt, margin, y, "\u{2022}");
+                (body_pt, margin + body_pt * 6 / 5, inline_runs(&s))
+            }
+            MdLine::Text(s) => (body_pt, margin, inline_runs(&s)),
+        };
+        for (run, bold) in runs {
+            let font = if bold { fonts.bold } else { fonts.body };
+            annotate(font, pt, x, y, &run);
+            x += (run.chars().count() as f64 * pt as f64 * CHAR_WIDTH_RATIO) as u32;
+        }
+        y += pt * 3 / 2;
+    }
+    args
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::command::fake::FakeRunner;
+    use std::collections::BTreeMap;
+    use vwf_runtime::{DryRunRuntime, MockLlmClient};
+
+    /// The ImageMagick args `execute` builds for `payload`.
+    fn slide_args(payload: serde_json::Value) -> Vec<String> {
+        let workdir = std::env::temp_dir().join(format!("vwf_slide_{}", std::process::id()));
+        let mut rt = DryRunRuntime::new(&workdir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let (runner, calls) = FakeRunner::succeeding();
+        let mut ctx = StepCtx::new(&mut rt, &vars, "slide").with_runner(Box::new(runner));
+        execute(&mut ctx, &payload).unwrap();
+        let _ = std::fs::remove_dir_all(&workdir);
+        calls.borrow()[0].1.clone()
+    }
+
+    #[test]
+    fn guides_draw_the_platform_safe_area() {
+        let plain = slide_args(serde_json::json!({"text": "Hi", "output_path": "s.png"}));
+        assert!(!plain.contains(&"-draw".to_string()));
+
+        let args = slide_args(serde_json::json!({
+            "text": "Hi",
+            "output_path": "s.png",
+            "platform": "tiktok",
+            "guides": true
+        }));
+        assert_eq!(args[1], "1080x1920");
+        let draw = args.iter().position(|a| a == "-draw").unwrap();
+        assert_eq!(args[draw + 1], "rectangle 60,160 940,1440");
+        assert_eq!(
+            args[draw - 6..draw],
+            ["-fill", "none", "-stroke", "#ff00ff", "-strokewidth", "4"]
+        );
+        // Guides go on top of the text, just before the output path.
+        assert_eq!(args.len(), draw + 3);
+    }
+
+    #[test]
+    fn safe_area_without_platform() {
+        assert_eq!(safe_area(1080, 1920, None), (60, 220, 940, 1440));
+        assert_eq!(safe_area(1920, 1080, None), (96, 54, 1824, 1026));
+    }
 
     #[test]
     fn test_defaults() {
@@ -225,4 +527,51 @@ mod tests {
         assert_eq!(default_text_color(), "#ffffff");
         assert_eq!(default_font(), "DejaVu-Sans-Bold");
     }
+
+    #[test]
+    fn parses_markdown_subset() {
+        let lines = parse_markdown("# Title\n\n- one\n* two\nplain #tag");
+        assert_eq!(
+            lines,
+            vec![
+                MdLine::Heading(1, "Title".into()),
+                MdLine::Blank,
+                MdLine::Bullet("one".into()),
+                MdLine::Bullet("two".into()),
+                MdLine::Text("plain #tag".into()),
+            ]
+        );
+        assert_eq!(
+            inline_runs("a **b** c"),
+            vec![
+                ("a ".into(), false),
+                ("b".into(), true),
+                (" c".into(), false)
+            ]
+        );
+        assert_eq!(inline_runs("a **b"), vec![("a **b".into(), false)]);
+    }
+
+    #[test]
+    fn markdown_maps_to_annotation_layers() {
+        let fonts = MdFonts {
+            bold: "Bold",
+            body: "Body",
+        };
+        let args = markdown_annotations("## Agenda\n- **Fast** builds", 1920, 1080, &fonts);
+        let layers: Vec<Vec<&str>> = args[2..]
+            .chunks(7)
+            .map(|c| vec![c[1].as_str(), c[3].as_str(), c[5].as_str(), c[6].as_str()])
+            .collect();
+        assert_eq!(&args[..2], ["-gravity", "northwest"]);
+        assert_eq!(
+            layers,
+            vec![
+                vec!["Bold", "80", "+120+108", "Agenda"],
+                vec!["Body", "60", "+120+228", "\u{2022}"],
+                vec!["Bold", "60", "+192+228", "Fast"],
+                vec!["Body", "60", "+336+228", " builds"],
+            ]
+        );
+    }
 }
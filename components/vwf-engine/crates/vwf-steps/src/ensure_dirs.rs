@@ -5,12 +5,23 @@ use serde::Deserialize;
 use serde_json::Value;
 
 use super::context::StepCtx;
+use crate::catalog::{FieldDoc, StepDoc};
 
 #[derive(Deserialize)]
 struct Payload {
     dirs: Vec<String>,
 }
 
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "ensure_dirs",
+    summary: "Create directories under the workdir",
+    fields: &[FieldDoc::required(
+        "dirs",
+        "array[string]",
+        "Directories to create",
+    )],
+};
+
 pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
     let p: Payload = serde_json::from_value(payload.clone())
         .with_context(|| ctx.error_context("payload decode ensure_dirs"))?;
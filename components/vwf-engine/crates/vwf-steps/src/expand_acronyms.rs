@@ -0,0 +1,217 @@
+//! Handler for expand_acronyms step kind.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::context::StepCtx;
+use crate::catalog::{FieldDoc, StepDoc};
+
+/// Spoken forms `vwf generate` asks the LLM to use, and the default map.
+pub const DEFAULT_ACRONYMS: &[(&str, &str)] = &[
+    ("VWF", "Vee Double-You Eff"),
+    ("API", "A-P-I"),
+    ("CLI", "command line"),
+    ("TTS", "text-to-speech"),
+    ("LLM", "large language model"),
+    ("YAML", "yaml"),
+    ("ffmpeg", "eff eff em peg"),
+];
+
+fn default_acronyms() -> BTreeMap<String, String> {
+    DEFAULT_ACRONYMS
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct Payload {
+    input_path: String,
+    output_path: String,
+    #[serde(default = "default_acronyms")]
+    acronyms: BTreeMap<String, String>,
+    /// Also match acronyms written in a different case (`Ffmpeg`, `api`).
+    #[serde(default)]
+    ignore_case: bool,
+}
+
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "expand_acronyms",
+    summary: "Rewrite acronyms in a script into their spoken form for TTS",
+    fields: &[
+        FieldDoc::required("input_path", "string", "Script to read"),
+        FieldDoc::required(
+            "output_path",
+            "string",
+            "Where to write the rewritten script (may equal input_path)",
+        ),
+        FieldDoc::defaulted(
+            "acronyms",
+            "map",
+            || serde_json::json!(default_acronyms()),
+            "Acronym to spoken form; replaces the default map",
+        ),
+        FieldDoc::defaulted(
+            "ignore_case",
+            "boolean",
+            || false.into(),
+            "Also match acronyms written in a different case",
+        ),
+    ],
+};
+
+pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
+    let p: Payload = serde_json::from_value(payload.clone())
+        .with_context(|| ctx.error_context("payload decode expand_acronyms"))?;
+    let input_path = ctx.render(&p.input_path)?;
+    let output_path = ctx.render(&p.output_path)?;
+    let text = ctx.rt.read_text(&input_path)?;
+    let expanded = expand(&text, &p.acronyms, p.ignore_case);
+    ctx.rt.write_text(&output_path, &expanded)
+}
+
+/// Replace each whole-word occurrence of an acronym with its spoken form.
+///
+/// Longer acronyms win over shorter ones they contain, and a replacement
+/// that starts a sentence or line gets a capital first letter.
+pub(crate) fn expand(text: &str, acronyms: &BTreeMap<String, String>, ignore_case: bool) -> String {
+    let Some(re) = pattern(acronyms, ignore_case) else {
+        return text.to_string();
+    };
+    let lookup: BTreeMap<String, &str> = acronyms
+        .iter()
+        .map(|(k, v)| (fold(k, ignore_case), v.as_str()))
+        .collect();
+    re.replace_all(text, |caps: &Captures| {
+        let m = caps.get(0).expect("whole match");
+        let spoken = lookup[&fold(m.as_str(), ignore_case)];
+        if starts_sentence(&text[..m.start()]) {
+            capitalize(spoken)
+        } else {
+            spoken.to_string()
+        }
+    })
+    .into_owned()
+}
+
+/// One alternation of all acronyms, bounded by `\b` on word-character edges.
+fn pattern(acronyms: &BTreeMap<String, String>, ignore_case: bool) -> Option<Regex> {
+    let mut keys: Vec<&String> = acronyms.keys().filter(|k| !k.is_empty()).collect();
+    if keys.is_empty() {
+        return None;
+    }
+    keys.sort_by_key(|k| std::cmp::Reverse(k.len()));
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let alternatives: Vec<String> = keys
+        .iter()
+        .map(|k| {
+            let start = if is_word(k.chars().next()) { r"\b" } else { "" };
+            let end = if is_word(k.chars().last()) { r"\b" } else { "" };
+            format!("{start}{}{end}", regex::escape(k))
+        })
+        .collect();
+    let flags = if ignore_case { "(?i)" } else { "" };
+    Some(Regex::new(&format!("{flags}(?:{})", alternatives.join("|"))).expect("escaped pattern"))
+}
+
+fn fold(s: &str, ignore_case: bool) -> String {
+    if ignore_case {
+        s.to_lowercase()
+    } else {
+        s.to_string()
+    }
+}
+
+fn starts_sentence(before: &str) -> bool {
+    let trimmed = before.trim_end();
+    trimmed.is_empty()
+        || trimmed.ends_with(['.', '!', '?'])
+        || before[trimmed.len()..].contains('\n')
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vwf_runtime::{DryRunRuntime, MockLlmClient, Runtime};
+
+    #[test]
+    fn replaces_whole_words_only() {
+        let acronyms = default_acronyms();
+        assert_eq!(
+            expand("Call the API, not RAPID or APIs.", &acronyms, false),
+            "Call the A-P-I, not RAPID or APIs."
+        );
+        assert_eq!(
+            expand(
+                "Pipe it through ffmpeg-static and ffmpeg.",
+                &acronyms,
+                false
+            ),
+            "Pipe it through eff eff em peg-static and eff eff em peg."
+        );
+    }
+
+    #[test]
+    fn longer_acronyms_win() {
+        let acronyms = BTreeMap::from([
+            ("AI".to_string(), "A-I".to_string()),
+            ("AI/ML".to_string(), "A-I and machine learning".to_string()),
+        ]);
+        assert_eq!(
+            expand("We do AI/ML, mostly AI.", &acronyms, false),
+            "We do A-I and machine learning, mostly A-I."
+        );
+    }
+
+    #[test]
+    fn case_is_significant_unless_ignored() {
+        let acronyms = BTreeMap::from([("IT".to_string(), "I-T".to_string())]);
+        assert_eq!(
+            expand("Ask IT if it works.", &acronyms, false),
+            "Ask I-T if it works."
+        );
+        let acronyms = default_acronyms();
+        assert_eq!(
+            expand("Use FFmpeg or Api.", &acronyms, true),
+            "Use eff eff em peg or A-P-I."
+        );
+    }
+
+    #[test]
+    fn sentence_starts_are_capitalized() {
+        let acronyms = default_acronyms();
+        assert_eq!(
+            expand("CLI tools rule. TTS is next!\nLLM output", &acronyms, false),
+            "Command line tools rule. Text-to-speech is next!\nLarge language model output"
+        );
+    }
+
+    #[test]
+    fn rewrites_the_script_file() {
+        let mut rt = DryRunRuntime::new("/tmp", Box::new(MockLlmClient::echo()));
+        rt.write_text("script.txt", "The VWF CLI.").unwrap();
+        let vars = BTreeMap::new();
+        let payload = serde_json::json!({"input_path": "script.txt", "output_path": "spoken.txt"});
+        let mut ctx = StepCtx::new(&mut rt, &vars, "test");
+        execute(&mut ctx, &payload).unwrap();
+        assert_eq!(
+            rt.planned_writes.last().unwrap(),
+            &(
+                "spoken.txt".into(),
+                "The Vee Double-You Eff command line.".into()
+            )
+        );
+    }
+}
@@ -0,0 +1,384 @@
+//! Handler for fetch_url step kind.
+//!
+//! Downloads to `<output_path>.part` and renames when complete. After a
+//! dropped connection the download continues from the partial file with an
+//! HTTP `Range` request; servers that ignore ranges (plain `200`) are
+//! downloaded again from the start. Without a known length a broken
+//! transfer can't be resumed safely, so it fails the step instead.
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::time::Duration;
+use vwf_runtime::Runtime;
+
+use crate::catalog::{FieldDoc, StepDoc};
+use crate::context::StepCtx;
+use crate::hash::sha256_file;
+
+/// Bytes between progress lines.
+const PROGRESS_INTERVAL: u64 = 8 * 1024 * 1024;
+
+#[derive(Deserialize)]
+struct Payload {
+    url: String,
+    output_path: String,
+    /// Expected SHA-256 of the file, hex encoded.
+    #[serde(default)]
+    sha256: Option<String>,
+    /// Extra attempts after a dropped connection or server error.
+    #[serde(default = "default_retries")]
+    retries: u32,
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "fetch_url",
+    summary: "Download a file over HTTP, resuming partial downloads",
+    fields: &[
+        FieldDoc::required("url", "string", "URL to download"),
+        FieldDoc::required("output_path", "string", "Where to save the file"),
+        FieldDoc::optional(
+            "sha256",
+            "string",
+            "Expected SHA-256 (hex); the download fails if it differs",
+        ),
+        FieldDoc::defaulted(
+            "retries",
+            "integer",
+            || default_retries().into(),
+            "Extra attempts after a dropped connection or server error",
+        ),
+    ],
+};
+
+/// Why one download attempt stopped.
+enum Attempt {
+    Complete,
+    /// Worth retrying: the connection dropped or the server had an error.
+    Interrupted(anyhow::Error),
+}
+
+pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
+    let p: Payload = serde_json::from_value(payload.clone())
+        .with_context(|| ctx.error_context("payload decode fetch_url"))?;
+    let url = ctx.render(&p.url)?;
+    let output_path = ctx.render(&p.output_path)?;
+    let dest = ctx.output_path(&output_path)?;
+    let expected = p.sha256.map(|s| s.trim().to_lowercase());
+
+    if let Some(expected) = &expected
+        && dest.exists()
+        && sha256_file(&dest)? == *expected
+    {
+        println!("  Already downloaded: {}", dest.display());
+        return Ok(());
+    }
+    if ctx.rt.is_dry_run() {
+        ctx.rt.check_writable(&output_path)?;
+        println!("  Would download {url} -> {output_path}");
+        return Ok(());
+    }
+
+    let client = reqwest::blocking::Client::builder()
+        .connect_timeout(Duration::from_secs(30))
+        .timeout(None)
+        .build()?;
+    let partial = format!("{output_path}.part");
+    let partial_abs = ctx.output_path(&partial)?;
+    download(
+        ctx.rt,
+        &client,
+        &url,
+        &partial,
+        p.retries,
+        Duration::from_secs(2),
+    )
+    .with_context(|| ctx.error_context("download"))?;
+
+    if let Some(expected) = &expected {
+        let actual = sha256_file(&partial_abs)?;
+        if actual != *expected {
+            let _ = std::fs::remove_file(&partial_abs);
+            bail!("checksum mismatch for {url}: expected {expected}, got {actual}");
+        }
+    }
+    ctx.rt.rename_file(&partial, &output_path)?;
+    println!("  Downloaded: {}", dest.display());
+    Ok(())
+}
+
+/// Download `url` into the workdir-relative `partial`, resuming after
+/// interruptions.
+fn download(
+    rt: &mut dyn Runtime,
+    client: &reqwest::blocking::Client,
+    url: &str,
+    partial: &str,
+    retries: u32,
+    backoff: Duration,
+) -> Result<()> {
+    let mut attempt = 0;
+    loop {
+        match fetch_once(rt, client, url, partial)? {
+            Attempt::Complete => return Ok(()),
+            Attempt::Interrupted(e) if attempt < retries => {
+                attempt += 1;
+                eprintln!("  Download interrupted ({e:#}); retry {attempt}/{retries}");
+                std::thread::sleep(backoff * attempt);
+            }
+            Attempt::Interrupted(e) => {
+                return Err(e.context(format!("giving up after {} attempts", attempt + 1)));
+            }
+        }
+    }
+}
+
+/// One request, continuing from the end of `partial` if it exists.
+fn fetch_once(
+    rt: &mut dyn Runtime,
+    client: &reqwest::blocking::Client,
+    url: &str,
+    partial: &str,
+) -> Result<Attempt> {
+    let partial_abs = rt.resolve_safe(partial)?;
+    let offset = std::fs::metadata(&partial_abs)
+        .map(|m| m.len())
+        .unwrap_or(0);
+    let mut request = client.get(url);
+    if offset > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={offset}-"));
+    }
+    let mut response = match request.send() {
+        Ok(r) => r,
+        Err(e) => return Ok(Attempt::Interrupted(e.into())),
+    };
+    let status = response.status();
+    let mut file = match status.as_u16() {
+        206 => {
+            println!("  Resuming {url} at {offset} bytes");
+            rt.open_stream(partial, true)?
+        }
+        200 => rt.open_stream(partial, false)?,
+        // The partial file already holds everything.
+        416 if offset > 0 => return Ok(Attempt::Complete),
+        _ if status.is_server_error() => {
+            return Ok(Attempt::Interrupted(anyhow::anyhow!("HTTP {status}")));
+        }
+        _ => bail!("HTTP {status} fetching {url}"),
+    };
+    let start = if status.as_u16() == 206 { offset } else { 0 };
+    let total = content_range_total(&response).or(response.content_length().map(|len| start + len));
+    match (
+        copy_with_progress(&mut response, &mut file, start, total),
+        total,
+    ) {
+        (Ok(written), Some(total)) if written < total => Ok(Attempt::Interrupted(anyhow::anyhow!(
+            "connection closed at {written} of {total} bytes"
+        ))),
+        (Ok(_), _) => Ok(Attempt::Complete),
+        (Err(e), Some(_)) => Ok(Attempt::Interrupted(e)),
+        (Err(e), None) => {
+            // Nothing tells how much is missing, so don't resume onto it.
+            drop(file);
+            let _ = std::fs::remove_file(&partial_abs);
+            Err(e.context("download broke off and the server sent no length"))
+        }
+    }
+}
+
+/// The full size from a `206` response's `Content-Range: bytes a-b/total`.
+fn content_range_total(response: &reqwest::blocking::Response) -> Option<u64> {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit_once('/')?
+        .1
+        .parse()
+        .ok()
+}
+
+/// Copy the body into `file`, logging progress. Returns the file's size.
+fn copy_with_progress(
+    body: &mut impl Read,
+    file: &mut dyn Write,
+    start: u64,
+    total: Option<u64>,
+) -> Result<u64> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut written = start;
+    let mut next_log = start + PROGRESS_INTERVAL;
+    loop {
+        let n = body.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        file.write_all(&buf[..n])?;
+        written += n as u64;
+        if written >= next_log {
+            match total {
+                Some(t) => println!("  {} / {} MB", written >> 20, t >> 20),
+                None => println!("  {} MB", written >> 20),
+            }
+            next_log += PROGRESS_INTERVAL;
+        }
+    }
+    file.flush()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::sha256_hex;
+    use std::collections::BTreeMap;
+    use std::io::BufRead;
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use vwf_runtime::{DryRunRuntime, FsRuntime, MockLlmClient};
+
+    const BODY: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    /// Serve `BODY` honoring `Range: bytes=N-`, recording each Range header.
+    fn serve(requests: usize) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/model.bin", listener.local_addr().unwrap());
+        let ranges = Arc::new(Mutex::new(vec![]));
+        let seen = Arc::clone(&ranges);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+                let mut range = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(v) = line.to_lowercase().strip_prefix("range:") {
+                        range = Some(v.trim().to_string());
+                    }
+                }
+                let start: usize = range
+                    .as_deref()
+                    .and_then(|r| r.strip_prefix("bytes="))
+                    .and_then(|r| r.trim_end_matches('-').parse().ok())
+                    .unwrap_or(0);
+                let status = if range.is_some() {
+                    "206 Partial Content"
+                } else {
+                    "200 OK"
+                };
+                let body = &BODY[start..];
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nAccept-Ranges: bytes\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(body).unwrap();
+                seen.lock().unwrap().push(range);
+            }
+        });
+        (url, ranges)
+    }
+
+    fn workdir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vwf_fetch_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn resumes_partial_download_with_range_request() {
+        let (url, ranges) = serve(1);
+        let dir = workdir("resume");
+        std::fs::write(dir.join("model.bin.part"), &BODY[..10]).unwrap();
+
+        let mut rt = FsRuntime::new(&dir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let mut ctx = StepCtx::new(&mut rt, &vars, "fetch");
+        let payload = serde_json::json!({
+            "url": url,
+            "output_path": "model.bin",
+            "sha256": sha256_hex(BODY),
+        });
+        execute(&mut ctx, &payload).unwrap();
+
+        assert_eq!(std::fs::read(dir.join("model.bin")).unwrap(), BODY);
+        assert!(!dir.join("model.bin.part").exists());
+        assert_eq!(*ranges.lock().unwrap(), vec![Some("bytes=10-".to_string())]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn checksum_mismatch_fails_and_discards_download() {
+        let (url, _) = serve(1);
+        let dir = workdir("checksum");
+        let mut rt = FsRuntime::new(&dir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let mut ctx = StepCtx::new(&mut rt, &vars, "fetch");
+        let payload = serde_json::json!({
+            "url": url,
+            "output_path": "model.bin",
+            "sha256": "00",
+        });
+        let err = execute(&mut ctx, &payload).unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"), "{err:#}");
+        assert!(!dir.join("model.bin").exists());
+        assert!(!dir.join("model.bin.part").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn broken_download_without_length_fails() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/model.bin", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            // A chunk that promises 16 bytes but stops after 10.
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n10\r\n0123456789")
+                .unwrap();
+        });
+        let dir = workdir("nolength");
+        let mut rt = FsRuntime::new(&dir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let mut ctx = StepCtx::new(&mut rt, &vars, "fetch");
+        let payload = serde_json::json!({"url": url, "output_path": "model.bin"});
+        let err = execute(&mut ctx, &payload).unwrap_err();
+        assert!(format!("{err:#}").contains("sent no length"), "{err:#}");
+        assert!(!dir.join("model.bin").exists());
+        assert!(!dir.join("model.bin.part").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn dry_run_downloads_nothing() {
+        let dir = workdir("dry");
+        let mut rt = DryRunRuntime::new(&dir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let mut ctx = StepCtx::new(&mut rt, &vars, "fetch");
+        // Nothing listens on port 9, so a real request would fail.
+        let payload = serde_json::json!({
+            "url": "http://127.0.0.1:9/model.bin",
+            "output_path": "models/model.bin",
+        });
+        execute(&mut ctx, &payload).unwrap();
+        assert!(!dir.join("models").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
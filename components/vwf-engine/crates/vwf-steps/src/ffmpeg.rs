@@ -0,0 +1,468 @@
+//! Shared ffmpeg/ffprobe helpers.
+//!
+//! Binaries are taken from `VWF_FFMPEG` / `VWF_FFPROBE` when set, otherwise
+//! looked up on `PATH`.
+
+use anyhow::{Context, Result, bail};
+
+use crate::command::{CommandRunner, SystemRunner};
+
+/// Lines of ffmpeg stderr quoted when a command fails.
+const STDERR_TAIL_LINES: usize = 10;
+
+/// Why a duration reported by ffprobe could not be used.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum DurationError {
+    /// Empty output or `N/A`, e.g. for a stream without timing
+    #[error("ffprobe reported no duration")]
+    Missing,
+    /// Something that isn't a non-negative number of seconds
+    #[error("invalid duration `{0}`")]
+    Invalid(String),
+}
+
+/// Container and first-stream properties reported by ffprobe.
+///
+/// Fields are `None` when the file has no matching stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MediaInfo {
+    pub duration: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub sample_rate: Option<u32>,
+}
+
+/// Audio stream format reported by ffprobe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioProperties {
+    pub sample_rate: u32,
+    pub channels: u32,
+}
+
+/// Stream parameters that must agree for the concat demuxer to copy.
+///
+/// Fields are `None` when the file has no matching stream.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamParams {
+    pub video_codec: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub frame_rate: Option<String>,
+    pub pix_fmt: Option<String>,
+    pub audio_codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+}
+
+impl StreamParams {
+    /// `name a vs b` for every parameter that differs from `other`.
+    pub fn differences(&self, other: &StreamParams) -> Vec<String> {
+        let (ours, theirs) = (self.fields(), other.fields());
+        ours.iter()
+            .zip(&theirs)
+            .filter(|(a, b)| a.1 != b.1)
+            .map(|((name, a), (_, b))| {
+                let show = |v: &Option<String>| v.clone().unwrap_or_else(|| "none".into());
+                format!("{name} {} vs {}", show(a), show(b))
+            })
+            .collect()
+    }
+
+    fn fields(&self) -> [(&'static str, Option<String>); 8] {
+        let num = |v: Option<u32>| v.map(|n| n.to_string());
+        [
+            ("video codec", self.video_codec.clone()),
+            ("width", num(self.width)),
+            ("height", num(self.height)),
+            ("frame rate", self.frame_rate.clone()),
+            ("pixel format", self.pix_fmt.clone()),
+            ("audio codec", self.audio_codec.clone()),
+            ("sample rate", num(self.sample_rate)),
+            ("channels", num(self.channels)),
+        ]
+    }
+}
+
+pub fn ffmpeg_bin() -> String {
+    std::env::var("VWF_FFMPEG").unwrap_or_else(|_| "ffmpeg".to_string())
+}
+
+pub fn ffprobe_bin() -> String {
+    std::env::var("VWF_FFPROBE").unwrap_or_else(|_| "ffprobe".to_string())
+}
+
+/// Run ffmpeg, failing with the tail of its stderr on a non-zero exit.
+///
+/// Returns stderr, which is where ffmpeg writes filter reports.
+pub fn run_ffmpeg<S: AsRef<str>>(args: &[S]) -> Result<String> {
+    run_ffmpeg_with(&mut SystemRunner, args)
+}
+
+/// [`run_ffmpeg`] through an injected runner.
+pub fn run_ffmpeg_with<S: AsRef<str>>(
+    runner: &mut dyn CommandRunner,
+    args: &[S],
+) -> Result<String> {
+    let bin = ffmpeg_bin();
+    let args: Vec<String> = args.iter().map(|a| a.as_ref().to_string()).collect();
+    let out = runner.run(&bin, &args)?;
+    if out.status != 0 {
+        bail!(
+            "{bin} failed with exit code {}:\n{}",
+            out.status,
+            out.stderr_tail(STDERR_TAIL_LINES)
+        );
+    }
+    Ok(out.stderr)
+}
+
+/// Container duration in seconds.
+pub fn duration_secs(path: &str) -> Result<f64> {
+    let stdout = run_ffprobe(&[
+        "-v",
+        "error",
+        "-show_entries",
+        "format=duration",
+        "-of",
+        "csv=p=0",
+        path,
+    ])?;
+    parse_duration(&stdout).with_context(|| format!("Failed to parse duration of {path}"))
+}
+
+/// Mean volume in dB as measured by the `volumedetect` filter.
+pub fn mean_volume_db(path: &str) -> Result<f64> {
+    let stderr = run_ffmpeg(&["-i", path, "-af", "volumedetect", "-f", "null", "-"])?;
+    parse_mean_volume(&stderr).with_context(|| format!("Could not detect mean volume for {path}"))
+}
+
+/// Sample rate and channel count of the first audio stream.
+pub fn audio_properties(path: &str) -> Result<AudioProperties> {
+    let stdout = run_ffprobe(&[
+        "-v",
+        "error",
+        "-select_streams",
+        "a:0",
+        "-show_entries",
+        "stream=sample_rate,channels",
+        "-of",
+        "default=noprint_wrappers=1",
+        path,
+    ])?;
+    parse_audio_properties(&stdout).with_context(|| format!("No audio stream found in {path}"))
+}
+
+/// Duration, video size and audio sample rate in one ffprobe call.
+pub fn media_info_with(runner: &mut dyn CommandRunner, path: &str) -> Result<MediaInfo> {
+    let stdout = run_ffprobe_with(
+        runner,
+        &[
+            "-v",
+            "error",
+            "-show_entries",
+            "format=duration:stream=codec_type,width,height,sample_rate",
+            "-of",
+            "json",
+            path,
+        ],
+    )?;
+    parse_media_info(&stdout).with_context(|| format!("Failed to parse ffprobe output for {path}"))
+}
+
+/// Codec, size, frame rate and audio format of the first video and audio streams.
+pub fn stream_params_with(runner: &mut dyn CommandRunner, path: &str) -> Result<StreamParams> {
+    let stdout = run_ffprobe_with(
+        runner,
+        &[
+            "-v",
+            "error",
+            "-show_entries",
+            "stream=codec_type,codec_name,width,height,r_frame_rate,pix_fmt,sample_rate,channels",
+            "-of",
+            "json",
+            path,
+        ],
+    )?;
+    parse_stream_params(&stdout)
+        .with_context(|| format!("Failed to parse ffprobe output for {path}"))
+}
+
+fn run_ffprobe(args: &[&str]) -> Result<String> {
+    run_ffprobe_with(&mut SystemRunner, args)
+}
+
+fn run_ffprobe_with(runner: &mut dyn CommandRunner, args: &[&str]) -> Result<String> {
+    let bin = ffprobe_bin();
+    let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    let out = runner.run(&bin, &args)?;
+    if out.status != 0 {
+        bail!(
+            "{bin} failed with exit code {}:\n{}",
+            out.status,
+            out.stderr_tail(STDERR_TAIL_LINES)
+        );
+    }
+    Ok(out.stdout)
+}
+
+/// Parse a duration in seconds as printed by ffprobe.
+///
+/// Accepts plain and scientific notation (`12.5`, `1.25e+01`), the
+/// `-sexagesimal` form (`0:00:12.500000`), a decimal comma from some
+/// locales, and ignores trailing whitespace, separators and units.
+pub fn parse_duration(raw: &str) -> Result<f64, DurationError> {
+    let line = raw.lines().map(str::trim).find(|l| !l.is_empty());
+    let Some(line) = line else {
+        return Err(DurationError::Missing);
+    };
+    let token = line
+        .split_whitespace()
+        .next()
+        .unwrap_or(line)
+        .trim_end_matches([',', ';', '|']);
+    if token.eq_ignore_ascii_case("N/A") {
+        return Err(DurationError::Missing);
+    }
+    let invalid = || DurationError::Invalid(line.to_string());
+    let seconds = if token.contains(':') {
+        let mut total = 0.0;
+        for part in token.split(':') {
+            total = total * 60.0 + decimal(part).ok_or_else(invalid)?;
+        }
+        total
+    } else {
+        decimal(token).ok_or_else(invalid)?
+    };
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err(invalid());
+    }
+    Ok(seconds)
+}
+
+/// Parse a number that may use a decimal comma (`12,5`).
+fn decimal(s: &str) -> Option<f64> {
+    let s = if s.contains(',') && !s.contains('.') {
+        s.replace(',', ".")
+    } else {
+        s.to_string()
+    };
+    s.parse().ok()
+}
+
+/// Parse the `mean_volume: -25.1 dB` line from volumedetect output.
+fn parse_mean_volume(stderr: &str) -> Result<f64> {
+    let line = stderr
+        .lines()
+        .find_map(|l| l.split("mean_volume:").nth(1))
+        .context("mean_volume not reported")?;
+    let db = line.trim().trim_end_matches("dB").trim();
+    db.parse::<f64>().context("Failed to parse mean_volume")
+}
+
+/// Parse `key=value` lines from `ffprobe -of default=noprint_wrappers=1`.
+fn parse_audio_properties(stdout: &str) -> Result<AudioProperties> {
+    let value = |key: &str| -> Result<u32> {
+        let raw = stdout
+            .lines()
+            .find_map(|l| l.trim().strip_prefix(key)?.strip_prefix('='))
+            .with_context(|| format!("`{key}` not reported"))?;
+        raw.parse()
+            .with_context(|| format!("invalid {key} `{raw}`"))
+    };
+    Ok(AudioProperties {
+        sample_rate: value("sample_rate")?,
+        channels: value("channels")?,
+    })
+}
+
+/// Parse `ffprobe -of json` output with `format` and `streams` entries.
+fn parse_media_info(stdout: &str) -> Result<MediaInfo> {
+    let json: serde_json::Value = serde_json::from_str(stdout)?;
+    // ffprobe reports numbers like duration and sample_rate as strings
+    let number =
+        |v: &serde_json::Value| -> Option<f64> { v.as_f64().or_else(|| v.as_str()?.parse().ok()) };
+    let streams = json["streams"].as_array().cloned().unwrap_or_default();
+    let stream = |kind: &str| streams.iter().find(|s| s["codec_type"] == kind);
+    let video = stream("video");
+    let audio = stream("audio");
+    Ok(MediaInfo {
+        duration: match &json["format"]["duration"] {
+            serde_json::Value::String(raw) => parse_duration(raw).ok(),
+            other => number(other),
+        },
+        width: video.and_then(|s| number(&s["width"])).map(|n| n as u32),
+        height: video.and_then(|s| number(&s["height"])).map(|n| n as u32),
+        sample_rate: audio
+            .and_then(|s| number(&s["sample_rate"]))
+            .map(|n| n as u32),
+    })
+}
+
+/// Parse `ffprobe -of json` output with `streams` entries.
+fn parse_stream_params(stdout: &str) -> Result<StreamParams> {
+    let json: serde_json::Value = serde_json::from_str(stdout)?;
+    let streams = json["streams"].as_array().cloned().unwrap_or_default();
+    let stream = |kind: &str| streams.iter().find(|s| s["codec_type"] == kind);
+    let text = |s: Option<&serde_json::Value>, key: &str| -> Option<String> {
+        s?[key].as_str().map(str::to_string)
+    };
+    // ffprobe reports sample_rate as a string but width and channels as numbers
+    let number = |s: Option<&serde_json::Value>, key: &str| -> Option<u32> {
+        let v = &s?[key];
+        v.as_u64()
+            .or_else(|| v.as_str()?.parse().ok())
+            .map(|n| n as u32)
+    };
+    let (video, audio) = (stream("video"), stream("audio"));
+    Ok(StreamParams {
+        video_codec: text(video, "codec_name"),
+        width: number(video, "width"),
+        height: number(video, "height"),
+        frame_rate: text(video, "r_frame_rate"),
+        pix_fmt: text(video, "pix_fmt"),
+        audio_codec: text(audio, "codec_name"),
+        sample_rate: number(audio, "sample_rate"),
+        channels: number(audio, "channels"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ffprobe_duration() {
+        assert_eq!(parse_duration("12.345000\n").unwrap(), 12.345);
+        assert!(parse_duration("N/A\n").is_err());
+    }
+
+    #[test]
+    fn parses_real_ffprobe_duration_samples() {
+        let ok = [
+            // -of csv=p=0
+            ("12.345000\n", 12.345),
+            ("0.040000\r\n", 0.04),
+            ("  7.000000  \n\n", 7.0),
+            // csv with a trailing separator, or several entries
+            ("12.345000,\n", 12.345),
+            ("30.030000\n30.030000\n", 30.03),
+            // -of default=nw=1:nk=1 with -unit
+            ("12.345000 s\n", 12.345),
+            // -sexagesimal
+            ("0:00:12.345000\n", 12.345),
+            ("1:02:03.500000\n", 3723.5),
+            // decimal comma from a localized build
+            ("12,345000\n", 12.345),
+            // scientific notation for very long or very short media
+            ("1.2345e+01\n", 12.345),
+            ("5E-3\n", 0.005),
+            ("0\n", 0.0),
+        ];
+        for (raw, expected) in ok {
+            let parsed = parse_duration(raw).unwrap_or_else(|e| panic!("{raw:?}: {e}"));
+            assert!((parsed - expected).abs() < 1e-9, "{raw:?} -> {parsed}");
+        }
+
+        let missing = ["", "\n", "N/A\n", "n/a", "N/A,\n"];
+        for raw in missing {
+            assert_eq!(parse_duration(raw), Err(DurationError::Missing), "{raw:?}");
+        }
+
+        let invalid = ["abc\n", "-1.5\n", "inf\n", "NaN\n", "1:xx:03\n"];
+        for raw in invalid {
+            assert!(
+                matches!(parse_duration(raw), Err(DurationError::Invalid(_))),
+                "{raw:?}"
+            );
+        }
+        assert_eq!(
+            parse_duration("garbage here\n").unwrap_err().to_string(),
+            "invalid duration `garbage here`"
+        );
+    }
+
+    #[test]
+    fn parses_volumedetect_output() {
+        let stderr = "\
+[Parsed_volumedetect_0 @ 0x600] n_samples: 2646000
+[Parsed_volumedetect_0 @ 0x600] mean_volume: -25.1 dB
+[Parsed_volumedetect_0 @ 0x600] max_volume: -4.0 dB
+";
+        assert_eq!(parse_mean_volume(stderr).unwrap(), -25.1);
+        assert!(parse_mean_volume("Stream #0:0: Audio: aac\n").is_err());
+    }
+
+    #[test]
+    fn parses_media_info() {
+        let stdout = r#"{
+            "programs": [],
+            "streams": [
+                {"codec_type": "video", "width": 1920, "height": 1080},
+                {"codec_type": "audio", "sample_rate": "44100"}
+            ],
+            "format": {"duration": "12.500000"}
+        }"#;
+        let info = parse_media_info(stdout).unwrap();
+        assert_eq!(
+            info,
+            MediaInfo {
+                duration: Some(12.5),
+                width: Some(1920),
+                height: Some(1080),
+                sample_rate: Some(44100),
+            }
+        );
+        let audio_only = r#"{"streams": [{"codec_type": "audio", "sample_rate": "48000"}], "format": {"duration": "3.0"}}"#;
+        let info = parse_media_info(audio_only).unwrap();
+        assert_eq!(info.width, None);
+        assert_eq!(info.sample_rate, Some(48000));
+        let untimed = r#"{"streams": [], "format": {"duration": "N/A"}}"#;
+        assert_eq!(parse_media_info(untimed).unwrap().duration, None);
+    }
+
+    #[test]
+    fn parses_stream_params_and_reports_differences() {
+        let stdout = r#"{
+            "streams": [
+                {"codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080,
+                 "r_frame_rate": "30/1", "pix_fmt": "yuv420p"},
+                {"codec_type": "audio", "codec_name": "aac", "sample_rate": "44100", "channels": 2}
+            ]
+        }"#;
+        let params = parse_stream_params(stdout).unwrap();
+        assert_eq!(params.width, Some(1920));
+        assert_eq!(params.frame_rate.as_deref(), Some("30/1"));
+        assert_eq!(params.sample_rate, Some(44100));
+        assert!(params.differences(&params).is_empty());
+
+        let silent = StreamParams {
+            width: Some(1280),
+            audio_codec: None,
+            sample_rate: None,
+            channels: None,
+            ..params.clone()
+        };
+        assert_eq!(
+            params.differences(&silent),
+            [
+                "width 1920 vs 1280",
+                "audio codec aac vs none",
+                "sample rate 44100 vs none",
+                "channels 2 vs none",
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_audio_properties() {
+        let props = parse_audio_properties("sample_rate=48000\nchannels=1\n").unwrap();
+        assert_eq!(
+            props,
+            AudioProperties {
+                sample_rate: 48000,
+                channels: 1
+            }
+        );
+        assert!(parse_audio_properties("").is_err());
+    }
+}
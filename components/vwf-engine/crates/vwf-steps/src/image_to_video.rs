@@ -5,9 +5,15 @@
 use anyhow::{Context, Result};
 use serde::Deserialize;
 use serde_json::Value;
+use std::path::Path;
 use std::process::Command;
 
 use super::context::StepCtx;
+use crate::cache;
+use crate::catalog::{FieldDoc, StepDoc};
+use crate::hash::sha256_file;
+use crate::python::escape_py;
+use crate::regenerate;
 
 #[derive(Deserialize)]
 struct Payload {
@@ -31,8 +37,60 @@ struct Payload {
     server: String,
     /// Python interpreter path (default: python3)
     python_path: Option<String>,
+    /// Checkpoint file on the ComfyUI server (default: svd_xt.safetensors)
+    #[serde(default = "default_checkpoint")]
+    checkpoint: String,
+    /// Regenerate with the next seed this many times if the video is corrupt
+    #[serde(default = "regenerate::default_validation_retries")]
+    validation_retries: u32,
 }
 
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "image_to_video",
+    summary: "Animate an image into a short video with SVD-XT via ComfyUI",
+    fields: &[
+        FieldDoc::required("input_path", "string", "Input image path"),
+        FieldDoc::required("output_path", "string", "Output video path (.mp4)"),
+        FieldDoc::defaulted(
+            "frames",
+            "integer",
+            || default_frames().into(),
+            "Number of frames to generate",
+        ),
+        FieldDoc::defaulted("fps", "integer", || default_fps().into(), "Frame rate"),
+        FieldDoc::defaulted(
+            "motion",
+            "integer",
+            || default_motion().into(),
+            "Motion bucket id 1-1023 (higher is more dramatic)",
+        ),
+        FieldDoc::optional("seed", "integer", "Random seed for reproducibility"),
+        FieldDoc::defaulted(
+            "server",
+            "string",
+            || default_server().into(),
+            "ComfyUI server URL",
+        ),
+        FieldDoc::optional(
+            "python_path",
+            "string",
+            "Python interpreter (python3 if unset)",
+        ),
+        FieldDoc::defaulted(
+            "checkpoint",
+            "string",
+            || default_checkpoint().into(),
+            "Checkpoint file on the ComfyUI server",
+        ),
+        FieldDoc::defaulted(
+            "validation_retries",
+            "integer",
+            || regenerate::DEFAULT_VALIDATION_RETRIES.into(),
+            "Regenerate with the next seed this many times if the video comes back corrupt",
+        ),
+    ],
+};
+
 fn default_frames() -> u32 {
     14
 }
@@ -45,16 +103,22 @@ fn default_motion() -> u32 {
 fn default_server() -> String {
     "http://192.168.1.64:8100".to_string()
 }
+fn default_checkpoint() -> String {
+    "svd_xt.safetensors".to_string()
+}
 
 pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
     let p: Payload = serde_json::from_value(payload.clone())
         .with_context(|| ctx.error_context("payload decode image_to_video"))?;
 
-    let input_path = ctx.render(&p.input_path)?;
+    let input_path = ctx.resolve(&ctx.render(&p.input_path)?);
+    let input_path = input_path.to_string_lossy().to_string();
     let output_path = ctx.render(&p.output_path)?;
+    let output_path = ctx.output_path(&output_path)?.to_string_lossy().to_string();
     let server = ctx.render(&p.server)?;
+    let checkpoint = ctx.render(&p.checkpoint)?;
 
-    let seed = p.seed.unwrap_or_else(rand::random);
+    let seed = ctx.seed(p.seed);
     let python = p
         .python_path
         .as_ref()
@@ -62,33 +126,58 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
         .transpose()?
         .unwrap_or_else(|| "python3".to_string());
 
-    let script = video_gen_script(
-        &server,
-        &input_path,
-        &output_path,
-        p.frames,
-        p.fps,
-        p.motion,
-        seed,
-    );
-
-    let status = Command::new(&python)
-        .args(["-c", &script])
-        .status()
-        .with_context(|| ctx.error_context("spawn image_to_video python"))?;
-
-    if !status.success() {
-        anyhow::bail!(
-            "Video generation failed with exit code: {:?}",
-            status.code()
+    let generate = |ctx: &mut StepCtx<'_>, seed: u64| {
+        let script = video_gen_script(
+            &server,
+            &checkpoint,
+            &input_path,
+            &output_path,
+            p.frames,
+            p.fps,
+            p.motion,
+            seed,
         );
-    }
 
-    Ok(())
+        let status = Command::new(&python)
+            .args(["-c", &script])
+            .status()
+            .with_context(|| ctx.error_context("spawn image_to_video python"))?;
+
+        if !status.success() {
+            anyhow::bail!(
+                "Video generation failed with exit code: {:?}",
+                status.code()
+            );
+        }
+        Ok(())
+    };
+    let key = || {
+        Ok(serde_json::json!({
+            "kind": "image_to_video",
+            "input_sha256": sha256_file(Path::new(&input_path))?,
+            "seed": seed,
+            "frames": p.frames,
+            "fps": p.fps,
+            "motion": p.motion,
+            "checkpoint": checkpoint,
+        }))
+    };
+    cache::cached(ctx, key, Path::new(&output_path), |ctx| {
+        regenerate::until_valid(
+            ctx,
+            Path::new(&output_path),
+            seed,
+            p.validation_retries,
+            generate,
+            regenerate::check_video,
+        )
+    })
 }
 
+#[allow(clippy::too_many_arguments)]
 fn video_gen_script(
     server: &str,
+    checkpoint: &str,
     input: &str,
     output: &str,
     frames: u32,
@@ -96,6 +185,7 @@ fn video_gen_script(
     motion: u32,
     seed: u64,
 ) -> String {
+    let checkpoint = escape_py(checkpoint);
     format!(
         r#"
 import requests
@@ -135,7 +225,7 @@ print(f"  Uploaded as: {{image_name}}")
 workflow = {{
     "prompt": {{
         "1": {{"class_type": "ImageOnlyCheckpointLoader",
-              "inputs": {{"ckpt_name": "svd_xt.safetensors"}}}},
+              "inputs": {{"ckpt_name": "{checkpoint}"}}}},
         "2": {{"class_type": "LoadImage",
               "inputs": {{"image": image_name}}}},
         "3": {{"class_type": "SVD_img2vid_Conditioning",
@@ -202,3 +292,103 @@ print(f"Saved: {{OUTPUT}}")
 "#
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::fake::FakeRunner;
+    use std::collections::BTreeMap;
+    use vwf_runtime::{FsRuntime, MockLlmClient};
+
+    /// Identical steps share a cached clip; a changed input image misses.
+    #[cfg(unix)]
+    #[test]
+    fn cache_key_follows_the_input_image() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let workdir = std::env::temp_dir().join(format!("vwf_i2v_cache_{}", std::process::id()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        // Stands in for python3: writes the script's OUTPUT and logs each call
+        let generator = workdir.join("fake-python");
+        let calls = workdir.join("calls.log");
+        std::fs::write(
+            &generator,
+            format!(
+                "#!/bin/sh\n\
+                 out=$(printf '%s\\n' \"$2\" | sed -n 's/^OUTPUT = \"\\(.*\\)\"$/\\1/p')\n\
+                 echo call >> {calls}\n\
+                 echo clip > \"$out\"\n",
+                calls = calls.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&generator, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::write(workdir.join("still.png"), "first still").unwrap();
+
+        let mut rt = FsRuntime::new(&workdir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::from([(
+            crate::CACHE_VAR.to_string(),
+            workdir.join("cache").to_string_lossy().to_string(),
+        )]);
+        let mut run = |out: &str| {
+            let (mut runner, _) = FakeRunner::succeeding();
+            runner.result.stdout = r#"{"format": {"duration": "2.0"}, "streams": [
+                {"codec_type": "video", "width": 1024, "height": 576}]}"#
+                .into();
+            let payload = serde_json::json!({
+                "input_path": "still.png",
+                "output_path": out,
+                "seed": 7,
+                "python_path": generator.to_string_lossy(),
+            });
+            let mut ctx = StepCtx::new(&mut rt, &vars, out).with_runner(Box::new(runner));
+            execute(&mut ctx, &payload).unwrap();
+        };
+        run("first.mp4");
+        run("second.mp4");
+        assert_eq!(std::fs::read_to_string(&calls).unwrap(), "call\n");
+        std::fs::write(workdir.join("still.png"), "second still").unwrap();
+        run("third.mp4");
+        assert_eq!(std::fs::read_to_string(&calls).unwrap(), "call\ncall\n");
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+
+    #[test]
+    fn checkpoint_override_reaches_workflow() {
+        let default = video_gen_script(
+            "http://x",
+            &default_checkpoint(),
+            "/in.png",
+            "/out.mp4",
+            14,
+            6,
+            127,
+            1,
+        );
+        assert!(default.contains(r#""ckpt_name": "svd_xt.safetensors""#));
+
+        let custom = video_gen_script(
+            "http://x",
+            "svd_xt_1_1.safetensors",
+            "/in.png",
+            "/out.mp4",
+            14,
+            6,
+            127,
+            1,
+        );
+        assert!(custom.contains(r#""ckpt_name": "svd_xt_1_1.safetensors""#));
+
+        let quoted = video_gen_script(
+            "http://x",
+            r#"svd"x.safetensors"#,
+            "/in.png",
+            "/out.mp4",
+            14,
+            6,
+            127,
+            1,
+        );
+        assert!(quoted.contains(r#""ckpt_name": "svd\"x.safetensors""#));
+    }
+}
@@ -0,0 +1,78 @@
+//! ImageMagick invocation.
+//!
+//! ImageMagick 6 installs `convert`; version 7 installs `magick` and only
+//! optionally the legacy `convert` shim. Both accept the same arguments.
+
+use anyhow::{Result, bail};
+use vwf_runtime::CmdOut;
+
+use crate::command::{CommandRunner, is_not_found};
+
+/// Binaries tried, in order, when no path is configured.
+const DEFAULT_BINARIES: &[&str] = &["convert", "magick"];
+
+/// Binaries to try: the configured one only, or the defaults.
+fn candidates(configured: Option<&str>) -> Vec<&str> {
+    match configured {
+        Some(path) => vec![path],
+        None => DEFAULT_BINARIES.to_vec(),
+    }
+}
+
+/// Run ImageMagick with the first candidate binary that is installed.
+pub fn run(
+    runner: &mut dyn CommandRunner,
+    configured: Option<&str>,
+    args: &[String],
+) -> Result<CmdOut> {
+    let candidates = candidates(configured);
+    for program in &candidates {
+        match runner.run(program, args) {
+            Err(e) if is_not_found(&e) => continue,
+            result => return result,
+        }
+    }
+    bail!(
+        "ImageMagick not found (tried {}); install it or set imagemagick_path",
+        candidates.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::fake::FakeRunner;
+
+    #[test]
+    fn falls_back_to_magick_when_convert_is_missing() {
+        let (mut runner, calls) = FakeRunner::succeeding();
+        runner.missing = vec!["convert".to_string()];
+        run(&mut runner, None, &["in.png".to_string()]).unwrap();
+        let calls = calls.borrow();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0, "magick");
+    }
+
+    #[test]
+    fn configured_path_is_the_only_candidate() {
+        let (mut runner, calls) = FakeRunner::succeeding();
+        run(&mut runner, Some("/opt/im/magick"), &[]).unwrap();
+        assert_eq!(calls.borrow()[0].0, "/opt/im/magick");
+
+        runner.missing = vec!["/opt/im/magick".to_string()];
+        let err = run(&mut runner, Some("/opt/im/magick"), &[]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ImageMagick not found (tried /opt/im/magick); install it or set imagemagick_path"
+        );
+    }
+
+    #[test]
+    fn reports_actionable_error_when_nothing_is_installed() {
+        let (mut runner, _) = FakeRunner::succeeding();
+        runner.missing = vec!["convert".to_string(), "magick".to_string()];
+        let err = run(&mut runner, None, &[]).unwrap_err();
+        assert!(err.to_string().contains("tried convert, magick"));
+        assert!(err.to_string().contains("set imagemagick_path"));
+    }
+}
@@ -1,15 +1,27 @@
 //! Step handlers for workflow execution.
 
 mod audio_mix;
+mod cache;
+mod catalog;
+mod command;
 mod context;
 mod create_slide;
 mod ensure_dirs;
+mod expand_acronyms;
+mod fetch_url;
+mod ffmpeg;
+mod hash;
 mod image_to_video;
+mod imagemagick;
 mod llm_audit;
 mod llm_generate;
 mod normalize_volume;
+mod probe_media;
+mod python;
+mod regenerate;
 mod run_command;
 mod split_sections;
+mod temp;
 mod text_to_image;
 mod text_to_video;
 mod tts_generate;
@@ -20,18 +32,104 @@ mod write_file;
 use anyhow::Result;
 use std::collections::BTreeMap;
 
+pub use cache::CACHE_VAR;
+pub use catalog::{FieldDoc, Presence, StepDoc};
+pub use command::{CommandRunner, SystemRunner, is_not_found};
 use context::StepCtx;
+pub use expand_acronyms::DEFAULT_ACRONYMS;
 use vwf_config::{StepConfig, StepKind};
 use vwf_runtime::Runtime;
 
+/// Var holding the workflow-wide seed that image and video steps derive
+/// their seeds from when they don't set `seed` themselves.
+pub const SEED_VAR: &str = "seed";
+
+/// What a step that finished left behind besides its files.
+#[derive(Debug, Default)]
+pub struct StepOutcome {
+    /// Vars the step exported for later steps (usually none).
+    pub exports: BTreeMap<String, String>,
+    /// Non-fatal problems the step ran into.
+    pub warnings: Vec<String>,
+    /// Times an output was regenerated after failing validation.
+    pub retries: u32,
+}
+
 /// Execute a single step with dependency injection via Runtime trait.
 pub fn execute_step(
     rt: &mut dyn Runtime,
     vars: &BTreeMap<String, String>,
     step: &StepConfig,
-) -> Result<()> {
-    let mut ctx = StepCtx::new(rt, vars, &step.id);
-    dispatch(&mut ctx, &step.kind, &step.payload)
+) -> Result<StepOutcome> {
+    let base_seed = vars.get(SEED_VAR).and_then(|s| s.trim().parse().ok());
+    let mut ctx = StepCtx::new(rt, vars, &step.id).with_base_seed(base_seed);
+    dispatch(&mut ctx, &step.kind, &step.payload)?;
+    Ok(StepOutcome {
+        exports: ctx.exports,
+        warnings: ctx.warnings,
+        retries: ctx.retries,
+    })
+}
+
+/// Vars a step will export when it runs, as named in its payload.
+pub fn exported_vars(kind: &StepKind, payload: &serde_json::Value) -> Vec<String> {
+    match kind {
+        StepKind::LlmGenerate => llm_generate::exported_vars(payload),
+        StepKind::ProbeMedia => probe_media::exported_vars(payload),
+        StepKind::RunCommand => run_command::exported_vars(payload),
+        _ => vec![],
+    }
+}
+
+/// Payload documentation for one step kind.
+pub fn step_doc(kind: &StepKind) -> &'static StepDoc {
+    match kind {
+        StepKind::EnsureDirs => &ensure_dirs::DOC,
+        StepKind::WriteFile => &write_file::DOC,
+        StepKind::SplitSections => &split_sections::DOC,
+        StepKind::RunCommand => &run_command::DOC,
+        StepKind::LlmGenerate => &llm_generate::DOC,
+        StepKind::TtsGenerate => &tts_generate::DOC,
+        StepKind::TextToImage => &text_to_image::DOC,
+        StepKind::ImageToVideo => &image_to_video::DOC,
+        StepKind::TextToVideo => &text_to_video::DOC,
+        StepKind::NormalizeVolume => &normalize_volume::DOC,
+        StepKind::WhisperTranscribe => &whisper_transcribe::DOC,
+        StepKind::VideoConcat => &video_concat::DOC,
+        StepKind::AudioMix => &audio_mix::DOC,
+        StepKind::CreateSlide => &create_slide::DOC,
+        StepKind::LlmAudit => &llm_audit::DOC,
+        StepKind::ProbeMedia => &probe_media::DOC,
+        StepKind::FetchUrl => &fetch_url::DOC,
+        StepKind::ExpandAcronyms => &expand_acronyms::DOC,
+    }
+}
+
+/// Documentation for every step kind, in dispatch order.
+pub fn step_docs() -> Vec<&'static StepDoc> {
+    [
+        StepKind::EnsureDirs,
+        StepKind::WriteFile,
+        StepKind::SplitSections,
+        StepKind::RunCommand,
+        StepKind::LlmGenerate,
+        StepKind::TtsGenerate,
+        StepKind::TextToImage,
+        StepKind::ImageToVideo,
+        StepKind::TextToVideo,
+        StepKind::NormalizeVolume,
+        StepKind::WhisperTranscribe,
+        StepKind::VideoConcat,
+        StepKind::AudioMix,
+        StepKind::CreateSlide,
+        StepKind::LlmAudit,
+        StepKind::ProbeMedia,
+        StepKind::FetchUrl,
+        StepKind::ExpandAcronyms,
+    ]
+    .iter()
+    .map(step_doc)
+    .collect()
 }
 
 fn dispatch(ctx: &mut StepCtx<'_>, kind: &StepKind, payload: &serde_json::Value) -> Result<()> {
@@ -51,5 +149,24 @@ fn dispatch(ctx: &mut StepCtx<'_>, kind: &StepKind, payload: &serde_json::Value)
         StepKind::AudioMix => audio_mix::execute(ctx, payload),
         StepKind::CreateSlide => create_slide::execute(ctx, payload),
         StepKind::LlmAudit => llm_audit::execute(ctx, payload),
+        StepKind::ProbeMedia => probe_media::execute(ctx, payload),
+        StepKind::FetchUrl => fetch_url::execute(ctx, payload),
+        StepKind::ExpandAcronyms => expand_acronyms::execute(ctx, payload),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_doc_matches_its_kind() {
+        let docs = step_docs();
+        assert_eq!(docs.len(), 18);
+        for doc in docs {
+            let kind: StepKind = serde_json::from_value(serde_json::json!(doc.kind)).unwrap();
+            assert_eq!(step_doc(&kind).kind, doc.kind);
+            assert!(!doc.fields.is_empty(), "{} has no fields", doc.kind);
+        }
     }
 }
@@ -4,14 +4,17 @@
 //! 1. For videos, extract frames at regular intervals
 //! 2. Send each frame to a vision model (llava, qwen2.5vl, llama3.2-vision)
 //! 3. Aggregate feedback and report issues
-//! 4. Optionally fail the workflow if critical issues found
+//! 4. Score the findings into a pass/warn/fail verdict
+//! 5. Optionally fail the workflow if the verdict is fail
 
 use anyhow::{bail, Context, Result};
 use serde::Deserialize;
 use std::path::Path;
-use std::process::Command;
 
+use crate::catalog::{FieldDoc, StepDoc};
 use crate::context::StepCtx;
+use crate::ffmpeg;
+use crate::temp::TempPath;
 
 #[derive(Deserialize)]
 struct Payload {
@@ -30,12 +33,95 @@ struct Payload {
     frame_count: u32,
     /// Output path for audit report
     output_path: String,
-    /// Fail workflow if issues found (default: false)
+    /// Fail workflow if the verdict is fail (default: false)
     #[serde(default)]
     fail_on_issues: bool,
     /// Keywords that indicate critical issues (default: ["error", "broken", "corrupt"])
     #[serde(default = "default_critical_keywords")]
     critical_keywords: Vec<String>,
+    /// Keywords that indicate minor issues (default: ["blurry", "distorted", "mismatch", ...])
+    #[serde(default = "default_warning_keywords")]
+    warning_keywords: Vec<String>,
+    /// Keep extracted frames next to the report (default: false)
+    #[serde(default)]
+    keep_frames: bool,
+    /// Directory for kept frames, relative to workdir (implies keep_frames)
+    #[serde(default)]
+    frames_dir: Option<String>,
+    /// Narration/script the frames should match, injected into the prompt
+    #[serde(default)]
+    context_path: Option<String>,
+}
+
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "llm_audit",
+    summary: "Review images/videos with a vision model and write a JSON report",
+    fields: &[
+        FieldDoc::required("assets", "array[string]", "Images or videos to audit"),
+        FieldDoc::required(
+            "audit_prompt",
+            "string",
+            "Audit criteria for the vision model",
+        ),
+        FieldDoc::defaulted("model", "string", || default_model().into(), "Vision model"),
+        FieldDoc::defaulted(
+            "server",
+            "string",
+            || default_server().into(),
+            "Ollama server URL",
+        ),
+        FieldDoc::defaulted(
+            "frame_count",
+            "integer",
+            || default_frame_count().into(),
+            "Frames to extract from each video",
+        ),
+        FieldDoc::required("output_path", "string", "Path for the JSON audit report"),
+        FieldDoc::defaulted(
+            "fail_on_issues",
+            "boolean",
+            || false.into(),
+            "Fail the step if the verdict is fail",
+        ),
+        FieldDoc::defaulted(
+            "critical_keywords",
+            "array[string]",
+            || default_critical_keywords().into(),
+            "Words that mark an issue as critical",
+        ),
+        FieldDoc::defaulted(
+            "warning_keywords",
+            "array[string]",
+            || default_warning_keywords().into(),
+            "Words that mark an issue as a warning",
+        ),
+        FieldDoc::defaulted(
+            "keep_frames",
+            "boolean",
+            || false.into(),
+            "Keep extracted frames next to the report",
+        ),
+        FieldDoc::optional(
+            "frames_dir",
+            "string",
+            "Directory for kept frames (implies keep_frames)",
+        ),
+        FieldDoc::optional(
+            "context_path",
+            "string",
+            "Narration the frames should match, added to the prompt",
+        ),
+    ],
+};
+
+/// Where frames go and how findings are classified.
+struct AuditOptions<'a> {
+    workdir: &'a Path,
+    frames_dir: &'a Path,
+    keep_frames: bool,
+    frame_count: u32,
+    critical_keywords: &'a [String],
+    warning_keywords: &'a [String],
 }
 
 fn default_model() -> String {
@@ -61,58 +147,168 @@ fn default_critical_keywords() -> Vec<String> {
     ]
 }
 
+fn default_warning_keywords() -> Vec<String> {
+    vec![
+        "blurry".to_string(),
+        "distorted".to_string(),
+        "artifact".to_string(),
+        "mismatch".to_string(),
+        "the narration says".to_string(),
+    ]
+}
+
 pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()> {
     let p: Payload = serde_json::from_value(payload.clone())
         .with_context(|| ctx.error_context("payload decode llm_audit"))?;
 
-    let workdir = ctx.rt.workdir();
-
     // Resolve paths
     let assets: Vec<String> = p
         .assets
         .iter()
-        .map(|a| {
-            let rendered = ctx.render(a)?;
-            Ok(if rendered.starts_with('/') {
-                rendered
-            } else {
-                workdir.join(&rendered).to_string_lossy().to_string()
-            })
-        })
+        .map(|a| Ok(ctx.resolve(&ctx.render(a)?).to_string_lossy().to_string()))
         .collect::<Result<Vec<_>>>()?;
 
     let output_path = ctx.render(&p.output_path)?;
-    let output_path = if output_path.starts_with('/') {
-        output_path
-    } else {
-        workdir.join(&output_path).to_string_lossy().to_string()
-    };
+    let output_path = ctx.output_path(&output_path)?.to_string_lossy().to_string();
 
     let server = ctx.render(&p.server)?;
     let model = ctx.render(&p.model)?;
-    let audit_prompt = ctx.render(&p.audit_prompt)?;
+    let narration = match &p.context_path {
+        Some(path) => {
+            let path = ctx.render(path)?;
+            let text = ctx
+                .rt
+                .read_text(&path)
+                .with_context(|| ctx.error_context(&format!("read context_path {path}")))?;
+            Some(text)
+        }
+        None => None,
+    };
+    let audit_prompt = build_prompt(&ctx.render(&p.audit_prompt)?, narration.as_deref());
 
     println!("Auditing {} assets with {}", assets.len(), model);
 
+    // Frames go to a temp dir unless the caller wants to inspect them
+    let keep_frames = p.keep_frames || p.frames_dir.is_some();
+    let mut temp_frames = None;
+    let frames_dir = match &p.frames_dir {
+        Some(dir) => ctx.output_path(&ctx.render(dir)?)?,
+        None if keep_frames => Path::new(&output_path).with_extension("frames"),
+        None => temp_frames
+            .insert(TempPath::new("audit", ctx.step_id, ""))
+            .path()
+            .to_path_buf(),
+    };
+    std::fs::create_dir_all(&frames_dir)
+        .with_context(|| format!("create frames dir {}", frames_dir.display()))?;
+
+    let opts = AuditOptions {
+        workdir: ctx.rt.workdir(),
+        frames_dir: &frames_dir,
+        keep_frames,
+        frame_count: p.frame_count,
+        critical_keywords: &p.critical_keywords,
+        warning_keywords: &p.warning_keywords,
+    };
+    let result = audit_assets(
+        &assets,
+        &model,
+        &audit_prompt,
+        &opts,
+        &mut |video, dir, n| extract_video_frames(video, dir, n),
+        &mut |frame, prompt| analyze_frame(&server, &model, frame, prompt),
+    );
+
+    drop(temp_frames);
+    let report = result?;
+
+    // Write report
+    if let Some(parent) = Path::new(&output_path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let report_json = serde_json::to_string_pretty(&report)?;
+    std::fs::write(&output_path, &report_json)?;
+
+    println!(
+        "Audit complete: {} frames analyzed, {} critical issues, verdict {} (score {})",
+        report.total_frames_analyzed,
+        report.critical_issues.len(),
+        report.verdict.as_str(),
+        report.score
+    );
+    println!("Report saved to: {}", output_path);
+
+    // Print critical issues
+    if !report.critical_issues.is_empty() {
+        println!("\nCritical issues found:");
+        for issue in &report.critical_issues {
+            println!("  - {}: {}", issue.asset, issue.feedback);
+        }
+    }
+
+    if p.fail_on_issues && report.verdict == Verdict::Fail {
+        bail!(
+            "Audit verdict fail (score {}): {} critical issues found",
+            report.score,
+            report.critical_issues.len()
+        );
+    }
+    if report.verdict != Verdict::Pass {
+        ctx.warn(format!(
+            "audit verdict {} (score {}): {} critical issue(s), see {output_path}",
+            report.verdict.as_str(),
+            report.score,
+            report.critical_issues.len()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Append the narration so the model can flag on-screen/spoken mismatches.
+fn build_prompt(audit_prompt: &str, narration: Option<&str>) -> String {
+    match narration {
+        Some(text) => format!(
+            "{audit_prompt}\n\nThe narration for this segment is:\n\"\"\"\n{}\n\"\"\"\n\
+             Also report any mismatch between what the narration says and what the frame shows, \
+             in the form: the narration says X but the frame shows Y.",
+            text.trim()
+        ),
+        None => audit_prompt.to_string(),
+    }
+}
+
+/// Extracts `n` frames from a video into a directory, returning their paths.
+type ExtractFrames<'a> = dyn FnMut(&str, &Path, u32) -> Result<Vec<String>> + 'a;
+
+/// Run the audit over every asset, extracting and analyzing frames.
+fn audit_assets(
+    assets: &[String],
+    model: &str,
+    prompt: &str,
+    opts: &AuditOptions,
+    extract: &mut ExtractFrames,
+    analyze: &mut dyn FnMut(&str, &str) -> Result<String>,
+) -> Result<AuditReport> {
     let mut report = AuditReport {
-        model: model.clone(),
+        model: model.to_string(),
         total_assets: assets.len(),
         total_frames_analyzed: 0,
         findings: Vec::new(),
         critical_issues: Vec::new(),
+        verdict: Verdict::Pass,
+        score: 100,
     };
 
-    // Create temp directory for extracted frames
-    let temp_dir = std::env::temp_dir().join(format!("vwf_audit_{}", std::process::id()));
-    std::fs::create_dir_all(&temp_dir)?;
-
-    for asset in &assets {
+    for asset in assets {
         if !Path::new(asset).exists() {
             report.findings.push(AssetFinding {
                 asset: asset.clone(),
                 frame: None,
                 feedback: "Asset file not found".to_string(),
                 is_critical: true,
+                severity: Severity::Critical,
             });
             continue;
         }
@@ -122,9 +318,10 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
             .map(|e| e.to_string_lossy().to_lowercase())
             .unwrap_or_default();
 
-        let frames = if ["mp4", "mov", "avi", "mkv", "webm"].contains(&ext.as_str()) {
+        let is_video = ["mp4", "mov", "avi", "mkv", "webm"].contains(&ext.as_str());
+        let frames = if is_video {
             // Extract frames from video
-            extract_video_frames(asset, &temp_dir, p.frame_count)?
+            extract(asset, opts.frames_dir, opts.frame_count)?
         } else if ["png", "jpg", "jpeg", "webp", "gif"].contains(&ext.as_str()) {
             // Single image
             vec![asset.clone()]
@@ -138,23 +335,38 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
         for frame in &frames {
             report.total_frames_analyzed += 1;
 
-            let feedback = analyze_frame(&server, &model, frame, &audit_prompt)?;
+            let feedback = analyze(frame, prompt)?;
 
-            // Check for critical issues
-            let is_critical = p
-                .critical_keywords
-                .iter()
-                .any(|kw| feedback.to_lowercase().contains(&kw.to_lowercase()));
+            // Classify by the worst keyword the feedback mentions
+            let lower = feedback.to_lowercase();
+            let mentions =
+                |keywords: &[String]| keywords.iter().any(|kw| lower.contains(&kw.to_lowercase()));
+            let severity = if mentions(opts.critical_keywords) {
+                Severity::Critical
+            } else if mentions(opts.warning_keywords) {
+                Severity::Warning
+            } else {
+                Severity::Info
+            };
+            let is_critical = severity == Severity::Critical;
+
+            let frame_ref = if is_video && opts.keep_frames {
+                // Kept frames are reported relative to workdir when possible
+                let path = Path::new(frame);
+                let rel = path.strip_prefix(opts.workdir).unwrap_or(path);
+                Some(rel.to_string_lossy().to_string())
+            } else if frames.len() > 1 {
+                Some(frame.clone())
+            } else {
+                None
+            };
 
             let finding = AssetFinding {
                 asset: asset.clone(),
-                frame: if frames.len() > 1 {
-                    Some(frame.clone())
-                } else {
-                    None
-                },
-                feedback: feedback.clone(),
+                frame: frame_ref,
+                feedback,
                 is_critical,
+                severity,
             };
 
             if is_critical {
@@ -164,46 +376,34 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
         }
     }
 
-    // Clean up temp directory
-    let _ = std::fs::remove_dir_all(&temp_dir);
-
-    // Write report
-    if let Some(parent) = Path::new(&output_path).parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-
-    let report_json = serde_json::to_string_pretty(&report)?;
-    std::fs::write(&output_path, &report_json)?;
-
-    println!(
-        "Audit complete: {} frames analyzed, {} critical issues",
-        report.total_frames_analyzed,
-        report.critical_issues.len()
-    );
-    println!("Report saved to: {}", output_path);
-
-    // Print critical issues
-    if !report.critical_issues.is_empty() {
-        println!("\nCritical issues found:");
-        for issue in &report.critical_issues {
-            println!("  - {}: {}", issue.asset, issue.feedback);
-        }
-
-        if p.fail_on_issues {
-            bail!(
-                "Audit failed: {} critical issues found",
-                report.critical_issues.len()
-            );
-        }
-    }
+    (report.verdict, report.score) = score_findings(&report.findings);
+    Ok(report)
+}
 
-    Ok(())
+/// Points deducted from 100 per finding of each severity.
+const CRITICAL_PENALTY: u32 = 25;
+const WARNING_PENALTY: u32 = 5;
+
+/// Overall verdict and 0-100 score: any critical finding fails the audit,
+/// any warning makes it a warn.
+fn score_findings(findings: &[AssetFinding]) -> (Verdict, u32) {
+    let count = |s: Severity| findings.iter().filter(|f| f.severity == s).count() as u32;
+    let (critical, warnings) = (count(Severity::Critical), count(Severity::Warning));
+    let penalty = critical * CRITICAL_PENALTY + warnings * WARNING_PENALTY;
+    let verdict = if critical > 0 {
+        Verdict::Fail
+    } else if warnings > 0 {
+        Verdict::Warn
+    } else {
+        Verdict::Pass
+    };
+    (verdict, 100u32.saturating_sub(penalty))
 }
 
 /// Extract frames from a video at regular intervals
 fn extract_video_frames(video_path: &str, temp_dir: &Path, frame_count: u32) -> Result<Vec<String>> {
     // Get video duration
-    let duration = get_video_duration(video_path)?;
+    let duration = ffmpeg::duration_secs(video_path)?;
     let interval = duration / (frame_count as f64 + 1.0);
 
     let mut frames = Vec::new();
@@ -216,25 +416,20 @@ fn extract_video_frames(video_path: &str, temp_dir: &Path, frame_count: u32) ->
         let timestamp = interval * i as f64;
         let output_frame = temp_dir.join(format!("{}_{:03}.png", video_name, i));
 
-        let status = Command::new("ffmpeg")
-            .args([
-                "-y",
-                "-ss",
-                &format!("{:.2}", timestamp),
-                "-i",
-                video_path,
-                "-frames:v",
-                "1",
-                "-q:v",
-                "2",
-                output_frame.to_str().unwrap(),
-            ])
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .status()
-            .context("Failed to extract video frame")?;
-
-        if status.success() && output_frame.exists() {
+        let extracted = ffmpeg::run_ffmpeg(&[
+            "-y",
+            "-ss",
+            &format!("{:.2}", timestamp),
+            "-i",
+            video_path,
+            "-frames:v",
+            "1",
+            "-q:v",
+            "2",
+            output_frame.to_str().unwrap(),
+        ]);
+
+        if extracted.is_ok() && output_frame.exists() {
             frames.push(output_frame.to_string_lossy().to_string());
         }
     }
@@ -242,27 +437,6 @@ fn extract_video_frames(video_path: &str, temp_dir: &Path, frame_count: u32) ->
     Ok(frames)
 }
 
-/// Get video duration in seconds
-fn get_video_duration(path: &str) -> Result<f64> {
-    let output = Command::new("ffprobe")
-        .args([
-            "-v",
-            "error",
-            "-show_entries",
-            "format=duration",
-            "-of",
-            "csv=p=0",
-            path,
-        ])
-        .output()
-        .context("Failed to run ffprobe")?;
-
-    String::from_utf8_lossy(&output.stdout)
-        .trim()
-        .parse::<f64>()
-        .context("Failed to parse duration")
-}
-
 /// Analyze a single frame using the vision model
 fn analyze_frame(server: &str, model: &str, frame_path: &str, prompt: &str) -> Result<String> {
     // Read image and encode as base64
@@ -315,6 +489,35 @@ struct AuditReport {
     total_frames_analyzed: usize,
     findings: Vec<AssetFinding>,
     critical_issues: Vec<AssetFinding>,
+    verdict: Verdict,
+    /// 100 minus a penalty per critical issue and warning, floored at 0.
+    score: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Verdict {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Verdict {
+    fn as_str(self) -> &'static str {
+        match self {
+            Verdict::Pass => "pass",
+            Verdict::Warn => "warn",
+            Verdict::Fail => "fail",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Info,
+    Warning,
+    Critical,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -323,6 +526,7 @@ struct AssetFinding {
     frame: Option<String>,
     feedback: String,
     is_critical: bool,
+    severity: Severity,
 }
 
 #[cfg(test)]
@@ -335,4 +539,145 @@ mod tests {
         assert_eq!(default_frame_count(), 5);
         assert!(!default_critical_keywords().is_empty());
     }
+
+    #[test]
+    fn kept_frames_are_referenced_relative_to_workdir() {
+        let workdir = std::env::temp_dir().join(format!("vwf_audit_keep_{}", std::process::id()));
+        let frames_dir = workdir.join("audit/report.frames");
+        std::fs::create_dir_all(&frames_dir).unwrap();
+        let video = workdir.join("clip.mp4");
+        std::fs::write(&video, b"fake").unwrap();
+
+        let keywords = default_critical_keywords();
+        let opts = AuditOptions {
+            workdir: &workdir,
+            frames_dir: &frames_dir,
+            keep_frames: true,
+            frame_count: 2,
+            critical_keywords: &keywords,
+            warning_keywords: &[],
+        };
+        let mut extract = |_: &str, dir: &Path, n: u32| -> Result<Vec<String>> {
+            (1..=n)
+                .map(|i| {
+                    let frame = dir.join(format!("clip_{i:03}.png"));
+                    std::fs::write(&frame, b"png")?;
+                    Ok(frame.to_string_lossy().to_string())
+                })
+                .collect()
+        };
+        let mut analyze = |_: &str, _: &str| Ok("frame looks broken".to_string());
+        let assets = vec![video.to_string_lossy().to_string()];
+        let report =
+            audit_assets(&assets, "llava", "Check", &opts, &mut extract, &mut analyze).unwrap();
+
+        assert_eq!(report.critical_issues.len(), 2);
+        for finding in &report.findings {
+            let frame = finding.frame.as_deref().unwrap();
+            assert!(frame.starts_with("audit/report.frames/"), "{frame}");
+            assert!(workdir.join(frame).exists());
+        }
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+
+    /// Answer `requests` Ollama generate calls with "ok", returning the
+    /// server URL and the prompt of each call.
+    fn serve_ollama(requests: usize) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        use std::io::{BufRead, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = format!("http://{}", listener.local_addr().unwrap());
+        let prompts = std::thread::spawn(move || {
+            let mut prompts = vec![];
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(v) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = v.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                prompts.push(request["prompt"].as_str().unwrap().to_string());
+                let reply = r#"{"response":"ok"}"#;
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{reply}",
+                    reply.len()
+                )
+                .unwrap();
+            }
+            prompts
+        });
+        (server, prompts)
+    }
+
+    #[test]
+    fn narration_context_reaches_model_prompt() {
+        use std::collections::BTreeMap;
+        use vwf_runtime::{FsRuntime, MockLlmClient};
+
+        let workdir = std::env::temp_dir().join(format!("vwf_audit_ctx_{}", std::process::id()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        std::fs::write(workdir.join("slide.png"), b"png").unwrap();
+        std::fs::write(workdir.join("narration.txt"), "Rust makes builds fast.\n").unwrap();
+
+        let (server, prompts) = serve_ollama(2);
+        let mut rt = FsRuntime::new(&workdir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let mut ctx = StepCtx::new(&mut rt, &vars, "audit");
+        let mut payload = serde_json::json!({
+            "assets": ["slide.png"],
+            "output_path": "audit.json",
+            "audit_prompt": "Check the slide.",
+            "server": server,
+            "context_path": "narration.txt",
+        });
+        execute(&mut ctx, &payload).unwrap();
+        payload.as_object_mut().unwrap().remove("context_path");
+        execute(&mut ctx, &payload).unwrap();
+
+        let prompts = prompts.join().unwrap();
+        assert!(prompts[0].starts_with("Check the slide."));
+        assert!(prompts[0].contains("Rust makes builds fast."));
+        assert_eq!(prompts[1], "Check the slide.");
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+
+    #[test]
+    fn findings_map_to_verdict_and_score() {
+        let finding = |severity| AssetFinding {
+            asset: "a.png".to_string(),
+            frame: None,
+            feedback: String::new(),
+            is_critical: severity == Severity::Critical,
+            severity,
+        };
+        let cases = [
+            (vec![], Verdict::Pass, 100),
+            (vec![finding(Severity::Info)], Verdict::Pass, 100),
+            (
+                vec![finding(Severity::Warning), finding(Severity::Warning)],
+                Verdict::Warn,
+                90,
+            ),
+            (
+                vec![finding(Severity::Critical), finding(Severity::Warning)],
+                Verdict::Fail,
+                70,
+            ),
+            (vec![finding(Severity::Critical); 5], Verdict::Fail, 0),
+        ];
+        for (findings, verdict, score) in cases {
+            assert_eq!(score_findings(&findings), (verdict, score), "{findings:?}");
+        }
+    }
 }
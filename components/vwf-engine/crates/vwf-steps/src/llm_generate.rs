@@ -3,9 +3,14 @@
 use anyhow::{Context, Result};
 use serde::Deserialize;
 use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::Write;
 
 use super::context::StepCtx;
-use vwf_runtime::LlmReq;
+use super::split_sections::extract_section;
+use crate::catalog::{FieldDoc, StepDoc};
+use crate::command::is_not_found;
+use vwf_runtime::{LlmClient, LlmReq};
 
 #[derive(Deserialize)]
 struct Payload {
@@ -15,6 +20,77 @@ struct Payload {
     provider: String,
     #[serde(default)]
     mock_response: Option<String>,
+    /// Heading -> destination for sections parsed out of the response
+    #[serde(default)]
+    extract_sections: BTreeMap<String, SectionTarget>,
+    /// Write the response to output_path as it is generated
+    #[serde(default)]
+    stream: bool,
+    /// Add the response to the end of output_path instead of replacing it
+    #[serde(default)]
+    append: bool,
+    /// JSON Schema the response must satisfy; output_path gets the JSON
+    #[serde(default)]
+    json_schema: Option<Value>,
+}
+
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "llm_generate",
+    summary: "Generate text with an LLM and write it to a file",
+    fields: &[
+        FieldDoc::required("system", "string", "System instructions"),
+        FieldDoc::required(
+            "user_prompt_path",
+            "string",
+            "File containing the user prompt",
+        ),
+        FieldDoc::required("output_path", "string", "Output file for the response"),
+        FieldDoc::required(
+            "provider",
+            "string",
+            "ollama, ollama:<model>, openai:<model> or mock",
+        ),
+        FieldDoc::optional(
+            "mock_response",
+            "string",
+            "Use this text as the prompt (for testing)",
+        ),
+        FieldDoc::optional(
+            "extract_sections",
+            "map",
+            "Heading -> var name (or {var, path}) parsed from the response",
+        ),
+        FieldDoc::defaulted(
+            "stream",
+            "boolean",
+            || false.into(),
+            "Write the response to output_path as it is generated",
+        ),
+        FieldDoc::defaulted(
+            "append",
+            "boolean",
+            || false.into(),
+            "Append to output_path instead of overwriting it",
+        ),
+        FieldDoc::optional(
+            "json_schema",
+            "map",
+            "Ask for JSON matching this schema and validate the reply",
+        ),
+    ],
+};
+
+/// Where an extracted section goes: a var name, or a var and/or file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SectionTarget {
+    Var(String),
+    Spec {
+        #[serde(default)]
+        var: Option<String>,
+        #[serde(default)]
+        path: Option<String>,
+    },
 }
 
 pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
@@ -24,14 +100,166 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
     let user = read_user_prompt(ctx, &p.user_prompt_path)?;
     let user = inject_mock_response(user, p.mock_response);
     let provider = ctx.render(&p.provider)?;
+    let output_path = ctx.render(&p.output_path)?;
+    let validator = match &p.json_schema {
+        Some(_) if p.stream => {
+            anyhow::bail!(ctx.error_context("json_schema cannot be combined with stream"))
+        }
+        Some(_) if p.append => {
+            anyhow::bail!(ctx.error_context("json_schema cannot be combined with append"))
+        }
+        Some(schema) => Some(
+            jsonschema::validator_for(schema)
+                .map_err(|e| anyhow::anyhow!("{e}"))
+                .with_context(|| ctx.error_context("invalid json_schema"))?,
+        ),
+        None => None,
+    };
+    let system = match &p.json_schema {
+        Some(schema) => format!(
+            "{system}\n\nReply with only a JSON document, no prose or code fences, \
+             that matches this JSON Schema:\n{schema}"
+        ),
+        None => system,
+    };
     let req = LlmReq {
         system,
         user,
-        provider,
+        provider: provider.clone(),
     };
-    let resp = ctx.rt.llm().generate(req)?;
-    let output_path = ctx.render(&p.output_path)?;
-    ctx.rt.write_text(&output_path, &resp)
+    let previous = if p.append {
+        match ctx.rt.read_text_lossy(&output_path) {
+            Ok(text) => text,
+            Err(e) if is_not_found(&e) => String::new(),
+            Err(e) => return Err(e.context(ctx.error_context("read output_path to append to"))),
+        }
+    } else {
+        String::new()
+    };
+    let mut live = if p.stream {
+        let open_ctx = ctx.error_context("open output_path for streaming");
+        Some(
+            ctx.rt
+                .open_stream(&output_path, p.append)
+                .context(open_ctx)?,
+        )
+    } else {
+        None
+    };
+    let select_ctx = ctx.error_context("select llm provider");
+    let json_ctx = ctx.error_context("reply does not match json_schema");
+    // Dry-run mocks echo the prompt, so there is no JSON to check
+    let dry_run = ctx.rt.is_dry_run();
+    let client = ctx.rt.llm_for(&provider).context(select_ctx)?;
+    let resp = match (&validator, &mut live) {
+        (Some(validator), _) if !dry_run => {
+            generate_json(client, req, validator).context(json_ctx)?
+        }
+        (_, Some(file)) => client.generate_streamed(req, &mut |chunk| {
+            file.write_all(chunk.as_bytes())?;
+            Ok(file.flush()?)
+        })?,
+        _ => client.generate(req)?,
+    };
+    // The streamed copy is only for watching; this write is authoritative
+    ctx.rt
+        .write_text(&output_path, &format!("{previous}{resp}"))?;
+    for (heading, target) in &p.extract_sections {
+        store_section(ctx, &resp, heading, target)?;
+    }
+    Ok(())
+}
+
+/// Generate a reply that validates against `validator`, retrying once with
+/// the validation errors appended to the prompt. Returns pretty-printed JSON.
+fn generate_json(
+    client: &mut dyn LlmClient,
+    req: LlmReq,
+    validator: &jsonschema::Validator,
+) -> Result<String> {
+    let first = client.generate(req.clone())?;
+    let errors = match check_json(&first, validator) {
+        Ok(json) => return Ok(json),
+        Err(errors) => errors,
+    };
+    let retry = LlmReq {
+        user: format!(
+            "{}\n\nYour previous reply was not valid:\n{}\nReply again with corrected JSON.",
+            req.user,
+            errors.join("\n")
+        ),
+        ..req
+    };
+    let second = client.generate(retry)?;
+    check_json(&second, validator).map_err(|errors| anyhow::anyhow!(errors.join("\n")))
+}
+
+/// Parse `resp` (ignoring a surrounding code fence) and validate it,
+/// returning it pretty-printed or one message per problem.
+fn check_json(resp: &str, validator: &jsonschema::Validator) -> Result<String, Vec<String>> {
+    let body = resp.trim();
+    let body = body
+        .strip_prefix("```json")
+        .or_else(|| body.strip_prefix("```"))
+        .and_then(|b| b.strip_suffix("```"))
+        .unwrap_or(body);
+    let value: Value = serde_json::from_str(body).map_err(|e| vec![format!("not JSON: {e}")])?;
+    let errors: Vec<String> = validator
+        .iter_errors(&value)
+        .map(|e| {
+            let path = e.instance_path().to_string();
+            let path = if path.is_empty() { "/" } else { &path };
+            format!("{path}: {e}")
+        })
+        .collect();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(serde_json::to_string_pretty(&value).expect("a parsed Value serializes"))
+}
+
+/// Parse `heading` out of the response (as `split_sections` does) and store it.
+fn store_section(
+    ctx: &mut StepCtx<'_>,
+    resp: &str,
+    heading: &str,
+    target: &SectionTarget,
+) -> Result<()> {
+    // Accept both `TITLE` and `TITLE:` as keys
+    let heading = format!("{}:", heading.trim_end_matches(':'));
+    let section = extract_section(resp, &heading, false, false)
+        .with_context(|| ctx.error_context(&format!("cannot extract heading `{heading}`")))?
+        .trim()
+        .to_string();
+    let (var, path) = match target {
+        SectionTarget::Var(var) => (Some(var), None),
+        SectionTarget::Spec { var, path } => (var.as_ref(), path.as_ref()),
+    };
+    if let Some(path) = path {
+        let path = ctx.render(path)?;
+        ctx.rt.write_text(&path, &section)?;
+    }
+    if let Some(var) = var {
+        ctx.exports.insert(var.clone(), section);
+    }
+    Ok(())
+}
+
+/// The vars named in `extract_sections`, or none if it doesn't parse.
+pub(crate) fn exported_vars(payload: &Value) -> Vec<String> {
+    let Some(sections) = payload
+        .get("extract_sections")
+        .and_then(|v| BTreeMap::<String, SectionTarget>::deserialize(v).ok())
+    else {
+        return vec![];
+    };
+    sections
+        .into_values()
+        .filter_map(|target| match target {
+            SectionTarget::Var(var) => Some(var),
+            SectionTarget::Spec { var, .. } => var,
+        })
+        .collect()
 }
 
 fn read_user_prompt(ctx: &mut StepCtx<'_>, path: &str) -> Result<String> {
@@ -49,8 +277,7 @@ fn inject_mock_response(user: String, mock: Option<String>) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::BTreeMap;
-    use vwf_runtime::{DryRunRuntime, MockLlmClient};
+    use vwf_runtime::{DryRunRuntime, MockLlmClient, Runtime};
 
     #[test]
     fn generates_llm_output() {
@@ -72,4 +299,267 @@ mod tests {
                 .any(|(p, c)| p == "out.txt" && c == "response")
         );
     }
+
+    #[test]
+    fn extracted_sections_land_in_vars() {
+        let resp = "TITLE:\nRust in 60s\n\nDESCRIPTION:\nA quick tour.\n\nNARRATION:\nHello there.\nBye.\n";
+        let mut rt = DryRunRuntime::new("/tmp", Box::new(MockLlmClient::canned(resp)));
+        rt.planned_writes
+            .push(("prompt.txt".into(), "user prompt".into()));
+        let vars = BTreeMap::new();
+        let payload = serde_json::json!({
+            "system": "sys",
+            "user_prompt_path": "prompt.txt",
+            "output_path": "out.txt",
+            "provider": "mock",
+            "extract_sections": {
+                "TITLE": "title",
+                "NARRATION:": {"var": "narration", "path": "work/narration.txt"},
+                "DESCRIPTION": {"path": "work/description.txt"}
+            }
+        });
+        let mut ctx = StepCtx::new(&mut rt, &vars, "test");
+        execute(&mut ctx, &payload).unwrap();
+        let exports = ctx.exports;
+        assert_eq!(
+            exports,
+            BTreeMap::from([
+                ("title".to_string(), "Rust in 60s".to_string()),
+                ("narration".to_string(), "Hello there.\nBye.".to_string()),
+            ])
+        );
+        assert!(
+            rt.planned_writes
+                .iter()
+                .any(|(p, c)| p == "work/narration.txt" && c == "Hello there.\nBye.")
+        );
+        assert!(
+            rt.planned_writes
+                .iter()
+                .any(|(p, c)| p == "work/description.txt" && c == "A quick tour.")
+        );
+    }
+
+    #[test]
+    fn missing_extracted_section_fails() {
+        let mut rt = DryRunRuntime::new("/tmp", Box::new(MockLlmClient::canned("no headings")));
+        rt.planned_writes.push(("prompt.txt".into(), "p".into()));
+        let vars = BTreeMap::new();
+        let payload = serde_json::json!({
+            "system": "sys",
+            "user_prompt_path": "prompt.txt",
+            "output_path": "out.txt",
+            "provider": "mock",
+            "extract_sections": {"TITLE": "title"}
+        });
+        let mut ctx = StepCtx::new(&mut rt, &vars, "test");
+        assert!(execute(&mut ctx, &payload).is_err());
+    }
+
+    /// Streams fixed chunks, recording the output file after each one.
+    struct ChunkedClient {
+        output: std::path::PathBuf,
+        seen: std::rc::Rc<std::cell::RefCell<Vec<String>>>,
+    }
+
+    impl vwf_runtime::LlmClient for ChunkedClient {
+        fn generate(&mut self, _req: LlmReq) -> Result<String> {
+            unreachable!("stream: true should stream")
+        }
+
+        fn generate_streamed(
+            &mut self,
+            _req: LlmReq,
+            on_chunk: &mut dyn FnMut(&str) -> Result<()>,
+        ) -> Result<String> {
+            for chunk in ["Once ", "upon ", "a time"] {
+                on_chunk(chunk)?;
+                let on_disk = std::fs::read_to_string(&self.output).unwrap();
+                self.seen.borrow_mut().push(on_disk);
+            }
+            Ok("Once upon a time".into())
+        }
+    }
+
+    #[test]
+    fn streamed_tokens_are_flushed_incrementally() {
+        let tmp = std::env::temp_dir().join(format!("vwf_llm_stream_{}", std::process::id()));
+        std::fs::create_dir_all(&tmp).unwrap();
+        std::fs::write(tmp.join("prompt.txt"), "tell a story").unwrap();
+        std::fs::write(tmp.join("story.txt"), "Chapter 1\n").unwrap();
+        let seen = std::rc::Rc::default();
+        let client = ChunkedClient {
+            output: tmp.join("story.txt"),
+            seen: std::rc::Rc::clone(&seen),
+        };
+        let mut rt = vwf_runtime::FsRuntime::new(&tmp, Box::new(client));
+        let vars = BTreeMap::new();
+        let payload = serde_json::json!({
+            "system": "sys",
+            "user_prompt_path": "prompt.txt",
+            "output_path": "story.txt",
+            "provider": "mock",
+            "stream": true,
+            "append": true
+        });
+        let mut ctx = StepCtx::new(&mut rt, &vars, "story");
+        execute(&mut ctx, &payload).unwrap();
+        assert_eq!(
+            *seen.borrow(),
+            [
+                "Chapter 1\nOnce ",
+                "Chapter 1\nOnce upon ",
+                "Chapter 1\nOnce upon a time"
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(tmp.join("story.txt")).unwrap(),
+            "Chapter 1\nOnce upon a time"
+        );
+        std::fs::remove_dir_all(&tmp).unwrap();
+    }
+
+    /// Replies with each canned response in turn, recording the prompts.
+    struct ScriptedClient {
+        replies: Vec<&'static str>,
+        prompts: std::rc::Rc<std::cell::RefCell<Vec<LlmReq>>>,
+    }
+
+    impl vwf_runtime::LlmClient for ScriptedClient {
+        fn generate(&mut self, req: LlmReq) -> Result<String> {
+            self.prompts.borrow_mut().push(req);
+            Ok(self.replies.remove(0).to_string())
+        }
+    }
+
+    fn json_schema_run(replies: Vec<&'static str>) -> (Result<()>, Vec<LlmReq>, Option<String>) {
+        let tmp = std::env::temp_dir().join(format!(
+            "vwf_llm_json_{}_{}",
+            replies.len(),
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&tmp).unwrap();
+        std::fs::write(tmp.join("prompt.txt"), "describe the video").unwrap();
+        let prompts = std::rc::Rc::default();
+        let client = ScriptedClient {
+            replies,
+            prompts: std::rc::Rc::clone(&prompts),
+        };
+        let mut rt = vwf_runtime::FsRuntime::new(&tmp, Box::new(client));
+        let vars = BTreeMap::new();
+        let payload = serde_json::json!({
+            "system": "sys",
+            "user_prompt_path": "prompt.txt",
+            "output_path": "meta.json",
+            "provider": "mock",
+            "json_schema": {
+                "type": "object",
+                "required": ["title", "tags"],
+                "properties": {
+                    "title": {"type": "string"},
+                    "tags": {"type": "array", "items": {"type": "string"}}
+                }
+            }
+        });
+        let mut ctx = StepCtx::new(&mut rt, &vars, "meta");
+        let result = execute(&mut ctx, &payload);
+        let written = std::fs::read_to_string(tmp.join("meta.json")).ok();
+        std::fs::remove_dir_all(&tmp).unwrap();
+        (result, prompts.take(), written)
+    }
+
+    #[test]
+    fn invalid_json_is_retried_with_the_errors() {
+        let (result, prompts, written) = json_schema_run(vec![
+            r#"{"title": 7}"#,
+            "```json\n{\"title\": \"Rust\", \"tags\": [\"cli\"]}\n```",
+        ]);
+        result.unwrap();
+        assert_eq!(prompts.len(), 2);
+        assert!(prompts[0].system.contains("JSON Schema"));
+        assert_eq!(prompts[0].user, "describe the video");
+        assert!(prompts[1].user.starts_with("describe the video"));
+        assert!(
+            prompts[1]
+                .user
+                .contains("/title: 7 is not of type \"string\"")
+        );
+        assert!(prompts[1].user.contains("\"tags\" is a required property"));
+        let written: Value = serde_json::from_str(&written.unwrap()).unwrap();
+        assert_eq!(
+            written,
+            serde_json::json!({"title": "Rust", "tags": ["cli"]})
+        );
+    }
+
+    #[test]
+    fn json_that_stays_invalid_fails() {
+        let (result, prompts, written) = json_schema_run(vec!["not json", r#"{"title": "x"}"#]);
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.contains("does not match json_schema"), "{err}");
+        assert!(err.contains("\"tags\" is a required property"), "{err}");
+        assert!(prompts[1].user.contains("not JSON"));
+        assert_eq!(written, None);
+    }
+
+    #[test]
+    fn append_keeps_earlier_output() {
+        let mut rt = DryRunRuntime::new("/tmp", Box::new(MockLlmClient::canned("part two")));
+        rt.planned_writes.push(("prompt.txt".into(), "p".into()));
+        rt.planned_writes
+            .push(("out.txt".into(), "part one\n".into()));
+        let vars = BTreeMap::new();
+        let payload = serde_json::json!({
+            "system": "sys",
+            "user_prompt_path": "prompt.txt",
+            "output_path": "out.txt",
+            "provider": "mock",
+            "append": true
+        });
+        let mut ctx = StepCtx::new(&mut rt, &vars, "test");
+        execute(&mut ctx, &payload).unwrap();
+        assert_eq!(rt.read_text("out.txt").unwrap(), "part one\npart two");
+    }
+
+    #[test]
+    fn append_starts_a_missing_output_and_fails_on_an_unreadable_one() {
+        let tmp = std::env::temp_dir().join(format!("vwf_llm_append_{}", std::process::id()));
+        std::fs::create_dir_all(tmp.join("taken")).unwrap();
+        std::fs::write(tmp.join("prompt.txt"), "p").unwrap();
+        let mut rt = vwf_runtime::FsRuntime::new(&tmp, Box::new(MockLlmClient::canned("new")));
+        let vars = BTreeMap::new();
+        let payload = |output: &str| {
+            serde_json::json!({
+                "system": "sys",
+                "user_prompt_path": "prompt.txt",
+                "output_path": output,
+                "provider": "mock",
+                "append": true
+            })
+        };
+        let mut ctx = StepCtx::new(&mut rt, &vars, "test");
+        execute(&mut ctx, &payload("out.txt")).unwrap();
+        let err = execute(&mut ctx, &payload("taken")).unwrap_err();
+        assert!(format!("{err:#}").contains("append"), "{err:#}");
+        assert_eq!(std::fs::read_to_string(tmp.join("out.txt")).unwrap(), "new");
+        std::fs::remove_dir_all(&tmp).unwrap();
+    }
+
+    #[test]
+    fn json_schema_cannot_append() {
+        let mut rt = DryRunRuntime::new("/tmp", Box::new(MockLlmClient::echo()));
+        rt.planned_writes.push(("prompt.txt".into(), "p".into()));
+        let vars = BTreeMap::new();
+        let payload = serde_json::json!({
+            "system": "sys",
+            "user_prompt_path": "prompt.txt",
+            "output_path": "meta.json",
+            "provider": "mock",
+            "append": true,
+            "json_schema": {"type": "object"}
+        });
+        let mut ctx = StepCtx::new(&mut rt, &vars, "meta");
+        let err = execute(&mut ctx, &payload).unwrap_err();
+        assert!(err.to_string().contains("combined with append"), "{err}");
+    }
 }
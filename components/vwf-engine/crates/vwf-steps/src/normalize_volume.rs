@@ -22,9 +22,14 @@
 
 use anyhow::{Context, Result, bail};
 use serde::Deserialize;
-use std::process::Command;
 
+use std::path::Path;
+
+use crate::catalog::{FieldDoc, StepDoc};
+use crate::command::CommandRunner;
 use crate::context::StepCtx;
+use crate::ffmpeg::{self, AudioProperties};
+use crate::temp::TempPath;
 
 #[derive(Deserialize)]
 struct Payload {
@@ -35,6 +40,24 @@ struct Payload {
     target_db: i32,
 }
 
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "normalize_volume",
+    summary: "Normalize a clip's mean volume in place with ffmpeg",
+    fields: &[
+        FieldDoc::required(
+            "clip_path",
+            "string",
+            "Clip to normalize (modified in place)",
+        ),
+        FieldDoc::defaulted(
+            "target_db",
+            "integer",
+            || default_target_db().into(),
+            "Target mean volume in dB",
+        ),
+    ],
+};
+
 fn default_target_db() -> i32 {
     -25
 }
@@ -43,16 +66,8 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
     let p: Payload = serde_json::from_value(payload.clone())
         .context("Failed to parse normalize_volume payload")?;
 
-    // Resolve clip path (absolute or relative to workdir)
-    let clip_path = if p.clip_path.starts_with('/') {
-        p.clip_path.clone()
-    } else {
-        ctx.rt
-            .workdir()
-            .join(&p.clip_path)
-            .to_string_lossy()
-            .to_string()
-    };
+    // The clip is normalized in place, so it must be inside the workdir
+    let clip_path = ctx.output_path(&p.clip_path)?.to_string_lossy().to_string();
 
     println!("Normalizing volume: {} to {} dB", clip_path, p.target_db);
 
@@ -62,19 +77,21 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
     }
 
     // Step 1: Check and fix audio format (44100 Hz stereo)
-    let sample_rate = get_audio_property(&clip_path, "sample_rate")?;
-    let channels = get_audio_property(&clip_path, "channels")?;
+    let AudioProperties {
+        sample_rate,
+        channels,
+    } = ffmpeg::audio_properties(&clip_path)?;
 
-    if sample_rate != "44100" || channels != "2" {
+    if sample_rate != 44100 || channels != 2 {
         println!(
             "  Fixing format: {}Hz {}ch -> 44100Hz stereo",
             sample_rate, channels
         );
-        fix_audio_format(&clip_path)?;
+        fix_audio_format(ctx.runner.as_mut(), &clip_path)?;
     }
 
     // Step 2: Get current mean volume
-    let current_db = get_mean_volume(&clip_path)?;
+    let current_db = ffmpeg::mean_volume_db(&clip_path)?;
     println!("  Current volume: {:.1} dB", current_db);
 
     // Step 3: Calculate adjustment needed
@@ -89,81 +106,50 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
 
     // Step 4: Apply volume adjustment
     println!("  Adjusting by {:.1} dB", adjust);
-    apply_volume_adjustment(&clip_path, adjust)?;
+    apply_volume_adjustment(ctx.runner.as_mut(), &clip_path, adjust)?;
 
     // Step 5: Verify new level
-    let new_db = get_mean_volume(&clip_path)?;
+    let new_db = ffmpeg::mean_volume_db(&clip_path)?;
     println!("  Normalized: {:.1} dB -> {:.1} dB", current_db, new_db);
 
     Ok(())
 }
 
-fn get_audio_property(clip_path: &str, property: &str) -> Result<String> {
-    let output = Command::new("ffprobe")
-        .args([
-            "-v",
-            "error",
-            "-select_streams",
-            "a:0",
-            "-show_entries",
-            &format!("stream={}", property),
-            "-of",
-            "csv=p=0",
-            clip_path,
-        ])
-        .output()
-        .context("Failed to run ffprobe")?;
-
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
-}
-
-fn get_mean_volume(clip_path: &str) -> Result<f64> {
-    let output = Command::new("ffmpeg")
-        .args(["-i", clip_path, "-af", "volumedetect", "-f", "null", "-"])
-        .output()
-        .context("Failed to run ffmpeg volumedetect")?;
-
-    let stderr = String::from_utf8_lossy(&output.stderr);
-
-    // Parse "mean_volume: -25.1 dB"
-    for line in stderr.lines() {
-        if line.contains("mean_volume:")
-            && let Some(db_str) = line.split("mean_volume:").nth(1)
-        {
-            let db_str = db_str.trim().replace(" dB", "");
-            return db_str.parse::<f64>().context("Failed to parse mean_volume");
-        }
-    }
-
-    bail!("Could not detect mean volume for {}", clip_path);
-}
-
-fn fix_audio_format(clip_path: &str) -> Result<()> {
-    let temp_path = format!("{}.tmp.mp4", clip_path);
-
-    let status = Command::new("ffmpeg")
-        .args([
+/// Re-encode audio to 44100 Hz stereo, replacing the clip.
+///
+/// ffmpeg writes to a `.tmp.mp4` beside the clip; it is removed if
+/// ffmpeg fails, so the original clip is left untouched.
+fn fix_audio_format(runner: &mut dyn CommandRunner, clip_path: &str) -> Result<()> {
+    let temp = TempPath::beside(Path::new(clip_path));
+    let temp_path = temp.path().to_string_lossy().to_string();
+
+    ffmpeg::run_ffmpeg_with(
+        runner,
+        &[
             "-y", "-i", clip_path, "-c:v", "copy", "-c:a", "aac", "-ar", "44100", "-ac", "2",
             &temp_path,
-        ])
-        .status()
-        .context("Failed to run ffmpeg for format fix")?;
-
-    if !status.success() {
-        bail!("ffmpeg format fix failed");
-    }
+        ],
+    )
+    .context("ffmpeg format fix failed")?;
 
-    std::fs::rename(&temp_path, clip_path).context("Failed to replace original clip")?;
+    temp.persist(Path::new(clip_path))
+        .context("Failed to replace original clip")?;
 
     Ok(())
 }
 
-fn apply_volume_adjustment(clip_path: &str, adjust_db: f64) -> Result<()> {
-    let temp_path = format!("{}.tmp.mp4", clip_path);
+fn apply_volume_adjustment(
+    runner: &mut dyn CommandRunner,
+    clip_path: &str,
+    adjust_db: f64,
+) -> Result<()> {
+    let temp = TempPath::beside(Path::new(clip_path));
+    let temp_path = temp.path().to_string_lossy().to_string();
     let volume_filter = format!("volume={}dB", adjust_db);
 
-    let status = Command::new("ffmpeg")
-        .args([
+    ffmpeg::run_ffmpeg_with(
+        runner,
+        &[
             "-y",
             "-i",
             clip_path,
@@ -178,15 +164,51 @@ fn apply_volume_adjustment(clip_path: &str, adjust_db: f64) -> Result<()> {
             "-ac",
             "2",
             &temp_path,
-        ])
-        .status()
-        .context("Failed to run ffmpeg for volume adjustment")?;
+        ],
+    )
+    .context("ffmpeg volume adjustment failed")?;
 
-    if !status.success() {
-        bail!("ffmpeg volume adjustment failed");
-    }
-
-    std::fs::rename(&temp_path, clip_path).context("Failed to replace original clip")?;
+    temp.persist(Path::new(clip_path))
+        .context("Failed to replace original clip")?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vwf_runtime::CmdOut;
+
+    /// Writes half of its output file, then exits with an error.
+    struct CrashingFfmpeg;
+
+    impl CommandRunner for CrashingFfmpeg {
+        fn run(&mut self, _program: &str, args: &[String]) -> Result<CmdOut> {
+            std::fs::write(args.last().unwrap(), "partial").unwrap();
+            Ok(CmdOut {
+                status: 1,
+                stdout: String::new(),
+                stderr: "Conversion failed!".into(),
+                duration_ms: 0,
+            })
+        }
+    }
+
+    #[test]
+    fn failed_ffmpeg_leaves_no_temp_file() {
+        let dir = std::env::temp_dir().join(format!("vwf_normalize_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let clip = dir.join("clip.mp4");
+        std::fs::write(&clip, "original").unwrap();
+        let clip = clip.to_string_lossy().to_string();
+
+        let err = fix_audio_format(&mut CrashingFfmpeg, &clip).unwrap_err();
+        assert!(format!("{err:#}").contains("Conversion failed!"), "{err:#}");
+        let err = apply_volume_adjustment(&mut CrashingFfmpeg, &clip, 3.0).unwrap_err();
+        assert!(format!("{err:#}").contains("Conversion failed!"), "{err:#}");
+
+        assert!(!dir.join("clip.mp4.tmp.mp4").exists());
+        assert_eq!(std::fs::read_to_string(&clip).unwrap(), "original");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
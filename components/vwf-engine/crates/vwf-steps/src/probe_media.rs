@@ -0,0 +1,156 @@
+//! Handler for probe_media step kind.
+//!
+//! Runs ffprobe on a media file and stores the requested properties in
+//! vars, so later steps can use e.g. `{{intro_duration}}` instead of a
+//! hardcoded value.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::context::StepCtx;
+use crate::catalog::{FieldDoc, StepDoc};
+use crate::ffmpeg::{MediaInfo, media_info_with};
+
+/// Var names to store each property in; unset properties are not stored.
+#[derive(Deserialize, Default)]
+struct Targets {
+    #[serde(default)]
+    duration: Option<String>,
+    #[serde(default)]
+    width: Option<String>,
+    #[serde(default)]
+    height: Option<String>,
+    #[serde(default)]
+    sample_rate: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Payload {
+    input_path: String,
+    into: Targets,
+}
+
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "probe_media",
+    summary: "Store a media file's duration, size or sample rate in vars",
+    fields: &[
+        FieldDoc::required("input_path", "string", "Audio/video file to probe"),
+        FieldDoc::required(
+            "into",
+            "map",
+            "Var name for each of duration, width, height, sample_rate",
+        ),
+    ],
+};
+
+pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
+    let p: Payload = serde_json::from_value(payload.clone())
+        .with_context(|| ctx.error_context("payload decode probe_media"))?;
+    let input_path = ctx.render(&p.input_path)?;
+    let resolved = ctx.resolve(&input_path);
+    let info = media_info_with(ctx.runner.as_mut(), &resolved.to_string_lossy())
+        .with_context(|| ctx.error_context("ffprobe"))?;
+    for (var, value) in requested_values(&p.into, &info, &input_path)? {
+        println!("  {var} = {value}");
+        ctx.exports.insert(var, value);
+    }
+    Ok(())
+}
+
+/// The var names in `into`, or none if the payload doesn't parse.
+pub(crate) fn exported_vars(payload: &Value) -> Vec<String> {
+    let Some(into) = payload
+        .get("into")
+        .and_then(|v| Targets::deserialize(v).ok())
+    else {
+        return vec![];
+    };
+    [into.duration, into.width, into.height, into.sample_rate]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Pair each requested var with its value, failing if the file lacks it.
+fn requested_values(
+    into: &Targets,
+    info: &MediaInfo,
+    input: &str,
+) -> Result<Vec<(String, String)>> {
+    let fields = [
+        (
+            &into.duration,
+            info.duration.map(|d| d.to_string()),
+            "duration",
+        ),
+        (&into.width, info.width.map(|w| w.to_string()), "width"),
+        (&into.height, info.height.map(|h| h.to_string()), "height"),
+        (
+            &into.sample_rate,
+            info.sample_rate.map(|r| r.to_string()),
+            "sample_rate",
+        ),
+    ];
+    let mut values = vec![];
+    for (var, value, name) in fields {
+        let Some(var) = var else { continue };
+        let value = value.with_context(|| format!("{input} has no {name}"))?;
+        values.push((var.clone(), value));
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::fake::FakeRunner;
+    use std::collections::BTreeMap;
+    use vwf_runtime::{DryRunRuntime, MockLlmClient};
+
+    #[test]
+    fn stores_probed_properties_in_vars() {
+        let mut rt = DryRunRuntime::new("/work", Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::from([("clip".to_string(), "intro".to_string())]);
+        let (mut runner, calls) = FakeRunner::succeeding();
+        runner.result.stdout = r#"{
+            "streams": [
+                {"codec_type": "video", "width": 1080, "height": 1920},
+                {"codec_type": "audio", "sample_rate": "48000"}
+            ],
+            "format": {"duration": "7.250000"}
+        }"#
+        .to_string();
+        let mut ctx = StepCtx::new(&mut rt, &vars, "probe").with_runner(Box::new(runner));
+        let payload = serde_json::json!({
+            "input_path": "work/{{clip}}.mp4",
+            "into": {"duration": "intro_duration", "height": "intro_height"}
+        });
+        execute(&mut ctx, &payload).unwrap();
+
+        assert_eq!(
+            ctx.exports,
+            BTreeMap::from([
+                ("intro_duration".to_string(), "7.25".to_string()),
+                ("intro_height".to_string(), "1920".to_string()),
+            ])
+        );
+        let calls = calls.borrow();
+        assert_eq!(calls[0].0, "ffprobe");
+        assert_eq!(calls[0].1.last().unwrap(), "/work/work/intro.mp4");
+    }
+
+    #[test]
+    fn missing_property_fails() {
+        let info = MediaInfo {
+            duration: Some(3.0),
+            ..Default::default()
+        };
+        let into = Targets {
+            width: Some("w".to_string()),
+            ..Default::default()
+        };
+        let err = requested_values(&into, &info, "a.wav").unwrap_err();
+        assert_eq!(err.to_string(), "a.wav has no width");
+    }
+}
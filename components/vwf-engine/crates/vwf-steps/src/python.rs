@@ -0,0 +1,6 @@
+//! Helpers for the Python scripts that drive ComfyUI.
+
+/// Escape `s` for a double-quoted Python string literal.
+pub(crate) fn escape_py(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
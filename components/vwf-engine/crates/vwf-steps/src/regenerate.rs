@@ -0,0 +1,187 @@
+//! Regenerating outputs that come back corrupt.
+//!
+//! Diffusion servers occasionally hand back a blank or truncated file while
+//! reporting success. Generative steps check what they got and, if it's
+//! bad, generate again with the next seed before giving up.
+
+use anyhow::{Context, Result, bail};
+use std::path::Path;
+
+use crate::command::is_not_found;
+use crate::context::StepCtx;
+use crate::ffmpeg;
+
+/// Regenerations after the first attempt when `validation_retries` is unset.
+pub(crate) const DEFAULT_VALIDATION_RETRIES: u32 = 2;
+
+/// Generated images smaller than this are a flat color or cut off.
+const MIN_IMAGE_BYTES: u64 = 4096;
+
+/// Last bytes of every complete PNG: the IEND chunk and its CRC.
+const PNG_TRAILER: &[u8] = b"IEND\xae\x42\x60\x82";
+
+pub(crate) fn default_validation_retries() -> u32 {
+    DEFAULT_VALIDATION_RETRIES
+}
+
+/// Run `generate` with `seed`, then `seed + 1`, ..., until `validate`
+/// accepts `output` or `retries` regenerations have been spent.
+///
+/// Generation errors are returned straight away; only bad output is
+/// retried. Output that is still bad at the end is moved to
+/// `<output>.rejected` so `--resume` doesn't treat it as done.
+pub(crate) fn until_valid(
+    ctx: &mut StepCtx<'_>,
+    output: &Path,
+    seed: u64,
+    retries: u32,
+    mut generate: impl FnMut(&mut StepCtx<'_>, u64) -> Result<()>,
+    mut validate: impl FnMut(&mut StepCtx<'_>, &Path) -> Result<()>,
+) -> Result<()> {
+    let mut seed = seed;
+    for attempt in 0..=retries {
+        generate(ctx, seed)?;
+        let err = match validate(ctx, output) {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+        if attempt == retries {
+            let mut rejected = output.as_os_str().to_owned();
+            rejected.push(".rejected");
+            let _ = std::fs::rename(output, &rejected);
+            return Err(err).with_context(|| {
+                ctx.error_context(&format!(
+                    "output still invalid after {} attempt(s) (kept at {})",
+                    retries + 1,
+                    Path::new(&rejected).display()
+                ))
+            });
+        }
+        seed = seed.wrapping_add(1);
+        ctx.retries += 1;
+        ctx.warn(format!(
+            "attempt {} produced invalid output ({err:#}); regenerated with seed {seed}",
+            attempt + 1
+        ));
+    }
+    unreachable!("the last attempt always returns")
+}
+
+/// A generated image must be a complete PNG, JPEG or WebP of some size.
+pub(crate) fn check_image(_ctx: &mut StepCtx<'_>, path: &Path) -> Result<()> {
+    let bytes = std::fs::read(path).with_context(|| format!("read {}", path.display()))?;
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        if !bytes.ends_with(PNG_TRAILER) {
+            bail!("{} is a truncated PNG", path.display());
+        }
+    } else if !bytes.starts_with(b"\xff\xd8") && !is_webp(&bytes) {
+        bail!("{} is not a PNG, JPEG or WebP image", path.display());
+    }
+    if (bytes.len() as u64) < MIN_IMAGE_BYTES {
+        bail!(
+            "{} is only {} bytes, likely blank",
+            path.display(),
+            bytes.len()
+        );
+    }
+    Ok(())
+}
+
+fn is_webp(bytes: &[u8]) -> bool {
+    bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP"
+}
+
+/// A generated video must have a video stream and a non-zero duration.
+///
+/// Without ffprobe there's nothing to check with, so that only warns.
+pub(crate) fn check_video(ctx: &mut StepCtx<'_>, path: &Path) -> Result<()> {
+    let info = match ffmpeg::media_info_with(ctx.runner.as_mut(), &path.to_string_lossy()) {
+        Err(e) if is_not_found(&e) => {
+            ctx.warn(format!(
+                "ffprobe not found; not validating {}",
+                path.display()
+            ));
+            return Ok(());
+        }
+        info => info?,
+    };
+    if info.width.is_none() {
+        bail!("{} has no video stream", path.display());
+    }
+    if !info.duration.is_some_and(|d| d > 0.0) {
+        bail!("{} has no duration", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::fake::FakeRunner;
+    use std::collections::BTreeMap;
+    use vwf_runtime::{DryRunRuntime, MockLlmClient};
+
+    /// A PNG that passes `check_image`: signature, padding and trailer.
+    fn good_png() -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.resize(MIN_IMAGE_BYTES as usize, 0);
+        png.extend_from_slice(PNG_TRAILER);
+        png
+    }
+
+    #[test]
+    fn corrupt_output_is_regenerated_with_the_next_seed() {
+        let dir = std::env::temp_dir().join(format!("vwf_regen_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("hero.png");
+        let mut rt = DryRunRuntime::new(&dir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let (runner, calls) = FakeRunner::succeeding();
+        let mut ctx = StepCtx::new(&mut rt, &vars, "hero").with_runner(Box::new(runner));
+
+        // The first generation comes back truncated, the second is fine.
+        let generate = |ctx: &mut StepCtx<'_>, seed: u64| {
+            ctx.runner.run("python3", &[format!("SEED = {seed}")])?;
+            let mut png = good_png();
+            if seed == 7 {
+                png.truncate(100);
+            }
+            std::fs::write(&output, png)?;
+            Ok(())
+        };
+        until_valid(&mut ctx, &output, 7, 2, generate, check_image).unwrap();
+        let seeds: Vec<_> = calls.borrow().iter().map(|c| c.1[0].clone()).collect();
+        assert_eq!(seeds, ["SEED = 7", "SEED = 8"]);
+        assert!(output.exists());
+        assert_eq!(ctx.retries, 1);
+
+        // Never valid: give up after the retries and move the output aside.
+        let always_blank = |_: &mut StepCtx<'_>, _: u64| Ok(std::fs::write(&output, b"")?);
+        let err = until_valid(&mut ctx, &output, 7, 1, always_blank, check_image).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("output still invalid after 2 attempt(s)"),
+            "{err:#}"
+        );
+        assert!(!output.exists());
+        assert!(dir.join("hero.png.rejected").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn video_needs_a_stream_and_duration() {
+        let mut rt = DryRunRuntime::new("/work", Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let (mut runner, _) = FakeRunner::succeeding();
+        runner.result.stdout = r#"{"format": {"duration": "0.000"}, "streams": [
+            {"codec_type": "video", "width": 720, "height": 1280}]}"#
+            .into();
+        let mut ctx = StepCtx::new(&mut rt, &vars, "clip").with_runner(Box::new(runner));
+        let err = check_video(&mut ctx, Path::new("/work/clip.mp4")).unwrap_err();
+        assert_eq!(err.to_string(), "/work/clip.mp4 has no duration");
+
+        let (mut runner, _) = FakeRunner::succeeding();
+        runner.missing = vec![ffmpeg::ffprobe_bin()];
+        let mut ctx = ctx.with_runner(Box::new(runner));
+        check_video(&mut ctx, Path::new("/work/clip.mp4")).unwrap();
+    }
+}
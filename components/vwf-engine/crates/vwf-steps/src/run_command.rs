@@ -1,10 +1,13 @@
 //! Handler for run_command step kind.
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use regex::Regex;
 use serde::Deserialize;
 use serde_json::Value;
+use std::time::Duration;
 
 use super::context::StepCtx;
+use crate::catalog::{FieldDoc, StepDoc};
 use vwf_runtime::CmdOut;
 
 #[derive(Deserialize)]
@@ -16,21 +19,188 @@ struct Payload {
     cwd: Option<String>,
     #[serde(default)]
     capture_path: Option<String>,
+    /// Fail the step unless the command exits with this status.
+    #[serde(default)]
+    expect_status: Option<i32>,
+    /// Echo the command's output to the terminal while it runs.
+    #[serde(default)]
+    inherit_stdio: bool,
+    /// Run `program` as a command line through the system shell.
+    #[serde(default)]
+    shell: bool,
+    /// Kill the program if it is still running after this many seconds.
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+    #[serde(default)]
+    extract: Option<Extract>,
+}
+
+/// Store part of the command's stdout in a var for later steps.
+#[derive(Deserialize)]
+struct Extract {
+    /// Applied to stdout; the first capture group (or the whole match) is kept.
+    regex: String,
+    into_var: String,
+}
+
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "run_command",
+    summary: "Run an allowlisted program",
+    fields: &[
+        FieldDoc::required(
+            "program",
+            "string",
+            "Program to run (must be allowed with --allow)",
+        ),
+        FieldDoc::optional("args", "array[string]", "Command arguments"),
+        FieldDoc::optional(
+            "cwd",
+            "string",
+            "Working directory for the program, relative to workdir",
+        ),
+        FieldDoc::optional(
+            "capture_path",
+            "string",
+            "File to write stdout/stderr to, relative to workdir (not cwd)",
+        ),
+        FieldDoc::optional(
+            "expect_status",
+            "integer",
+            "Fail unless the command exits with this status",
+        ),
+        FieldDoc::defaulted(
+            "inherit_stdio",
+            "boolean",
+            || false.into(),
+            "Show output live (still captured for capture_path)",
+        ),
+        FieldDoc::defaulted(
+            "shell",
+            "boolean",
+            || false.into(),
+            "Run program as a command line via sh -c (the shell must be allowed)",
+        ),
+        FieldDoc::optional(
+            "timeout_secs",
+            "integer",
+            "Kill the program and fail the step if it runs longer than this",
+        ),
+        FieldDoc::optional(
+            "extract",
+            "map",
+            "{regex, into_var}: store the regex's first capture group from stdout in a var",
+        ),
+    ],
+};
+
+/// The shell `shell: true` runs through, and the flag that takes the command.
+#[cfg(windows)]
+const SHELL: (&str, &str) = ("cmd", "/C");
+#[cfg(not(windows))]
+const SHELL: (&str, &str) = ("sh", "-c");
+
+/// Lines of stderr quoted in an `expect_status` failure.
+const STDERR_TAIL_LINES: usize = 20;
+
+/// Var names a step's `extract` stores, or none if the payload doesn't parse.
+pub(crate) fn exported_vars(payload: &Value) -> Vec<String> {
+    payload
+        .get("extract")
+        .and_then(|v| Extract::deserialize(v).ok())
+        .map(|e| vec![e.into_var])
+        .unwrap_or_default()
 }
 
 pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
     let p: Payload = serde_json::from_value(payload.clone())
         .with_context(|| ctx.error_context("payload decode run_command"))?;
-    let program = ctx.render(&p.program)?;
-    let args = render_args(ctx, &p.args)?;
+    let (program, args) = if p.shell {
+        shell_command(ctx, &p)?
+    } else {
+        (ctx.render(&p.program)?, render_args(ctx, &p.args)?)
+    };
     let cwd = render_cwd(ctx, &p.cwd)?;
-    let out = ctx.rt.run_command(&program, &args, cwd.as_deref())?;
+    let out = if let Some(secs) = p.timeout_secs {
+        if secs == 0 {
+            bail!(
+                "{}: timeout_secs must be at least 1",
+                ctx.error_context("run_command")
+            );
+        }
+        let timeout = Duration::from_secs(secs);
+        ctx.rt
+            .run_command_timeout(&program, &args, cwd.as_deref(), p.inherit_stdio, timeout)?
+    } else if p.inherit_stdio {
+        ctx.rt
+            .run_command_streamed(&program, &args, cwd.as_deref())?
+    } else {
+        ctx.rt.run_command(&program, &args, cwd.as_deref())?
+    };
     if let Some(cap) = p.capture_path {
         write_capture(ctx, &cap, &out)?;
     }
+    if let Some(expected) = p.expect_status
+        && out.status != expected
+    {
+        bail!(
+            "{}: `{program}` exited with status {} (expected {expected}) after {}ms\nstderr (last {STDERR_TAIL_LINES} lines):\n{}",
+            ctx.error_context("run_command"),
+            out.status,
+            out.duration_ms,
+            out.stderr_tail(STDERR_TAIL_LINES)
+        );
+    }
+    if let Some(extract) = &p.extract {
+        let value = extract_value(ctx, extract, &out.stdout)?;
+        println!("  {} = {value}", extract.into_var);
+        ctx.exports.insert(extract.into_var.clone(), value);
+    }
     Ok(())
 }
 
+/// The text `extract.regex` picks out of `stdout`.
+///
+/// Dry runs have no real output to match, so they get a placeholder once
+/// the regex is known to compile.
+fn extract_value(ctx: &StepCtx<'_>, extract: &Extract, stdout: &str) -> Result<String> {
+    let re = Regex::new(&extract.regex)
+        .with_context(|| ctx.error_context("run_command extract regex"))?;
+    if ctx.rt.is_dry_run() {
+        return Ok(format!("[dry-run] {}", extract.into_var));
+    }
+    let Some(caps) = re.captures(stdout) else {
+        bail!(
+            "{}: extract regex `{}` matched nothing in stdout",
+            ctx.error_context("run_command"),
+            extract.regex
+        );
+    };
+    let found = caps
+        .get(1)
+        .or_else(|| caps.get(0))
+        .map_or("", |m| m.as_str());
+    Ok(found.to_string())
+}
+
+/// The shell invocation for a `shell: true` step.
+///
+/// The allowlist only sees the shell, not what the command line runs, so
+/// this warns every time.
+fn shell_command(ctx: &mut StepCtx<'_>, p: &Payload) -> Result<(String, Vec<String>)> {
+    if !p.args.is_empty() {
+        bail!(
+            "{}: `args` can't be used with `shell: true`; put the whole command line in `program`",
+            ctx.error_context("run_command")
+        );
+    }
+    let line = ctx.render(&p.program)?;
+    let (shell, flag) = SHELL;
+    ctx.warn(format!(
+        "runs `{line}` through `{shell}`; the command allowlist only checks `{shell}`"
+    ));
+    Ok((shell.to_string(), vec![flag.to_string(), line]))
+}
+
 fn render_args(ctx: &StepCtx<'_>, args: &[String]) -> Result<Vec<String>> {
     args.iter().map(|a| ctx.render(a)).collect()
 }
@@ -45,8 +215,8 @@ fn render_cwd(ctx: &StepCtx<'_>, cwd: &Option<String>) -> Result<Option<String>>
 fn write_capture(ctx: &mut StepCtx<'_>, path: &str, out: &CmdOut) -> Result<()> {
     let path = ctx.render(path)?;
     let content = format!(
-        "status: {}\n\nstdout:\n{}\n\nstderr:\n{}\n",
-        out.status, out.stdout, out.stderr
+        "status: {}\nduration_ms: {}\n\nstdout:\n{}\n\nstderr:\n{}\n",
+        out.status, out.duration_ms, out.stdout, out.stderr
     );
     ctx.rt.write_text(&path, &content)
 }
@@ -55,7 +225,7 @@ fn write_capture(ctx: &mut StepCtx<'_>, path: &str, out: &CmdOut) -> Result<()>
 mod tests {
     use super::*;
     use std::collections::BTreeMap;
-    use vwf_runtime::{DryRunRuntime, MockLlmClient};
+    use vwf_runtime::{DryRunRuntime, FsRuntime, MockLlmClient};
 
     #[test]
     fn captures_command_output() {
@@ -67,4 +237,157 @@ mod tests {
         execute(&mut ctx, &payload).unwrap();
         assert!(!rt.planned_writes.is_empty());
     }
+
+    #[test]
+    fn expect_status_failure_includes_stderr_tail() {
+        let mut rt = FsRuntime::new(std::env::temp_dir(), Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let payload = serde_json::json!({
+            "program": "sh",
+            "args": ["-c", "echo ffmpeg: codec not found >&2; exit 3"],
+            "expect_status": 0
+        });
+        let mut ctx = StepCtx::new(&mut rt, &vars, "test");
+        let err = execute(&mut ctx, &payload).unwrap_err().to_string();
+        assert!(err.contains("exited with status 3 (expected 0)"), "{err}");
+        assert!(err.contains("ffmpeg: codec not found"), "{err}");
+    }
+
+    #[test]
+    fn timeout_secs_kills_a_hung_program() {
+        let mut rt = FsRuntime::new(std::env::temp_dir(), Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let payload = serde_json::json!({"program": "sleep", "args": ["30"], "timeout_secs": 1});
+        let mut ctx = StepCtx::new(&mut rt, &vars, "test");
+        let started = std::time::Instant::now();
+        let err = format!("{:#}", execute(&mut ctx, &payload).unwrap_err());
+        assert!(err.contains("timed out after 1s"), "{err}");
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "sleep was not killed"
+        );
+    }
+
+    #[test]
+    fn inherit_stdio_still_writes_capture() {
+        let tmp = std::env::temp_dir().join(format!("vwf_stream_{}", std::process::id()));
+        std::fs::create_dir_all(&tmp).unwrap();
+        let mut rt = FsRuntime::new(&tmp, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let payload = serde_json::json!({
+            "program": "echo",
+            "args": ["rendering"],
+            "capture_path": "render.log",
+            "inherit_stdio": true
+        });
+        let mut ctx = StepCtx::new(&mut rt, &vars, "test");
+        execute(&mut ctx, &payload).unwrap();
+        let log = std::fs::read_to_string(tmp.join("render.log")).unwrap();
+        assert!(log.contains("stdout:\nrendering\n"), "{log}");
+        std::fs::remove_dir_all(&tmp).unwrap();
+    }
+
+    #[test]
+    fn cwd_only_moves_the_program() {
+        let tmp = std::env::temp_dir().join(format!("vwf_cwd_{}", std::process::id()));
+        std::fs::create_dir_all(tmp.join("scenes")).unwrap();
+        let mut rt = FsRuntime::new(&tmp, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let payload = serde_json::json!({
+            "program": "pwd",
+            "cwd": "scenes",
+            "capture_path": "logs/pwd.log"
+        });
+        let mut ctx = StepCtx::new(&mut rt, &vars, "test");
+        execute(&mut ctx, &payload).unwrap();
+        // capture_path is workdir-relative even though the program ran in scenes/.
+        let log = std::fs::read_to_string(tmp.join("logs/pwd.log")).unwrap();
+        assert!(log.contains("scenes\n"), "{log}");
+        assert!(!tmp.join("scenes/logs").exists());
+        std::fs::remove_dir_all(&tmp).unwrap();
+    }
+
+    #[test]
+    fn shell_mode_runs_pipelines() {
+        let tmp = std::env::temp_dir().join(format!("vwf_shell_{}", std::process::id()));
+        std::fs::create_dir_all(&tmp).unwrap();
+        let mut rt = FsRuntime::new(&tmp, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let mut ctx = StepCtx::new(&mut rt, &vars, "test");
+        let shell = serde_json::json!({
+            "program": "echo hi | tr a-z A-Z",
+            "shell": true,
+            "capture_path": "shell.log"
+        });
+        execute(&mut ctx, &shell).unwrap();
+        let literal = serde_json::json!({
+            "program": "echo",
+            "args": ["hi | tr a-z A-Z"],
+            "capture_path": "literal.log"
+        });
+        execute(&mut ctx, &literal).unwrap();
+        let shell_log = std::fs::read_to_string(tmp.join("shell.log")).unwrap();
+        let literal_log = std::fs::read_to_string(tmp.join("literal.log")).unwrap();
+        assert!(shell_log.contains("stdout:\nHI\n"), "{shell_log}");
+        assert!(
+            literal_log.contains("stdout:\nhi | tr a-z A-Z\n"),
+            "{literal_log}"
+        );
+        std::fs::remove_dir_all(&tmp).unwrap();
+    }
+
+    #[test]
+    fn shell_mode_needs_the_shell_allowed() {
+        let mut rt = FsRuntime::new(std::env::temp_dir(), Box::new(MockLlmClient::echo()));
+        rt.command_allowlist.insert("echo".into());
+        let vars = BTreeMap::new();
+        let payload = serde_json::json!({"program": "echo hi", "shell": true});
+        let mut ctx = StepCtx::new(&mut rt, &vars, "test");
+        let err = execute(&mut ctx, &payload).unwrap_err().to_string();
+        assert!(err.contains("Command not allowed: `sh`"), "{err}");
+    }
+
+    #[test]
+    fn extract_stores_first_capture_group() {
+        let mut rt = FsRuntime::new(std::env::temp_dir(), Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let payload = serde_json::json!({
+            "program": "echo",
+            "args": ["rendering...\nwrote out/take_07.mp4 in 3s"],
+            "extract": {"regex": r"wrote (\S+\.mp4)", "into_var": "take"}
+        });
+        let mut ctx = StepCtx::new(&mut rt, &vars, "test");
+        execute(&mut ctx, &payload).unwrap();
+        assert_eq!(ctx.exports["take"], "out/take_07.mp4");
+
+        let payload = serde_json::json!({
+            "program": "echo",
+            "args": ["nothing here"],
+            "extract": {"regex": r"wrote (\S+)", "into_var": "take"}
+        });
+        let err = execute(&mut ctx, &payload).unwrap_err().to_string();
+        assert!(err.contains("matched nothing in stdout"), "{err}");
+        assert_eq!(exported_vars(&payload), ["take"]);
+
+        let mut dry = DryRunRuntime::new(std::env::temp_dir(), Box::new(MockLlmClient::echo()));
+        let mut ctx = StepCtx::new(&mut dry, &vars, "test");
+        let payload = serde_json::json!({
+            "program": "echo",
+            "extract": {"regex": r"wrote (\S+", "into_var": "take"}
+        });
+        let err = execute(&mut ctx, &payload).unwrap_err();
+        assert!(format!("{err:#}").contains("extract regex"), "{err:#}");
+    }
+
+    #[test]
+    fn stderr_tail_keeps_last_lines() {
+        let out = CmdOut {
+            status: 1,
+            stdout: String::new(),
+            stderr: "a\nb\nc\n".into(),
+            duration_ms: 5,
+        };
+        assert_eq!(out.stderr_tail(2), "b\nc");
+        assert_eq!(out.stderr_tail(10), "a\nb\nc");
+    }
 }
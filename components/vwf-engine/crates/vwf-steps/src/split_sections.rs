@@ -5,45 +5,118 @@ use serde::Deserialize;
 use serde_json::Value;
 
 use super::context::StepCtx;
+use crate::catalog::{FieldDoc, StepDoc};
 
 #[derive(Deserialize)]
 struct Output {
     heading: String,
     path: String,
+    /// Capture from the heading to end of file, ignoring later headings.
+    #[serde(default)]
+    until_eof: bool,
+    /// Overrides the payload-wide `case_insensitive` for this heading.
+    #[serde(default)]
+    case_insensitive: Option<bool>,
 }
 
 #[derive(Deserialize)]
 struct Payload {
     input_path: String,
     outputs: Vec<Output>,
+    #[serde(default)]
+    case_insensitive: bool,
 }
 
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "split_sections",
+    summary: "Split a text file into files by heading",
+    fields: &[
+        FieldDoc::required("input_path", "string", "Input text file"),
+        FieldDoc::required(
+            "outputs",
+            "array",
+            "Sections to write, each {heading, path, until_eof, case_insensitive}",
+        ),
+        FieldDoc::optional(
+            "case_insensitive",
+            "bool",
+            "Match headings ignoring case (default: false)",
+        ),
+    ],
+};
+
 pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
     let p: Payload = serde_json::from_value(payload.clone())
         .with_context(|| ctx.error_context("payload decode split_sections"))?;
     let input_path = ctx.render(&p.input_path)?;
     let text = ctx.rt.read_text(&input_path)?;
     for out in p.outputs {
-        write_section(ctx, &text, &out)?;
+        let case_insensitive = out.case_insensitive.unwrap_or(p.case_insensitive);
+        write_section(ctx, &text, &out, case_insensitive)?;
     }
     Ok(())
 }
 
-fn write_section(ctx: &mut StepCtx<'_>, text: &str, out: &Output) -> Result<()> {
+fn write_section(
+    ctx: &mut StepCtx<'_>,
+    text: &str,
+    out: &Output,
+    case_insensitive: bool,
+) -> Result<()> {
     let heading = ctx.render(&out.heading)?;
     let path = ctx.render(&out.path)?;
-    let section = extract_section(text, &heading)
+    let section = extract_section(text, &heading, out.until_eof, case_insensitive)
         .with_context(|| ctx.error_context(&format!("cannot extract heading `{heading}`")))?;
     ctx.rt.write_text(&path, section.trim())
 }
 
-fn extract_section<'a>(text: &'a str, heading: &str) -> Result<&'a str> {
-    let start = text
-        .find(heading)
+/// The text after `heading` up to the next heading (or end of file).
+///
+/// `heading` must start a line (leading whitespace aside). Trailing
+/// whitespace on `heading` and on the heading's line is ignored. With
+/// `case_insensitive`, `Title:` matches `TITLE:`.
+pub(crate) fn extract_section<'a>(
+    text: &'a str,
+    heading: &str,
+    until_eof: bool,
+    case_insensitive: bool,
+) -> Result<&'a str> {
+    let heading = heading.trim_end();
+    let start = find_heading(text, heading, case_insensitive)
         .ok_or_else(|| anyhow::anyhow!("heading not found"))?;
     let after = &text[start + heading.len()..];
-    let end_idx = find_next_heading(after);
-    Ok(after[..end_idx].trim_matches(['\n', '\r'].as_ref()))
+    let end_idx = if until_eof {
+        after.len()
+    } else {
+        find_next_heading(after)
+    };
+    let section = &after[..end_idx];
+    let section = match section.split_once('\n') {
+        Some((rest_of_line, body)) if rest_of_line.trim().is_empty() => body,
+        _ => section,
+    };
+    Ok(section.trim_matches(['\n', '\r'].as_ref()))
+}
+
+/// Byte offset of `heading` at the start of a line of `text`;
+/// case-insensitive matching folds ASCII letters only.
+fn find_heading(text: &str, heading: &str, case_insensitive: bool) -> Option<usize> {
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let indent = line.len() - line.trim_start().len();
+        let found = line[indent..].get(..heading.len()).is_some_and(|s| {
+            if case_insensitive {
+                s.eq_ignore_ascii_case(heading)
+            } else {
+                s == heading
+            }
+        });
+        if found {
+            return Some(offset + indent);
+        }
+        offset += line.len();
+    }
+    None
 }
 
 fn find_next_heading(text: &str) -> usize {
@@ -69,3 +142,55 @@ fn is_heading(line: &str) -> bool {
 fn byte_offset_of_line(text: &str, line_num: usize) -> usize {
     text.lines().take(line_num).map(|l| l.len() + 1).sum()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str =
+        "INTRO:\nWelcome.\n\nOUTRO:\nThanks for watching.\nNOTES:\nfree-form trailer\n";
+
+    #[test]
+    fn section_stops_at_next_heading() {
+        assert_eq!(
+            extract_section(SCRIPT, "INTRO:", false, false).unwrap(),
+            "Welcome."
+        );
+        assert_eq!(
+            extract_section(SCRIPT, "OUTRO:", false, false).unwrap(),
+            "Thanks for watching."
+        );
+    }
+
+    #[test]
+    fn until_eof_captures_past_later_headings() {
+        assert_eq!(
+            extract_section(SCRIPT, "OUTRO:", true, false).unwrap(),
+            "Thanks for watching.\nNOTES:\nfree-form trailer"
+        );
+    }
+
+    #[test]
+    fn heading_must_start_a_line() {
+        let text = "SUBTITLE: none\nSee the TITLE: below.\n  TITLE:\nMy Video\n";
+        assert_eq!(
+            extract_section(text, "TITLE:", false, false).unwrap(),
+            "My Video"
+        );
+    }
+
+    #[test]
+    fn lowercase_heading_matches_only_when_case_insensitive() {
+        let text =
+            "Subtitle: none\nthe title: comes next\nTitle:  \nMy Video\nDESCRIPTION:\nAbout it.\n";
+        assert!(extract_section(text, "TITLE:", false, false).is_err());
+        assert_eq!(
+            extract_section(text, "TITLE: ", false, true).unwrap(),
+            "My Video"
+        );
+        assert_eq!(
+            extract_section(text, "description:", false, true).unwrap(),
+            "About it."
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! Temporary paths that are removed when dropped.
+//!
+//! Names from [`TempPath::new`] combine the process id, the step id and a
+//! per-process counter, so steps running in the same process (or in
+//! parallel) never share a path.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A path whose file or directory is deleted on drop, unless persisted.
+#[derive(Debug)]
+pub struct TempPath {
+    path: PathBuf,
+    armed: bool,
+}
+
+impl TempPath {
+    /// `vwf_<prefix>_<pid>_<step>_<n><suffix>` in the system temp dir.
+    ///
+    /// Nothing is created; the caller writes the file or directory.
+    pub fn new(prefix: &str, step_id: &str, suffix: &str) -> Self {
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let step: String = step_id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let name = format!("vwf_{prefix}_{}_{step}_{n}{suffix}", std::process::id());
+        Self::guard(std::env::temp_dir().join(name))
+    }
+
+    /// `<target>.tmp.<ext>` next to `target`, for writing a replacement
+    /// that [`persist`](Self::persist) then renames over it. The extension
+    /// is repeated so tools that pick a format from it (ffmpeg) still can.
+    pub fn beside(target: &Path) -> Self {
+        let mut name = target.as_os_str().to_owned();
+        name.push(".tmp");
+        if let Some(ext) = target.extension() {
+            name.push(".");
+            name.push(ext);
+        }
+        Self::guard(PathBuf::from(name))
+    }
+
+    /// Delete whatever ends up at `path` on drop, e.g. an intermediate
+    /// file a step writes next to its output.
+    pub fn guard(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            armed: true,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Rename the file over `target`.
+    pub fn persist(mut self, target: &Path) -> std::io::Result<()> {
+        std::fs::rename(&self.path, target)?;
+        self.armed = false;
+        Ok(())
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let _ = if self.path.is_dir() {
+            std::fs::remove_dir_all(&self.path)
+        } else {
+            std::fs::remove_file(&self.path)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_are_unique_and_removed_on_drop() {
+        let a = TempPath::new("test", "step/1", ".txt");
+        let b = TempPath::new("test", "step/1", ".txt");
+        assert_ne!(a.path(), b.path());
+        let name = a.path().file_name().unwrap().to_string_lossy().to_string();
+        assert!(name.starts_with(&format!("vwf_test_{}_step_1_", std::process::id())));
+
+        std::fs::write(a.path(), "x").unwrap();
+        std::fs::create_dir_all(b.path().join("nested")).unwrap();
+        let (pa, pb) = (a.path().to_path_buf(), b.path().to_path_buf());
+        drop(a);
+        drop(b);
+        assert!(!pa.exists());
+        assert!(!pb.exists());
+    }
+
+    #[test]
+    fn beside_keeps_the_extension_and_persists_over_target() {
+        let dir = std::env::temp_dir().join(format!("vwf_beside_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("clip.mp4");
+        std::fs::write(&target, "old").unwrap();
+
+        let tmp = TempPath::beside(&target);
+        assert_eq!(tmp.path(), dir.join("clip.mp4.tmp.mp4"));
+        std::fs::write(tmp.path(), "new").unwrap();
+        tmp.persist(&target).unwrap();
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "new");
+
+        assert!(!dir.join("clip.mp4.tmp.mp4").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,20 +1,62 @@
 //! Handler for text_to_image step kind.
 //!
 //! Generates images using FLUX.1 schnell via ComfyUI REST API.
+//! ComfyUI always returns PNG; other formats are converted with ImageMagick.
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use serde::Deserialize;
 use serde_json::Value;
+use std::path::Path;
 use std::process::Command;
 
 use super::context::StepCtx;
+use crate::cache;
+use crate::catalog::{FieldDoc, StepDoc};
+use crate::imagemagick;
+use crate::python::escape_py;
+use crate::regenerate;
+use crate::temp::TempPath;
+
+/// File format written to `output_path`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ImageFormat {
+    #[default]
+    Png,
+    Webp,
+    #[serde(alias = "jpg")]
+    Jpeg,
+}
+
+impl ImageFormat {
+    /// ImageMagick's name for the format, as in `webp:out.webp`.
+    fn magick_name(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Webp => "webp",
+            Self::Jpeg => "jpeg",
+        }
+    }
+}
 
 #[derive(Deserialize)]
 struct Payload {
     /// Text prompt for image generation
     prompt: String,
-    /// Output image path (.png)
+    /// Output image path (extension should match `format`)
     output_path: String,
+    /// Format to save in (default: png, as ComfyUI produces it)
+    #[serde(default)]
+    format: ImageFormat,
+    /// Encoder quality 1-100 for webp/jpeg (default: 90)
+    #[serde(default = "default_quality")]
+    quality: u8,
+    /// ImageMagick binary for format conversion (default: convert, then magick)
+    #[serde(default)]
+    imagemagick_path: Option<String>,
+    /// Regenerate with the next seed this many times if the image is corrupt
+    #[serde(default = "regenerate::default_validation_retries")]
+    validation_retries: u32,
     /// Image orientation: portrait (768x1344), landscape (1344x768), square (1024x1024)
     #[serde(default = "default_orientation")]
     orientation: String,
@@ -30,6 +72,131 @@ struct Payload {
     /// Python interpreter path (default: python3)
     #[serde(default = "default_python")]
     python_path: Option<String>,
+    /// Negative prompt (default: empty)
+    #[serde(default)]
+    negative_prompt: String,
+    /// Sampling steps (default: 4, tuned for schnell)
+    #[serde(default = "default_steps")]
+    steps: u32,
+    /// CFG scale (default: 1.0)
+    #[serde(default = "default_cfg")]
+    cfg: f32,
+    /// KSampler sampler name (default: euler)
+    #[serde(default = "default_sampler")]
+    sampler: String,
+    /// KSampler scheduler (default: simple)
+    #[serde(default = "default_scheduler")]
+    scheduler: String,
+    /// Checkpoint file on the ComfyUI server (default: flux1-schnell-fp8.safetensors)
+    #[serde(default = "default_checkpoint")]
+    checkpoint: String,
+}
+
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "text_to_image",
+    summary: "Generate an image with FLUX via ComfyUI",
+    fields: &[
+        FieldDoc::required("prompt", "string", "Text prompt"),
+        FieldDoc::required(
+            "output_path",
+            "string",
+            "Output image path (extension should match format)",
+        ),
+        FieldDoc::defaulted(
+            "format",
+            "string",
+            || "png".into(),
+            "png, webp or jpeg; non-PNG output is converted with ImageMagick",
+        ),
+        FieldDoc::defaulted(
+            "quality",
+            "integer",
+            || default_quality().into(),
+            "Encoder quality 1-100 for webp/jpeg",
+        ),
+        FieldDoc::defaulted(
+            "orientation",
+            "string",
+            || default_orientation().into(),
+            "portrait, landscape or square",
+        ),
+        FieldDoc::optional("width", "integer", "Custom width (overrides orientation)"),
+        FieldDoc::optional("height", "integer", "Custom height (overrides orientation)"),
+        FieldDoc::optional("seed", "integer", "Random seed for reproducibility"),
+        FieldDoc::defaulted(
+            "server",
+            "string",
+            || default_server().into(),
+            "ComfyUI server URL",
+        ),
+        FieldDoc::optional(
+            "python_path",
+            "string",
+            "Python interpreter (python3 if unset)",
+        ),
+        FieldDoc::defaulted("negative_prompt", "string", || "".into(), "Negative prompt"),
+        FieldDoc::defaulted(
+            "steps",
+            "integer",
+            || default_steps().into(),
+            "Sampling steps",
+        ),
+        FieldDoc::defaulted("cfg", "number", || default_cfg().into(), "CFG scale"),
+        FieldDoc::defaulted(
+            "sampler",
+            "string",
+            || default_sampler().into(),
+            "KSampler sampler name",
+        ),
+        FieldDoc::defaulted(
+            "scheduler",
+            "string",
+            || default_scheduler().into(),
+            "KSampler scheduler",
+        ),
+        FieldDoc::defaulted(
+            "checkpoint",
+            "string",
+            || default_checkpoint().into(),
+            "Checkpoint file on the ComfyUI server",
+        ),
+        FieldDoc::optional(
+            "imagemagick_path",
+            "string",
+            "ImageMagick binary for format conversion (convert, then magick, if unset)",
+        ),
+        FieldDoc::defaulted(
+            "validation_retries",
+            "integer",
+            || regenerate::DEFAULT_VALIDATION_RETRIES.into(),
+            "Regenerate with the next seed this many times if the image comes back corrupt",
+        ),
+    ],
+};
+
+/// KSampler settings threaded into the ComfyUI workflow.
+struct Sampling {
+    negative_prompt: String,
+    steps: u32,
+    cfg: f32,
+    sampler: String,
+    scheduler: String,
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Self {
+            negative_prompt: String::new(),
+            steps: default_steps(),
+            cfg: default_cfg(),
+            sampler: default_sampler(),
+            scheduler: default_scheduler(),
+        }
+    }
+}
+
+fn default_quality() -> u8 {
+    90
 }
 
 fn default_python() -> Option<String> {
@@ -44,14 +211,50 @@ fn default_server() -> String {
     "http://192.168.1.64:8570".to_string()
 }
 
+fn default_checkpoint() -> String {
+    "flux1-schnell-fp8.safetensors".to_string()
+}
+
+fn default_steps() -> u32 {
+    4
+}
+
+fn default_cfg() -> f32 {
+    1.0
+}
+
+fn default_sampler() -> String {
+    "euler".to_string()
+}
+
+fn default_scheduler() -> String {
+    "simple".to_string()
+}
+
 pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
     let p: Payload = serde_json::from_value(payload.clone())
         .with_context(|| ctx.error_context("payload decode text_to_image"))?;
 
     let prompt = ctx.render(&p.prompt)?;
     let output_path = ctx.render(&p.output_path)?;
+    let output_path = ctx.output_path(&output_path)?.to_string_lossy().to_string();
     let server = ctx.render(&p.server)?;
     let orientation = ctx.render(&p.orientation)?;
+    let checkpoint = ctx.render(&p.checkpoint)?;
+    if !(1..=100).contains(&p.quality) {
+        bail!(
+            "{}: quality must be 1-100, got {}",
+            ctx.error_context("text_to_image"),
+            p.quality
+        );
+    }
+    // ComfyUI hands back PNG; anything else is downloaded beside the output
+    // and converted from there.
+    let download =
+        (p.format != ImageFormat::Png).then(|| TempPath::guard(format!("{output_path}.tmp.png")));
+    let download_path = download.as_ref().map_or(output_path.clone(), |d| {
+        d.path().to_string_lossy().to_string()
+    });
 
     // Determine dimensions - exact aspect ratios, divisible by 8 for FLUX latent space
     // 720x1280 is standard HD, exact 9:16/16:9, scales cleanly to 1080x1920/1920x1080
@@ -64,41 +267,148 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
         },
     };
 
-    let seed = p.seed.unwrap_or_else(rand::random);
+    let seed = ctx.seed(p.seed);
     let python = p
         .python_path
         .map(|pp| ctx.render(&pp))
         .transpose()?
         .unwrap_or_else(|| "python3".to_string());
 
+    let sampling = Sampling {
+        negative_prompt: ctx.render(&p.negative_prompt)?,
+        steps: p.steps,
+        cfg: p.cfg,
+        sampler: ctx.render(&p.sampler)?,
+        scheduler: ctx.render(&p.scheduler)?,
+    };
+
     // Generate image via Python script
-    let script = image_gen_script(&server, &prompt, width, height, seed, &output_path);
+    let generate = |ctx: &mut StepCtx<'_>, seed: u64| {
+        let script = image_gen_script(
+            &server,
+            &checkpoint,
+            &prompt,
+            width,
+            height,
+            seed,
+            &download_path,
+            &sampling,
+        );
 
-    let status = Command::new(&python)
-        .args(["-c", &script])
-        .status()
-        .with_context(|| ctx.error_context("spawn text_to_image python"))?;
+        let status = Command::new(&python)
+            .args(["-c", &script])
+            .status()
+            .with_context(|| ctx.error_context("spawn text_to_image python"))?;
 
-    if !status.success() {
-        anyhow::bail!(
-            "Image generation failed with exit code: {:?}",
-            status.code()
+        if !status.success() {
+            anyhow::bail!(
+                "Image generation failed with exit code: {:?}",
+                status.code()
+            );
+        }
+        Ok(())
+    };
+    let key = || {
+        Ok(serde_json::json!({
+            "kind": "text_to_image",
+            "prompt": prompt,
+            "negative_prompt": sampling.negative_prompt,
+            "seed": seed,
+            "width": width,
+            "height": height,
+            "checkpoint": checkpoint,
+            "steps": sampling.steps,
+            "cfg": sampling.cfg,
+            "sampler": sampling.sampler,
+            "scheduler": sampling.scheduler,
+            "format": p.format.magick_name(),
+            "quality": p.quality,
+        }))
+    };
+    cache::cached(ctx, key, Path::new(&output_path), |ctx| {
+        regenerate::until_valid(
+            ctx,
+            Path::new(&download_path),
+            seed,
+            p.validation_retries,
+            generate,
+            regenerate::check_image,
+        )?;
+
+        if download.is_some() {
+            convert_image(
+                ctx,
+                p.imagemagick_path.as_deref(),
+                Path::new(&download_path),
+                Path::new(&output_path),
+                p.format,
+                p.quality,
+            )?;
+        }
+        Ok(())
+    })
+}
+
+/// Re-encode the downloaded PNG as `output` in `format`.
+///
+/// ImageMagick writes beside `output` and the result replaces it only once
+/// the conversion succeeded, so a failure keeps any previous image.
+fn convert_image(
+    ctx: &mut StepCtx<'_>,
+    imagemagick_path: Option<&str>,
+    png: &Path,
+    output: &Path,
+    format: ImageFormat,
+    quality: u8,
+) -> Result<()> {
+    let partial = TempPath::beside(output);
+    let args = vec![
+        png.to_string_lossy().to_string(),
+        "-quality".to_string(),
+        quality.to_string(),
+        format!("{}:{}", format.magick_name(), partial.path().display()),
+    ];
+    let out = imagemagick::run(ctx.runner.as_mut(), imagemagick_path, &args)
+        .with_context(|| ctx.error_context("ImageMagick convert"))?;
+    if out.status != 0 {
+        bail!(
+            "{}: ImageMagick failed with exit code {}: {}",
+            ctx.error_context("text_to_image"),
+            out.status,
+            out.stderr.trim()
         );
     }
-
+    partial
+        .persist(output)
+        .with_context(|| format!("Failed to replace {}", output.display()))?;
+    println!("  Converted: {}", output.display());
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn image_gen_script(
     server: &str,
+    checkpoint: &str,
     prompt: &str,
     width: u32,
     height: u32,
     seed: u64,
     output: &str,
+    sampling: &Sampling,
 ) -> String {
-    // Escape prompt for Python string
-    let prompt_escaped = prompt.replace('\\', "\\\\").replace('"', "\\\"");
+    // Escape prompts for Python string
+    let prompt_escaped = escape_py(prompt);
+    let negative_escaped = escape_py(&sampling.negative_prompt);
+    let Sampling {
+        steps,
+        cfg,
+        sampler,
+        scheduler,
+        ..
+    } = sampling;
+    let checkpoint = escape_py(checkpoint);
+    let sampler = escape_py(sampler);
+    let scheduler = escape_py(scheduler);
 
     format!(
         r#"
@@ -108,6 +418,7 @@ from pathlib import Path
 
 SERVER = "{server}"
 PROMPT = "{prompt_escaped}"
+NEGATIVE = "{negative_escaped}"
 WIDTH = {width}
 HEIGHT = {height}
 SEED = {seed}
@@ -116,18 +427,18 @@ OUTPUT = "{output}"
 workflow = {{
     "prompt": {{
         "1": {{"class_type": "CheckpointLoaderSimple",
-              "inputs": {{"ckpt_name": "flux1-schnell-fp8.safetensors"}}}},
+              "inputs": {{"ckpt_name": "{checkpoint}"}}}},
         "2": {{"class_type": "CLIPTextEncode",
               "inputs": {{"text": PROMPT, "clip": ["1", 1]}}}},
         "3": {{"class_type": "CLIPTextEncode",
-              "inputs": {{"text": "", "clip": ["1", 1]}}}},
+              "inputs": {{"text": NEGATIVE, "clip": ["1", 1]}}}},
         "4": {{"class_type": "EmptyLatentImage",
               "inputs": {{"width": WIDTH, "height": HEIGHT, "batch_size": 1}}}},
         "5": {{"class_type": "KSampler",
               "inputs": {{"model": ["1", 0], "positive": ["2", 0],
                          "negative": ["3", 0], "latent_image": ["4", 0],
-                         "seed": SEED, "steps": 4, "cfg": 1.0,
-                         "sampler_name": "euler", "scheduler": "simple",
+                         "seed": SEED, "steps": {steps}, "cfg": {cfg},
+                         "sampler_name": "{sampler}", "scheduler": "{scheduler}",
                          "denoise": 1.0}}}},
         "6": {{"class_type": "VAEDecode",
               "inputs": {{"samples": ["5", 0], "vae": ["1", 2]}}}},
@@ -177,20 +488,208 @@ print(f"Saved: {{OUTPUT}}")
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::command::fake::FakeRunner;
+    use std::collections::BTreeMap;
+    use vwf_runtime::{DryRunRuntime, MockLlmClient};
+
+    #[test]
+    fn non_png_formats_are_converted_with_quality() {
+        let format: ImageFormat = serde_json::from_value("jpg".into()).unwrap();
+        assert_eq!(format, ImageFormat::Jpeg);
+
+        let dir = std::env::temp_dir().join(format!("vwf_t2i_convert_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("hero.webp");
+        let mut rt = DryRunRuntime::new(&dir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let (mut runner, calls) = FakeRunner::succeeding();
+        runner.writes_output = true;
+        let mut ctx = StepCtx::new(&mut rt, &vars, "hero").with_runner(Box::new(runner));
+        convert_image(
+            &mut ctx,
+            None,
+            Path::new("hero.webp.tmp.png"),
+            &output,
+            ImageFormat::Webp,
+            75,
+        )
+        .unwrap();
+        let calls = calls.borrow();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0, "convert");
+        let partial = dir.join("hero.webp.tmp.webp");
+        assert_eq!(
+            calls[0].1,
+            [
+                "hero.webp.tmp.png".to_string(),
+                "-quality".to_string(),
+                "75".to_string(),
+                format!("webp:{}", partial.display()),
+            ]
+        );
+        assert!(output.is_file());
+        assert!(!partial.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_conversion_keeps_the_previous_image() {
+        let dir = std::env::temp_dir().join(format!("vwf_t2i_failed_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("hero.jpg");
+        std::fs::write(&output, "old").unwrap();
+        let mut rt = DryRunRuntime::new(&dir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let (mut runner, _) = FakeRunner::succeeding();
+        runner.writes_output = true;
+        runner.result.status = 1;
+        let mut ctx = StepCtx::new(&mut rt, &vars, "hero").with_runner(Box::new(runner));
+        let converted = convert_image(
+            &mut ctx,
+            None,
+            Path::new("hero.jpg.tmp.png"),
+            &output,
+            ImageFormat::Jpeg,
+            90,
+        );
+        assert!(converted.is_err());
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "old");
+        assert!(!dir.join("hero.jpg.tmp.jpg").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// A second identical step is served from the generation cache.
+    #[cfg(unix)]
+    #[test]
+    fn identical_step_reuses_the_cached_image() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let workdir = std::env::temp_dir().join(format!("vwf_t2i_cache_{}", std::process::id()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        // Stands in for python3: writes a valid PNG to the script's OUTPUT
+        // and logs each call.
+        let generator = workdir.join("fake-python");
+        let calls = workdir.join("calls.log");
+        std::fs::write(
+            &generator,
+            format!(
+                "#!/bin/sh\n\
+                 out=$(printf '%s\\n' \"$2\" | sed -n 's/^OUTPUT = \"\\(.*\\)\"$/\\1/p')\n\
+                 echo call >> {calls}\n\
+                 {{ printf '\\211PNG\\r\\n\\032\\n'; head -c 5000 /dev/zero; \
+                 printf 'IEND\\256B`\\202'; }} > \"$out\"\n",
+                calls = calls.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&generator, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut rt = vwf_runtime::FsRuntime::new(&workdir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::from([(
+            crate::CACHE_VAR.to_string(),
+            workdir.join("cache").to_string_lossy().to_string(),
+        )]);
+        for out in ["first.png", "second.png"] {
+            let payload = serde_json::json!({
+                "prompt": "a lighthouse at dusk",
+                "output_path": out,
+                "seed": 7,
+                "python_path": generator.to_string_lossy(),
+            });
+            let mut ctx = StepCtx::new(&mut rt, &vars, out);
+            execute(&mut ctx, &payload).unwrap();
+        }
+        assert_eq!(std::fs::read_to_string(&calls).unwrap(), "call\n");
+        assert_eq!(
+            std::fs::read(workdir.join("first.png")).unwrap(),
+            std::fs::read(workdir.join("second.png")).unwrap()
+        );
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
 
     #[test]
     fn generates_image_script() {
         let script = image_gen_script(
             "http://localhost:8570",
+            &default_checkpoint(),
             "A test prompt",
             720,
             1280,
             42,
             "/tmp/out.png",
+            &Sampling::default(),
         );
         assert!(script.contains("flux1-schnell-fp8.safetensors"));
         assert!(script.contains("A test prompt"));
         assert!(script.contains("720"));
         assert!(script.contains("1280"));
     }
+
+    #[test]
+    fn sampler_overrides_reach_workflow() {
+        let sampling = Sampling {
+            negative_prompt: "blurry, \"text\"".to_string(),
+            steps: 20,
+            cfg: 3.5,
+            sampler: "dpmpp_2m".to_string(),
+            scheduler: "karras".to_string(),
+        };
+        let script = image_gen_script(
+            "http://x",
+            &default_checkpoint(),
+            "p",
+            720,
+            1280,
+            1,
+            "/o.png",
+            &sampling,
+        );
+        assert!(script.contains(r#"NEGATIVE = "blurry, \"text\"""#));
+        assert!(script.contains(r#""steps": 20, "cfg": 3.5"#));
+        assert!(script.contains(r#""sampler_name": "dpmpp_2m", "scheduler": "karras""#));
+
+        let quoted = Sampling {
+            sampler: r#"euler", "x": "y"#.to_string(),
+            ..sampling
+        };
+        let script = image_gen_script(
+            "http://x",
+            &default_checkpoint(),
+            "p",
+            720,
+            1280,
+            1,
+            "/o.png",
+            &quoted,
+        );
+        assert!(script.contains(r#""sampler_name": "euler\", \"x\": \"y","#));
+    }
+
+    #[test]
+    fn checkpoint_override_reaches_workflow() {
+        let script = image_gen_script(
+            "http://x",
+            "flux1-dev-fp8.safetensors",
+            "p",
+            720,
+            1280,
+            1,
+            "/o.png",
+            &Sampling::default(),
+        );
+        assert!(script.contains(r#""ckpt_name": "flux1-dev-fp8.safetensors""#));
+        assert!(!script.contains("flux1-schnell"));
+
+        let script = image_gen_script(
+            "http://x",
+            r#"a"b.safetensors"#,
+            "p",
+            720,
+            1280,
+            1,
+            "/o.png",
+            &Sampling::default(),
+        );
+        assert!(script.contains(r#""ckpt_name": "a\"b.safetensors""#));
+    }
 }
@@ -1,13 +1,20 @@
 //! Handler for text_to_video step kind.
 //!
 //! Generates video from a text prompt using Wan 2.2 via ComfyUI REST API.
+//! With `init_image`, the clip starts from that frame instead of noise.
 
 use anyhow::{Context, Result};
 use serde::Deserialize;
 use serde_json::Value;
+use std::path::Path;
 use std::process::Command;
 
 use super::context::StepCtx;
+use crate::cache;
+use crate::catalog::{FieldDoc, StepDoc};
+use crate::hash::sha256_file;
+use crate::python::escape_py;
+use crate::regenerate;
 
 #[derive(Deserialize)]
 struct Payload {
@@ -29,11 +36,107 @@ struct Payload {
     cfg: f32,
     /// Random seed for reproducibility
     seed: Option<u64>,
+    /// Image to use as the first frame (e.g. the preceding slide)
+    #[serde(default)]
+    init_image: Option<String>,
     /// ComfyUI server URL
     #[serde(default = "default_server")]
     server: String,
     /// Python interpreter path (default: python3)
     python_path: Option<String>,
+    /// Diffusion model file (default: wan2.2_ti2v_5B_fp16.safetensors)
+    #[serde(default = "default_unet")]
+    model: String,
+    /// Text encoder file (default: umt5_xxl_fp8_e4m3fn_scaled.safetensors)
+    #[serde(default = "default_clip")]
+    clip_model: String,
+    /// VAE file (default: wan2.2_vae.safetensors)
+    #[serde(default = "default_vae")]
+    vae_model: String,
+    /// Regenerate with the next seed this many times if the video is corrupt
+    #[serde(default = "regenerate::default_validation_retries")]
+    validation_retries: u32,
+}
+
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "text_to_video",
+    summary: "Generate a video clip from text with Wan 2.2 via ComfyUI",
+    fields: &[
+        FieldDoc::required("prompt", "string", "Text prompt"),
+        FieldDoc::required("output_path", "string", "Output video path (.mp4)"),
+        FieldDoc::defaulted(
+            "orientation",
+            "string",
+            || default_orientation().into(),
+            "portrait, landscape or square",
+        ),
+        FieldDoc::defaulted(
+            "length",
+            "integer",
+            || default_length().into(),
+            "Number of frames (81 is about 5s at 16fps)",
+        ),
+        FieldDoc::defaulted(
+            "steps",
+            "integer",
+            || default_steps().into(),
+            "Sampling steps",
+        ),
+        FieldDoc::defaulted("cfg", "number", || default_cfg().into(), "CFG scale"),
+        FieldDoc::optional("seed", "integer", "Random seed for reproducibility"),
+        FieldDoc::optional(
+            "init_image",
+            "string",
+            "Start the video from this image instead of noise",
+        ),
+        FieldDoc::defaulted(
+            "server",
+            "string",
+            || default_server().into(),
+            "ComfyUI server URL",
+        ),
+        FieldDoc::optional(
+            "python_path",
+            "string",
+            "Python interpreter (python3 if unset)",
+        ),
+        FieldDoc::defaulted(
+            "model",
+            "string",
+            || default_unet().into(),
+            "Diffusion model file",
+        ),
+        FieldDoc::defaulted(
+            "clip_model",
+            "string",
+            || default_clip().into(),
+            "Text encoder file",
+        ),
+        FieldDoc::defaulted("vae_model", "string", || default_vae().into(), "VAE file"),
+        FieldDoc::defaulted(
+            "validation_retries",
+            "integer",
+            || regenerate::DEFAULT_VALIDATION_RETRIES.into(),
+            "Regenerate with the next seed this many times if the video comes back corrupt",
+        ),
+    ],
+};
+
+/// Model files loaded by the Wan workflow.
+struct WanModels {
+    unet: String,
+    clip: String,
+    vae: String,
+}
+
+impl Default for WanModels {
+    fn default() -> Self {
+        Self {
+            unet: default_unet(),
+            clip: default_clip(),
+            vae: default_vae(),
+        }
+    }
 }
 
 fn default_orientation() -> String {
@@ -51,6 +154,15 @@ fn default_cfg() -> f32 {
 fn default_server() -> String {
     "http://192.168.1.64:6000".to_string()
 }
+fn default_unet() -> String {
+    "wan2.2_ti2v_5B_fp16.safetensors".to_string()
+}
+fn default_clip() -> String {
+    "umt5_xxl_fp8_e4m3fn_scaled.safetensors".to_string()
+}
+fn default_vae() -> String {
+    "wan2.2_vae.safetensors".to_string()
+}
 
 pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
     let p: Payload = serde_json::from_value(payload.clone())
@@ -58,8 +170,14 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
 
     let prompt = ctx.render(&p.prompt)?;
     let output_path = ctx.render(&p.output_path)?;
+    let output_path = ctx.output_path(&output_path)?.to_string_lossy().to_string();
     let server = ctx.render(&p.server)?;
     let orientation = ctx.render(&p.orientation)?;
+    let models = WanModels {
+        unet: ctx.render(&p.model)?,
+        clip: ctx.render(&p.clip_model)?,
+        vae: ctx.render(&p.vae_model)?,
+    };
 
     // Resolution presets (latent dimensions - output is 2x)
     let (width, height) = match orientation.as_str() {
@@ -68,7 +186,15 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
         _ => (832, 480),          // Output: 1664x960 (landscape)
     };
 
-    let seed = p.seed.unwrap_or_else(rand::random);
+    let seed = ctx.seed(p.seed);
+    let init_image = match &p.init_image {
+        Some(image) => Some(
+            ctx.resolve(&ctx.render(image)?)
+                .to_string_lossy()
+                .to_string(),
+        ),
+        None => None,
+    };
     let python = p
         .python_path
         .as_ref()
@@ -76,37 +202,70 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
         .transpose()?
         .unwrap_or_else(|| "python3".to_string());
 
-    let script = video_gen_script(
-        &server,
-        &prompt,
-        &output_path,
-        width,
-        height,
-        p.length,
-        p.steps,
-        p.cfg,
-        seed,
-    );
-
-    let status = Command::new(&python)
-        .args(["-c", &script])
-        .status()
-        .with_context(|| ctx.error_context("spawn text_to_video python"))?;
-
-    if !status.success() {
-        anyhow::bail!(
-            "Video generation failed with exit code: {:?}",
-            status.code()
+    let generate = |ctx: &mut StepCtx<'_>, seed: u64| {
+        let script = video_gen_script(
+            &server,
+            &models,
+            &prompt,
+            init_image.as_deref(),
+            &output_path,
+            width,
+            height,
+            p.length,
+            p.steps,
+            p.cfg,
+            seed,
         );
-    }
 
-    Ok(())
+        let status = Command::new(&python)
+            .args(["-c", &script])
+            .status()
+            .with_context(|| ctx.error_context("spawn text_to_video python"))?;
+
+        if !status.success() {
+            anyhow::bail!(
+                "Video generation failed with exit code: {:?}",
+                status.code()
+            );
+        }
+        Ok(())
+    };
+    let key = || {
+        let init_image_sha256 = init_image
+            .as_deref()
+            .map(|image| sha256_file(Path::new(image)))
+            .transpose()?;
+        Ok(serde_json::json!({
+            "kind": "text_to_video",
+            "prompt": prompt,
+            "init_image_sha256": init_image_sha256,
+            "seed": seed,
+            "width": width,
+            "height": height,
+            "length": p.length,
+            "models": [&models.unet, &models.clip, &models.vae],
+            "steps": p.steps,
+            "cfg": p.cfg,
+        }))
+    };
+    cache::cached(ctx, key, Path::new(&output_path), |ctx| {
+        regenerate::until_valid(
+            ctx,
+            Path::new(&output_path),
+            seed,
+            p.validation_retries,
+            generate,
+            regenerate::check_video,
+        )
+    })
 }
 
 #[allow(clippy::too_many_arguments)]
 fn video_gen_script(
     server: &str,
+    models: &WanModels,
     prompt: &str,
+    init_image: Option<&str>,
     output: &str,
     width: u32,
     height: u32,
@@ -115,8 +274,12 @@ fn video_gen_script(
     cfg: f32,
     seed: u64,
 ) -> String {
-    let prompt_escaped = prompt.replace('\\', "\\\\").replace('"', "\\\"");
+    let prompt_escaped = escape_py(prompt);
     let negative = "blurry, low quality, distorted, watermark, text, deformed";
+    let unet = escape_py(&models.unet);
+    let clip = escape_py(&models.clip);
+    let vae = escape_py(&models.vae);
+    let init_block = init_image.map(start_frame_block).unwrap_or_default();
 
     format!(
         r#"
@@ -139,13 +302,13 @@ SEED = {seed}
 workflow = {{
     "prompt": {{
         "1": {{"class_type": "UNETLoader",
-              "inputs": {{"unet_name": "wan2.2_ti2v_5B_fp16.safetensors",
+              "inputs": {{"unet_name": "{unet}",
                          "weight_dtype": "fp8_e4m3fn"}}}},
         "2": {{"class_type": "CLIPLoader",
-              "inputs": {{"clip_name": "umt5_xxl_fp8_e4m3fn_scaled.safetensors",
+              "inputs": {{"clip_name": "{clip}",
                          "type": "wan"}}}},
         "3": {{"class_type": "VAELoader",
-              "inputs": {{"vae_name": "wan2.2_vae.safetensors"}}}},
+              "inputs": {{"vae_name": "{vae}"}}}},
         "4": {{"class_type": "CLIPTextEncode",
               "inputs": {{"text": PROMPT, "clip": ["2", 0]}}}},
         "5": {{"class_type": "CLIPTextEncode",
@@ -172,7 +335,7 @@ workflow = {{
                   "save_output": True}}}},
     }}
 }}
-
+{init_block}
 print(f"Submitting to {{SERVER}}...")
 print(f"  Prompt: {{PROMPT[:60]}}{{'...' if len(PROMPT) > 60 else ''}}")
 print(f"  Latent: {{WIDTH}}x{{HEIGHT}} -> Output: {{WIDTH*2}}x{{HEIGHT*2}}")
@@ -221,3 +384,81 @@ print(f"Saved: {{OUTPUT}}")
 "#
     )
 }
+
+/// Python that uploads `image` and makes it the first frame of node 6.
+fn start_frame_block(image: &str) -> String {
+    let image = escape_py(image);
+    format!(
+        r#"
+INIT_IMAGE = "{image}"
+print(f"Uploading start frame: {{INIT_IMAGE}}")
+with open(INIT_IMAGE, "rb") as f:
+    r = requests.post(f"{{SERVER}}/upload/image",
+        files={{"image": (Path(INIT_IMAGE).name, f)}},
+        data={{"subfolder": "", "type": "input"}})
+r.raise_for_status()
+image_name = r.json()["name"]
+print(f"  Uploaded as: {{image_name}}")
+workflow["prompt"]["10"] = {{"class_type": "LoadImage",
+                            "inputs": {{"image": image_name}}}}
+workflow["prompt"]["6"]["inputs"]["start_image"] = ["10", 0]
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn model_overrides_reach_workflow() {
+        let models = WanModels {
+            unet: "wan2.2_t2v_14B_fp8.safetensors".to_string(),
+            ..Default::default()
+        };
+        let script = video_gen_script(
+            "http://x", &models, "p", None, "/o.mp4", 832, 480, 81, 20, 5.0, 1,
+        );
+        assert!(script.contains(r#""unet_name": "wan2.2_t2v_14B_fp8.safetensors""#));
+        assert!(script.contains(r#""vae_name": "wan2.2_vae.safetensors""#));
+
+        let models = WanModels {
+            clip: r#"umt5"x.safetensors"#.to_string(),
+            ..Default::default()
+        };
+        let script = video_gen_script(
+            "http://x", &models, "p", None, "/o.mp4", 832, 480, 81, 20, 5.0, 1,
+        );
+        assert!(script.contains(r#""clip_name": "umt5\"x.safetensors""#));
+    }
+
+    #[test]
+    fn init_image_is_uploaded_and_wired_as_start_frame() {
+        let models = WanModels::default();
+        let script = video_gen_script(
+            "http://x",
+            &models,
+            "p",
+            Some("work/slides/title.png"),
+            "/o.mp4",
+            832,
+            480,
+            81,
+            20,
+            5.0,
+            1,
+        );
+        assert!(script.contains(r#"INIT_IMAGE = "work/slides/title.png""#));
+        assert!(script.contains("/upload/image"));
+        assert!(script.contains(r#"["inputs"]["start_image"] = ["10", 0]"#));
+
+        let text_only = video_gen_script(
+            "http://x", &models, "p", None, "/o.mp4", 832, 480, 81, 20, 5.0, 1,
+        );
+        assert!(!text_only.contains("upload"));
+        assert!(!text_only.contains("start_image"));
+
+        let quoted = start_frame_block(r#"slides\"title".png"#);
+        assert!(quoted.contains(r#"INIT_IMAGE = "slides\\\"title\".png""#));
+    }
+}
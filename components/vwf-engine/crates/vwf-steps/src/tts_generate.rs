@@ -2,12 +2,14 @@
 //!
 //! Generates TTS audio using VoxCPM voice cloning via Gradio API.
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use serde::Deserialize;
 use serde_json::Value;
 use std::process::Command;
 
 use super::context::StepCtx;
+use crate::catalog::{FieldDoc, StepDoc};
+use crate::ffmpeg;
 
 #[derive(Deserialize)]
 struct Payload {
@@ -25,8 +27,61 @@ struct Payload {
     /// Python interpreter path (default: python3)
     #[serde(default = "default_python")]
     python_path: String,
+    /// Reject output whose mean volume is below this (default: -50 dB)
+    #[serde(default = "default_min_volume_db")]
+    min_volume_db: f64,
+    /// Speaking rate used to estimate duration; enables the duration check
+    #[serde(default)]
+    expected_wpm: Option<f64>,
+    /// Allowed relative deviation from the estimated duration (default: 0.5)
+    #[serde(default = "default_duration_tolerance")]
+    duration_tolerance: f64,
 }
 
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "tts_generate",
+    summary: "Synthesize narration with VoxCPM voice cloning",
+    fields: &[
+        FieldDoc::required("script_path", "string", "Script text file"),
+        FieldDoc::required("output_path", "string", "Output audio path (.wav)"),
+        FieldDoc::defaulted(
+            "server",
+            "string",
+            || default_server().into(),
+            "VoxCPM server URL",
+        ),
+        FieldDoc::required("reference_audio", "string", "Reference voice WAV"),
+        FieldDoc::required(
+            "reference_text",
+            "string",
+            "Transcript of the reference audio",
+        ),
+        FieldDoc::defaulted(
+            "python_path",
+            "string",
+            || default_python().into(),
+            "Python interpreter",
+        ),
+        FieldDoc::defaulted(
+            "min_volume_db",
+            "number",
+            || default_min_volume_db().into(),
+            "Reject output quieter than this mean volume",
+        ),
+        FieldDoc::optional(
+            "expected_wpm",
+            "number",
+            "Speaking rate; enables the duration check",
+        ),
+        FieldDoc::defaulted(
+            "duration_tolerance",
+            "number",
+            || default_duration_tolerance().into(),
+            "Allowed relative deviation from the estimated duration",
+        ),
+    ],
+};
+
 fn default_server() -> String {
     "http://curiosity:7860".to_string()
 }
@@ -35,6 +90,14 @@ fn default_python() -> String {
     "python3".to_string()
 }
 
+fn default_min_volume_db() -> f64 {
+    -50.0
+}
+
+fn default_duration_tolerance() -> f64 {
+    0.5
+}
+
 pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
     let p: Payload = serde_json::from_value(payload.clone())
         .with_context(|| ctx.error_context("payload decode tts_generate"))?;
@@ -42,7 +105,8 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
     let script_path = ctx.render(&p.script_path)?;
     let output_path = ctx.render(&p.output_path)?;
     let server = ctx.render(&p.server)?;
-    let ref_audio = ctx.render(&p.reference_audio)?;
+    let ref_audio = ctx.resolve(&ctx.render(&p.reference_audio)?);
+    let ref_audio = ref_audio.to_string_lossy();
     let ref_text = ctx.render(&p.reference_text)?;
 
     // Read script text
@@ -50,15 +114,7 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
     let python_path = ctx.render(&p.python_path)?;
 
     // Make output path absolute (relative paths are relative to workdir)
-    let abs_output = if output_path.starts_with('/') {
-        output_path.clone()
-    } else {
-        ctx.rt
-            .workdir()
-            .join(&output_path)
-            .to_string_lossy()
-            .to_string()
-    };
+    let abs_output = ctx.output_path(&output_path)?.to_string_lossy().to_string();
 
     // Call TTS via Python gradio_client
     let status = Command::new(&python_path)
@@ -73,6 +129,50 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
         anyhow::bail!("TTS generation failed with exit code: {:?}", status.code());
     }
 
+    // Servers sometimes hand back silent or truncated audio; move it aside
+    // so --resume doesn't treat it as done.
+    if let Err(e) = validate_output(&abs_output, &script_text, &p) {
+        let rejected = format!("{abs_output}.rejected");
+        let _ = std::fs::rename(&abs_output, &rejected);
+        return Err(e).with_context(|| {
+            ctx.error_context(&format!("rejected TTS output (kept at {rejected})"))
+        });
+    }
+
+    Ok(())
+}
+
+fn validate_output(path: &str, script: &str, p: &Payload) -> Result<()> {
+    check_not_silent(ffmpeg::mean_volume_db(path)?, p.min_volume_db)?;
+    if let Some(wpm) = p.expected_wpm {
+        let words = script.split_whitespace().count();
+        check_duration(
+            ffmpeg::duration_secs(path)?,
+            words,
+            wpm,
+            p.duration_tolerance,
+        )?;
+    }
+    Ok(())
+}
+
+fn check_not_silent(mean_db: f64, floor_db: f64) -> Result<()> {
+    if mean_db < floor_db {
+        bail!("audio is silent: mean volume {mean_db:.1} dB is below {floor_db:.1} dB");
+    }
+    Ok(())
+}
+
+/// Compare against `words / wpm` minutes, allowing `tolerance` either way.
+fn check_duration(actual_secs: f64, words: usize, wpm: f64, tolerance: f64) -> Result<()> {
+    let expected = words as f64 / wpm * 60.0;
+    let (min, max) = (expected * (1.0 - tolerance), expected * (1.0 + tolerance));
+    if actual_secs < min || actual_secs > max {
+        bail!(
+            "audio is {actual_secs:.1}s but {words} words at {wpm} wpm should take \
+             {min:.1}-{max:.1}s (possibly truncated)"
+        );
+    }
     Ok(())
 }
 
@@ -116,4 +216,22 @@ mod tests {
         assert!(script.contains("Client(\"http://localhost:7860\")"));
         assert!(script.contains("text_input=\"\"\"world\"\"\""));
     }
+
+    #[test]
+    fn silence_threshold() {
+        let floor = default_min_volume_db();
+        assert!(check_not_silent(-25.0, floor).is_ok());
+        assert!(check_not_silent(-50.0, floor).is_ok());
+        let err = check_not_silent(-91.0, floor).unwrap_err().to_string();
+        assert!(err.contains("silent"), "{err}");
+    }
+
+    #[test]
+    fn duration_tolerance_window() {
+        // 150 words at 150 wpm = 60s, +/-50% = 30-90s
+        assert!(check_duration(60.0, 150, 150.0, 0.5).is_ok());
+        assert!(check_duration(31.0, 150, 150.0, 0.5).is_ok());
+        assert!(check_duration(12.0, 150, 150.0, 0.5).is_err());
+        assert!(check_duration(95.0, 150, 150.0, 0.5).is_err());
+    }
 }
@@ -1,14 +1,37 @@
 //! Concatenate multiple video clips into a single video.
 //!
 //! Uses ffmpeg's concat demuxer for efficient lossless concatenation
-//! when clips have matching codecs and parameters.
+//! when clips have matching codecs and parameters. In `auto` mode (the
+//! default) the clips are probed first and re-encoded only when their
+//! stream parameters differ.
+//!
+//! With `chapters`, each clip's duration is probed and an ffmetadata
+//! chapters file is muxed into the output so players can jump between
+//! segments.
 
 use anyhow::{Context, Result, bail};
 use serde::Deserialize;
 use std::io::Write;
-use std::process::Command;
+use std::path::Path;
 
+use crate::catalog::{FieldDoc, StepDoc};
 use crate::context::StepCtx;
+use crate::ffmpeg;
+use crate::temp::TempPath;
+
+/// Audio rate every clip is resampled to when `target_fps` is set.
+const SYNC_SAMPLE_RATE: u32 = 48000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ConcatMode {
+    /// Stream copy; fast and lossless, requires matching parameters
+    Copy,
+    /// Re-encode to H.264/AAC; works with mismatched clips
+    Reencode,
+    /// Probe clips and copy if they match, else re-encode
+    Auto,
+}
 
 #[derive(Deserialize)]
 struct Payload {
@@ -16,11 +39,55 @@ struct Payload {
     clips: Vec<String>,
     /// Output path for the concatenated video
     output_path: String,
-    /// Whether to re-encode (default: false for copy mode)
+    /// Shorthand for `mode: reencode` when true
     #[serde(default)]
     reencode: bool,
+    /// Copy, re-encode, or decide by probing (default: auto)
+    #[serde(default)]
+    mode: Option<ConcatMode>,
+    /// Force this constant frame rate and a common audio rate (re-encode only)
+    #[serde(default)]
+    target_fps: Option<u32>,
+    /// Add a chapter per segment
+    #[serde(default)]
+    chapters: bool,
+    /// Chapter title for each clip, filled in from `segments` by the engine
+    #[serde(default)]
+    chapter_titles: Vec<String>,
 }
 
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "video_concat",
+    summary: "Concatenate video clips with ffmpeg",
+    fields: &[
+        FieldDoc::required("clips", "array[string]", "Clips to join, in order"),
+        FieldDoc::required("output_path", "string", "Output video path"),
+        FieldDoc::defaulted(
+            "reencode",
+            "boolean",
+            || false.into(),
+            "Shorthand for mode: reencode",
+        ),
+        FieldDoc::defaulted(
+            "mode",
+            "string",
+            || "auto".into(),
+            "copy, reencode, or auto (probe clips and copy only if their parameters match)",
+        ),
+        FieldDoc::optional(
+            "target_fps",
+            "integer",
+            "Re-encode at this constant frame rate, resampling audio to 48 kHz",
+        ),
+        FieldDoc::defaulted(
+            "chapters",
+            "boolean",
+            || false.into(),
+            "Add a chapter per segment, titled by the segment of the step that writes each clip",
+        ),
+    ],
+};
+
 pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()> {
     let p: Payload = serde_json::from_value(payload.clone())
         .with_context(|| ctx.error_context("payload decode video_concat"))?;
@@ -39,23 +106,11 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
     let output_path = ctx.render(&p.output_path)?;
 
     // Resolve paths (relative to workdir)
-    let workdir = ctx.rt.workdir();
     let resolved_clips: Vec<String> = clips
         .iter()
-        .map(|c| {
-            if c.starts_with('/') {
-                c.clone()
-            } else {
-                workdir.join(c).to_string_lossy().to_string()
-            }
-        })
+        .map(|c| ctx.resolve(c).to_string_lossy().to_string())
         .collect();
-
-    let resolved_output = if output_path.starts_with('/') {
-        output_path.clone()
-    } else {
-        workdir.join(&output_path).to_string_lossy().to_string()
-    };
+    let resolved_output = ctx.output_path(&output_path)?.to_string_lossy().to_string();
 
     // Verify all input clips exist
     for clip in &resolved_clips {
@@ -70,8 +125,54 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
         resolved_output
     );
 
+    let chapters = if !p.chapters {
+        None
+    } else {
+        if p.chapter_titles.len() != resolved_clips.len() {
+            bail!(
+                "video_concat has {} chapter titles for {} clips; chapters are titled from the workflow's segments",
+                p.chapter_titles.len(),
+                resolved_clips.len()
+            );
+        }
+        let titles = p
+            .chapter_titles
+            .iter()
+            .map(|t| ctx.render(t))
+            .collect::<Result<Vec<_>>>()?;
+        let timed = probe_chapters(ctx, titles, &resolved_clips)?;
+        println!("  {} chapters", merge_chapters(&timed).len());
+        Some(write_chapter_file(&timed, ctx.step_id)?)
+    };
+
+    let mode = match p.mode {
+        Some(mode) => mode,
+        None if p.reencode => ConcatMode::Reencode,
+        None => ConcatMode::Auto,
+    };
+    let reencode = match mode {
+        ConcatMode::Copy if p.target_fps.is_some() => {
+            bail!("video_concat target_fps requires re-encoding; use mode: reencode or auto")
+        }
+        ConcatMode::Copy => false,
+        ConcatMode::Reencode => true,
+        ConcatMode::Auto if p.target_fps.is_some() => {
+            ctx.warn("auto mode: re-encoding (target_fps set)");
+            true
+        }
+        ConcatMode::Auto => {
+            let (reencode, reason) = choose_reencode(ctx, &resolved_clips);
+            if reencode {
+                ctx.warn(format!("auto mode: re-encoding ({reason})"));
+            } else {
+                println!("  auto mode: stream copy ({reason})");
+            }
+            reencode
+        }
+    };
+
     // Create concat list file for ffmpeg
-    let concat_list = create_concat_list(&resolved_clips)?;
+    let concat_list = create_concat_list(&resolved_clips, ctx.step_id)?;
 
     // Ensure output directory exists
     if let Some(parent) = std::path::Path::new(&resolved_output).parent() {
@@ -87,10 +188,20 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
         "-safe".to_string(),
         "0".to_string(),
         "-i".to_string(),
-        concat_list.clone(),
+        concat_list.path().to_string_lossy().to_string(),
     ];
+    if let Some(chapters) = &chapters {
+        args.extend([
+            "-i".to_string(),
+            chapters.path().to_string_lossy().to_string(),
+            "-map".to_string(),
+            "0".to_string(),
+            "-map_chapters".to_string(),
+            "1".to_string(),
+        ]);
+    }
 
-    if p.reencode {
+    if reencode {
         // Re-encode mode: useful when clips have different parameters
         args.extend([
             "-c:v".to_string(),
@@ -104,34 +215,149 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
             "-b:a".to_string(),
             "192k".to_string(),
         ]);
+        if let Some(fps) = p.target_fps {
+            args.extend(sync_args(fps));
+        }
     } else {
         // Copy mode: fast, lossless (requires matching codecs)
         args.extend(["-c".to_string(), "copy".to_string()]);
     }
 
-    args.push(resolved_output.clone());
+    // Write beside the output so a failed concat neither leaves a
+    // half-written file for --resume to trust nor destroys the last good one
+    let temp = TempPath::beside(Path::new(&resolved_output));
+    args.push(temp.path().to_string_lossy().to_string());
+    ffmpeg::run_ffmpeg_with(ctx.runner.as_mut(), &args)
+        .with_context(|| ctx.error_context("ffmpeg concat"))?;
+    temp.persist(Path::new(&resolved_output))
+        .with_context(|| format!("Failed to replace {resolved_output}"))?;
 
-    let status = Command::new("ffmpeg")
-        .args(&args)
-        .status()
-        .with_context(|| ctx.error_context("spawn ffmpeg concat"))?;
+    println!("  Created: {}", resolved_output);
+    Ok(())
+}
 
-    // Clean up temp concat list
-    let _ = std::fs::remove_file(&concat_list);
+/// Constant frame rate and resampled audio so mixed sources stay in sync.
+///
+/// Stills-based clips and variable-rate generated clips otherwise drift
+/// apart once their timestamps are stitched together.
+fn sync_args(fps: u32) -> Vec<String> {
+    vec![
+        "-vf".to_string(),
+        format!("fps={fps}"),
+        "-r".to_string(),
+        fps.to_string(),
+        "-af".to_string(),
+        "aresample=async=1".to_string(),
+        "-ar".to_string(),
+        SYNC_SAMPLE_RATE.to_string(),
+    ]
+}
 
-    if !status.success() {
-        bail!("ffmpeg concat failed with exit code: {:?}", status.code());
+/// Probe every clip and decide whether the concat needs a re-encode.
+///
+/// Returns the decision and the reason for logging. A clip that can't be
+/// probed forces a re-encode, which is slower but never produces a broken file.
+fn choose_reencode(ctx: &mut StepCtx<'_>, clips: &[String]) -> (bool, String) {
+    let mut probed = Vec::with_capacity(clips.len());
+    for clip in clips {
+        match ffmpeg::stream_params_with(ctx.runner.as_mut(), clip) {
+            Ok(params) => probed.push((clip.as_str(), params)),
+            Err(e) => return (true, format!("could not probe {clip}: {e:#}")),
+        }
     }
+    decide(&probed)
+}
 
-    println!("  Created: {}", resolved_output);
-    Ok(())
+/// Re-encode unless every clip's stream parameters match the first clip's.
+fn decide(probed: &[(&str, ffmpeg::StreamParams)]) -> (bool, String) {
+    let Some(((first, reference), rest)) = probed.split_first() else {
+        return (false, "no clips".to_string());
+    };
+    for (clip, params) in rest {
+        let diffs = reference.differences(params);
+        if !diffs.is_empty() {
+            return (
+                true,
+                format!("{clip} differs from {first}: {}", diffs.join(", ")),
+            );
+        }
+    }
+    (false, format!("all {} clips match", probed.len()))
+}
+
+/// Pair each chapter title with its clip's probed duration in seconds.
+fn probe_chapters(
+    ctx: &mut StepCtx<'_>,
+    titles: Vec<String>,
+    clips: &[String],
+) -> Result<Vec<(String, f64)>> {
+    let mut timed = Vec::with_capacity(clips.len());
+    for (title, clip) in titles.into_iter().zip(clips) {
+        let info = ffmpeg::media_info_with(ctx.runner.as_mut(), clip)
+            .with_context(|| ctx.error_context(&format!("probe {clip} for chapters")))?;
+        let Some(duration) = info.duration else {
+            bail!(ctx.error_context(&format!("ffprobe reported no duration for {clip}")));
+        };
+        timed.push((title, duration));
+    }
+    Ok(timed)
+}
+
+/// Merge adjacent clips with the same title into `(title, start_ms, end_ms)`.
+fn merge_chapters(timed: &[(String, f64)]) -> Vec<(&str, u64, u64)> {
+    let mut chapters: Vec<(&str, u64, u64)> = Vec::new();
+    let mut elapsed = 0.0;
+    for (title, duration) in timed {
+        let start = (elapsed * 1000.0_f64).round() as u64;
+        elapsed += duration;
+        let end = (elapsed * 1000.0_f64).round() as u64;
+        match chapters.last_mut() {
+            Some(last) if last.0 == title => last.2 = end,
+            _ => chapters.push((title, start, end)),
+        }
+    }
+    chapters
+}
+
+/// Render `(title, duration)` pairs as an ffmetadata chapters file.
+fn chapter_metadata(timed: &[(String, f64)]) -> String {
+    let mut out = String::from(";FFMETADATA1\n");
+    for (title, start, end) in merge_chapters(timed) {
+        out.push_str(&format!(
+            "[CHAPTER]\nTIMEBASE=1/1000\nSTART={start}\nEND={end}\ntitle={}\n",
+            escape_metadata(title)
+        ));
+    }
+    out
+}
+
+/// Backslash-escape the characters ffmetadata treats specially.
+fn escape_metadata(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Write the chapters file to a temporary path, deleted on drop.
+fn write_chapter_file(timed: &[(String, f64)], step_id: &str) -> Result<TempPath> {
+    let temp_path = TempPath::new("chapters", step_id, ".txt");
+    std::fs::write(temp_path.path(), chapter_metadata(timed))
+        .context("Failed to write chapters file")?;
+    Ok(temp_path)
 }
 
-/// Create a temporary file listing all clips for ffmpeg concat demuxer
-fn create_concat_list(clips: &[String]) -> Result<String> {
-    let temp_path = std::env::temp_dir().join(format!("vwf_concat_{}.txt", std::process::id()));
+/// Create a temporary file listing all clips for ffmpeg concat demuxer.
+///
+/// The file is deleted when the returned path is dropped.
+fn create_concat_list(clips: &[String], step_id: &str) -> Result<TempPath> {
+    let temp_path = TempPath::new("concat", step_id, ".txt");
     let mut file =
-        std::fs::File::create(&temp_path).context("Failed to create concat list file")?;
+        std::fs::File::create(temp_path.path()).context("Failed to create concat list file")?;
 
     for clip in clips {
         // ffmpeg concat format: file 'path'
@@ -140,13 +366,16 @@ fn create_concat_list(clips: &[String]) -> Result<String> {
         writeln!(file, "file '{}'", escaped)?;
     }
 
-    Ok(temp_path.to_string_lossy().to_string())
+    Ok(temp_path)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::command::fake::FakeRunner;
+    use std::collections::BTreeMap;
     use std::io::Read;
+    use vwf_runtime::{DryRunRuntime, MockLlmClient};
 
     #[test]
     fn creates_concat_list() {
@@ -155,9 +384,9 @@ mod tests {
             "/path/to/clip2.mp4".to_string(),
         ];
 
-        let list_path = create_concat_list(&clips).unwrap();
+        let list_path = create_concat_list(&clips, "list").unwrap();
         let mut contents = String::new();
-        std::fs::File::open(&list_path)
+        std::fs::File::open(list_path.path())
             .unwrap()
             .read_to_string(&mut contents)
             .unwrap();
@@ -165,6 +394,272 @@ mod tests {
         assert!(contents.contains("file '/path/to/clip1.mp4'"));
         assert!(contents.contains("file '/path/to/clip2.mp4'"));
 
-        std::fs::remove_file(&list_path).unwrap();
+        let path = list_path.path().to_path_buf();
+        drop(list_path);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn concat_lists_in_one_process_are_distinct() {
+        let clips = vec!["/a.mp4".to_string()];
+        let first = create_concat_list(&clips, "concat").unwrap();
+        let second = create_concat_list(&clips, "concat").unwrap();
+        assert_ne!(first.path(), second.path());
+        assert!(first.path().exists() && second.path().exists());
+    }
+
+    #[test]
+    fn builds_ffmpeg_concat_args() {
+        let workdir = std::env::temp_dir().join(format!("vwf_concat_args_{}", std::process::id()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        std::fs::write(workdir.join("a.mp4"), b"a").unwrap();
+        std::fs::write(workdir.join("b.mp4"), b"b").unwrap();
+
+        let mut rt = DryRunRuntime::new(&workdir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let (mut runner, calls) = FakeRunner::succeeding();
+        runner.writes_output = true;
+        let mut ctx = StepCtx::new(&mut rt, &vars, "concat").with_runner(Box::new(runner));
+        let payload = serde_json::json!({
+            "clips": ["a.mp4", "b.mp4"],
+            "output_path": "out/final.mp4",
+            "reencode": true
+        });
+        execute(&mut ctx, &payload).unwrap();
+
+        let calls = calls.borrow();
+        assert_eq!(calls.len(), 1);
+        let (program, args) = &calls[0];
+        assert_eq!(program, "ffmpeg");
+        let list_prefix = std::env::temp_dir()
+            .join(format!("vwf_concat_{}_concat_", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        assert!(args[6].starts_with(&list_prefix) && args[6].ends_with(".txt"));
+        assert!(!Path::new(&args[6]).exists(), "concat list not cleaned up");
+        let output = workdir.join("out/final.mp4");
+        let temp = workdir.join("out/final.mp4.tmp.mp4");
+        let expected = [
+            "-y",
+            "-f",
+            "concat",
+            "-safe",
+            "0",
+            "-i",
+            &args[6],
+            "-c:v",
+            "libx264",
+            "-preset",
+            "medium",
+            "-crf",
+            "23",
+            "-c:a",
+            "aac",
+            "-b:a",
+            "192k",
+            &temp.to_string_lossy(),
+        ];
+        assert_eq!(args, &expected);
+        assert!(output.exists() && !temp.exists());
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+
+    #[test]
+    fn chapter_file_from_titles_and_durations() {
+        let timed = [
+            ("Intro".to_string(), 4.5),
+            ("Setup; install".to_string(), 10.0),
+            ("Setup; install".to_string(), 2.25),
+            ("Outro".to_string(), 3.0),
+        ];
+        assert_eq!(
+            chapter_metadata(&timed),
+            ";FFMETADATA1\n\
+             [CHAPTER]\nTIMEBASE=1/1000\nSTART=0\nEND=4500\ntitle=Intro\n\
+             [CHAPTER]\nTIMEBASE=1/1000\nSTART=4500\nEND=16750\ntitle=Setup\\; install\n\
+             [CHAPTER]\nTIMEBASE=1/1000\nSTART=16750\nEND=19750\ntitle=Outro\n"
+        );
+    }
+
+    #[test]
+    fn chapters_are_muxed_into_the_output() {
+        let workdir =
+            std::env::temp_dir().join(format!("vwf_concat_chapters_{}", std::process::id()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        std::fs::write(workdir.join("a.mp4"), b"a").unwrap();
+        std::fs::write(workdir.join("b.mp4"), b"b").unwrap();
+
+        let mut rt = DryRunRuntime::new(&workdir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let (mut runner, calls) = FakeRunner::succeeding();
+        runner.result.stdout = r#"{"format": {"duration": "2.0"}}"#.to_string();
+        runner.writes_output = true;
+        let mut ctx = StepCtx::new(&mut rt, &vars, "concat").with_runner(Box::new(runner));
+        let payload = serde_json::json!({
+            "clips": ["a.mp4", "b.mp4"],
+            "output_path": "final.mp4",
+            "mode": "copy",
+            "chapters": true,
+            "chapter_titles": ["Intro", "Main"]
+        });
+        execute(&mut ctx, &payload).unwrap();
+
+        {
+            let calls = calls.borrow();
+            let programs: Vec<&str> = calls.iter().map(|(p, _)| p.as_str()).collect();
+            assert_eq!(programs, ["ffprobe", "ffprobe", "ffmpeg"]);
+            let args = &calls[2].1;
+            assert_eq!(args[7], "-i");
+            assert!(args[8].ends_with(".txt") && !Path::new(&args[8]).exists());
+            assert_eq!(args[9..13], ["-map", "0", "-map_chapters", "1"]);
+        }
+
+        let unresolved = serde_json::json!({
+            "clips": ["a.mp4", "b.mp4"],
+            "output_path": "final.mp4",
+            "chapters": true
+        });
+        let err = execute(&mut ctx, &unresolved).unwrap_err();
+        assert!(err.to_string().contains("workflow's segments"), "{err:#}");
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+
+    #[test]
+    fn failed_ffmpeg_keeps_the_previous_output() {
+        let workdir =
+            std::env::temp_dir().join(format!("vwf_concat_partial_{}", std::process::id()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        std::fs::write(workdir.join("a.mp4"), b"a").unwrap();
+        std::fs::write(workdir.join("final.mp4"), b"last good concat").unwrap();
+
+        let mut rt = DryRunRuntime::new(&workdir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let (mut runner, _) = FakeRunner::succeeding();
+        runner.result.status = 1;
+        runner.writes_output = true;
+        let mut ctx = StepCtx::new(&mut rt, &vars, "concat").with_runner(Box::new(runner));
+        let payload = serde_json::json!({
+            "clips": ["a.mp4"],
+            "output_path": "final.mp4",
+            "mode": "reencode"
+        });
+        assert!(execute(&mut ctx, &payload).is_err());
+        assert_eq!(
+            std::fs::read(workdir.join("final.mp4")).unwrap(),
+            b"last good concat"
+        );
+        assert!(!workdir.join("final.mp4.tmp.mp4").exists());
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+
+    fn params(width: u32, frame_rate: &str) -> ffmpeg::StreamParams {
+        ffmpeg::StreamParams {
+            video_codec: Some("h264".into()),
+            width: Some(width),
+            height: Some(1080),
+            frame_rate: Some(frame_rate.into()),
+            pix_fmt: Some("yuv420p".into()),
+            audio_codec: Some("aac".into()),
+            sample_rate: Some(44100),
+            channels: Some(2),
+        }
+    }
+
+    #[test]
+    fn mismatched_clips_select_reencode() {
+        let probed = [
+            ("title.mp4", params(1920, "30/1")),
+            ("body.mp4", params(1920, "30/1")),
+            ("outro.mp4", params(1280, "25/1")),
+        ];
+        let (reencode, reason) = decide(&probed);
+        assert!(reencode);
+        assert_eq!(
+            reason,
+            "outro.mp4 differs from title.mp4: width 1920 vs 1280, frame rate 30/1 vs 25/1"
+        );
+
+        let (reencode, reason) = decide(&probed[..2]);
+        assert!(!reencode);
+        assert_eq!(reason, "all 2 clips match");
+    }
+
+    #[test]
+    fn auto_mode_copies_matching_clips() {
+        let workdir = std::env::temp_dir().join(format!("vwf_concat_auto_{}", std::process::id()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        std::fs::write(workdir.join("a.mp4"), b"a").unwrap();
+        std::fs::write(workdir.join("b.mp4"), b"b").unwrap();
+
+        let mut rt = DryRunRuntime::new(&workdir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let (mut runner, calls) = FakeRunner::succeeding();
+        runner.result.stdout = r#"{"streams": [{"codec_type": "video", "codec_name": "h264",
+            "width": 1920, "height": 1080, "r_frame_rate": "30/1", "pix_fmt": "yuv420p"}]}"#
+            .to_string();
+        runner.writes_output = true;
+        let mut ctx = StepCtx::new(&mut rt, &vars, "concat").with_runner(Box::new(runner));
+        let payload = serde_json::json!({
+            "clips": ["a.mp4", "b.mp4"],
+            "output_path": "final.mp4"
+        });
+        execute(&mut ctx, &payload).unwrap();
+
+        let calls = calls.borrow();
+        let programs: Vec<&str> = calls.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(programs, ["ffprobe", "ffprobe", "ffmpeg"]);
+        assert_eq!(calls[2].1[7..9], ["-c", "copy"]);
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+
+    #[test]
+    fn target_fps_forces_constant_rate_and_resampled_audio() {
+        assert_eq!(
+            sync_args(30),
+            [
+                "-vf",
+                "fps=30",
+                "-r",
+                "30",
+                "-af",
+                "aresample=async=1",
+                "-ar",
+                "48000"
+            ]
+        );
+
+        let workdir = std::env::temp_dir().join(format!("vwf_concat_fps_{}", std::process::id()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        std::fs::write(workdir.join("a.mp4"), b"a").unwrap();
+
+        let mut rt = DryRunRuntime::new(&workdir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let (mut runner, calls) = FakeRunner::succeeding();
+        runner.writes_output = true;
+        let mut ctx = StepCtx::new(&mut rt, &vars, "concat").with_runner(Box::new(runner));
+        let payload = serde_json::json!({
+            "clips": ["a.mp4"],
+            "output_path": "final.mp4",
+            "target_fps": 24
+        });
+        execute(&mut ctx, &payload).unwrap();
+        {
+            // auto mode skips probing and re-encodes
+            let calls = calls.borrow();
+            assert_eq!(calls.len(), 1);
+            let args = &calls[0].1;
+            assert_eq!(args[7..9], ["-c:v", "libx264"]);
+            assert_eq!(args[args.len() - 9..args.len() - 1], sync_args(24));
+        }
+
+        let copy = serde_json::json!({
+            "clips": ["a.mp4"],
+            "output_path": "final.mp4",
+            "mode": "copy",
+            "target_fps": 24
+        });
+        let err = execute(&mut ctx, &copy).unwrap_err();
+        assert!(err.to_string().contains("requires re-encoding"), "{err:#}");
+        std::fs::remove_dir_all(&workdir).unwrap();
     }
 }
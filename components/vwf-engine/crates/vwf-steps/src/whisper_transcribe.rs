@@ -14,7 +14,9 @@ use anyhow::{Context, Result, bail};
 use serde::Deserialize;
 use std::process::Command;
 
+use crate::catalog::{FieldDoc, StepDoc};
 use crate::context::StepCtx;
+use crate::ffmpeg;
 
 #[derive(Deserialize)]
 struct Payload {
@@ -36,6 +38,39 @@ struct Payload {
     format: String,
 }
 
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "whisper_transcribe",
+    summary: "Transcribe audio with whisper.cpp",
+    fields: &[
+        FieldDoc::required("input_path", "string", "Input audio file"),
+        FieldDoc::required("output_path", "string", "Output transcript path"),
+        FieldDoc::defaulted(
+            "model",
+            "string",
+            || default_model().into(),
+            "Whisper model file (.bin)",
+        ),
+        FieldDoc::defaulted(
+            "whisper_cli",
+            "string",
+            || default_whisper_cli().into(),
+            "Path to whisper-cli",
+        ),
+        FieldDoc::defaulted(
+            "language",
+            "string",
+            || default_language().into(),
+            "Language code",
+        ),
+        FieldDoc::defaulted(
+            "format",
+            "string",
+            || default_format().into(),
+            "txt, srt or vtt",
+        ),
+    ],
+};
+
 fn default_model() -> String {
     "/Users/mike/.whisper-models/ggml-base.en.bin".to_string()
 }
@@ -58,31 +93,15 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
 
     let input_path = ctx.render(&p.input_path)?;
     let output_path = ctx.render(&p.output_path)?;
-    let model = ctx.render(&p.model)?;
+    let model = ctx.resolve(&ctx.render(&p.model)?);
+    let model = model.to_string_lossy().to_string();
     let whisper_cli = ctx.render(&p.whisper_cli)?;
     let language = ctx.render(&p.language)?;
     let format = ctx.render(&p.format)?;
 
     // Resolve paths
-    let input_abs = if input_path.starts_with('/') {
-        input_path.clone()
-    } else {
-        ctx.rt
-            .workdir()
-            .join(&input_path)
-            .to_string_lossy()
-            .to_string()
-    };
-
-    let output_abs = if output_path.starts_with('/') {
-        output_path.clone()
-    } else {
-        ctx.rt
-            .workdir()
-            .join(&output_path)
-            .to_string_lossy()
-            .to_string()
-    };
+    let input_abs = ctx.resolve(&input_path).to_string_lossy().to_string();
+    let output_abs = ctx.output_path(&output_path)?.to_string_lossy().to_string();
 
     // Check input exists
     if !std::path::Path::new(&input_abs).exists() {
@@ -100,26 +119,19 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
 
     // Convert to 16kHz mono WAV (whisper requirement)
     let temp_wav = format!("{}.whisper_temp.wav", input_abs);
-    let convert_status = Command::new("ffmpeg")
-        .args([
-            "-y",
-            "-i",
-            &input_abs,
-            "-ar",
-            "16000",
-            "-ac",
-            "1",
-            "-c:a",
-            "pcm_s16le",
-            &temp_wav,
-        ])
-        .output()
-        .with_context(|| ctx.error_context("ffmpeg convert for whisper"))?;
-
-    if !convert_status.status.success() {
-        let stderr = String::from_utf8_lossy(&convert_status.stderr);
-        bail!("Failed to convert audio for whisper: {}", stderr);
-    }
+    ffmpeg::run_ffmpeg(&[
+        "-y",
+        "-i",
+        &input_abs,
+        "-ar",
+        "16000",
+        "-ac",
+        "1",
+        "-c:a",
+        "pcm_s16le",
+        &temp_wav,
+    ])
+    .with_context(|| ctx.error_context("convert audio for whisper"))?;
 
     // Run whisper transcription
     let whisper_output = Command::new(&whisper_cli)
@@ -188,3 +200,29 @@ pub fn execute(ctx: &mut StepCtx<'_>, payload: &serde_json::Value) -> Result<()>
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use vwf_runtime::{FsRuntime, MockLlmClient};
+
+    #[test]
+    fn relative_model_is_found_in_the_workdir() {
+        let workdir = std::env::temp_dir().join(format!("vwf_whisper_{}", std::process::id()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        std::fs::write(workdir.join("voice.wav"), b"wav").unwrap();
+        let mut rt = FsRuntime::new(&workdir, Box::new(MockLlmClient::echo()));
+        let vars = BTreeMap::new();
+        let mut ctx = StepCtx::new(&mut rt, &vars, "transcribe");
+        let payload = serde_json::json!({
+            "input_path": "voice.wav",
+            "output_path": "voice.txt",
+            "model": "models/ggml-base.en.bin"
+        });
+        let err = execute(&mut ctx, &payload).unwrap_err().to_string();
+        let model = workdir.join("models/ggml-base.en.bin");
+        assert_eq!(err, format!("Whisper model not found: {}", model.display()));
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+}
@@ -5,6 +5,7 @@ use serde::Deserialize;
 use serde_json::Value;
 
 use super::context::StepCtx;
+use crate::catalog::{FieldDoc, StepDoc};
 
 #[derive(Deserialize)]
 struct Payload {
@@ -12,6 +13,19 @@ struct Payload {
     content: String,
 }
 
+pub(crate) const DOC: StepDoc = StepDoc {
+    kind: "write_file",
+    summary: "Write rendered text to a file",
+    fields: &[
+        FieldDoc::required("path", "string", "Output file path"),
+        FieldDoc::required(
+            "content",
+            "string",
+            "Text to write (templates are rendered)",
+        ),
+    ],
+};
+
 pub fn execute(ctx: &mut StepCtx<'_>, payload: &Value) -> Result<()> {
     let p: Payload = serde_json::from_value(payload.clone())
         .with_context(|| ctx.error_context("payload decode write_file"))?;
@@ -2,7 +2,7 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Unique identifier for an artifact.
 pub type ArtifactId = String;
@@ -44,6 +44,14 @@ pub struct Artifact {
 
     /// Is this a placeholder for a missing optional input?
     pub is_placeholder: bool,
+
+    /// File size when last verified (None if never seen on disk).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+
+    /// Media type inferred from the extension: image, video, audio or text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
 }
 
 impl Artifact {
@@ -59,6 +67,8 @@ impl Artifact {
             produced_by: None,
             status: ArtifactStatus::Missing,
             is_placeholder: false,
+            size_bytes: None,
+            kind: None,
         }
     }
 
@@ -77,6 +87,19 @@ impl Artifact {
         self.produced_by = produced_by;
         self.status = ArtifactStatus::Ready;
         self.is_placeholder = false;
+        self.kind = media_kind(&self.path).map(String::from);
+    }
+
+    /// Refresh `size_bytes` from the file under `workdir`.
+    ///
+    /// Returns false (and clears the size) if the file doesn't exist.
+    pub fn verify(&mut self, workdir: &Path) -> bool {
+        self.size_bytes = std::fs::metadata(workdir.join(&self.path))
+            .ok()
+            .filter(|m| m.is_file())
+            .map(|m| m.len());
+        self.kind = media_kind(&self.path).map(String::from);
+        self.size_bytes.is_some()
     }
 
     /// Mark artifact as invalidated.
@@ -92,3 +115,15 @@ impl Artifact {
         )
     }
 }
+
+/// Media type for a path's extension, if it's one workflows commonly produce.
+fn media_kind(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "png" | "jpg" | "jpeg" | "webp" | "gif" | "bmp" => Some("image"),
+        "mp4" | "mov" | "mkv" | "webm" | "avi" => Some("video"),
+        "wav" | "mp3" | "flac" | "ogg" | "m4a" | "aac" => Some("audio"),
+        "txt" | "md" | "json" | "yaml" | "yml" | "srt" | "vtt" => Some("text"),
+        _ => None,
+    }
+}
@@ -0,0 +1,95 @@
+//! Pluggable storage for artifact contents and persisted state.
+
+use anyhow::{Context, Result, bail};
+use sha2::{Digest, Sha256};
+use std::path::{Component, Path, PathBuf};
+
+/// Where artifact bytes live.
+///
+/// Keys are workdir-relative paths such as `work/audio/intro.wav`. The
+/// local filesystem is the default; a shared cache can implement this on
+/// top of an object store or HTTP server.
+pub trait ArtifactStore {
+    fn put(&self, key: &str, bytes: &[u8]) -> Result<()>;
+    /// The stored bytes, or `None` if `key` isn't stored.
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+    fn exists(&self, key: &str) -> bool;
+    /// Hex SHA-256 of the stored bytes, or `None` if `key` isn't stored.
+    fn checksum(&self, key: &str) -> Result<Option<String>>;
+}
+
+/// Artifacts as plain files under a root directory (usually the workdir).
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl LocalStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Where `key` lives, refusing keys that would escape the root.
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        let key = Path::new(key);
+        if !key
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            bail!("artifact key {} is outside the store", key.display());
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+impl ArtifactStore for LocalStore {
+    fn put(&self, key: &str, bytes: &[u8]) -> Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, bytes).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.path(key)?;
+        if !path.is_file() {
+            return Ok(None);
+        }
+        std::fs::read(&path)
+            .map(Some)
+            .with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    fn exists(&self, key: &str) -> bool {
+        self.path(key).is_ok_and(|path| path.is_file())
+    }
+
+    fn checksum(&self, key: &str) -> Result<Option<String>> {
+        let path = self.path(key)?;
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let mut file = std::fs::File::open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut file, &mut hasher)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Some(format!("{:x}", hasher.finalize())))
+    }
+}
+
+/// Checksum of the file at `path` (inside `workdir`) as `store` holds it.
+pub(crate) fn stored_checksum(
+    store: &dyn ArtifactStore,
+    workdir: &Path,
+    path: &Path,
+) -> Result<String> {
+    let key = path.strip_prefix(workdir).unwrap_or(path).to_string_lossy();
+    store
+        .checksum(&key)?
+        .with_context(|| format!("{key} is not in the artifact store"))
+}
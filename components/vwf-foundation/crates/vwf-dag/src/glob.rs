@@ -0,0 +1,76 @@
+//! Minimal `*` / `?` path patterns for output specs.
+//!
+//! Wildcards match within a single path component; `/` is never crossed.
+
+use std::path::Path;
+
+/// Whether `s` contains wildcard characters.
+pub fn is_pattern(s: &str) -> bool {
+    s.contains(['*', '?'])
+}
+
+/// Match a `/`-separated relative path against a pattern.
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let pat: Vec<&str> = pattern.split('/').collect();
+    let parts: Vec<&str> = path.split('/').collect();
+    pat.len() == parts.len() && pat.iter().zip(&parts).all(|(p, s)| component_matches(p, s))
+}
+
+/// Files under `workdir` matching `pattern`, as sorted relative paths.
+pub fn expand(workdir: &Path, pattern: &str) -> Vec<String> {
+    let mut found = vec![];
+    let components: Vec<&str> = pattern.split('/').collect();
+    walk(workdir, "", &components, &mut found);
+    found.sort();
+    found
+}
+
+fn walk(dir: &Path, prefix: &str, components: &[&str], found: &mut Vec<String>) {
+    let Some((head, rest)) = components.split_first() else {
+        return;
+    };
+    let join = |name: &str| {
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{prefix}/{name}")
+        }
+    };
+    let names: Vec<String> = if is_pattern(head) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        entries
+            .filter_map(|e| e.ok()?.file_name().into_string().ok())
+            .filter(|n| component_matches(head, n))
+            .collect()
+    } else {
+        vec![head.to_string()]
+    };
+    for name in names {
+        let path = dir.join(&name);
+        if rest.is_empty() {
+            if path.is_file() {
+                found.push(join(&name));
+            }
+        } else if path.is_dir() {
+            walk(&path, &join(&name), rest, found);
+        }
+    }
+}
+
+fn component_matches(pattern: &str, name: &str) -> bool {
+    let pat: Vec<char> = pattern.chars().collect();
+    let s: Vec<char> = name.chars().collect();
+    chars_match(&pat, &s)
+}
+
+fn chars_match(pat: &[char], s: &[char]) -> bool {
+    match (pat.first(), s.first()) {
+        (None, None) => true,
+        (Some('*'), _) => chars_match(&pat[1..], s) || (!s.is_empty() && chars_match(pat, &s[1..])),
+        (Some('?'), Some(_)) => chars_match(&pat[1..], &s[1..]),
+        (Some(p), Some(c)) if p == c => chars_match(&pat[1..], &s[1..]),
+        _ => false,
+    }
+}
@@ -4,16 +4,24 @@
 //! - **Task**: Unit of work with inputs, outputs, and constraints
 //! - **Artifact**: File with checksum for incremental builds
 //! - **Scheduler**: Runs tasks when dependencies are satisfied
+//! - **DagRunner**: Drives the scheduler, stopping only branches behind an
+//!   unapproved checkpoint
 //! - **State**: Persisted workflow state for resume
+//! - **ArtifactStore**: Where artifact contents and state are kept
 
 mod artifact;
+mod artifact_store;
+mod glob;
 mod scheduler;
 mod state;
 mod store;
+mod summary;
 mod task;
 
 pub use artifact::{Artifact, ArtifactId, ArtifactStatus};
-pub use scheduler::{Scheduler, SchedulerEvent};
+pub use artifact_store::{ArtifactStore, LocalStore};
+pub use scheduler::{DagRunner, Scheduler, SchedulerEvent};
 pub use state::{CheckpointStatus, WorkflowState};
 pub use store::StateStore;
-pub use task::{Constraint, InputSpec, OutputSpec, Task, TaskId, TaskStatus};
+pub use summary::StateSummary;
+pub use task::{CHECKPOINT_KIND, Constraint, InputSpec, OutputSpec, Task, TaskId, TaskStatus};
@@ -2,10 +2,11 @@
 
 use std::collections::{HashMap, HashSet};
 
+use crate::glob;
 use crate::{ArtifactStatus, InputSpec, Task, TaskStatus, WorkflowState};
 
 pub fn update_all_task_statuses(state: &mut WorkflowState) {
-    let available: HashSet<_> = state
+    let mut available: HashSet<_> = state
         .artifacts
         .values()
         .filter(|a| {
@@ -16,6 +17,15 @@ pub fn update_all_task_statuses(state: &mut WorkflowState) {
         })
         .map(|a| a.id.clone())
         .collect();
+    // Optional outputs of finished producers never block consumers.
+    available.extend(
+        state
+            .tasks
+            .values()
+            .filter(|t| t.is_complete())
+            .flat_map(|t| t.outputs.iter().filter(|o| o.optional))
+            .map(|o| o.artifact.clone()),
+    );
     let producers: HashMap<_, _> = state
         .tasks
         .values()
@@ -35,7 +45,7 @@ pub fn update_all_task_statuses(state: &mut WorkflowState) {
             .iter()
             .filter_map(|i| {
                 if let InputSpec::Required { artifact } = i {
-                    if !available.contains(artifact) {
+                    if !is_available(&available, artifact) {
                         return Some(
                             producers
                                 .get(artifact)
@@ -57,6 +67,12 @@ pub fn update_all_task_statuses(state: &mut WorkflowState) {
     }
 }
 
+/// A pattern input is satisfied once any available artifact matches it.
+fn is_available(available: &HashSet<String>, artifact: &str) -> bool {
+    available.contains(artifact)
+        || (glob::is_pattern(artifact) && available.iter().any(|a| glob::matches(artifact, a)))
+}
+
 pub fn collect_invalidation_targets(state: &WorkflowState, changed: &str) -> HashSet<String> {
     let mut to_process = vec![changed.to_string()];
     let mut invalidated = HashSet::new();
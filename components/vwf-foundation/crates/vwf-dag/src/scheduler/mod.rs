@@ -1,11 +1,14 @@
 //! DAG scheduler with constraint enforcement.
 
 mod helpers;
+mod runner;
 
 use std::collections::BTreeSet;
 
 use crate::{Task, TaskId, WorkflowState};
 
+pub use runner::DagRunner;
+
 /// Events emitted by the scheduler.
 #[derive(Debug, Clone)]
 pub enum SchedulerEvent {
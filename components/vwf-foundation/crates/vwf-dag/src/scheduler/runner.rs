@@ -0,0 +1,197 @@
+//! Sequential workflow runner built on the [`Scheduler`].
+
+use chrono::Utc;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use super::{Scheduler, SchedulerEvent};
+use crate::artifact_store::stored_checksum;
+use crate::{ArtifactStore, CheckpointStatus, LocalStore, Task, TaskId, TaskStatus, WorkflowState};
+
+/// Runs ready tasks one at a time until nothing more can run.
+///
+/// An unapproved checkpoint only holds back the tasks downstream of it:
+/// the runner records it, emits [`SchedulerEvent::CheckpointReached`] and
+/// keeps scheduling independent branches. Run again after approving it to
+/// continue the gated branch.
+pub struct DagRunner {
+    workdir: PathBuf,
+    store: Box<dyn ArtifactStore>,
+    scheduler: Scheduler,
+    stop_at_checkpoints: bool,
+}
+
+impl DagRunner {
+    /// Runner for tasks whose outputs live under `workdir`.
+    pub fn new(workdir: impl AsRef<Path>) -> Self {
+        let workdir = workdir.as_ref().to_path_buf();
+        Self {
+            store: Box::new(LocalStore::new(&workdir)),
+            workdir,
+            scheduler: Scheduler::default(),
+            stop_at_checkpoints: false,
+        }
+    }
+
+    /// Look up the checksums of produced artifacts in `store` instead of
+    /// the workdir (builder pattern).
+    pub fn with_store(mut self, store: Box<dyn ArtifactStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Stop the whole workflow at the first unapproved checkpoint instead
+    /// of finishing unrelated branches first (builder pattern).
+    pub fn stop_at_checkpoints(mut self) -> Self {
+        self.stop_at_checkpoints = true;
+        self
+    }
+
+    /// Run `state` as far as it can go.
+    ///
+    /// `execute` performs a task; on success its outputs are registered
+    /// with their checksums from the artifact store (as in
+    /// [`WorkflowState::mark_outputs_ready`]). A task that fails, or that
+    /// succeeds without writing a required output, is marked failed and
+    /// only its dependents stall. Every scheduling decision is
+    /// reported to `on_event`.
+    pub fn run(
+        &mut self,
+        state: &mut WorkflowState,
+        mut execute: impl FnMut(&Task) -> anyhow::Result<()>,
+        mut on_event: impl FnMut(SchedulerEvent),
+    ) -> anyhow::Result<()> {
+        let mut reached = BTreeSet::new();
+        loop {
+            self.scheduler.update_task_statuses(state);
+            let runnable: Vec<Task> = self
+                .scheduler
+                .get_runnable_tasks(state)
+                .into_iter()
+                .cloned()
+                .collect();
+            let mut progressed = false;
+            for task in runnable.iter().filter(|t| t.is_checkpoint()) {
+                if state.checkpoints.get(&task.id).is_some_and(|c| c.approved) {
+                    state.get_task_mut(&task.id).unwrap().status = TaskStatus::Complete;
+                    on_event(SchedulerEvent::TaskComplete {
+                        task_id: task.id.clone(),
+                    });
+                    progressed = true;
+                } else if reached.insert(task.id.clone()) {
+                    reach_checkpoint(state, task);
+                    on_event(SchedulerEvent::CheckpointReached {
+                        name: task.id.clone(),
+                    });
+                    if self.stop_at_checkpoints {
+                        on_event(SchedulerEvent::WorkflowBlocked {
+                            reason: format!("checkpoint `{}` awaits approval", task.id),
+                        });
+                        return Ok(());
+                    }
+                }
+            }
+            if progressed {
+                continue;
+            }
+            let Some(task) = runnable.into_iter().find(|t| !t.is_checkpoint()) else {
+                break;
+            };
+            self.run_task(state, &task, &mut execute, &mut on_event)?;
+        }
+        self.finish(state, &reached, &mut on_event);
+        Ok(())
+    }
+
+    fn run_task(
+        &mut self,
+        state: &mut WorkflowState,
+        task: &Task,
+        execute: &mut impl FnMut(&Task) -> anyhow::Result<()>,
+        on_event: &mut impl FnMut(SchedulerEvent),
+    ) -> anyhow::Result<()> {
+        let task_id = task.id.clone();
+        self.scheduler.start_task(task);
+        state.get_task_mut(&task_id).unwrap().status = TaskStatus::Running;
+        on_event(SchedulerEvent::TaskStarted {
+            task_id: task_id.clone(),
+        });
+        let result = execute(task).and_then(|()| {
+            let missing = task.missing_required_outputs(&self.workdir);
+            if !missing.is_empty() {
+                anyhow::bail!("did not write {}", missing.join(", "));
+            }
+            Ok(())
+        });
+        self.scheduler.finish_task(task);
+        match result {
+            Ok(()) => {
+                state.get_task_mut(&task_id).unwrap().status = TaskStatus::Complete;
+                let store = &*self.store;
+                state.mark_outputs_ready(&task_id, &self.workdir, |path| {
+                    stored_checksum(store, &self.workdir, path)
+                })?;
+                on_event(SchedulerEvent::TaskComplete { task_id });
+            }
+            Err(e) => {
+                let error = format!("{e:#}");
+                state.get_task_mut(&task_id).unwrap().status = TaskStatus::Failed {
+                    error: error.clone(),
+                };
+                on_event(SchedulerEvent::TaskFailed { task_id, error });
+            }
+        }
+        Ok(())
+    }
+
+    /// Report how the run ended once no task can make progress.
+    fn finish(
+        &self,
+        state: &mut WorkflowState,
+        reached: &BTreeSet<TaskId>,
+        on_event: &mut impl FnMut(SchedulerEvent),
+    ) {
+        let unfinished: Vec<&TaskId> = state
+            .tasks
+            .values()
+            .filter(|t| !matches!(t.status, TaskStatus::Complete | TaskStatus::Skipped { .. }))
+            .map(|t| &t.id)
+            .collect();
+        let failed: Vec<&str> = state
+            .tasks
+            .values()
+            .filter(|t| matches!(t.status, TaskStatus::Failed { .. }))
+            .map(|t| t.id.as_str())
+            .collect();
+        if unfinished.is_empty() {
+            state.complete = true;
+            on_event(SchedulerEvent::WorkflowComplete);
+        } else if reached.is_empty() {
+            let ids: Vec<&str> = unfinished.iter().map(|id| id.as_str()).collect();
+            on_event(SchedulerEvent::WorkflowBlocked {
+                reason: format!("cannot run: {}", ids.join(", ")),
+            });
+        } else if !failed.is_empty() {
+            // Pending checkpoints were already reported, failures were not
+            on_event(SchedulerEvent::WorkflowBlocked {
+                reason: format!("failed: {}", failed.join(", ")),
+            });
+        }
+        state.updated_at = Utc::now();
+    }
+}
+
+/// Record a checkpoint as reached, keeping an earlier record if there is one.
+fn reach_checkpoint(state: &mut WorkflowState, task: &Task) {
+    let message = task.config["message"].as_str().unwrap_or_default();
+    state
+        .checkpoints
+        .entry(task.id.clone())
+        .or_insert_with(|| CheckpointStatus {
+            name: task.id.clone(),
+            message: message.to_string(),
+            reached_at: Utc::now(),
+            approved: false,
+            approved_at: None,
+        });
+}
@@ -3,8 +3,12 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::path::Path;
 
-use crate::{Artifact, ArtifactId, Task, TaskId};
+use crate::{
+    Artifact, ArtifactId, ArtifactStatus, ArtifactStore, Scheduler, StateSummary, Task, TaskId,
+    TaskStatus,
+};
 
 /// Checkpoint status for user review points.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,7 +74,174 @@ impl WorkflowState {
         self.artifacts.get(id)
     }
 
+    /// Register the files a finished task produced as ready artifacts.
+    ///
+    /// Pattern outputs such as `frames/*.png` are expanded against `workdir`
+    /// and each match becomes its own artifact. `checksum` fingerprints a
+    /// file given its path under `workdir`. Returns the registered ids.
+    pub fn mark_outputs_ready(
+        &mut self,
+        task_id: &str,
+        workdir: &Path,
+        checksum: impl Fn(&Path) -> anyhow::Result<String>,
+    ) -> anyhow::Result<Vec<ArtifactId>> {
+        let task = self
+            .tasks
+            .get(task_id)
+            .ok_or_else(|| anyhow::anyhow!("unknown task `{task_id}`"))?;
+        let produced = task.resolve_outputs(workdir);
+        for path in &produced {
+            let sum = checksum(&workdir.join(path))?;
+            let mut artifact = self
+                .artifacts
+                .remove(path)
+                .unwrap_or_else(|| Artifact::missing(path));
+            artifact.mark_ready(sum, Some(task_id.to_string()));
+            artifact.verify(workdir);
+            self.artifacts.insert(path.clone(), artifact);
+        }
+        self.updated_at = Utc::now();
+        Ok(produced)
+    }
+
+    /// Repair state left behind by a run that died mid-task.
+    ///
+    /// A `Running` task whose required outputs all exist and are non-empty
+    /// is marked complete and its outputs registered. Any other `Running`
+    /// task has its partial outputs deleted and goes back to `Ready` or
+    /// `Blocked`, depending on its inputs. Returns the ids of reset tasks.
+    pub fn reconcile(
+        &mut self,
+        workdir: &Path,
+        checksum: impl Fn(&Path) -> anyhow::Result<String>,
+    ) -> anyhow::Result<Vec<TaskId>> {
+        let stuck: Vec<TaskId> = self
+            .tasks
+            .values()
+            .filter(|t| t.status == TaskStatus::Running)
+            .map(|t| t.id.clone())
+            .collect();
+        let mut reset = vec![];
+        for id in stuck {
+            let task = &self.tasks[&id];
+            let produced = task.resolve_outputs(workdir);
+            let finished = !task.outputs.is_empty()
+                && task.missing_required_outputs(workdir).is_empty()
+                && produced.iter().all(|p| non_empty(&workdir.join(p)));
+            if finished {
+                self.tasks.get_mut(&id).unwrap().status = TaskStatus::Complete;
+                self.mark_outputs_ready(&id, workdir, &checksum)?;
+                continue;
+            }
+            for path in produced {
+                let file = workdir.join(&path);
+                std::fs::remove_file(&file)
+                    .map_err(|e| anyhow::anyhow!("remove partial {}: {e}", file.display()))?;
+                if let Some(artifact) = self.artifacts.get_mut(&path) {
+                    artifact.status = ArtifactStatus::Missing;
+                    artifact.checksum = None;
+                    artifact.size_bytes = None;
+                }
+            }
+            self.tasks.get_mut(&id).unwrap().status = TaskStatus::default();
+            reset.push(id);
+        }
+        Scheduler::default().update_task_statuses(self);
+        self.updated_at = Utc::now();
+        Ok(reset)
+    }
+
+    /// Put `Failed` tasks back in line to run, e.g. after the service they
+    /// needed is fixed.
+    ///
+    /// Their errors are dropped and each goes back to `Ready` or `Blocked`
+    /// depending on its inputs; tasks waiting on them stay blocked until
+    /// they complete. The workflow's `error` and `complete` flag are cleared
+    /// when anything was reset. Returns the ids of reset tasks.
+    pub fn reset_failed(&mut self) -> Vec<TaskId> {
+        let mut reset = vec![];
+        for task in self.tasks.values_mut() {
+            if matches!(task.status, TaskStatus::Failed { .. }) {
+                task.status = TaskStatus::default();
+                reset.push(task.id.clone());
+            }
+        }
+        if !reset.is_empty() {
+            self.error = None;
+            self.complete = false;
+        }
+        Scheduler::default().update_task_statuses(self);
+        self.updated_at = Utc::now();
+        reset
+    }
+
+    /// Bring artifact statuses in line with what `store` holds.
+    ///
+    /// Stored artifacts become ready with their current checksum; a ready
+    /// artifact whose checksum changed invalidates everything downstream of
+    /// it. Ready artifacts that are gone become missing, and invalidated
+    /// ones stay invalidated until rebuilt. Task statuses are then
+    /// recomputed. Returns the ids of artifacts that changed.
+    pub fn sync_artifacts(&mut self, store: &dyn ArtifactStore) -> anyhow::Result<Vec<ArtifactId>> {
+        let mut changed = vec![];
+        let mut edited = vec![];
+        let ids: Vec<ArtifactId> = self.artifacts.keys().cloned().collect();
+        for id in ids {
+            let artifact = &self.artifacts[&id];
+            let was_ready = artifact.status == ArtifactStatus::Ready;
+            match store.checksum(&id)? {
+                Some(_) if artifact.status == ArtifactStatus::Invalidated => {}
+                Some(sum) if was_ready && artifact.checksum.as_deref() == Some(sum.as_str()) => {}
+                Some(sum) => {
+                    let artifact = self.artifacts.get_mut(&id).unwrap();
+                    let produced_by = artifact.produced_by.clone();
+                    artifact.mark_ready(sum, produced_by);
+                    if was_ready {
+                        edited.push(id.clone());
+                    }
+                    changed.push(id);
+                }
+                None if was_ready => {
+                    let artifact = self.artifacts.get_mut(&id).unwrap();
+                    artifact.status = ArtifactStatus::Missing;
+                    artifact.checksum = None;
+                    artifact.size_bytes = None;
+                    changed.push(id);
+                }
+                None => {}
+            }
+        }
+        for id in edited {
+            Scheduler::invalidate_downstream(self, &id);
+            // The edited artifact itself is current.
+            self.artifacts.get_mut(&id).unwrap().status = ArtifactStatus::Ready;
+        }
+        Scheduler::default().update_task_statuses(self);
+        self.updated_at = Utc::now();
+        Ok(changed)
+    }
+
+    /// Approve a reached checkpoint. Returns false if it hasn't been reached.
+    pub fn approve_checkpoint(&mut self, name: &str) -> bool {
+        let Some(checkpoint) = self.checkpoints.get_mut(name) else {
+            return false;
+        };
+        checkpoint.approved = true;
+        checkpoint.approved_at = Some(Utc::now());
+        self.updated_at = Utc::now();
+        true
+    }
+
     pub fn ready_tasks(&self) -> Vec<&Task> {
         self.tasks.values().filter(|t| t.is_ready()).collect()
     }
+
+    /// Build a human-readable progress summary.
+    pub fn summary(&self) -> StateSummary {
+        StateSummary::from_state(self)
+    }
+}
+
+fn non_empty(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|m| m.len() > 0)
 }
@@ -1,44 +1,71 @@
-//! State persistence to filesystem.
+//! State persistence through an [`ArtifactStore`].
 
 use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 
+use super::artifact_store::{ArtifactStore, LocalStore, stored_checksum};
 use super::state::WorkflowState;
 
-/// State persistence to filesystem.
+/// Key the state is stored under, relative to the workdir.
+const STATE_KEY: &str = "state.json";
+
+/// State persistence, to the workdir unless another store is given.
 pub struct StateStore {
-    path: PathBuf,
+    workdir: PathBuf,
+    store: Box<dyn ArtifactStore>,
 }
 
 impl StateStore {
     pub fn new(workdir: impl AsRef<Path>) -> Self {
+        let workdir = workdir.as_ref().to_path_buf();
         Self {
-            path: workdir.as_ref().join("state.json"),
+            store: Box::new(LocalStore::new(&workdir)),
+            workdir,
+        }
+    }
+
+    /// Keep `state.json` in `store` instead of the workdir.
+    pub fn with_store(workdir: impl AsRef<Path>, store: Box<dyn ArtifactStore>) -> Self {
+        Self {
+            workdir: workdir.as_ref().to_path_buf(),
+            store,
         }
     }
 
     pub fn load(&self) -> Result<Option<WorkflowState>> {
-        if !self.path.exists() {
+        let Some(bytes) = self.store.get(STATE_KEY)? else {
             return Ok(None);
-        }
-        let content = std::fs::read_to_string(&self.path)
-            .with_context(|| format!("Failed to read {}", self.path.display()))?;
-        let state: WorkflowState = serde_json::from_str(&content)
-            .with_context(|| format!("Failed to parse {}", self.path.display()))?;
+        };
+        let state: WorkflowState = serde_json::from_slice(&bytes)
+            .with_context(|| format!("Failed to parse {}", self.path().display()))?;
+        Ok(Some(state))
+    }
+
+    /// Load the state for resuming: [reconcile](WorkflowState::reconcile)
+    /// it after a crash, then [sync](WorkflowState::sync_artifacts) its
+    /// artifacts with the store, both using the store's checksums.
+    pub fn load_reconciled(&self) -> Result<Option<WorkflowState>> {
+        let Some(mut state) = self.load()? else {
+            return Ok(None);
+        };
+        let store = &*self.store;
+        state.reconcile(&self.workdir, |path| {
+            stored_checksum(store, &self.workdir, path)
+        })?;
+        state.sync_artifacts(store)?;
         Ok(Some(state))
     }
 
     pub fn save(&self, state: &WorkflowState) -> Result<()> {
         let content = serde_json::to_string_pretty(state)?;
-        if let Some(parent) = self.path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-        std::fs::write(&self.path, content)
-            .with_context(|| format!("Failed to write {}", self.path.display()))?;
-        Ok(())
+        self.store.put(STATE_KEY, content.as_bytes())
     }
 
     pub fn exists(&self) -> bool {
-        self.path.exists()
+        self.store.exists(STATE_KEY)
+    }
+
+    fn path(&self) -> PathBuf {
+        self.workdir.join(STATE_KEY)
     }
 }
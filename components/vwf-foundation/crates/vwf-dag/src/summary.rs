@@ -0,0 +1,114 @@
+//! Human-readable summary of workflow state.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::{TaskId, TaskStatus, WorkflowState};
+
+/// Snapshot of workflow progress for CLI inspection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateSummary {
+    pub workflow_name: String,
+    pub total: usize,
+    /// Task counts keyed by status name (`complete`, `blocked`, ...).
+    pub counts: BTreeMap<&'static str, usize>,
+    pub ready: Vec<TaskId>,
+    /// Blocked tasks with the tasks/artifacts they wait on.
+    pub blocked: Vec<(TaskId, Vec<TaskId>)>,
+    /// Checkpoints reached but not yet approved, as (name, message).
+    pub pending_checkpoints: Vec<(String, String)>,
+}
+
+impl StateSummary {
+    pub fn from_state(state: &WorkflowState) -> Self {
+        let mut counts = BTreeMap::new();
+        let mut ready = vec![];
+        let mut blocked = vec![];
+        for task in state.tasks.values() {
+            *counts.entry(status_name(&task.status)).or_insert(0) += 1;
+            match &task.status {
+                TaskStatus::Ready => ready.push(task.id.clone()),
+                TaskStatus::Blocked { waiting_on } => {
+                    blocked.push((task.id.clone(), waiting_on.clone()))
+                }
+                _ => {}
+            }
+        }
+        let pending_checkpoints = state
+            .checkpoints
+            .values()
+            .filter(|c| !c.approved)
+            .map(|c| (c.name.clone(), c.message.clone()))
+            .collect();
+        Self {
+            workflow_name: state.workflow_name.clone(),
+            total: state.tasks.len(),
+            counts,
+            ready,
+            blocked,
+            pending_checkpoints,
+        }
+    }
+
+    /// Number of tasks with the given status name.
+    pub fn count(&self, status: &str) -> usize {
+        self.counts.get(status).copied().unwrap_or(0)
+    }
+
+    /// Percentage of tasks that are complete or skipped.
+    pub fn progress_percent(&self) -> f64 {
+        if self.total == 0 {
+            return 100.0;
+        }
+        let done = self.count("complete") + self.count("skipped");
+        done as f64 * 100.0 / self.total as f64
+    }
+}
+
+impl fmt::Display for StateSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Workflow: {}", self.workflow_name)?;
+        writeln!(
+            f,
+            "Progress: {:.0}% ({} tasks)",
+            self.progress_percent(),
+            self.total
+        )?;
+        let counts: Vec<String> = self
+            .counts
+            .iter()
+            .map(|(status, n)| format!("{n} {status}"))
+            .collect();
+        writeln!(f, "Status: {}", counts.join(", "))?;
+        if !self.ready.is_empty() {
+            writeln!(f, "\nReady:")?;
+            for id in &self.ready {
+                writeln!(f, "  - {id}")?;
+            }
+        }
+        if !self.blocked.is_empty() {
+            writeln!(f, "\nBlocked:")?;
+            for (id, waiting_on) in &self.blocked {
+                writeln!(f, "  - {id} (waiting on: {})", waiting_on.join(", "))?;
+            }
+        }
+        if !self.pending_checkpoints.is_empty() {
+            writeln!(f, "\nPending checkpoints:")?;
+            for (name, message) in &self.pending_checkpoints {
+                writeln!(f, "  - {name}: {message}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn status_name(status: &TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Blocked { .. } => "blocked",
+        TaskStatus::Ready => "ready",
+        TaskStatus::Running => "running",
+        TaskStatus::Complete => "complete",
+        TaskStatus::Failed { .. } => "failed",
+        TaskStatus::Skipped { .. } => "skipped",
+    }
+}
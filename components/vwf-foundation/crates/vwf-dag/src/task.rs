@@ -1,10 +1,16 @@
 //! Task definitions for DAG-based workflow.
 
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::glob;
 
 /// Unique identifier for a task.
 pub type TaskId = String;
 
+/// Task kind of a [`Task::checkpoint`].
+pub const CHECKPOINT_KIND: &str = "checkpoint";
+
 /// Status of a task.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -67,12 +73,37 @@ pub struct OutputSpec {
     pub artifact: String,
     #[serde(default = "output_primary_default")]
     pub primary: bool,
+    /// Output is produced only sometimes; its absence is not a failure.
+    #[serde(default)]
+    pub optional: bool,
 }
 
 const fn output_primary_default() -> bool {
     true
 }
 
+impl OutputSpec {
+    /// Whether `artifact` is a `*` / `?` pattern (e.g. `frames/*.png`)
+    /// rather than a single path.
+    pub fn is_pattern(&self) -> bool {
+        glob::is_pattern(&self.artifact)
+    }
+
+    /// Concrete files under `workdir` this output refers to.
+    ///
+    /// A plain path yields itself if it exists; a pattern yields every
+    /// matching file, sorted.
+    pub fn resolve(&self, workdir: &Path) -> Vec<String> {
+        if self.is_pattern() {
+            glob::expand(workdir, &self.artifact)
+        } else if workdir.join(&self.artifact).exists() {
+            vec![self.artifact.clone()]
+        } else {
+            vec![]
+        }
+    }
+}
+
 /// Execution constraints for a task.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Constraint {
@@ -116,6 +147,17 @@ impl Task {
         }
     }
 
+    /// A review point named `name`, shown to the user with `message`.
+    ///
+    /// Tasks gated by it take `checkpoint:<name>` as a required input; the
+    /// checkpoint completes once approved in the workflow state.
+    pub fn checkpoint(name: impl Into<String>, message: impl Into<String>) -> Self {
+        let name = name.into();
+        let mut task = Self::new(&name, CHECKPOINT_KIND);
+        task.config = serde_json::json!({ "message": message.into() });
+        task.with_optional_output(format!("checkpoint:{name}"))
+    }
+
     /// Add a required input artifact (builder pattern).
     pub fn with_input(mut self, artifact: impl Into<String>) -> Self {
         self.inputs.push(InputSpec::Required {
@@ -129,6 +171,17 @@ impl Task {
         self.outputs.push(OutputSpec {
             artifact: artifact.into(),
             primary: true,
+            optional: false,
+        });
+        self
+    }
+
+    /// Add an output artifact that may legitimately be absent (builder pattern).
+    pub fn with_optional_output(mut self, artifact: impl Into<String>) -> Self {
+        self.outputs.push(OutputSpec {
+            artifact: artifact.into(),
+            primary: false,
+            optional: true,
         });
         self
     }
@@ -138,8 +191,35 @@ impl Task {
         matches!(self.status, TaskStatus::Ready)
     }
 
+    /// Check if task is a review point rather than work to run.
+    pub fn is_checkpoint(&self) -> bool {
+        self.kind == CHECKPOINT_KIND
+    }
+
     /// Check if task is complete.
     pub fn is_complete(&self) -> bool {
         matches!(self.status, TaskStatus::Complete)
     }
+
+    /// Required outputs that do not exist under `workdir`.
+    ///
+    /// Optional outputs are ignored, so an empty result means the task
+    /// produced everything it must. A pattern output counts as missing
+    /// when nothing matches it.
+    pub fn missing_required_outputs(&self, workdir: &Path) -> Vec<&str> {
+        self.outputs
+            .iter()
+            .filter(|o| !o.optional && o.resolve(workdir).is_empty())
+            .map(|o| o.artifact.as_str())
+            .collect()
+    }
+
+    /// Every concrete file currently produced for this task's outputs,
+    /// with patterns expanded.
+    pub fn resolve_outputs(&self, workdir: &Path) -> Vec<String> {
+        self.outputs
+            .iter()
+            .flat_map(|o| o.resolve(workdir))
+            .collect()
+    }
 }
@@ -22,3 +22,27 @@ fn placeholder_artifact() {
     assert_eq!(artifact.status, ArtifactStatus::Placeholder);
     assert!(artifact.needs_rebuild());
 }
+
+#[test]
+fn verify_records_size_and_kind() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("work")).unwrap();
+    std::fs::write(dir.path().join("work/title.PNG"), [0u8; 1200]).unwrap();
+
+    let mut artifact = Artifact::missing("work/title.PNG");
+    artifact.mark_ready("abc".to_string(), Some("slide".to_string()));
+    assert_eq!(artifact.kind.as_deref(), Some("image"));
+    assert!(artifact.verify(dir.path()));
+    assert_eq!(artifact.size_bytes, Some(1200));
+
+    let json = serde_json::to_string(&artifact).unwrap();
+    let back: Artifact = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        (back.size_bytes, back.kind),
+        (Some(1200), Some("image".into()))
+    );
+
+    let mut gone = Artifact::missing("work/none.mp4");
+    assert!(!gone.verify(dir.path()));
+    assert_eq!(gone.size_bytes, None);
+}
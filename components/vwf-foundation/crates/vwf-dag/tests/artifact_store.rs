@@ -0,0 +1,139 @@
+//! Artifact store tests.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use vwf_dag::{
+    Artifact, ArtifactStatus, ArtifactStore, LocalStore, StateStore, Task, TaskStatus,
+    WorkflowState,
+};
+
+#[test]
+fn local_store_round_trips_and_checks_existence() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+
+    assert!(!store.exists("work/script.txt"));
+    assert_eq!(store.get("work/script.txt").unwrap(), None);
+    assert_eq!(store.checksum("work/script.txt").unwrap(), None);
+
+    store.put("work/script.txt", b"hello").unwrap();
+    assert!(store.exists("work/script.txt"));
+    assert!(dir.path().join("work/script.txt").is_file());
+    assert_eq!(
+        store.get("work/script.txt").unwrap().as_deref(),
+        Some(&b"hello"[..])
+    );
+    assert_eq!(
+        store.checksum("work/script.txt").unwrap().as_deref(),
+        Some("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824")
+    );
+    assert!(!store.exists("work"), "directories are not artifacts");
+}
+
+#[test]
+fn sync_picks_up_new_changed_and_removed_artifacts() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path());
+    let mut state = WorkflowState::new("sync", 1);
+    state.add_task(
+        Task::new("voice", "tts_generate")
+            .with_input("script.txt")
+            .with_output("voice.wav"),
+    );
+    state.add_artifact(Artifact::missing("script.txt"));
+    state.add_artifact(Artifact::missing("voice.wav"));
+
+    store.put("script.txt", b"v1").unwrap();
+    assert_eq!(state.sync_artifacts(&store).unwrap(), ["script.txt"]);
+    assert_eq!(state.tasks["voice"].status, TaskStatus::Ready);
+
+    store.put("voice.wav", b"audio").unwrap();
+    state.sync_artifacts(&store).unwrap();
+    state.get_task_mut("voice").unwrap().status = TaskStatus::Complete;
+    assert!(state.sync_artifacts(&store).unwrap().is_empty());
+
+    // Editing the script invalidates the voice built from it
+    store.put("script.txt", b"v2").unwrap();
+    assert_eq!(state.sync_artifacts(&store).unwrap(), ["script.txt"]);
+    assert_eq!(state.artifacts["script.txt"].status, ArtifactStatus::Ready);
+    assert_eq!(
+        state.artifacts["voice.wav"].status,
+        ArtifactStatus::Invalidated
+    );
+    assert_eq!(state.tasks["voice"].status, TaskStatus::Ready);
+
+    std::fs::remove_file(dir.path().join("script.txt")).unwrap();
+    assert_eq!(state.sync_artifacts(&store).unwrap(), ["script.txt"]);
+    assert_eq!(
+        state.artifacts["script.txt"].status,
+        ArtifactStatus::Missing
+    );
+}
+
+/// Stand-in for a remote backend.
+#[derive(Default)]
+struct MemoryStore(RefCell<BTreeMap<String, Vec<u8>>>);
+
+impl ArtifactStore for MemoryStore {
+    fn put(&self, key: &str, bytes: &[u8]) -> anyhow::Result<()> {
+        self.0.borrow_mut().insert(key.to_string(), bytes.to_vec());
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self.0.borrow().get(key).cloned())
+    }
+
+    fn exists(&self, key: &str) -> bool {
+        self.0.borrow().contains_key(key)
+    }
+
+    fn checksum(&self, key: &str) -> anyhow::Result<Option<String>> {
+        Ok(self.get(key)?.map(|b| format!("len{}", b.len())))
+    }
+}
+
+#[test]
+fn state_can_live_in_another_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = StateStore::with_store(dir.path(), Box::new(MemoryStore::default()));
+    assert!(!store.exists());
+    store.save(&WorkflowState::new("remote", 2)).unwrap();
+    assert!(store.exists());
+    assert_eq!(store.load().unwrap().unwrap().workflow_name, "remote");
+    assert!(!dir.path().join("state.json").exists());
+}
+
+#[test]
+fn local_store_rejects_keys_outside_its_root() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = LocalStore::new(dir.path().join("work"));
+    for key in ["../escape.txt", "a/../../escape.txt", "/tmp/escape.txt"] {
+        assert!(store.put(key, b"x").is_err(), "{key}");
+        assert!(store.get(key).is_err(), "{key}");
+        assert!(store.checksum(key).is_err(), "{key}");
+        assert!(!store.exists(key), "{key}");
+    }
+    assert!(!dir.path().join("escape.txt").exists());
+}
+
+#[test]
+fn reconciled_load_syncs_with_the_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let files = LocalStore::new(dir.path());
+    let mut state = WorkflowState::new("sync", 1);
+    state.add_task(
+        Task::new("voice", "tts_generate")
+            .with_input("script.txt")
+            .with_output("voice.wav"),
+    );
+    state.add_artifact(Artifact::missing("script.txt"));
+    let store = StateStore::new(dir.path());
+    store.save(&state).unwrap();
+
+    files.put("script.txt", b"v1").unwrap();
+    let state = store.load_reconciled().unwrap().unwrap();
+    assert_eq!(state.artifacts["script.txt"].status, ArtifactStatus::Ready);
+    assert_eq!(state.tasks["voice"].status, TaskStatus::Ready);
+}
@@ -0,0 +1,68 @@
+//! Pattern output tests.
+
+use std::fs;
+
+use tempfile::TempDir;
+use vwf_dag::{ArtifactStatus, Scheduler, Task, TaskStatus, WorkflowState};
+
+fn file_len(path: &std::path::Path) -> anyhow::Result<String> {
+    Ok(fs::metadata(path)?.len().to_string())
+}
+
+#[test]
+fn pattern_output_registers_matching_files() {
+    let tmp = TempDir::new().unwrap();
+    fs::create_dir_all(tmp.path().join("frames/nested")).unwrap();
+    for name in ["f002.png", "f001.png", "notes.txt", "nested/f003.png"] {
+        fs::write(tmp.path().join("frames").join(name), name).unwrap();
+    }
+
+    let mut state = WorkflowState::new("test", 1);
+    let mut extract = Task::new("extract", "extract_frames").with_output("frames/*.png");
+    extract.status = TaskStatus::Complete;
+    state.add_task(extract);
+    state.add_task(Task::new("review", "llm_audit").with_input("frames/*.png"));
+
+    let registered = state
+        .mark_outputs_ready("extract", tmp.path(), file_len)
+        .unwrap();
+    assert_eq!(registered, ["frames/f001.png", "frames/f002.png"]);
+    for id in &registered {
+        let artifact = state.get_artifact(id).unwrap();
+        assert_eq!(artifact.status, ArtifactStatus::Ready);
+        assert_eq!(artifact.produced_by.as_deref(), Some("extract"));
+        assert_eq!(artifact.checksum.as_deref(), Some("8"));
+        assert_eq!(artifact.size_bytes, Some(8));
+        assert_eq!(artifact.kind.as_deref(), Some("image"));
+    }
+    assert!(state.get_artifact("frames/*.png").is_none());
+
+    Scheduler::default().update_task_statuses(&mut state);
+    assert!(state.get_task("review").unwrap().is_ready());
+}
+
+#[test]
+fn pattern_output_without_matches_is_missing() {
+    let tmp = TempDir::new().unwrap();
+    let task = Task::new("extract", "extract_frames").with_output("frames/*.png");
+    assert_eq!(task.missing_required_outputs(tmp.path()), ["frames/*.png"]);
+
+    fs::create_dir_all(tmp.path().join("frames")).unwrap();
+    fs::write(tmp.path().join("frames/a.png"), "x").unwrap();
+    assert!(task.missing_required_outputs(tmp.path()).is_empty());
+}
+
+#[test]
+fn pattern_input_blocks_until_a_match_is_ready() {
+    let mut state = WorkflowState::new("test", 1);
+    state.add_task(Task::new("extract", "extract_frames").with_output("frames/*.png"));
+    state.add_task(Task::new("review", "llm_audit").with_input("frames/*.png"));
+
+    Scheduler::default().update_task_statuses(&mut state);
+    assert_eq!(
+        state.get_task("review").unwrap().status,
+        TaskStatus::Blocked {
+            waiting_on: vec!["extract".to_string()]
+        }
+    );
+}
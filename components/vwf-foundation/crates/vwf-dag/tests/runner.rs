@@ -0,0 +1,212 @@
+//! Runner integration tests.
+
+use std::path::Path;
+
+use tempfile::TempDir;
+use vwf_dag::{DagRunner, SchedulerEvent, Task, TaskStatus, WorkflowState};
+
+/// Two branches: `script -> review -> narrate` and an independent `music`.
+fn gated_workflow() -> WorkflowState {
+    let mut state = WorkflowState::new("test", 1);
+    state.add_task(Task::new("script", "llm_generate").with_output("script.txt"));
+    state.add_task(Task::checkpoint("review", "Check the script").with_input("script.txt"));
+    state.add_task(
+        Task::new("narrate", "tts_generate")
+            .with_input("checkpoint:review")
+            .with_output("narration.wav"),
+    );
+    state.add_task(Task::new("music", "run_command").with_output("music.wav"));
+    state
+}
+
+/// Run `state`, writing each task's outputs, and return the events.
+fn run(state: &mut WorkflowState, workdir: &Path) -> Vec<String> {
+    let mut events = vec![];
+    DagRunner::new(workdir)
+        .run(
+            state,
+            |task| {
+                for output in &task.outputs {
+                    std::fs::write(workdir.join(&output.artifact), &task.id)?;
+                }
+                Ok(())
+            },
+            |event| events.push(describe(event)),
+        )
+        .unwrap();
+    events
+}
+
+fn describe(event: SchedulerEvent) -> String {
+    match event {
+        SchedulerEvent::TaskReady { task_id } => format!("ready {task_id}"),
+        SchedulerEvent::TaskStarted { task_id } => format!("start {task_id}"),
+        SchedulerEvent::TaskComplete { task_id } => format!("done {task_id}"),
+        SchedulerEvent::TaskFailed { task_id, .. } => format!("fail {task_id}"),
+        SchedulerEvent::CheckpointReached { name } => format!("checkpoint {name}"),
+        SchedulerEvent::WorkflowComplete => "complete".to_string(),
+        SchedulerEvent::WorkflowBlocked { reason } => format!("blocked: {reason}"),
+    }
+}
+
+#[test]
+fn independent_branch_finishes_while_checkpoint_is_pending() {
+    let tmp = TempDir::new().unwrap();
+    let mut state = gated_workflow();
+
+    let events = run(&mut state, tmp.path());
+    assert_eq!(
+        events,
+        [
+            "start music",
+            "done music",
+            "start script",
+            "done script",
+            "checkpoint review",
+        ]
+    );
+    assert!(state.get_task("music").unwrap().is_complete());
+    assert!(matches!(
+        &state.get_task("narrate").unwrap().status,
+        TaskStatus::Blocked { waiting_on } if waiting_on == &["review"]
+    ));
+    let review = &state.checkpoints["review"];
+    assert_eq!(review.message, "Check the script");
+    assert!(!review.approved);
+    assert!(!state.complete);
+
+    assert!(state.approve_checkpoint("review"));
+    let events = run(&mut state, tmp.path());
+    assert_eq!(
+        events,
+        ["done review", "start narrate", "done narrate", "complete"]
+    );
+    assert!(state.complete);
+    assert!(tmp.path().join("narration.wav").exists());
+}
+
+#[test]
+fn stop_at_checkpoints_blocks_the_whole_workflow() {
+    let tmp = TempDir::new().unwrap();
+    let mut state = WorkflowState::new("test", 1);
+    state.add_task(Task::checkpoint("approve_brief", "Check the brief"));
+    state.add_task(Task::new("music", "run_command").with_output("music.wav"));
+
+    let mut events = vec![];
+    DagRunner::new(tmp.path())
+        .stop_at_checkpoints()
+        .run(&mut state, |_| Ok(()), |e| events.push(describe(e)))
+        .unwrap();
+    assert_eq!(
+        events,
+        [
+            "checkpoint approve_brief",
+            "blocked: checkpoint `approve_brief` awaits approval"
+        ]
+    );
+    assert!(!state.get_task("music").unwrap().is_complete());
+}
+
+#[test]
+fn failed_task_only_stalls_its_dependents() {
+    let tmp = TempDir::new().unwrap();
+    let mut state = WorkflowState::new("test", 1);
+    state.add_task(Task::new("broken", "run_command").with_output("a.txt"));
+    state.add_task(Task::new("after", "run_command").with_input("a.txt"));
+    state.add_task(Task::new("other", "run_command").with_output("b.txt"));
+
+    let mut events = vec![];
+    DagRunner::new(tmp.path())
+        .run(
+            &mut state,
+            |task| {
+                if task.id == "broken" {
+                    anyhow::bail!("exit 1");
+                }
+                for output in &task.outputs {
+                    std::fs::write(tmp.path().join(&output.artifact), "x")?;
+                }
+                Ok(())
+            },
+            |e| events.push(describe(e)),
+        )
+        .unwrap();
+    assert_eq!(
+        events,
+        [
+            "start broken",
+            "fail broken",
+            "start other",
+            "done other",
+            "blocked: cannot run: after, broken"
+        ]
+    );
+}
+
+#[test]
+fn only_a_missing_required_output_fails_a_task() {
+    let tmp = TempDir::new().unwrap();
+    let mut state = WorkflowState::new("test", 1);
+    state.add_task(
+        Task::new("audit", "llm_audit")
+            .with_output("audit.txt")
+            .with_optional_output("issues.txt"),
+    );
+    state.add_task(Task::new("fix", "run_command").with_input("issues.txt"));
+    state.add_task(Task::new("lazy", "run_command").with_output("lazy.txt"));
+
+    let mut errors = vec![];
+    DagRunner::new(tmp.path())
+        .run(
+            &mut state,
+            |task| {
+                if task.id == "audit" {
+                    std::fs::write(tmp.path().join("audit.txt"), "no issues")?;
+                }
+                Ok(())
+            },
+            |e| {
+                if let SchedulerEvent::TaskFailed { task_id, error } = e {
+                    errors.push(format!("{task_id}: {error}"));
+                }
+            },
+        )
+        .unwrap();
+    assert!(state.get_task("audit").unwrap().is_complete());
+    assert!(state.get_task("fix").unwrap().is_complete());
+    assert_eq!(errors, ["lazy: did not write lazy.txt"]);
+}
+
+#[test]
+fn failure_is_reported_while_checkpoint_is_pending() {
+    let tmp = TempDir::new().unwrap();
+    let mut state = gated_workflow();
+
+    let mut events = vec![];
+    DagRunner::new(tmp.path())
+        .run(
+            &mut state,
+            |task| {
+                if task.id == "music" {
+                    anyhow::bail!("exit 1");
+                }
+                for output in &task.outputs {
+                    std::fs::write(tmp.path().join(&output.artifact), "x")?;
+                }
+                Ok(())
+            },
+            |e| events.push(describe(e)),
+        )
+        .unwrap();
+    assert_eq!(
+        events,
+        [
+            "start music",
+            "fail music",
+            "start script",
+            "done script",
+            "checkpoint review",
+            "blocked: failed: music"
+        ]
+    );
+}
@@ -8,6 +8,7 @@ fn create_test_state() -> WorkflowState {
     task_a.outputs.push(OutputSpec {
         artifact: "artifact_a".to_string(),
         primary: true,
+        optional: false,
     });
     state.add_task(task_a);
 
@@ -18,6 +19,7 @@ fn create_test_state() -> WorkflowState {
     task_b.outputs.push(OutputSpec {
         artifact: "artifact_b".to_string(),
         primary: true,
+        optional: false,
     });
     state.add_task(task_b);
 
@@ -92,3 +94,30 @@ fn invalidation_cascade() {
     assert!(!state.get_task("task_b").unwrap().is_complete());
     assert!(!state.get_task("task_c").unwrap().is_complete());
 }
+
+#[test]
+fn missing_optional_output_does_not_block() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    std::fs::write(tmp.path().join("frames.txt"), "ok").unwrap();
+
+    let mut state = WorkflowState::new("test", 1);
+    let mut audit = Task::new("audit", "llm_audit")
+        .with_output("frames.txt")
+        .with_optional_output("issues.json");
+    assert!(audit.missing_required_outputs(tmp.path()).is_empty());
+    audit.status = TaskStatus::Complete;
+    state.add_task(audit);
+    state.add_task(Task::new("fix", "write_file").with_input("issues.json"));
+
+    Scheduler::default().update_task_statuses(&mut state);
+    assert!(state.get_task("fix").unwrap().is_ready());
+}
+
+#[test]
+fn missing_required_output_is_reported() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let task = Task::new("tts", "tts_generate")
+        .with_output("audio.wav")
+        .with_optional_output("audio.log");
+    assert_eq!(task.missing_required_outputs(tmp.path()), vec!["audio.wav"]);
+}
@@ -2,7 +2,7 @@
 
 use chrono::Utc;
 use tempfile::TempDir;
-use vwf_dag::{CheckpointStatus, StateStore, Task, WorkflowState};
+use vwf_dag::{ArtifactStatus, CheckpointStatus, StateStore, Task, TaskStatus, WorkflowState};
 
 #[test]
 fn state_persistence() {
@@ -54,3 +54,69 @@ fn checkpoint_approval() {
 
     assert!(state.checkpoints.get("review_text").unwrap().approved);
 }
+
+#[test]
+fn reset_failed_makes_failed_tasks_runnable_again() {
+    let mut state = WorkflowState::new("tts_down", 1);
+    let mut narrate = Task::new("narrate", "tts_generate").with_output("work/voice.wav");
+    narrate.status = TaskStatus::Failed {
+        error: "Connection refused".to_string(),
+    };
+    let mut mix = Task::new("mix", "audio_mix").with_input("work/voice.wav");
+    mix.status = TaskStatus::Blocked {
+        waiting_on: vec!["narrate".to_string()],
+    };
+    let mut title = Task::new("title", "create_slide").with_output("work/title.png");
+    title.status = TaskStatus::Complete;
+    state.add_task(narrate);
+    state.add_task(mix);
+    state.add_task(title);
+    state.error = Some("1 task failed".to_string());
+
+    assert_eq!(state.reset_failed(), ["narrate"]);
+    assert_eq!(state.get_task("narrate").unwrap().status, TaskStatus::Ready);
+    assert!(state.get_task("title").unwrap().is_complete());
+    assert_eq!(
+        state.get_task("mix").unwrap().status,
+        TaskStatus::Blocked {
+            waiting_on: vec!["narrate".to_string()]
+        }
+    );
+    assert!(state.error.is_none());
+    let ready: Vec<&str> = state.ready_tasks().iter().map(|t| t.id.as_str()).collect();
+    assert_eq!(ready, ["narrate"]);
+    assert!(state.reset_failed().is_empty());
+}
+
+#[test]
+fn reconcile_resets_stuck_running_tasks() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir_all(tmp.path().join("work")).unwrap();
+    // `narrate` died mid-write; `title` finished but state wasn't saved.
+    std::fs::write(tmp.path().join("work/voice.wav"), "").unwrap();
+    std::fs::write(tmp.path().join("work/title.png"), "png").unwrap();
+
+    let mut state = WorkflowState::new("crashed", 1);
+    let mut narrate = Task::new("narrate", "tts_generate").with_output("work/voice.wav");
+    narrate.status = TaskStatus::Running;
+    let mut title = Task::new("title", "create_slide").with_output("work/title.png");
+    title.status = TaskStatus::Running;
+    state.add_task(narrate);
+    state.add_task(title);
+    state.add_task(Task::new("mix", "audio_mix").with_input("work/voice.wav"));
+    let store = StateStore::new(tmp.path());
+    store.save(&state).unwrap();
+
+    let state = store.load_reconciled().unwrap().unwrap();
+    assert_eq!(state.get_task("narrate").unwrap().status, TaskStatus::Ready);
+    assert!(!tmp.path().join("work/voice.wav").exists());
+    assert!(state.get_task("title").unwrap().is_complete());
+    let title_png = state.get_artifact("work/title.png").unwrap();
+    assert_eq!(title_png.status, ArtifactStatus::Ready);
+    assert_eq!(
+        state.get_task("mix").unwrap().status,
+        TaskStatus::Blocked {
+            waiting_on: vec!["narrate".to_string()]
+        }
+    );
+}
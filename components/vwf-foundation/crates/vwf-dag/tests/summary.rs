@@ -0,0 +1,35 @@
+//! State summary tests.
+
+use vwf_dag::{Task, TaskStatus, WorkflowState};
+
+#[test]
+fn summary_reflects_task_statuses() {
+    let mut state = WorkflowState::new("demo", 1);
+    let mut done = Task::new("write_script", "write_file");
+    done.status = TaskStatus::Complete;
+    state.add_task(done);
+    let mut waiting = Task::new("tts", "tts_generate");
+    waiting.status = TaskStatus::Blocked {
+        waiting_on: vec!["write_script".to_string()],
+    };
+    state.add_task(waiting);
+    let mut ready = Task::new("slide", "create_slide");
+    ready.status = TaskStatus::Ready;
+    state.add_task(ready);
+
+    let summary = state.summary();
+    assert_eq!(summary.total, 3);
+    assert_eq!(summary.count("complete"), 1);
+    assert_eq!(summary.count("blocked"), 1);
+    assert_eq!(summary.count("ready"), 1);
+    assert_eq!(summary.ready, vec!["slide".to_string()]);
+    assert_eq!(
+        summary.blocked,
+        vec![("tts".to_string(), vec!["write_script".to_string()])]
+    );
+    assert!((summary.progress_percent() - 100.0 / 3.0).abs() < 1e-9);
+
+    let text = summary.to_string();
+    assert!(text.contains("Progress: 33%"));
+    assert!(text.contains("tts (waiting on: write_script)"));
+}
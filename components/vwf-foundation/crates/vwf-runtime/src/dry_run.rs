@@ -1,16 +1,21 @@
 //! Dry-run runtime: records intended writes without touching disk.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 
 use super::traits::{CmdOut, LlmClient, Runtime};
 
 /// Dry-run runtime: records writes without disk I/O.
+///
+/// Each dir and written path is recorded once, in first-seen order; a later
+/// write to the same path replaces the planned content.
 pub struct DryRunRuntime {
     workdir: PathBuf,
     llm: Box<dyn LlmClient>,
     pub planned_writes: Vec<(String, String)>,
     pub planned_dirs: Vec<String>,
+    /// `(from, to)` copies, in order.
+    pub planned_copies: Vec<(String, String)>,
 }
 
 impl DryRunRuntime {
@@ -20,8 +25,25 @@ impl DryRunRuntime {
             llm,
             planned_writes: vec![],
             planned_dirs: vec![],
+            planned_copies: vec![],
         }
     }
+
+    /// The planned dirs, writes and copies, each sorted so plans diff cleanly.
+    pub fn plan(&self) -> serde_json::Value {
+        let mut dirs = self.planned_dirs.clone();
+        dirs.sort();
+        let mut writes: Vec<&str> = self
+            .planned_writes
+            .iter()
+            .map(|(p, _)| p.as_str())
+            .collect();
+        writes.sort();
+        let mut copies = self.planned_copies.clone();
+        copies.sort();
+        copies.dedup();
+        serde_json::json!({ "dirs": dirs, "writes": writes, "copies": copies })
+    }
 }
 
 impl Runtime for DryRunRuntime {
@@ -30,13 +52,21 @@ impl Runtime for DryRunRuntime {
     }
 
     fn ensure_dir(&mut self, rel: &str) -> Result<()> {
-        self.planned_dirs.push(rel.to_string());
+        self.resolve_safe(rel)?;
+        if !self.planned_dirs.iter().any(|d| d == rel) {
+            self.planned_dirs.push(rel.to_string());
+        }
         Ok(())
     }
 
     fn write_text(&mut self, rel: &str, content: &str) -> Result<()> {
-        self.planned_writes
-            .push((rel.to_string(), content.to_string()));
+        self.resolve_safe(rel)?;
+        match self.planned_writes.iter_mut().find(|(path, _)| path == rel) {
+            Some((_, planned)) => *planned = content.to_string(),
+            None => self
+                .planned_writes
+                .push((rel.to_string(), content.to_string())),
+        }
         Ok(())
     }
 
@@ -46,7 +76,14 @@ impl Runtime for DryRunRuntime {
             .rev()
             .find(|(path, _)| path == rel)
             .map(|(_, content)| content.clone())
-            .ok_or_else(|| anyhow::anyhow!("DryRunRuntime: file `{rel}` not found"))
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))
+            .with_context(|| format!("DryRunRuntime: file `{rel}` not found"))
+    }
+
+    fn copy_file(&mut self, from: &str, to: &str) -> Result<()> {
+        self.resolve_safe(to)?;
+        self.planned_copies.push((from.to_string(), to.to_string()));
+        Ok(())
     }
 
     fn run_command(&mut self, prog: &str, _args: &[String], _cwd: Option<&str>) -> Result<CmdOut> {
@@ -54,10 +91,62 @@ impl Runtime for DryRunRuntime {
             status: 0,
             stdout: format!("[dry-run] would run {prog}"),
             stderr: String::new(),
+            duration_ms: 0,
         })
     }
 
     fn llm(&mut self) -> &mut dyn LlmClient {
         self.llm.as_mut()
     }
+
+    fn is_dry_run(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockLlmClient;
+
+    fn runtime() -> DryRunRuntime {
+        DryRunRuntime::new("/tmp", Box::new(MockLlmClient::echo()))
+    }
+
+    #[test]
+    fn repeated_dir_is_planned_once() {
+        let mut rt = runtime();
+        rt.ensure_dir("work/audio").unwrap();
+        rt.ensure_dir("work").unwrap();
+        rt.ensure_dir("work/audio").unwrap();
+        assert_eq!(rt.planned_dirs, ["work/audio", "work"]);
+    }
+
+    #[test]
+    fn rewrite_replaces_planned_content() {
+        let mut rt = runtime();
+        rt.write_text("b.txt", "first").unwrap();
+        rt.write_text("a.txt", "a").unwrap();
+        rt.write_text("b.txt", "second").unwrap();
+        let paths: Vec<&str> = rt.planned_writes.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, ["b.txt", "a.txt"]);
+        assert_eq!(rt.read_text("b.txt").unwrap(), "second");
+    }
+
+    #[test]
+    fn plan_is_sorted() {
+        let mut rt = runtime();
+        rt.ensure_dir("work/video").unwrap();
+        rt.ensure_dir("work/audio").unwrap();
+        rt.write_text("z.txt", "").unwrap();
+        rt.write_text("a.txt", "").unwrap();
+        assert_eq!(
+            rt.plan(),
+            serde_json::json!({
+                "dirs": ["work/audio", "work/video"],
+                "writes": ["a.txt", "z.txt"],
+                "copies": []
+            })
+        );
+    }
 }
@@ -0,0 +1,57 @@
+//! Printing LLM requests for prompt engineering.
+
+use anyhow::Result;
+use std::io::Write;
+
+use super::traits::{LlmClient, LlmReq};
+
+/// Prints every request's provider and rendered prompts, then answers
+/// with the wrapped client (a mock, so no model is called).
+pub struct ExplainingLlmClient {
+    inner: Box<dyn LlmClient>,
+    out: Box<dyn Write>,
+    count: usize,
+}
+
+impl ExplainingLlmClient {
+    /// Print to stdout.
+    pub fn new(inner: Box<dyn LlmClient>) -> Self {
+        Self {
+            inner,
+            out: Box::new(std::io::stdout()),
+            count: 0,
+        }
+    }
+
+    /// Print somewhere else, e.g. a file.
+    pub fn with_output(mut self, out: Box<dyn Write>) -> Self {
+        self.out = out;
+        self
+    }
+
+    fn explain(&mut self, req: &LlmReq) -> Result<()> {
+        self.count += 1;
+        writeln!(
+            self.out,
+            "=== LLM request {} (provider: {}) ===\n--- system ---\n{}\n--- user ---\n{}\n",
+            self.count, req.provider, req.system, req.user
+        )?;
+        Ok(self.out.flush()?)
+    }
+}
+
+impl LlmClient for ExplainingLlmClient {
+    fn generate(&mut self, req: LlmReq) -> Result<String> {
+        self.explain(&req)?;
+        self.inner.generate(req)
+    }
+
+    fn generate_streamed(
+        &mut self,
+        req: LlmReq,
+        on_chunk: &mut dyn FnMut(&str) -> Result<()>,
+    ) -> Result<String> {
+        self.explain(&req)?;
+        self.inner.generate_streamed(req, on_chunk)
+    }
+}
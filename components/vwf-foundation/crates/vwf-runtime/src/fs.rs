@@ -1,16 +1,48 @@
 //! Filesystem-based runtime implementation.
 
 use anyhow::{Context, Result};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
 
+use super::paths::stays_inside;
+use super::provider::{make_llm_client, uses_default_client};
 use super::traits::{CmdOut, LlmClient, Runtime};
 
+/// Default cap on each of a command's captured stdout and stderr.
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+
+/// Programs `--safe` allows when no allowlist is given: the media tools
+/// the bundled steps shell out to.
+pub const SAFE_COMMANDS: &[&str] = &[
+    "ffmpeg",
+    "ffprobe",
+    "convert",
+    "magick",
+    "python3",
+    "whisper-cli",
+];
+
+/// Appended to captured output that hit the cap.
+const TRUNCATED_MARKER: &str = "\n[output truncated]\n";
+
+/// How often a command with a timeout is checked for having exited.
+const TIMEOUT_POLL: Duration = Duration::from_millis(20);
+
 /// Real filesystem runtime.
 pub struct FsRuntime {
     workdir: PathBuf,
     llm: Box<dyn LlmClient>,
+    /// Clients built on demand for per-step providers, keyed by provider.
+    llm_cache: BTreeMap<String, Box<dyn LlmClient>>,
+    /// Programs `run_command` may start; empty allows any program.
     pub command_allowlist: BTreeSet<String>,
+    /// Echo every command's output to the terminal while capturing it.
+    pub stream_output: bool,
+    /// Keep at most this many bytes of each stream; the rest is discarded.
+    pub max_output_bytes: usize,
 }
 
 impl FsRuntime {
@@ -18,53 +50,578 @@ impl FsRuntime {
         Self {
             workdir: workdir.into(),
             llm,
+            llm_cache: BTreeMap::new(),
             command_allowlist: Default::default(),
+            stream_output: false,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        }
+    }
+}
+
+impl FsRuntime {
+    /// Resolve a command's `cwd`, which must be an existing directory
+    /// inside the workdir.
+    fn command_dir(&self, cwd: &str) -> Result<PathBuf> {
+        if !stays_inside(Path::new(cwd)) {
+            anyhow::bail!("cwd `{cwd}` is outside the workdir");
+        }
+        let dir = self.workdir.join(cwd);
+        if !dir.is_dir() {
+            anyhow::bail!(
+                "cwd `{cwd}` does not exist ({}); create it first, e.g. with an ensure_dirs step",
+                dir.display()
+            );
+        }
+        Ok(dir)
+    }
+
+    /// Run a command, optionally echoing its output live while capturing it.
+    fn spawn(
+        &self,
+        prog: &str,
+        args: &[String],
+        cwd: Option<&str>,
+        stream: bool,
+        timeout: Option<Duration>,
+    ) -> Result<CmdOut> {
+        if !self.command_allowlist.is_empty() && !self.command_allowlist.contains(prog) {
+            anyhow::bail!("Command not allowed: `{prog}`. Add it to the allowlist.");
+        }
+        let dir = match cwd {
+            Some(c) => self.command_dir(c)?,
+            None => self.workdir.clone(),
+        };
+        let mut cmd = Command::new(prog);
+        cmd.args(args).current_dir(dir);
+        let started = Instant::now();
+        let output = collect_output(&mut cmd, stream, self.max_output_bytes, timeout)
+            .with_context(|| format!("run `{prog}`"))?;
+        Ok(CmdOut {
+            status: output.status.code().unwrap_or(-1),
+            stdout: captured_text(output.stdout),
+            stderr: captured_text(output.stderr),
+            duration_ms: started.elapsed().as_millis(),
+        })
+    }
+}
+
+/// Captured bytes of one stream, and whether some were dropped.
+struct Captured {
+    bytes: Vec<u8>,
+    truncated: bool,
+}
+
+struct Collected {
+    status: ExitStatus,
+    stdout: Captured,
+    stderr: Captured,
+}
+
+fn captured_text(c: Captured) -> String {
+    let mut text = String::from_utf8_lossy(&c.bytes).into_owned();
+    if c.truncated {
+        text.push_str(TRUNCATED_MARKER);
+    }
+    text
+}
+
+/// `Command::output`, keeping at most `cap` bytes of each stream and, when
+/// `echo` is set, copying both to this process's as they arrive.
+///
+/// A child still running after `timeout` is killed, along with anything it
+/// started, and a `TimedOut` error returned.
+fn collect_output(
+    cmd: &mut Command,
+    echo: bool,
+    cap: usize,
+    timeout: Option<Duration>,
+) -> std::io::Result<Collected> {
+    #[cfg(unix)]
+    if timeout.is_some() {
+        // Its own process group, so the kill reaches grandchildren too
+        std::os::unix::process::CommandExt::process_group(cmd, 0);
+    }
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let out = child.stdout.take().expect("piped stdout");
+    let err = child.stderr.take().expect("piped stderr");
+    let out = std::thread::spawn(move || tee(out, echo.then(std::io::stdout), cap));
+    let err = std::thread::spawn(move || tee(err, echo.then(std::io::stderr), cap));
+    let status = match timeout {
+        Some(timeout) => wait_or_kill(&mut child, timeout)?,
+        None => child.wait()?,
+    };
+    let joined = |h: std::thread::JoinHandle<std::io::Result<Captured>>| {
+        h.join()
+            .unwrap_or_else(|_| Err(std::io::Error::other("output reader panicked")))
+    };
+    Ok(Collected {
+        status,
+        stdout: joined(out)?,
+        stderr: joined(err)?,
+    })
+}
+
+/// Wait for `child`, killing its process group once `timeout` has passed.
+fn wait_or_kill(child: &mut Child, timeout: Duration) -> std::io::Result<ExitStatus> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
         }
+        if Instant::now() >= deadline {
+            kill_group(child)?;
+            child.wait()?;
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("timed out after {timeout:?}; the process was killed"),
+            ));
+        }
+        std::thread::sleep(TIMEOUT_POLL);
     }
 }
 
+/// Kill `child` and, on Unix, every process in its group.
+fn kill_group(child: &mut Child) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        let group = child.id() as libc::pid_t;
+        // SAFETY: kill(2) takes plain integers and touches no memory of ours
+        if unsafe { libc::kill(-group, libc::SIGKILL) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    child.kill()
+}
+
+/// Read `from` until EOF, echoing to `to` and keeping the first `cap` bytes.
+///
+/// Output past the cap is still drained so the child never blocks on a
+/// full pipe.
+fn tee(mut from: impl Read, mut to: Option<impl Write>, cap: usize) -> std::io::Result<Captured> {
+    let mut kept = Captured {
+        bytes: vec![],
+        truncated: false,
+    };
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = from.read(&mut buf)?;
+        if n == 0 {
+            return Ok(kept);
+        }
+        if let Some(to) = to.as_mut() {
+            // A closed terminal shouldn't fail the command.
+            let _ = to.write_all(&buf[..n]).and_then(|_| to.flush());
+        }
+        let room = cap - kept.bytes.len();
+        kept.bytes.extend_from_slice(&buf[..n.min(room)]);
+        kept.truncated |= n > room;
+    }
+}
+
+/// Write `path` all-or-nothing: `fill` writes a sibling temp file, which
+/// is synced and renamed over `path` only if every write succeeded.
+///
+/// A crash or error part way leaves the previous file (or none), never a
+/// truncated one that `--resume` would take for finished output.
+fn write_atomic(
+    path: &Path,
+    fill: impl FnOnce(&mut std::fs::File) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let tmp = atomic_temp_path(path);
+    let written = std::fs::File::create(&tmp).and_then(|mut file| {
+        fill(&mut file)?;
+        file.sync_all()
+    });
+    match written.and_then(|_| std::fs::rename(&tmp, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmp);
+            Err(e)
+        }
+    }
+}
+
+/// Sibling of `path` that `write_atomic` fills before renaming it into place.
+fn atomic_temp_path(path: &Path) -> PathBuf {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".tmp-{}", std::process::id()));
+    PathBuf::from(tmp)
+}
+
+/// Create and remove the temp file `write_atomic` would use for `path`.
+fn write_atomic_probe(path: &Path) -> std::io::Result<()> {
+    let tmp = atomic_temp_path(path);
+    std::fs::File::create(&tmp)?;
+    std::fs::remove_file(&tmp)
+}
+
 impl Runtime for FsRuntime {
     fn workdir(&self) -> &Path {
         &self.workdir
     }
 
     fn ensure_dir(&mut self, rel: &str) -> Result<()> {
-        let p = self.workdir.join(rel);
+        let p = self.resolve_safe(rel)?;
         std::fs::create_dir_all(&p).with_context(|| format!("create_dir_all {}", p.display()))
     }
 
     fn write_text(&mut self, rel: &str, content: &str) -> Result<()> {
-        let p = self.workdir.join(rel);
+        let p = self.resolve_safe(rel)?;
         if let Some(parent) = p.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        std::fs::write(&p, content).with_context(|| format!("write {}", p.display()))
+        write_atomic(&p, |f| f.write_all(content.as_bytes()))
+            .with_context(|| format!("write {}", p.display()))
     }
 
     fn read_text(&self, rel: &str) -> Result<String> {
         let p = self.workdir.join(rel);
         let bytes = std::fs::read(&p).with_context(|| format!("read {}", p.display()))?;
-        Ok(String::from_utf8(bytes)?)
+        String::from_utf8(bytes).map_err(|e| {
+            let offset = e.utf8_error().valid_up_to();
+            anyhow::Error::new(e).context(format!(
+                "{} is not valid UTF-8 (bad byte at offset {offset})",
+                p.display()
+            ))
+        })
+    }
+
+    fn read_text_lossy(&self, rel: &str) -> Result<String> {
+        let p = self.workdir.join(rel);
+        let bytes = std::fs::read(&p).with_context(|| format!("read {}", p.display()))?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    fn open_stream(&mut self, rel: &str, append: bool) -> Result<Box<dyn Write>> {
+        let p = self.resolve_safe(rel)?;
+        if let Some(parent) = p.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(&p)
+            .with_context(|| format!("open {}", p.display()))?;
+        Ok(Box::new(file))
+    }
+
+    fn check_writable(&mut self, rel: &str) -> Result<()> {
+        let p = self.resolve_safe(rel)?;
+        if p.is_dir() {
+            anyhow::bail!("{} is a directory", p.display());
+        }
+        if let Some(parent) = p.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("create_dir_all {}", parent.display()))?;
+        }
+        // Probe with the temp name `write_text` would use, so `rel` itself
+        // (possibly a finished output) is left alone.
+        write_atomic_probe(&p).with_context(|| format!("{} is not writable", p.display()))
+    }
+
+    fn copy_file(&mut self, from: &str, to: &str) -> Result<()> {
+        let src = self.workdir.join(from);
+        let dst = self.resolve_safe(to)?;
+        if let Some(parent) = dst.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::File::open(&src)
+            .and_then(|mut from| {
+                write_atomic(&dst, |to| {
+                    std::io::copy(&mut from, to)?;
+                    to.set_permissions(from.metadata()?.permissions())
+                })
+            })
+            .with_context(|| format!("copy {} -> {}", src.display(), dst.display()))
+    }
+
+    fn rename_file(&mut self, from: &str, to: &str) -> Result<()> {
+        let src = self.resolve_safe(from)?;
+        let dst = self.resolve_safe(to)?;
+        if let Some(parent) = dst.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(&src, &dst)
+            .with_context(|| format!("rename {} -> {}", src.display(), dst.display()))
     }
 
     fn run_command(&mut self, prog: &str, args: &[String], cwd: Option<&str>) -> Result<CmdOut> {
-        if !self.command_allowlist.is_empty() && !self.command_allowlist.contains(prog) {
-            anyhow::bail!("Command not allowed: `{prog}`. Add it to the allowlist.");
+        let stream = self.stream_output;
+        self.spawn(prog, args, cwd, stream, None)
+    }
+
+    fn run_command_streamed(
+        &mut self,
+        prog: &str,
+        args: &[String],
+        cwd: Option<&str>,
+    ) -> Result<CmdOut> {
+        self.spawn(prog, args, cwd, true, None)
+    }
+
+    fn run_command_timeout(
+        &mut self,
+        prog: &str,
+        args: &[String],
+        cwd: Option<&str>,
+        stream: bool,
+        timeout: Duration,
+    ) -> Result<CmdOut> {
+        let stream = stream || self.stream_output;
+        self.spawn(prog, args, cwd, stream, Some(timeout))
+    }
+
+    fn llm(&mut self) -> &mut dyn LlmClient {
+        self.llm.as_mut()
+    }
+
+    fn llm_for(&mut self, provider: &str) -> Result<&mut dyn LlmClient> {
+        if uses_default_client(provider) {
+            return Ok(self.llm.as_mut());
+        }
+        if !self.llm_cache.contains_key(provider) {
+            let client = make_llm_client(provider)?;
+            self.llm_cache.insert(provider.to_string(), client);
         }
-        let mut cmd = std::process::Command::new(prog);
-        cmd.args(args).current_dir(
-            cwd.map(|c| self.workdir.join(c))
-                .unwrap_or_else(|| self.workdir.clone()),
+        Ok(self.llm_cache.get_mut(provider).unwrap().as_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockLlmClient;
+
+    fn runtime(dir: &Path) -> FsRuntime {
+        FsRuntime::new(dir, Box::new(MockLlmClient::echo()))
+    }
+
+    #[test]
+    fn check_writable_creates_parents_without_writing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut rt = runtime(tmp.path());
+        rt.check_writable("output/final.mp4").unwrap();
+        assert!(tmp.path().join("output").is_dir());
+        assert_eq!(
+            std::fs::read_dir(tmp.path().join("output"))
+                .unwrap()
+                .count(),
+            0
         );
-        let output = cmd.output().with_context(|| format!("run `{prog}`"))?;
-        Ok(CmdOut {
-            status: output.status.code().unwrap_or(-1),
-            stdout: String::from_utf8_lossy(&output.stdout).into(),
-            stderr: String::from_utf8_lossy(&output.stderr).into(),
+
+        rt.write_text("output/final.mp4", "done").unwrap();
+        rt.check_writable("output/final.mp4").unwrap();
+        let text = std::fs::read_to_string(tmp.path().join("output/final.mp4")).unwrap();
+        assert_eq!(text, "done");
+
+        // A file where the parent directory should be
+        std::fs::write(tmp.path().join("work"), "").unwrap();
+        assert!(rt.check_writable("work/clip.mp4").is_err());
+        assert!(rt.check_writable("output").is_err(), "directory as a file");
+    }
+
+    #[test]
+    fn failed_write_keeps_the_previous_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut rt = runtime(tmp.path());
+        rt.write_text("work/script.txt", "take one").unwrap();
+        let path = tmp.path().join("work/script.txt");
+
+        let err = write_atomic(&path, |f| {
+            f.write_all(b"take tw")?;
+            Err(std::io::Error::other("disk full"))
         })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "disk full");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "take one");
+        let names: Vec<_> = std::fs::read_dir(tmp.path().join("work"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["script.txt"], "temp file left behind");
+
+        // With nothing there before, a failed write leaves nothing.
+        let fresh = tmp.path().join("work/new.txt");
+        write_atomic(&fresh, |_| Err(std::io::Error::other("boom"))).unwrap_err();
+        assert!(!fresh.exists());
+
+        rt.write_text("work/script.txt", "take two").unwrap();
+        rt.copy_file("work/script.txt", "out/final.txt").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("out/final.txt")).unwrap(),
+            "take two"
+        );
     }
 
-    fn llm(&mut self) -> &mut dyn LlmClient {
-        self.llm.as_mut()
+    #[test]
+    fn missing_cwd_names_the_directory() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut rt = runtime(tmp.path());
+        let err = rt.run_command("true", &[], Some("build/out")).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("cwd `build/out` does not exist"),
+            "{err}"
+        );
+
+        std::fs::create_dir_all(tmp.path().join("build/out")).unwrap();
+        let out = rt.run_command("pwd", &[], Some("build/out")).unwrap();
+        assert!(out.stdout.trim_end().ends_with("build/out"));
+    }
+
+    #[test]
+    fn cwd_escaping_workdir_is_rejected() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(tmp.path().join("a")).unwrap();
+        let mut rt = runtime(tmp.path());
+        for cwd in ["..", "a/../..", "/tmp"] {
+            let err = rt.run_command("true", &[], Some(cwd)).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("cwd `{cwd}` is outside the workdir")
+            );
+        }
+        assert!(rt.run_command("true", &[], Some("a/../a/.")).is_ok());
+    }
+
+    #[test]
+    fn writes_escaping_workdir_are_rejected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let workdir = tmp.path().join("project");
+        std::fs::create_dir_all(&workdir).unwrap();
+        let mut rt = runtime(&workdir);
+        for rel in ["../../etc/x", "a/../../x", "/etc/x"] {
+            let err = rt.write_text(rel, "owned").unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("path `{rel}` is outside the workdir")
+            );
+        }
+        assert!(rt.ensure_dir("../sibling").is_err());
+        assert!(!tmp.path().join("x").exists() && !tmp.path().join("sibling").exists());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(tmp.path(), workdir.join("link")).unwrap();
+            let err = rt.write_text("link/x", "owned").unwrap_err();
+            assert!(err.to_string().contains("outside the workdir"), "{err}");
+        }
+
+        rt.write_text("out/../out/a.txt", "ok").unwrap();
+        let inside = workdir.join("out/b.txt");
+        rt.write_text(&inside.to_string_lossy(), "ok").unwrap();
+        assert!(workdir.join("out/a.txt").exists() && inside.exists());
+    }
+
+    #[test]
+    fn invalid_utf8_error_names_file_and_offset() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("clip.mp4"), b"ok\xff\xfe").unwrap();
+        let rt = runtime(tmp.path());
+        let err = rt.read_text("clip.mp4").unwrap_err().to_string();
+        assert!(err.contains("clip.mp4"), "{err}");
+        assert!(err.contains("offset 2"), "{err}");
+        assert_eq!(
+            rt.read_text_lossy("clip.mp4").unwrap(),
+            "ok\u{fffd}\u{fffd}"
+        );
+    }
+
+    #[test]
+    fn oversized_output_is_truncated() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut rt = runtime(tmp.path());
+        rt.max_output_bytes = 1000;
+        let args = ["-c".to_string(), "yes | head -c 100000".to_string()];
+        let out = rt.run_command("sh", &args, None).unwrap();
+        assert_eq!(out.status, 0);
+        assert!(out.stdout.ends_with("[output truncated]\n"));
+        assert_eq!(out.stdout.len(), 1000 + TRUNCATED_MARKER.len());
+
+        let out = rt
+            .run_command("echo", &["short".to_string()], None)
+            .unwrap();
+        assert_eq!(out.stdout, "short\n");
+    }
+
+    #[test]
+    fn streamed_output_is_still_captured() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut rt = runtime(tmp.path());
+        let args = [
+            "-c".to_string(),
+            "echo out; echo err >&2; exit 4".to_string(),
+        ];
+        let out = rt.run_command_streamed("sh", &args, None).unwrap();
+        assert_eq!((out.status, out.stdout.as_str()), (4, "out\n"));
+        assert_eq!(out.stderr, "err\n");
+
+        rt.stream_output = true;
+        let out = rt.run_command("sh", &args, None).unwrap();
+        assert_eq!(out.stdout, "out\n");
+    }
+
+    #[test]
+    fn command_past_its_timeout_is_killed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut rt = runtime(tmp.path());
+        let started = Instant::now();
+        let err = rt
+            .run_command_timeout(
+                "sleep",
+                &["30".to_string()],
+                None,
+                false,
+                Duration::from_millis(200),
+            )
+            .unwrap_err();
+        assert!(err.to_string().starts_with("run `sleep`"), "{err}");
+        assert!(
+            format!("{err:#}").contains("timed out after 200ms"),
+            "{err:#}"
+        );
+        assert!(started.elapsed() < Duration::from_secs(10), "not killed");
+
+        let out = rt
+            .run_command_timeout(
+                "echo",
+                &["quick".to_string()],
+                None,
+                false,
+                Duration::from_secs(30),
+            )
+            .unwrap();
+        assert_eq!(out.stdout, "quick\n");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn timeout_kills_what_the_command_started() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut rt = runtime(tmp.path());
+        let script = "sleep 30 & echo $! > pid; wait".to_string();
+        rt.run_command_timeout(
+            "sh",
+            &["-c".to_string(), script],
+            None,
+            false,
+            Duration::from_millis(300),
+        )
+        .unwrap_err();
+        let pid = std::fs::read_to_string(tmp.path().join("pid")).unwrap();
+        // Gone, or a zombie waiting for init to reap it
+        let alive = || {
+            std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()))
+                .is_ok_and(|stat| !stat.rsplit(')').next().unwrap().starts_with(" Z"))
+        };
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while alive() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(!alive(), "sleep {} outlived the timeout", pid.trim());
     }
 }
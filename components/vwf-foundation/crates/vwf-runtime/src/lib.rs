@@ -1,16 +1,26 @@
 //! Runtime abstraction for workflow execution.
 
 mod dry_run;
+mod explain;
 mod fs;
 mod mock;
 mod ollama;
+mod openai;
+mod paths;
+mod provider;
+mod retry;
 mod traits;
 mod validate;
 
 pub use dry_run::DryRunRuntime;
-pub use fs::FsRuntime;
-pub use mock::MockLlmClient;
+pub use explain::ExplainingLlmClient;
+pub use fs::{DEFAULT_MAX_OUTPUT_BYTES, FsRuntime, SAFE_COMMANDS};
+pub use mock::{Behavior, MockLlmClient, Response};
 pub use ollama::OllamaClient;
+pub use openai::OpenAiClient;
+pub use paths::resolve_within;
+pub use provider::{LlmProvider, make_llm_client};
+pub use retry::RetryingLlmClient;
 pub use traits::{CmdOut, LlmClient, LlmReq, Runtime};
 pub use validate::output_is_valid;
 
@@ -1,31 +1,129 @@
 //! Mock LLM client for testing.
 
-use anyhow::Result;
+use anyhow::{Result, bail};
+use std::time::Duration;
 
 use super::traits::{LlmClient, LlmReq};
 
+/// What a mock answers once its simulated failures are used up.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Response {
+    /// Return this text.
+    Canned(String),
+    /// Return the request's system and user prompts.
+    Echo,
+}
+
+/// Scripted latency and failures, for exercising retries and timeouts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Behavior {
+    /// Sleep this long before every answer, including failures.
+    pub delay_ms: u64,
+    /// Fail this many calls before answering.
+    pub fail_times: u32,
+    pub then: Response,
+}
+
 /// Mock LLM: returns a configured response or echoes input.
 pub struct MockLlmClient {
     pub canned: Option<String>,
+    delay: Duration,
+    failures_left: u32,
+    fail_times: u32,
 }
 
 impl MockLlmClient {
     pub fn canned(s: impl Into<String>) -> Self {
-        Self {
-            canned: Some(s.into()),
-        }
+        Self::with_behavior(Behavior {
+            delay_ms: 0,
+            fail_times: 0,
+            then: Response::Canned(s.into()),
+        })
     }
 
     pub fn echo() -> Self {
-        Self { canned: None }
+        Self::with_behavior(Behavior {
+            delay_ms: 0,
+            fail_times: 0,
+            then: Response::Echo,
+        })
+    }
+
+    pub fn with_behavior(behavior: Behavior) -> Self {
+        Self {
+            canned: match behavior.then {
+                Response::Canned(s) => Some(s),
+                Response::Echo => None,
+            },
+            delay: Duration::from_millis(behavior.delay_ms),
+            failures_left: behavior.fail_times,
+            fail_times: behavior.fail_times,
+        }
     }
 }
 
 impl LlmClient for MockLlmClient {
     fn generate(&mut self, req: LlmReq) -> Result<String> {
+        std::thread::sleep(self.delay);
+        if self.failures_left > 0 {
+            self.failures_left -= 1;
+            bail!(
+                "mock LLM failure {} of {}",
+                self.fail_times - self.failures_left,
+                self.fail_times
+            );
+        }
         Ok(self
             .canned
             .clone()
             .unwrap_or_else(|| format!("SYSTEM:\n{}\n\nUSER:\n{}", req.system, req.user)))
     }
+
+    /// Streams the response a word at a time.
+    fn generate_streamed(
+        &mut self,
+        req: LlmReq,
+        on_chunk: &mut dyn FnMut(&str) -> Result<()>,
+    ) -> Result<String> {
+        let text = self.generate(req)?;
+        for word in text.split_inclusive(char::is_whitespace) {
+            on_chunk(word)?;
+        }
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn req() -> LlmReq {
+        LlmReq {
+            system: "sys".into(),
+            user: "user".into(),
+            provider: "mock".into(),
+        }
+    }
+
+    #[test]
+    fn behavior_delays_then_fails_then_answers() {
+        let mut llm = MockLlmClient::with_behavior(Behavior {
+            delay_ms: 20,
+            fail_times: 2,
+            then: Response::Canned("ok".into()),
+        });
+        let started = Instant::now();
+        assert_eq!(
+            llm.generate(req()).unwrap_err().to_string(),
+            "mock LLM failure 1 of 2"
+        );
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        assert_eq!(
+            llm.generate(req()).unwrap_err().to_string(),
+            "mock LLM failure 2 of 2"
+        );
+        assert_eq!(llm.generate(req()).unwrap(), "ok");
+        assert_eq!(llm.generate(req()).unwrap(), "ok");
+    }
 }
@@ -3,6 +3,8 @@
 //! Connects to local Ollama server for text generation.
 
 use anyhow::{Context, Result};
+use std::io::BufRead;
+use std::time::Duration;
 
 use super::traits::{LlmClient, LlmReq};
 
@@ -12,6 +14,23 @@ pub struct OllamaClient {
     pub server: String,
     /// Model to use (e.g., "qwen2.5-coder:14b", "gemma2:9b")
     pub model: String,
+    /// Retries while the model is being pulled or loaded (default: 5)
+    pub loading_retries: u32,
+    /// Delay between loading retries (default: 10s)
+    pub loading_delay: Duration,
+    /// Whether `/api/tags` has listed the model
+    model_found: bool,
+}
+
+/// How a failed Ollama request should be handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Failure {
+    /// Model is still being downloaded; retry after a long delay.
+    Pulling,
+    /// Model is still being loaded into memory; retry after a long delay.
+    Loading,
+    /// Unrecoverable (bad request, unknown model, ...); fail immediately.
+    Fatal,
 }
 
 impl OllamaClient {
@@ -19,6 +38,9 @@ impl OllamaClient {
         Self {
             server: "http://localhost:11434".to_string(),
             model: model.into(),
+            loading_retries: 5,
+            loading_delay: Duration::from_secs(10),
+            model_found: false,
         }
     }
 
@@ -26,21 +48,180 @@ impl OllamaClient {
         self.server = server.into();
         self
     }
-}
 
-impl LlmClient for OllamaClient {
-    fn generate(&mut self, req: LlmReq) -> Result<String> {
-        // Build the request payload
-        let payload = serde_json::json!({
+    pub fn with_loading_retry(mut self, retries: u32, delay: Duration) -> Self {
+        self.loading_retries = retries;
+        self.loading_delay = delay;
+        self
+    }
+
+    /// Fail early with a pull hint if `model` is not installed on `server`.
+    ///
+    /// Only a model that was found is remembered, so one pulled after a
+    /// failed check is picked up by the next call. An unreachable server is
+    /// left to the generate call, which reports it.
+    pub fn ensure_model(&mut self) -> Result<()> {
+        if self.model_found {
+            return Ok(());
+        }
+        let response = match reqwest::blocking::Client::new()
+            .get(format!("{}/api/tags", self.server))
+            .timeout(Duration::from_secs(10))
+            .send()
+        {
+            Ok(r) if r.status().is_success() => r,
+            _ => return Ok(()),
+        };
+        let tags: serde_json::Value = response
+            .json()
+            .context("Failed to parse Ollama /api/tags response")?;
+        check_model(&tags, &self.model, &self.server)?;
+        self.model_found = true;
+        Ok(())
+    }
+
+    fn payload(&self, req: &LlmReq, stream: bool) -> serde_json::Value {
+        serde_json::json!({
             "model": self.model,
             "prompt": req.user,
             "system": req.system,
-            "stream": false,
+            "stream": stream,
             "options": {
                 "temperature": 0.7,
                 "num_predict": 2048
             }
-        });
+        })
+    }
+
+    fn post_generate(&self, payload: &serde_json::Value) -> Result<serde_json::Value> {
+        self.send_generate(payload)?
+            .json()
+            .context("Failed to parse Ollama response")
+    }
+
+    /// POST to `/api/generate`, retrying while the model is pulled or loaded.
+    ///
+    /// A server that refuses the connection fails at once: it is not
+    /// running, and waiting won't start it.
+    fn send_generate(&self, payload: &serde_json::Value) -> Result<reqwest::blocking::Response> {
+        let client = reqwest::blocking::Client::new();
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let retry = attempt <= self.loading_retries;
+            let response = match client
+                .post(format!("{}/api/generate", self.server))
+                .json(payload)
+                .timeout(Duration::from_secs(300)) // 5 min timeout for long generations
+                .send()
+            {
+                Ok(r) => r,
+                Err(e) if e.is_connect() => {
+                    return Err(e).with_context(|| {
+                        format!(
+                            "Ollama is not running at {}; start it with `ollama serve`",
+                            self.server
+                        )
+                    });
+                }
+                Err(e) => return Err(e).context("Failed to connect to Ollama server"),
+            };
+
+            if response.status().is_success() {
+                return Ok(response);
+            }
+
+            let status = response.status();
+            let body = response.text().unwrap_or_default();
+            let state = match classify_failure(status.as_u16(), &body) {
+                Failure::Pulling => "pulling",
+                Failure::Loading => "loading",
+                Failure::Fatal => anyhow::bail!("Ollama request failed ({}): {}", status, body),
+            };
+            if !retry {
+                anyhow::bail!(
+                    "Ollama was still {state} model `{}` after {} retries: {body}",
+                    self.model,
+                    self.loading_retries
+                );
+            }
+            println!("  Ollama is {state} model `{}`, retrying...", self.model);
+            std::thread::sleep(self.loading_delay);
+        }
+    }
+}
+
+/// Distinguish a model still being pulled or loaded from a genuine
+/// request error.
+fn classify_failure(status: u16, body: &str) -> Failure {
+    let body = body.to_lowercase();
+    if status < 500 {
+        Failure::Fatal
+    } else if body.contains("pulling") {
+        Failure::Pulling
+    } else if body.contains("loading model") || body.contains("model is loading") {
+        Failure::Loading
+    } else {
+        Failure::Fatal
+    }
+}
+
+/// Error unless `model` is listed in an `/api/tags` response.
+fn check_model(tags: &serde_json::Value, model: &str, server: &str) -> Result<()> {
+    // Untagged names refer to `:latest`
+    let normalize = |name: &str| {
+        if name.contains(':') {
+            name.to_string()
+        } else {
+            format!("{name}:latest")
+        }
+    };
+    let wanted = normalize(model);
+    let found = tags["models"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|m| m["name"].as_str().or_else(|| m["model"].as_str()))
+        .any(|name| normalize(name) == wanted);
+    if !found {
+        anyhow::bail!("model '{model}' not found on {server}; run: ollama pull {model}");
+    }
+    Ok(())
+}
+
+/// Concatenate the `response` pieces of a streamed `/api/generate` reply,
+/// passing each to `on_chunk` as it's read.
+fn read_stream(
+    reader: impl BufRead,
+    on_chunk: &mut dyn FnMut(&str) -> Result<()>,
+) -> Result<String> {
+    let mut text = String::new();
+    for line in reader.lines() {
+        let line = line.context("Failed to read Ollama stream")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let chunk: serde_json::Value =
+            serde_json::from_str(&line).context("Failed to parse Ollama stream chunk")?;
+        if let Some(error) = chunk["error"].as_str() {
+            anyhow::bail!("Ollama stream failed: {error}");
+        }
+        if let Some(piece) = chunk["response"].as_str()
+            && !piece.is_empty()
+        {
+            on_chunk(piece)?;
+            text.push_str(piece);
+        }
+        if chunk["done"].as_bool() == Some(true) {
+            break;
+        }
+    }
+    Ok(text)
+}
+
+impl LlmClient for OllamaClient {
+    fn generate(&mut self, req: LlmReq) -> Result<String> {
+        let payload = self.payload(&req, false);
 
         println!("LLM Generate via Ollama:");
         println!("  Server: {}", self.server);
@@ -54,23 +235,10 @@ impl LlmClient for OllamaClient {
             req.user.chars().take(50).collect::<String>()
         );
 
-        // Make HTTP request to Ollama
-        let client = reqwest::blocking::Client::new();
-        let response = client
-            .post(format!("{}/api/generate", self.server))
-            .json(&payload)
-            .timeout(std::time::Duration::from_secs(300)) // 5 min timeout for long generations
-            .send()
-            .context("Failed to connect to Ollama server")?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().unwrap_or_default();
-            anyhow::bail!("Ollama request failed ({}): {}", status, body);
-        }
+        self.ensure_model()?;
 
-        let result: serde_json::Value =
-            response.json().context("Failed to parse Ollama response")?;
+        // Make HTTP request to Ollama
+        let result = self.post_generate(&payload)?;
 
         let text = result["response"]
             .as_str()
@@ -81,4 +249,142 @@ impl LlmClient for OllamaClient {
 
         Ok(text)
     }
+
+    fn generate_streamed(
+        &mut self,
+        req: LlmReq,
+        on_chunk: &mut dyn FnMut(&str) -> Result<()>,
+    ) -> Result<String> {
+        let payload = self.payload(&req, true);
+        println!(
+            "LLM Generate via Ollama (streaming): {} @ {}",
+            self.model, self.server
+        );
+        self.ensure_model()?;
+        let response = self.send_generate(&payload)?;
+        let text = read_stream(std::io::BufReader::new(response), on_chunk)?;
+        println!("  Response: {} chars", text.len());
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loading_model_is_retryable() {
+        let body = r#"{"error":"llm server loading model"}"#;
+        assert_eq!(classify_failure(500, body), Failure::Loading);
+        let body = r#"{"error":"pulling manifest"}"#;
+        assert_eq!(classify_failure(503, body), Failure::Pulling);
+    }
+
+    #[test]
+    fn refused_connection_fails_without_retrying() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut client = OllamaClient::new("llama3")
+            .with_server(format!("http://127.0.0.1:{port}"))
+            .with_loading_retry(5, Duration::from_secs(10));
+        let started = std::time::Instant::now();
+        let err = client
+            .generate(LlmReq {
+                system: "sys".into(),
+                user: "hi".into(),
+                provider: "ollama".into(),
+            })
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("ollama serve"), "{err:#}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn client_errors_are_fatal() {
+        let body = r#"{"error":"model 'nope' not found, try pulling it first"}"#;
+        assert_eq!(classify_failure(404, body), Failure::Fatal);
+        assert_eq!(classify_failure(400, "invalid options"), Failure::Fatal);
+        assert_eq!(classify_failure(500, "out of memory"), Failure::Fatal);
+    }
+
+    #[test]
+    fn reads_streamed_response_chunks() {
+        let body = concat!(
+            r#"{"response":"Hello","done":false}"#,
+            "\n",
+            r#"{"response":" world","done":false}"#,
+            "\n\n",
+            r#"{"response":"","done":true,"total_duration":1}"#,
+            "\n",
+        );
+        let mut chunks = vec![];
+        let text = read_stream(body.as_bytes(), &mut |c| {
+            chunks.push(c.to_string());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(text, "Hello world");
+        assert_eq!(chunks, ["Hello", " world"]);
+
+        let failed = r#"{"error":"model crashed"}"#;
+        let err = read_stream(failed.as_bytes(), &mut |_| Ok(())).unwrap_err();
+        assert_eq!(err.to_string(), "Ollama stream failed: model crashed");
+    }
+
+    #[test]
+    fn missing_model_is_checked_again_until_found() {
+        use std::io::Write;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = format!("http://{}", listener.local_addr().unwrap());
+        let served = std::thread::spawn(move || {
+            let bodies = [
+                r#"{"models":[]}"#,
+                r#"{"models":[{"name":"llama3:latest"}]}"#,
+            ];
+            for body in bodies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = std::io::BufReader::new(&stream);
+                let mut line = String::new();
+                while request.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+        });
+        let mut client = OllamaClient::new("llama3").with_server(server);
+        assert!(client.ensure_model().is_err());
+        client.ensure_model().unwrap();
+        served.join().unwrap();
+        // Found once, the server isn't asked again
+        client.ensure_model().unwrap();
+    }
+
+    #[test]
+    fn detects_missing_model_in_tags() {
+        let tags = serde_json::json!({
+            "models": [
+                {"name": "qwen2.5-coder:14b", "model": "qwen2.5-coder:14b", "size": 8988124069u64},
+                {"name": "llama3:latest", "model": "llama3:latest", "size": 4661224676u64}
+            ]
+        });
+        let server = "http://localhost:11434";
+        assert!(check_model(&tags, "qwen2.5-coder:14b", server).is_ok());
+        assert!(check_model(&tags, "llama3", server).is_ok());
+
+        let err = check_model(&tags, "gemma2:9b", server).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "model 'gemma2:9b' not found on http://localhost:11434; run: ollama pull gemma2:9b"
+        );
+        assert!(check_model(&serde_json::json!({}), "llama3", server).is_err());
+    }
 }
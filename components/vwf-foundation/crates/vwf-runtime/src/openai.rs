@@ -0,0 +1,72 @@
+//! OpenAI-compatible chat completions client.
+//!
+//! Reads the API key from `OPENAI_API_KEY` at request time.
+
+use anyhow::{Context, Result};
+
+use super::traits::{LlmClient, LlmReq};
+
+/// OpenAI chat completions client.
+pub struct OpenAiClient {
+    /// API base URL (default: https://api.openai.com/v1)
+    pub base_url: String,
+    /// Model to use (e.g., "gpt-4o-mini")
+    pub model: String,
+}
+
+impl OpenAiClient {
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            base_url: "https://api.openai.com/v1".to_string(),
+            model: model.into(),
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+}
+
+impl LlmClient for OpenAiClient {
+    fn generate(&mut self, req: LlmReq) -> Result<String> {
+        let api_key = std::env::var("OPENAI_API_KEY")
+            .context("OPENAI_API_KEY is not set for openai provider")?;
+        let payload = serde_json::json!({
+            "model": self.model,
+            "messages": [
+                {"role": "system", "content": req.system},
+                {"role": "user", "content": req.user},
+            ],
+        });
+
+        println!("LLM Generate via OpenAI:");
+        println!("  Model: {}", self.model);
+
+        let client = reqwest::blocking::Client::new();
+        let response = client
+            .post(format!("{}/chat/completions", self.base_url))
+            .bearer_auth(api_key)
+            .json(&payload)
+            .timeout(std::time::Duration::from_secs(300))
+            .send()
+            .context("Failed to connect to OpenAI API")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().unwrap_or_default();
+            anyhow::bail!("OpenAI request failed ({}): {}", status, body);
+        }
+
+        let result: serde_json::Value =
+            response.json().context("Failed to parse OpenAI response")?;
+        let text = result["choices"][0]["message"]["content"]
+            .as_str()
+            .context("No 'choices[0].message.content' in OpenAI output")?
+            .to_string();
+
+        println!("  Response: {} chars", text.len());
+
+        Ok(text)
+    }
+}
@@ -0,0 +1,62 @@
+//! Keeping workflow paths inside the workdir.
+
+use anyhow::{Result, bail};
+use std::path::{Component, Path, PathBuf};
+
+/// Join `rel` onto `workdir`, rejecting paths that end up outside it.
+///
+/// Absolute paths are accepted only when they point into the workdir. `..`
+/// is resolved without touching the disk, then the deepest existing
+/// ancestor is canonicalized so a symlink can't lead out either.
+pub fn resolve_within(workdir: &Path, rel: &str) -> Result<PathBuf> {
+    let path = Path::new(rel);
+    let inner = if path.is_absolute() {
+        let root = std::path::absolute(workdir)?;
+        match path.strip_prefix(&root) {
+            Ok(inner) => inner,
+            Err(_) => bail!("path `{rel}` is outside the workdir"),
+        }
+    } else {
+        path
+    };
+    if !stays_inside(inner) {
+        bail!("path `{rel}` is outside the workdir");
+    }
+    let mut resolved = workdir.to_path_buf();
+    for part in inner.components() {
+        match part {
+            Component::Normal(name) => resolved.push(name),
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            _ => {}
+        }
+    }
+    // Nothing on disk to follow yet when the workdir doesn't exist (dry runs).
+    if let Ok(root) = workdir.canonicalize() {
+        let existing = resolved.ancestors().find(|a| a.exists());
+        if let Some(real) = existing.and_then(|a| a.canonicalize().ok())
+            && !real.starts_with(&root)
+        {
+            bail!(
+                "path `{rel}` is outside the workdir (via {})",
+                real.display()
+            );
+        }
+    }
+    Ok(resolved)
+}
+
+/// Whether a relative path never climbs above its starting directory.
+pub(crate) fn stays_inside(rel: &Path) -> bool {
+    let mut depth = 0usize;
+    for part in rel.components() {
+        match part {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => depth -= 1,
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    true
+}
@@ -0,0 +1,98 @@
+//! LLM client selection from step `provider` strings.
+//!
+//! Providers look like `mock`, `ollama:<model>` or `openai:<model>`.
+
+use anyhow::{Result, bail};
+
+use super::mock::MockLlmClient;
+use super::ollama::OllamaClient;
+use super::openai::OpenAiClient;
+use super::traits::LlmClient;
+
+/// Parsed form of a step's `provider` string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LlmProvider {
+    Mock,
+    Ollama { model: String },
+    OpenAi { model: String },
+}
+
+impl LlmProvider {
+    pub fn parse(provider: &str) -> Result<Self> {
+        let provider = provider.trim();
+        if provider.is_empty() || provider == "mock" {
+            return Ok(Self::Mock);
+        }
+        let Some((name, model)) = provider.split_once(':') else {
+            bail!("LLM provider `{provider}` needs a model, e.g. `{provider}:<model>`");
+        };
+        if model.is_empty() {
+            bail!("LLM provider `{provider}` has an empty model name");
+        }
+        match name {
+            "ollama" => Ok(Self::Ollama {
+                model: model.to_string(),
+            }),
+            "openai" => Ok(Self::OpenAi {
+                model: model.to_string(),
+            }),
+            _ => {
+                bail!("Unknown LLM provider `{name}` (use mock, ollama:<model> or openai:<model>)")
+            }
+        }
+    }
+}
+
+/// Build the LLM client named by a provider string.
+pub fn make_llm_client(provider: &str) -> Result<Box<dyn LlmClient>> {
+    Ok(match LlmProvider::parse(provider)? {
+        LlmProvider::Mock => Box::new(MockLlmClient::echo()),
+        LlmProvider::Ollama { model } => Box::new(OllamaClient::new(model)),
+        LlmProvider::OpenAi { model } => Box::new(OpenAiClient::new(model)),
+    })
+}
+
+/// Whether a step provider should use the runtime's configured client.
+///
+/// Bare names (`mock`, `ollama`) carry no model, so they defer to whatever
+/// the CLI set up (`--llm-model`, `--mock-llm-canned`).
+pub fn uses_default_client(provider: &str) -> bool {
+    !provider.contains(':')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_provider_strings() {
+        assert_eq!(LlmProvider::parse("mock").unwrap(), LlmProvider::Mock);
+        assert_eq!(LlmProvider::parse("").unwrap(), LlmProvider::Mock);
+        assert_eq!(
+            LlmProvider::parse("ollama:qwen2.5-coder:14b").unwrap(),
+            LlmProvider::Ollama {
+                model: "qwen2.5-coder:14b".to_string()
+            }
+        );
+        assert_eq!(
+            LlmProvider::parse("openai:gpt-4o-mini").unwrap(),
+            LlmProvider::OpenAi {
+                model: "gpt-4o-mini".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_or_incomplete_providers() {
+        assert!(LlmProvider::parse("ollama").is_err());
+        assert!(LlmProvider::parse("ollama:").is_err());
+        assert!(make_llm_client("bogus:model").is_err());
+    }
+
+    #[test]
+    fn bare_providers_use_default_client() {
+        assert!(uses_default_client("mock"));
+        assert!(uses_default_client("ollama"));
+        assert!(!uses_default_client("openai:gpt-4o-mini"));
+    }
+}
@@ -0,0 +1,165 @@
+//! Retry wrapper for flaky LLM backends.
+
+use anyhow::Result;
+use std::time::Duration;
+
+use super::traits::{LlmClient, LlmReq};
+
+/// Retries failed requests on any client, doubling the delay each time.
+pub struct RetryingLlmClient {
+    inner: Box<dyn LlmClient>,
+    retries: u32,
+    delay: Duration,
+}
+
+impl RetryingLlmClient {
+    pub fn new(inner: Box<dyn LlmClient>, retries: u32) -> Self {
+        Self {
+            inner,
+            retries,
+            delay: Duration::from_secs(1),
+        }
+    }
+
+    /// Delay before the first retry (default: 1s).
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+impl LlmClient for RetryingLlmClient {
+    fn generate(&mut self, req: LlmReq) -> Result<String> {
+        let mut delay = self.delay;
+        for attempt in 1..=self.retries {
+            match self.inner.generate(req.clone()) {
+                Ok(text) => return Ok(text),
+                Err(e) => {
+                    println!(
+                        "  LLM request failed ({e:#}); retry {attempt}/{}",
+                        self.retries
+                    );
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                }
+            }
+        }
+        self.inner.generate(req)
+    }
+
+    /// Retries only while nothing has been streamed: once `on_chunk` has
+    /// seen part of a reply, starting over would repeat it.
+    fn generate_streamed(
+        &mut self,
+        req: LlmReq,
+        on_chunk: &mut dyn FnMut(&str) -> Result<()>,
+    ) -> Result<String> {
+        let mut delay = self.delay;
+        let mut attempt = 0;
+        loop {
+            let mut streamed = false;
+            let result = self.inner.generate_streamed(req.clone(), &mut |chunk| {
+                streamed = true;
+                on_chunk(chunk)
+            });
+            match result {
+                Err(e) if !streamed && attempt < self.retries => {
+                    attempt += 1;
+                    println!(
+                        "  LLM request failed ({e:#}); retry {attempt}/{}",
+                        self.retries
+                    );
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{Behavior, MockLlmClient, Response};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn flaky(fail_times: u32) -> Box<dyn LlmClient> {
+        Box::new(MockLlmClient::with_behavior(Behavior {
+            delay_ms: 0,
+            fail_times,
+            then: Response::Canned("script".into()),
+        }))
+    }
+
+    fn req() -> LlmReq {
+        LlmReq {
+            system: String::new(),
+            user: "write".into(),
+            provider: "mock".into(),
+        }
+    }
+
+    #[test]
+    fn recovers_after_two_failures() {
+        let mut llm = RetryingLlmClient::new(flaky(2), 2).with_delay(Duration::ZERO);
+        assert_eq!(llm.generate(req()).unwrap(), "script");
+
+        let mut llm = RetryingLlmClient::new(flaky(2), 1).with_delay(Duration::ZERO);
+        let err = llm.generate(req()).unwrap_err();
+        assert_eq!(err.to_string(), "mock LLM failure 2 of 2");
+    }
+
+    #[test]
+    fn streamed_reply_still_arrives_in_chunks() {
+        let inner = Box::new(MockLlmClient::with_behavior(Behavior {
+            delay_ms: 0,
+            fail_times: 1,
+            then: Response::Canned("one two three".into()),
+        }));
+        let mut llm = RetryingLlmClient::new(inner, 1).with_delay(Duration::ZERO);
+        let mut chunks = vec![];
+        let text = llm
+            .generate_streamed(req(), &mut |c| {
+                chunks.push(c.to_string());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(text, "one two three");
+        assert_eq!(chunks, ["one ", "two ", "three"]);
+    }
+
+    /// Streams one chunk, then fails, counting its calls.
+    struct CutOff {
+        calls: Rc<Cell<u32>>,
+    }
+
+    impl LlmClient for CutOff {
+        fn generate(&mut self, _: LlmReq) -> Result<String> {
+            unreachable!("only streamed in these tests")
+        }
+
+        fn generate_streamed(
+            &mut self,
+            _: LlmReq,
+            on_chunk: &mut dyn FnMut(&str) -> Result<()>,
+        ) -> Result<String> {
+            self.calls.set(self.calls.get() + 1);
+            on_chunk("partial")?;
+            anyhow::bail!("connection reset")
+        }
+    }
+
+    #[test]
+    fn stream_cut_off_midway_is_not_retried() {
+        let calls = Rc::new(Cell::new(0));
+        let inner = Box::new(CutOff {
+            calls: Rc::clone(&calls),
+        });
+        let mut llm = RetryingLlmClient::new(inner, 3).with_delay(Duration::ZERO);
+        let err = llm.generate_streamed(req(), &mut |_| Ok(())).unwrap_err();
+        assert_eq!(err.to_string(), "connection reset");
+        assert_eq!(calls.get(), 1);
+    }
+}
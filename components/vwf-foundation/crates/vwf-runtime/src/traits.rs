@@ -1,16 +1,106 @@
 //! Core runtime traits and types.
 
 use anyhow::Result;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// Runtime mediates side effects so the engine can be tested.
 pub trait Runtime {
     fn workdir(&self) -> &Path;
     fn ensure_dir(&mut self, rel: &str) -> Result<()>;
+    /// Write a file in the workdir, creating its parents. The file is
+    /// replaced all at once, so readers never see it half-written.
     fn write_text(&mut self, rel: &str, content: &str) -> Result<()>;
     fn read_text(&self, rel: &str) -> Result<String>;
+    /// Like `read_text`, but invalid UTF-8 becomes U+FFFD instead of an error.
+    fn read_text_lossy(&self, rel: &str) -> Result<String> {
+        self.read_text(rel)
+    }
+    /// A writer for watching a file fill up while it's generated.
+    ///
+    /// Streamed bytes are for live inspection and partial output after a
+    /// crash; callers still `write_text` the final content. Defaults to
+    /// discarding the bytes, for runtimes without real files.
+    fn open_stream(&mut self, rel: &str, append: bool) -> Result<Box<dyn Write>> {
+        let _ = (rel, append);
+        Ok(Box::new(std::io::sink()))
+    }
+    /// Check that a file could be written at `rel`: create its parent
+    /// directory and make sure it accepts new files, without writing `rel`.
+    ///
+    /// Defaults to `ensure_dir` on the parent, which is all a runtime
+    /// without real files can check.
+    fn check_writable(&mut self, rel: &str) -> Result<()> {
+        self.resolve_safe(rel)?;
+        match Path::new(rel).parent().and_then(Path::to_str) {
+            Some(parent) if !parent.is_empty() => self.ensure_dir(parent),
+            _ => Ok(()),
+        }
+    }
+    /// Copy a file within the workdir, creating the destination's parents.
+    /// Like `write_text`, the destination is replaced all at once.
+    fn copy_file(&mut self, from: &str, to: &str) -> Result<()>;
+    /// Move a file within the workdir over `to`, creating its parents.
+    ///
+    /// Defaults to `copy_file`, for runtimes without real files.
+    fn rename_file(&mut self, from: &str, to: &str) -> Result<()> {
+        self.copy_file(from, to)
+    }
     fn run_command(&mut self, prog: &str, args: &[String], cwd: Option<&str>) -> Result<CmdOut>;
     fn llm(&mut self) -> &mut dyn LlmClient;
+
+    /// Whether commands and writes are only being planned, so command
+    /// output is a placeholder.
+    fn is_dry_run(&self) -> bool {
+        false
+    }
+
+    /// Absolute path for a workdir-relative path, or an error if it leads
+    /// outside the workdir (via `..`, an absolute path or a symlink).
+    fn resolve_safe(&self, rel: &str) -> Result<PathBuf> {
+        crate::paths::resolve_within(self.workdir(), rel)
+    }
+
+    /// Like `run_command`, but the child's output is also echoed to the
+    /// terminal as it arrives.
+    ///
+    /// Defaults to `run_command`.
+    fn run_command_streamed(
+        &mut self,
+        prog: &str,
+        args: &[String],
+        cwd: Option<&str>,
+    ) -> Result<CmdOut> {
+        self.run_command(prog, args, cwd)
+    }
+
+    /// Like `run_command` (or `run_command_streamed` with `stream`), but
+    /// the child is killed and an error returned if it is still running
+    /// after `timeout`.
+    ///
+    /// Defaults to running without a limit.
+    fn run_command_timeout(
+        &mut self,
+        prog: &str,
+        args: &[String],
+        cwd: Option<&str>,
+        stream: bool,
+        _timeout: Duration,
+    ) -> Result<CmdOut> {
+        if stream {
+            self.run_command_streamed(prog, args, cwd)
+        } else {
+            self.run_command(prog, args, cwd)
+        }
+    }
+
+    /// LLM client for a step's `provider` string.
+    ///
+    /// Defaults to the runtime's configured client.
+    fn llm_for(&mut self, _provider: &str) -> Result<&mut dyn LlmClient> {
+        Ok(self.llm())
+    }
 }
 
 /// Output from a shell command execution.
@@ -19,11 +109,35 @@ pub struct CmdOut {
     pub status: i32,
     pub stdout: String,
     pub stderr: String,
+    /// Wall-clock time the command took.
+    pub duration_ms: u128,
+}
+
+impl CmdOut {
+    /// The last `n` lines of stderr, for error messages.
+    pub fn stderr_tail(&self, n: usize) -> String {
+        let lines: Vec<&str> = self.stderr.lines().collect();
+        lines[lines.len().saturating_sub(n)..].join("\n")
+    }
 }
 
 /// LLM provider abstraction.
 pub trait LlmClient {
     fn generate(&mut self, req: LlmReq) -> Result<String>;
+
+    /// Like `generate`, but hands each piece of the response to `on_chunk`
+    /// as it arrives. Returns the whole response.
+    ///
+    /// Defaults to a single chunk once generation finishes.
+    fn generate_streamed(
+        &mut self,
+        req: LlmReq,
+        on_chunk: &mut dyn FnMut(&str) -> Result<()>,
+    ) -> Result<String> {
+        let text = self.generate(req)?;
+        on_chunk(&text)?;
+        Ok(text)
+    }
 }
 
 /// LLM request parameters.